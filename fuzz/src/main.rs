@@ -1,4 +1,4 @@
-use margarine::{Arena, CompilationSettings, CompilationTarget, Compiler, Extension, FileData};
+use margarine::{Arena, CompilationSettings, CompilationTarget, Compiler, Extension, FileData, OptLevel, CrateType};
 
 #[macro_use]
 extern crate afl;
@@ -20,6 +20,12 @@ fn main() {
             cache: "artifacts".to_owned(),
             arena: &arena,
             tests: false,
+            overflow_checks: true,
+            debug_info: false,
+            backtrace: false,
+            opt_level: OptLevel::default(),
+            emit: Vec::new(),
+            crate_type: CrateType::default(),
         });
     });
 
@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use common::string_map::StringIndex;
+use margarine::{Arena, CompilationResult, FileData, StringMap};
+use parser::nodes::expr::{Expr, ExprId};
+use semantic_analysis::{namespace::NamespaceId, scope::ScopeKind, syms::{containers::ContainerKind, ty::Type, SymbolKind}};
+use tower_lsp::lsp_types::{CompletionItemKind, Position, Url};
+use tracing::trace;
+
+use crate::{position_to_offset, Lsp};
+
+
+/// Spliced in at the cursor so the source parses even
+/// when the user stopped mid-expression
+const PLACEHOLDER : &str = "__margarine_lsp_completion";
+
+
+/// What's written right before the cursor
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Context {
+    /// `value.`
+    Field,
+    /// `path::`
+    Namespace,
+    /// Anywhere else
+    Scope,
+}
+
+
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionItemKind,
+    pub detail: Option<String>,
+}
+
+
+impl Lsp {
+    /// Everything that could be written at `position`
+    pub fn completion(&mut self, uri: &Url, position: Position) -> Vec<Completion> {
+        trace!("margarine-lsp/completion: uri = '{uri}', position = {position:?}");
+
+        let _ = self.resolve_file(uri);
+        let Some(file) = self.files.get(uri)
+        else { return vec![] };
+
+        let Some(offset) = position_to_offset(position, &file.rope)
+        else { return vec![] };
+
+        let text = file.rope.to_string();
+        let offset = offset as usize;
+
+        let prefix_start = text[..offset]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map(|i| i + text[i..].chars().next().map(char::len_utf8).unwrap_or(1))
+            .unwrap_or(0);
+
+        let before = text[..prefix_start].trim_end();
+        let context =
+            if before.ends_with("::") { Context::Namespace }
+            else if before.ends_with('.') { Context::Field }
+            else { Context::Scope };
+
+        let name = format!("{}{PLACEHOLDER}", &text[prefix_start..offset]);
+        let name = self.compiler.string_map.insert(&name);
+
+        let mut patched = String::with_capacity(text.len() + PLACEHOLDER.len());
+        patched.push_str(&text[..offset]);
+        patched.push_str(PLACEHOLDER);
+        patched.push_str(&text[offset..]);
+
+        let file_path = file.file_path;
+        let root = file.root;
+
+        self.compiler.files.register(FileData::new(patched, file_path, margarine::Extension::Mar));
+
+        let arena = Arena::new();
+        let settings = self.settings(&arena, root);
+        let mut result = self.compiler.run(&settings);
+
+        // put the real source back for everyone else
+        self.compiler.files.register(FileData::new(text, file_path, margarine::Extension::Mar));
+
+        let mut items = Items {
+            string_map: &self.compiler.string_map,
+            seen: HashSet::new(),
+            items: vec![],
+        };
+
+        let exprs = result.ast().exprs().kiter()
+            .map(|(id, (expr, _))| (id, *expr))
+            .collect::<Vec<_>>();
+
+        for (id, expr) in exprs {
+            match (context, expr) {
+                (Context::Field, Expr::AccessField { val, field_name, .. }) if field_name == name => {
+                    if result.ty_info().exprs[val].is_none() { continue }
+
+                    let ty = result.ty_info().expr(val);
+                    items.members(&mut result, ty, context);
+                },
+
+
+                (Context::Namespace | Context::Scope, Expr::Identifier(ident, _)) if ident == name => {
+                    items.scope(&mut result, id, context);
+                },
+
+
+                _ => continue,
+            }
+
+            break;
+        }
+
+        items.items
+    }
+}
+
+
+struct Items<'a> {
+    string_map: &'a StringMap<'a>,
+    seen: HashSet<StringIndex>,
+    items: Vec<Completion>,
+}
+
+
+impl Items<'_> {
+    fn push(&mut self, name: StringIndex, kind: CompletionItemKind, detail: Option<String>) {
+        let label = self.string_map.get(name);
+        if label.contains(PLACEHOLDER) || !self.seen.insert(name) { return }
+
+        self.items.push(Completion { label: label.to_string(), kind, detail });
+    }
+
+
+    /// Fields, methods and, in a namespace context, the
+    /// associated items of `ty`
+    fn members(&mut self, result: &mut CompilationResult, ty: Type, context: Context) {
+        let Ok(sym) = ty.sym(&mut result.syms)
+        else { return };
+
+        if let SymbolKind::Container(cont) = result.syms.sym(sym).kind() {
+            let gens = ty.gens(&mut result.syms);
+            let gens = result.syms.get_gens(gens);
+
+            let kind = match (cont.kind(), context) {
+                (ContainerKind::Enum, Context::Namespace) => Some(CompletionItemKind::ENUM_MEMBER),
+                (ContainerKind::Struct | ContainerKind::Tuple, Context::Field) => Some(CompletionItemKind::FIELD),
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                for (name, field) in cont.fields() {
+                    let field = field.to_ty(gens, &mut result.syms);
+                    let field = field.display(self.string_map, &mut result.syms);
+                    self.push(*name, kind, Some(field.to_string()));
+                }
+            }
+        }
+
+        let ns = result.syms.sym_ns(sym);
+        self.namespace(result, ns, context);
+
+        let traits = result.syms.traits(sym).keys().copied().collect::<Vec<_>>();
+        for tr in traits {
+            let SymbolKind::Trait(tr) = result.syms.sym(tr).kind()
+            else { continue };
+
+            for (name, _) in tr.funcs {
+                self.push(*name, CompletionItemKind::METHOD, None);
+            }
+        }
+    }
+
+
+    fn namespace(&mut self, result: &mut CompilationResult, ns: NamespaceId, context: Context) {
+        let syms = result.namespaces().get_ns(ns).syms().iter()
+            .filter_map(|(name, entry)| Some((*name, entry.result().ok()?)))
+            .collect::<Vec<_>>();
+
+        for (name, sym) in syms {
+            let kind = match result.syms.sym(sym).kind() {
+                SymbolKind::Function(_) if context == Context::Field => CompletionItemKind::METHOD,
+                SymbolKind::Function(_) => CompletionItemKind::FUNCTION,
+                _ if context == Context::Field => continue,
+
+                SymbolKind::Container(cont) if cont.kind() == ContainerKind::Enum => CompletionItemKind::ENUM,
+                SymbolKind::Container(_) => CompletionItemKind::STRUCT,
+                SymbolKind::Trait(_) => CompletionItemKind::INTERFACE,
+                SymbolKind::Alias(_) => CompletionItemKind::TYPE_PARAMETER,
+                SymbolKind::Global(global) if global.is_const => CompletionItemKind::CONSTANT,
+                SymbolKind::Global(_) => CompletionItemKind::VARIABLE,
+                SymbolKind::Namespace => CompletionItemKind::MODULE,
+                SymbolKind::Opaque => CompletionItemKind::STRUCT,
+                SymbolKind::Error(_) => continue,
+            };
+
+            self.push(name, kind, None);
+        }
+    }
+
+
+    /// Everything visible from the scope the placeholder identifier
+    /// was resolved in
+    fn scope(&mut self, result: &mut CompilationResult, expr: ExprId, context: Context) {
+        let Some(scope) = result.ty_info().ident_scope(expr)
+        else { return };
+
+        let scopes = {
+            let mut scopes = vec![];
+            let scope_map = result.scopes();
+            scope_map.get(scope).over::<()>(scope_map, |s| { scopes.push(s.kind()); None });
+            scopes
+        };
+
+        if context == Context::Namespace {
+            let ty = scopes.iter().find_map(|s| match s {
+                ScopeKind::QualifiedTypeNamespace(ty, _) => Some(*ty),
+                _ => None,
+            });
+
+            if let Some(ty) = ty { self.members(result, ty, context) }
+            return;
+        }
+
+        for kind in scopes {
+            match kind {
+                ScopeKind::VariableScope(var) => {
+                    let ty = var.ty().display(self.string_map, &mut result.syms);
+                    self.push(var.name(), CompletionItemKind::VARIABLE, Some(ty.to_string()));
+                },
+
+                ScopeKind::ImplicitNamespace(ns) => self.namespace(result, ns, context),
+
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use common::string_map::{StringIndex, StringMap};
+use lexer::Literal;
+use margarine::{CompilationResult, SourceRange};
+use parser::nodes::{decl::{AttributeValue, Decl, DeclId}, expr::{Expr, ExprId}, AST};
+use semantic_analysis::syms::{sym_map::{GenListId, SymbolId}, ty::Type, SymbolKind};
+use tower_lsp::lsp_types::{Position, Range, Url};
+use tracing::trace;
+
+use crate::{position_to_offset, to_range, Lsp};
+
+
+/// What the cursor is on, resolved far enough to be displayed
+enum Subject {
+    /// A variable or a field
+    Value(StringIndex, Type),
+
+    /// A named function along with the generics it was used with
+    Function {
+        name: StringIndex,
+        sym: SymbolId,
+        gens: GenListId,
+        decl: Option<DeclId>,
+    },
+
+    Expr(Type),
+}
+
+
+impl Lsp {
+    /// Markdown describing the expression under `position`, that being
+    /// its type or signature followed by its documentation
+    pub fn hover(&mut self, uri: &Url, position: Position) -> Option<(String, Range)> {
+        trace!("margarine-lsp/hover: uri = '{uri}', position = {position:?}");
+
+        let root = self.resolve_file(uri).root;
+        self.with_analysis(root, |lsp, result, index| lsp.hover_in(result, index, uri, position))
+    }
+
+
+    fn hover_in(&self, result: &mut CompilationResult, index: &SourceIndex, uri: &Url, position: Position) -> Option<(String, Range)> {
+        let file = self.files.get(uri)?;
+        let local_offset = position_to_offset(position, &file.rope)?;
+
+        let (_, base) = *result.file_offsets().iter().find(|f| f.0 == file.file_path)?;
+        let offset = base + local_offset;
+
+        let expr = index.expr_at(offset)?;
+        let range = result.ast().range(expr);
+        let subject = subject(result, expr)?;
+
+        let string_map = &self.compiler.string_map;
+        let mut value = String::new();
+        value.push_str("```margarine\n");
+
+        let doc = match subject {
+            Subject::Value(name, ty) => {
+                let ty = ty.display(string_map, &mut result.syms);
+                sti::write!(&mut value, "{}: {ty}", string_map.get(name));
+                None
+            },
+
+
+            Subject::Function { name, sym, gens, decl } => {
+                let SymbolKind::Function(func) = result.syms.sym(sym).kind()
+                else { return None };
+
+                let gens = result.syms.get_gens(gens);
+
+                sti::write!(&mut value, "fn {}(", string_map.get(name));
+                for (i, arg) in func.args().iter().enumerate() {
+                    if i != 0 { value.push_str(", ") }
+                    if arg.is_inout() { value.push('&') }
+
+                    let ty = arg.symbol().to_ty(gens, &mut result.syms);
+                    let ty = ty.display(string_map, &mut result.syms);
+                    sti::write!(&mut value, "{}: {ty}", string_map.get(arg.name()));
+                }
+                value.push(')');
+
+                let ret = func.ret().to_ty(gens, &mut result.syms);
+                if ret != Type::UNIT {
+                    let ret = ret.display(string_map, &mut result.syms);
+                    sti::write!(&mut value, ": {ret}");
+                }
+
+                decl.and_then(|decl| index.doc(decl))
+            },
+
+
+            Subject::Expr(ty) => {
+                value.push_str(ty.display(string_map, &mut result.syms));
+                None
+            },
+        };
+
+        value.push_str("\n```");
+
+        if let Some(doc) = doc {
+            value.push_str("\n---\n");
+            value.push_str(&doc_text(string_map.get(doc)));
+        }
+
+        Some((value, to_range(&file.rope, range.base(base))))
+    }
+}
+
+
+/// The type checked expressions & the docs of a checked program,
+/// built once so lookups don't walk the whole AST
+pub struct SourceIndex {
+    /// sorted by start with outer expressions first, expressions
+    /// sharing a range are in reverse order of creation
+    exprs: Vec<(SourceRange, ExprId)>,
+    docs: HashMap<DeclId, StringIndex>,
+}
+
+
+impl SourceIndex {
+    pub fn new(result: &CompilationResult) -> Self {
+        let mut exprs = result.ast().exprs().kiter()
+            .filter(|(id, _)| result.ty_info().exprs[*id].is_some())
+            .map(|(id, (_, range))| (*range, id))
+            .collect::<Vec<_>>();
+
+        exprs.reverse();
+        exprs.sort_by_key(|(range, _)| (range.start(), Reverse(range.end())));
+
+        Self { exprs, docs: docs(result.ast()) }
+    }
+
+
+    /// The innermost type checked expression that contains `offset`
+    pub fn expr_at(&self, offset: u32) -> Option<ExprId> {
+        // expressions nest so the last one starting before
+        // `offset` which still contains it is the innermost
+        let starts = self.exprs.partition_point(|(range, _)| range.start() <= offset);
+        self.exprs[..starts].iter().rev()
+            .find(|(range, _)| offset <= range.end())
+            .map(|(_, id)| *id)
+    }
+
+
+    /// The `@doc` attached to `decl`
+    pub fn doc(&self, decl: DeclId) -> Option<StringIndex> {
+        self.docs.get(&decl).copied()
+    }
+}
+
+
+fn subject(result: &mut CompilationResult, expr: ExprId) -> Option<Subject> {
+    let ty = result.ty_info().expr(expr);
+    if ty.is_err(&mut result.syms) { return None }
+
+    Some(match result.ast().expr(expr) {
+        Expr::Identifier(name, _) => {
+            let Some(Some(sym)) = result.ty_info().ident(expr)
+            else { return Some(Subject::Value(name, ty)) };
+
+            let decl = match result.syms.sym(sym).kind() {
+                SymbolKind::Function(func) => func.decl(),
+                _ => None,
+            };
+
+            let (sym, gens) = function_ty(result, ty)?;
+            Subject::Function { name, sym, gens, decl }
+        },
+
+
+        Expr::AccessField { val, field_name, .. } => {
+            let Some((sym, gens)) = function_ty(result, ty)
+            else { return Some(Subject::Value(field_name, ty)) };
+
+            // trait methods don't have a declaration to pull docs from
+            let decl =
+            if result.ty_info().trait_func(expr).is_some() { None }
+            else {
+                let val_ty = result.ty_info().expr(val);
+                let val_sym = val_ty.sym(&mut result.syms).ok()?;
+                let ns = result.syms.sym_ns(val_sym);
+
+                result.namespaces().get_ns(ns).get_sym(field_name)
+                    .and_then(|sym| sym.ok())
+                    .and_then(|sym| match result.syms.sym(sym).kind() {
+                        SymbolKind::Function(func) => func.decl(),
+                        _ => None,
+                    })
+            };
+
+            Subject::Function { name: field_name, sym, gens, decl }
+        },
+
+
+        Expr::CallFunction { lhs, .. } => {
+            let Some((sym, gens)) = result.ty_info().func_call(expr)
+            else { return Some(Subject::Expr(ty)) };
+
+            match subject(result, lhs) {
+                Some(Subject::Function { name, decl, .. }) => Subject::Function { name, sym, gens, decl },
+                _ => Subject::Function { name: StringMap::CLOSURE, sym, gens, decl: None },
+            }
+        },
+
+
+        _ => Subject::Expr(ty),
+    })
+}
+
+
+/// The function symbol & generics of `ty` if it's callable
+fn function_ty(result: &mut CompilationResult, ty: Type) -> Option<(SymbolId, GenListId)> {
+    let sym = ty.sym(&mut result.syms).ok()?;
+    let SymbolKind::Function(_) = result.syms.sym(sym).kind()
+    else { return None };
+
+    Some((sym, ty.gens(&mut result.syms)))
+}
+
+
+/// The `@doc` attached to each declaration, either written as
+/// an attribute or as `///` comments
+fn docs(ast: &AST) -> HashMap<DeclId, StringIndex> {
+    let mut docs = HashMap::new();
+    for (_, (node, _)) in ast.decls().kiter() {
+        let Decl::Attribute { attr, decl: mut inner } = *node
+        else { continue };
+
+        if attr.identifier() != Some(StringMap::DOC) { continue }
+
+        while let Decl::Attribute { decl, .. } = ast.decl(inner) {
+            inner = decl;
+        }
+
+        if let Some(AttributeValue::Literal(Literal::String(doc))) = attr.params.first().map(|p| p.value) {
+            docs.entry(inner).or_insert(doc);
+        }
+    }
+
+    docs
+}
+
+
+/// Strips the comment markers off of a `///` doc
+fn doc_text(doc: &str) -> String {
+    let mut text = String::with_capacity(doc.len());
+    for (i, line) in doc.lines().enumerate() {
+        if i != 0 { text.push('\n') }
+
+        let line = line.strip_prefix("///").unwrap_or(line);
+        text.push_str(line.strip_prefix(' ').unwrap_or(line));
+    }
+
+    text
+}
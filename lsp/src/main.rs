@@ -1,5 +1,11 @@
 #![feature(backtrace_frames)]
 mod json;
+mod completion;
+mod hover;
+mod navigation;
+mod semantic_tokens;
+#[cfg(test)]
+mod tests;
 
 use std::{backtrace::Backtrace, cell::RefCell, collections::HashMap, fs::File, io::{Read, Write}, path::Path, str::FromStr, sync::{Mutex, RwLock}, time::{Duration, Instant}};
 
@@ -7,12 +13,14 @@ use chrono::Local;
 use color_eyre::owo_colors::colored;
 use common::string_map::{self, StringIndex};
 use dashmap::DashMap;
-use margarine::{Arena, Compiler, FileData, SourceRange, StringMap};
+use margarine::{Arena, CompilationResult, CompilationSettings, CompilationTarget, Compiler, CrateType, FileData, OptLevel, Prelude, SourceRange, StringMap};
 use parser::nodes::{decl::Decl, AST};
 use llvm_api::ctx::default_target_triple;
 use ropey::Rope;
+use hover::SourceIndex;
+use semantic_tokens::TokenType;
 use sti::{ext::FromIn, key::Key};
-use tower_lsp::lsp_types::{CodeLensParams, Diagnostic, DidChangeTextDocumentParams, DidOpenTextDocumentParams, Hover, HoverParams, MessageType, Position, Range, TextDocumentItem, Url};
+use tower_lsp::lsp_types::{CodeLensParams, DidChangeTextDocumentParams, DidOpenTextDocumentParams, Hover, HoverParams, MessageType, Position, Range, TextDocumentItem, Url};
 use tracing::{debug, error, info, level_filters::LevelFilter, trace, warn};
 use tracing_appender::{non_blocking, rolling};
 use tracing_error::ErrorLayer;
@@ -65,6 +73,30 @@ fn offset_to_position(offset: u32, rope: &Rope) -> Option<Position> {
 }
 
 
+fn encode_range(range: Range) -> serde_json::Value {
+    serde_json::json!({
+        "start": { "line": range.start.line, "character": range.start.character },
+        "end": { "line": range.end.line, "character": range.end.character },
+    })
+}
+
+
+fn encode_location((uri, range): (Url, Range)) -> serde_json::Value {
+    serde_json::json!({ "uri": uri.as_str(), "range": encode_range(range) })
+}
+
+
+fn position_to_offset(position: Position, rope: &Rope) -> Option<u32> {
+    let line = rope.try_line_to_char(position.line as usize).ok()?;
+
+    // The column is in UTF-16 code units
+    let line_utf16 = rope.try_char_to_utf16_cu(line).ok()?;
+    let char_idx = rope.try_utf16_cu_to_char(line_utf16 + position.character as usize).ok()?;
+
+    Some(rope.try_char_to_byte(char_idx).ok()? as u32)
+}
+
+
 const URI_PREFIX : &str = "file://";
 
 
@@ -79,6 +111,22 @@ struct Lsp {
 
     files: HashMap<Url, LspFile>,
     compiler: Compiler<'static>,
+    preludes: Vec<Prelude>,
+
+    /// Checked programs by their root, kept until a file in
+    /// them changes so requests don't check them again
+    analyses: HashMap<StringIndex, Analysis>,
+}
+
+
+/// A checked program and the diagnostics it produced
+struct Analysis {
+    result: CompilationResult<'static>,
+    index: SourceIndex,
+    diagnostics: HashMap<Url, Vec<serde_json::Value>>,
+
+    // declared after `result` so it's dropped after it
+    _arena: Box<Arena>,
 }
 
 
@@ -93,6 +141,27 @@ struct LspFile {
 
 
 impl Lsp {
+    fn new(preludes: Vec<Prelude>) -> Self {
+        let arena = Box::leak(Box::new(Arena::new()));
+
+        // stdout is the transport, the compiler must not print to it
+        let mut compiler = Compiler::new(arena);
+        compiler.silent = true;
+
+        Self {
+            message: vec![],
+            initialized: false,
+            next_request_id: 1,
+            active_requests: HashMap::new(),
+            arena,
+            files: HashMap::new(),
+            compiler,
+            preludes,
+            analyses: HashMap::new(),
+        }
+    }
+
+
     /// Path index is in normal file system
     fn resolve_file(&mut self, path: &Url) -> &mut LspFile {
         if !self.files.contains_key(&path) {
@@ -209,792 +278,134 @@ impl Lsp {
     }
 
 
-    pub fn on_change(&mut self, version: u32, uri_path: Url, text: &str) {
-        trace!("margarine-lsp/on-change: version = {version}, uri_path = {uri_path}");
-
-
-        // fanks rust. very cool
-        let _ = self.resolve_file(&uri_path);
-        let file = self.files.get_mut(&uri_path).unwrap();
-
-        if file.version < version {
-            file.rope = text.into();
-            file.version = version;
+    fn settings<'a>(&self, arena: &'a Arena, root: StringIndex) -> CompilationSettings<'a> {
+        CompilationSettings {
+            compilation_target: CompilationTarget::host(),
+            preludes: self.preludes.clone(),
+            entry: self.compiler.string_map.get(root).to_string(),
+            output: String::new(),
+            cache: "artifacts".to_string(),
+            arena,
+            tests: false,
+            overflow_checks: true,
+            debug_info: false,
+            backtrace: false,
+            opt_level: OptLevel::default(),
+            emit: Vec::new(),
+            crate_type: CrateType::default(),
         }
+    }
 
 
-        trace!("root of '{}' is {}", self.compiler.string_map.get(file.file_path), self.compiler.string_map.get(file.root));
-        let fd = FileData::new(text.to_string(), file.file_path, margarine::Extension::Mar);
-
-        let compiler = &mut self.compiler;
-        compiler.files.register(fd);
-
-        let arena = Arena::new();
-        let mut result = compiler.run(&arena, file.root);
-
-        trace!("{:#?}", &result.errors);
-
-        let mut diagnostics = vec![];
-
-        result
-            .errors
-            .lexer_errors
-            .iter()
-            .flatten()
-            .filter_map(|(_, e)| {
-                let (message, span) = match e {
-                    lexer::errors::Error::InvalidCharacter { character, position } => {
-                        (format!("Syntax Error: invalid character '{character}'"), *position)
-                    },
-
-
-                    lexer::errors::Error::UnterminatedString(source_range) => {
-                        (format!("Syntax Error: unterminated string"), *source_range)
-                    },
-
-
-                    lexer::errors::Error::CorruptUnicodeEscape(source_range) => {
-                        (format!("Syntax Error: corrupt unicode escape"), *source_range)
-                    },
-
-
-                    lexer::errors::Error::InvalidUnicodeCharacter(source_range) => {
-                        (format!("Syntax Error: invalid unicode escape"),
-                        SourceRange::new(source_range.start(), source_range.end()))
-                    },
-
-
-                    lexer::errors::Error::NumberTooLarge(source_range) => {
-                        (format!("Syntax: Error: constant number is too large to represent in an i64"),
-                         SourceRange::new(source_range.start(), source_range.end()))
-                    },
-
-
-                    lexer::errors::Error::TooManyDots(source_range) => {
-                        (format!("Syntax Error: number has too many dots"),
-                        SourceRange::new(source_range.start(), source_range.end()))
-                    },
-
-
-                    lexer::errors::Error::BasedFloatsArentSupported(source_range) => {
-                        (format!("Syntax Error: based floats are not supported"),
-                        SourceRange::new(source_range.start(), source_range.end()))
-                    },
-
-
-                    lexer::errors::Error::InvalidBaseForNumber(source_range) => {
-                        (format!("Syntax Error: invalid base for number"),
-                        SourceRange::new(source_range.start(), source_range.end()))
-                    },
-
-
-                    lexer::errors::Error::InvalidExponent(source_range) => {
-                        (format!("Syntax Error: invalid exponent"),
-                        SourceRange::new(source_range.start(), source_range.end()))
-                    },
-
-
-                    lexer::errors::Error::InvalidEscape { character, position } => {
-                        (format!("Syntax Error: invalid escape '\\{character}'"),
-                        SourceRange::new(position.start(), position.end()))
-                    },
-                };
-
-
-                Some((
-                    span,
-                    message,
-                ))
-            })
-
-            .for_each(|d| diagnostics.push(d));
-
-
-        result
-            .errors
-            .parser_errors
-            .iter()
-            .flatten()
-            .filter_map(|(_, e)| {
-                let (message, &span) = match e {
-                    parser::errors::Error::ExpectedLiteralString { source, token } => {
-                        (format!("expected a string literal, found '{token:?}'"), source)
-                    },
-
-
-                    parser::errors::Error::ExpectedLiteralBool { source, token } => {
-                        (format!("expected a boolean literal, found '{token:?}'"), source)
-                    },
-
-
-                    parser::errors::Error::ExpectedIdentifier { source, token } => {
-                        (format!("expected an identifier, found '{token:?}'"), source)
-                    },
-
-
-                    parser::errors::Error::UnexpectedToken(source_range) => {
-                        (format!("unexpected token"), source_range)
-                    },
-
-
-                    parser::errors::Error::ExpectedXFoundY { source, found, expected } => {
-                        (format!("expected '{expected:?}' found '{found:?}';"), source)
-
-                    },
-
-
-                    parser::errors::Error::ExpectedXFoundYMulti { source, found, expected } => {
-                        let mut str = String::new();
-                        str.push_str("expected ");
-
-                        for (i, e) in expected.iter().enumerate() {
-                            if i == expected.len() - 1 {
-                                str.push_str(" or ");
-                            } else if i != 0 {
-                                str.push_str(", ");
-                            }
-
-                            sti::write!(&mut str, "'{:?}'", e);
-                        }
-
-                        sti::write!(&mut str, " but found '{found:?}'");
-
-                        (str, source)
-                    },
-
+    /// Applies `textDocument/didChange` content changes to the
+    /// file's rope
+    fn apply_changes(&mut self, uri: &Url, changes: &[json::Value]) {
+        let file = self.resolve_file(uri);
 
-                    parser::errors::Error::DeclarationOnlyBlock { source } => {
-                        (format!("this block only supports declarations"), source)
-                    },
+        for change in changes {
+            let text = change["text"].as_string();
 
+            let Some(range) = change.get("range")
+            else {
+                file.rope = text.into();
+                continue;
+            };
 
-                    parser::errors::Error::FileDoesntExist { source, path } => {
-                        let path = self.compiler.string_map.get(*path);
-                        (format!("file '{path}' doesn't exist"), source)
-                    },
+            let position = |pos: json::Value| Position::new(
+                pos["line"].as_number() as u32,
+                pos["character"].as_number() as u32,
+            );
 
+            let start = position_to_offset(position(range["start"]), &file.rope);
+            let end = position_to_offset(position(range["end"]), &file.rope);
+            let (Some(start), Some(end)) = (start, end)
+            else {
+                error!("content change out of bounds: {range}");
+                continue;
+            };
 
-                    parser::errors::Error::RepoDoesntExist { source, path } => {
-                        let path = self.compiler.string_map.get(*path);
-                        (format!("repository '{path}' doesn't exist"), source)
-                    },
+            let start = file.rope.byte_to_char(start as usize);
+            let end = file.rope.byte_to_char(end as usize);
+            file.rope.remove(start..end);
+            file.rope.insert(start, text);
+        }
+    }
 
 
-                    parser::errors::Error::TooManyEnumVariants(source) => {
-                        (format!("too many enum variants"), source)
-                    },
+    /// Re-checks every program `uri_path` is part of against its
+    /// rope, programs which don't include it keep their analysis.
+    /// The AST & namespaces live in the run's arena so a program
+    /// that is checked is parsed and checked as a whole
+    pub fn on_change(&mut self, version: u32, uri_path: Url) {
+        trace!("margarine-lsp/on-change: version = {version}, uri_path = {uri_path}");
 
 
-                    parser::errors::Error::InvalidCfg { source, expected } => {
-                        (format!("invalid cfg predicate: {expected}"), source)
-                    },
+        // fanks rust. very cool
+        let _ = self.resolve_file(&uri_path);
+        let file = self.files.get_mut(&uri_path).unwrap();
+        file.version = file.version.max(version);
 
 
-                    parser::errors::Error::MissingCfgEnvironment { source, name } => {
-                        let name = self.compiler.string_map.get(*name);
-                        (format!("cfg environment variable '{name}' is not defined"), source)
-                    },
-                };
+        trace!("root of '{}' is {}", self.compiler.string_map.get(file.file_path), self.compiler.string_map.get(file.root));
+        let fd = FileData::new(file.rope.to_string(), file.file_path, margarine::Extension::Mar);
+        let (root, file_path) = (file.root, file.file_path);
 
+        self.compiler.files.register(fd);
 
+        let mut stale = self.analyses.iter()
+            .filter(|(_, analysis)| analysis.result.file_offsets().iter().any(|f| f.0 == file_path))
+            .map(|(root, _)| *root)
+            .collect::<Vec<_>>();
 
+        if !stale.contains(&root) { stale.push(root) }
 
-                Some((
-                    span,
-                    message,
-                ))
-            })
-
-            .for_each(|d| diagnostics.push(d));
-
-        let sm = &self.compiler.string_map;
-        let syms = &mut result.syms;
-
-        result
-            .errors
-            .sema_errors
-            .iter()
-            .filter_map(|e| {
-                let (message, &span) = match e {
-                    semantic_analysis::errors::Error::IteratorFunctionInvalidSig(source_range) => {
-                        (
-                            format!(
-                                "signature must match 'fn __next__(&self): Option<[type]>'",
-                            ),
-                            source_range,
-                        )
-
-                    },
-
-
-                    semantic_analysis::errors::Error::InvalidCast { range, from_ty, to_ty } => {
-                        (
-                            format!(
-                                "invalid cast from '{}' to '{}'",
-                                from_ty.display(sm, syms),
-                                to_ty.display(sm, syms),
-                            ),
-                            range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::InvalidValueForAttr { attr, value, expected } => {
-                        (
-                            format!("attribute '{}' expected '{expected}'", sm.get(attr.1)),
-                            value
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::UnknownAttr(source_range) => {
-                        (
-                            "unknown attribute".to_string(),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::NameIsAlreadyDefined { source, name } => {
-                        (
-                            format!(
-                                "name '{}' is already defined",
-                                sm.get(*name),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::UnknownType(string_index, source_range) => {
-                        (
-                            format!(
-                                "unknown type '{}'",
-                                sm.get(*string_index),
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::FunctionBodyAndReturnMismatch { header, item, return_type, body_type } => {
-                        (
-                            format!(
-                                "function body returns '{}'",
-                                return_type.display(sm, syms),
-                            ),
-                            item,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::OutsideOfAFunction { source } => {
-                        (
-                            format!(
-                                "this can't be outside of a function",
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::InvalidType { source, found, expected } => {
-                        (
-                            format!(
-                                "expected '{}' found '{}'",
-                                expected.display(sm, syms),
-                                found.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::DuplicateField { declared_at, error_point } => {
-                        (
-                            format!(
-                                "duplicate field",
-                            ),
-                            error_point,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::DuplicateArg { declared_at, error_point } => {
-                        (
-                            format!(
-                                "duplicate argument",
-                            ),
-                            error_point,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::VariableValueAndHintDiffer { value_type, hint_type, source } => {
-                        (
-                            format!(
-                                "..is of type '{}' but the hint expects '{}'",
-                                value_type.display(sm, syms),
-                                hint_type.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::VariableValueNotTuple(source_range) => {
-                        (
-                            format!(
-                                "expected a tuple",
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::VariableTupleAndHintTupleSizeMismatch(source_range, found, expected) => {
-                        (
-                            format!(
-                                "expected a tuple of size '{expected}' but found a tuple of size '{found}'",
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::VariableNotFound { name, source } => {
-                        (
-                            format!(
-                                "variable '{}' not found",
-                                sm.get(*name),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::InvalidBinaryOp { operator, lhs, rhs, source } => {
-                        (
-                            format!(
-                                "can't apply binary op '{}' between '{}' and '{}'",
-                                operator,
-                                lhs.display(sm, syms),
-                                rhs.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::InvalidUnaryOp { operator, rhs, source } => {
-                        (
-                            format!(
-                                "can't apply unary op '{}' on '{}'",
-                                operator,
-                                rhs.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::IfMissingElse { body } => {
-                        (
-                            format!(
-                                "main branch returns '{}' but there's no else branch",
-                                body.1.display(sm, syms),
-                            ),
-                            &body.0,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::IfBodyAndElseMismatch { body, else_block } => {
-                        (
-                            format!(
-                                "main branch returns '{}' but this returns '{}'",
-                                body.1.display(sm, syms),
-                                else_block.1.display(sm, syms),
-                            ),
-                            &else_block.0,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::MatchValueIsntEnum { source, typ } => {
-                        (
-                            format!(
-                                "'{}' is not an enum",
-                                typ.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::MatchBranchesDifferInReturnType { initial_source, initial_typ, branch_source, branch_typ } => {
-                        (
-                            format!(
-                                "previously returned '{}' but this returns '{}'",
-                                initial_typ.display(sm, syms),
-                                branch_typ.display(sm, syms),
-                            ),
-                            branch_source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::DuplicateMatch { declared_at, error_point } => {
-                        (
-                            format!(
-                                "..is already handled",
-                            ),
-                            error_point,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::InvalidMatch { name, range, value } => {
-                        (
-                            format!(
-                                "there's no variant named '{}' in '{}'",
-                                sm.get(*name),
-                                value.display(sm, syms),
-                            ),
-                            range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::MissingMatch { name, range } => {
-                        let mut msg = format!("missing variants: ");
-                        let mut is_first = true;
-                        for n in name.iter() {
-                            if !is_first {
-                                sti::write!(&mut msg, ", ");
-                            }
-
-                            is_first = false;
-                            sti::write!(&mut msg, "{}", sm.get(*n));
-                        }
+        for root in stale {
+            let analysis = self.analyse(root);
+            self.analyses.insert(root, analysis);
+        }
 
+        self.send_diagnostics();
+    }
 
-                        (
-                            msg,
-                            range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::ValueIsntAnIterator { ty, range } => {
-                        (
-                            format!(
-                                "'{}' isn't an iterator",
-                                ty.display(sm, syms),
-                            ),
-                            range,
-                        )
-
-                    },
-
-
-                    semantic_analysis::errors::Error::StructCreationOnNonStruct { source, typ } => {
-                        (
-                            format!(
-                                "'{}' isn't a struct type",
-                                typ.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::FieldAccessOnNonEnumOrStruct { source, typ } => {
-                        (
-                            format!(
-                                "'{}' is an opaque type",
-                                typ.display(sm, syms)
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::FieldDoesntExist { source, field, typ } => {
-                        (
-                            format!(
-                                "'{}' doesn't have a field named '{}'",
-                                typ.display(sm, syms),
-                                sm.get(*field),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::MissingFields { source, fields } => {
-                        let mut msg = format!("missing fields: ");
-                        let mut is_first = true;
-                        for (_, n) in fields {
-                            if !is_first {
-                                sti::write!(&mut msg, ", ");
-                            }
-
-                            is_first = false;
-                            sti::write!(&mut msg, "{}", sm.get(*n));
-                        }
 
-                        (
-                            msg,
-                            source,
-                        )
-
-
-                    },
-
-                    semantic_analysis::errors::Error::FunctionArgsMismatch { source, sig_len, call_len } => {
-                        (
-                            format!(
-                                "expected '{}' args but found '{}",
-                                sig_len,
-                                call_len,
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::NamespaceNotFound { source, namespace } => {
-                        (
-                            format!(
-                                "namespace '{}' not found",
-                                sm.get(*namespace)
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::ValueUpdateTypeMismatch { lhs, rhs, source } => {
-                        (
-                            format!(
-                                "lhs is '{}' while the rhs is '{}'",
-                                lhs.display(sm, syms),
-                                rhs.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::ContinueOutsideOfLoop(source_range) => {
-                        (
-                            format!(
-                                "continue outside of a loop",
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::BreakOutsideOfLoop(source_range) => {
-                        (
-                            format!(
-                                "break outside of a loop",
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::CantUnwrapOnGivenType(source_range, sym) => {
-                        (
-                            format!(
-                                "can't unwrap on '{}'",
-                                sym.display(sm, syms),
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::CantTryOnGivenType(source_range, sym) => {
-                        (
-                            format!(
-                                "can't try on '{}'",
-                                sym.display(sm, syms),
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::FunctionDoesntReturnAnOption { source, func_typ } => {
-                        (
-                            format!(
-                                "function returns '{}' but an option is required",
-                                func_typ.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::FunctionDoesntReturnAResult { source, func_typ } => {
-                        (
-                            format!(
-                                "function returns '{}' but a result is required",
-                                func_typ.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::FunctionReturnsAResultButTheErrIsntTheSame { source, func_source, func_err_typ, err_typ } => {
-                        (
-                            format!(
-                                "function returns a result with '{}' as an error but '{}' is required",
-                                func_err_typ.display(sm, syms),
-                                err_typ.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::ReturnAndFuncTypDiffer { source, func_source, typ, func_typ } => {
-                        (
-                            format!(
-                                "function returns '{}', but tried to return '{}'",
-                                func_typ.display(sm, syms),
-                                typ.display(sm, syms)
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::AssignIsNotLHSValue { source } => {
-                        (
-                            format!(
-                                "..is not a valid LHS value",
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::UnableToInfer(source_range, _) => {
-                        (
-                            format!(
-                                "unable to infer type",
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::InvalidRange { source, ty } => {
-                        (
-                            format!(
-                                "can't create a range out of '{}'",
-                                ty.display(sm, syms),
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::ImplOnGeneric(source_range) => {
-                        (
-                            format!(
-                                "impl on a complete generic is not supported",
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::GenericLenMismatch { source, found, expected } => {
-                        (
-                            format!(
-                                "expected '{expected}' generics but found '{found}'",
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::CantUseHoleHere { source } => {
-                        (
-                            format!(
-                                "a hole isn't supported here",
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::NameIsReservedForFunctions { source } => {
-                        (
-                            format!(
-                                "this name is reserved for special functions",
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::IndexOnNonList(source_range, sym) => {
-                        (
-                            format!(
-                                "can't inedx '{}'",
-                                sym.display(sm, syms),
-                            ),
-                            source_range,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::CallOnNonFunction { source } => {
-                        (
-                            format!(
-                                "..is not a function",
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::CallOnField { source, .. } => {
-                        (
-                            format!(
-                                "..is a field. add parenthesis around it",
-                            ),
-                            source,
-                        )
-                    },
-
-
-                    semantic_analysis::errors::Error::Bypass => return None,
-                    _ => todo!(),
-                };
+    /// Runs & checks the program at `root`, its diagnostics are
+    /// located right away since a later run may reorder the files
+    fn analyse(&mut self, root: StringIndex) -> Analysis {
+        trace!("margarine-lsp/analyse: root = {}", self.compiler.string_map.get(root));
 
+        let arena = Box::new(Arena::new());
+        // SAFETY: the arena is boxed so it doesn't move and the
+        //         analysis drops the result before the arena
+        let arena_ref = unsafe { &*(&*arena as *const Arena) };
 
+        let settings = self.settings(arena_ref, root);
+        let mut result = self.compiler.run(&settings);
 
+        trace!("{:#?}", &result.errors);
 
-                Some((
-                    span,
-                    message,
-                ))
-            })
+        let diagnostics = self.compiler.check(&mut result);
+        let diagnostics = self.encode_diagnostics(diagnostics.iter().cloned().collect());
 
-            .for_each(|d| diagnostics.push(d));
+        let index = SourceIndex::new(&result);
+        Analysis { result, index, diagnostics, _arena: arena }
+    }
 
-        self.send_diagnostics(diagnostics);
 
+    /// Calls `f` with the checked program at `root`, checking it
+    /// first if it hasn't been yet
+    fn with_analysis<T>(
+        &mut self,
+        root: StringIndex,
+        f: impl FnOnce(&mut Self, &mut CompilationResult<'static>, &SourceIndex) -> T,
+    ) -> T {
+        let mut analysis = match self.analyses.remove(&root) {
+            Some(analysis) => analysis,
+            None => self.analyse(root),
+        };
 
+        let value = f(self, &mut analysis.result, &analysis.index);
+        self.analyses.insert(root, analysis);
+        value
     }
 }
 
@@ -1119,28 +530,18 @@ impl Lsp {
                 return true;
             }
 
+            let legend = TokenType::LEGEND.iter()
+                .map(|t| json::Value::String(t))
+                .collect::<Vec<_>>();
+
             self.send_response(id, Ok(json::Value::Object(&[
                 ("capabilities", json::Value::Object(&[
                     ("positionEncoding", "utf-16".into()),
 
-                    ("textDocumentSync", 1.0.into()),
+                    ("textDocumentSync", 2.0.into()),
                     ("semanticTokensProvider", json::Value::Object(&[
                         ("legend", json::Value::Object(&[
-                            ("tokenTypes", json::Value::Array(&[
-                                "error".into(),
-                                "comment".into(),
-                                "keyword".into(),
-                                "punctuation".into(),
-                                "operator".into(),
-                                "string".into(),
-                                "number".into(),
-                                "type".into(),
-                                "parameter".into(),
-                                "variable".into(),
-                                "property".into(),
-                                "function".into(),
-                                "method".into(),
-                            ])),
+                            ("tokenTypes", json::Value::Array(&legend)),
                             ("tokenModifiers", json::Value::Array(&[])),
                         ])),
                         ("full", json::Value::Object(&[
@@ -1149,6 +550,11 @@ impl Lsp {
                     ])),
 
                     ("hoverProvider", true.into()),
+                    ("definitionProvider", true.into()),
+                    ("referencesProvider", true.into()),
+                    ("completionProvider", json::Value::Object(&[
+                        ("triggerCharacters", json::Value::Array(&[".".into(), ":".into()])),
+                    ])),
                 ])),
             ])));
 
@@ -1163,6 +569,127 @@ impl Lsp {
             }
 
 
+            "textDocument/hover" => {
+                let uri = params["textDocument"]["uri"].as_string();
+                let position = params["position"];
+                let position = Position::new(
+                    position["line"].as_number() as u32,
+                    position["character"].as_number() as u32,
+                );
+
+                trace!("uri = '{uri}', position = {position:?}");
+
+                let Some((contents, range)) = self.hover(&Url::from_str(uri).unwrap(), position)
+                else {
+                    self.send_response(id, Ok(json::Value::Null));
+                    return true;
+                };
+
+                let range = encode_range(range).to_string();
+                self.send_response(id, Ok(json::Value::Object(&[
+                    ("contents", json::Value::Object(&[
+                        ("kind", "markdown".into()),
+                        ("value", contents.as_str().into()),
+                    ])),
+                    ("range", json::Value::Encoded(&range)),
+                ])));
+            }
+
+
+            "textDocument/definition" => {
+                let uri = params["textDocument"]["uri"].as_string();
+                let position = params["position"];
+                let position = Position::new(
+                    position["line"].as_number() as u32,
+                    position["character"].as_number() as u32,
+                );
+
+                trace!("uri = '{uri}', position = {position:?}");
+
+                let Some(location) = self.definition(&Url::from_str(uri).unwrap(), position)
+                else {
+                    self.send_response(id, Ok(json::Value::Null));
+                    return true;
+                };
+
+                let location = encode_location(location).to_string();
+                self.send_response(id, Ok(json::Value::Encoded(&location)));
+            }
+
+
+            "textDocument/references" => {
+                let uri = params["textDocument"]["uri"].as_string();
+                let position = params["position"];
+                let position = Position::new(
+                    position["line"].as_number() as u32,
+                    position["character"].as_number() as u32,
+                );
+
+                let include_decl = params.get("context")
+                    .and_then(|c| c.get("includeDeclaration"))
+                    .and_then(|c| c.try_bool())
+                    .unwrap_or(false);
+
+                trace!("uri = '{uri}', position = {position:?}, include_decl = {include_decl}");
+
+                let locations = self.references(&Url::from_str(uri).unwrap(), position, include_decl);
+
+                let encoded = locations.into_iter()
+                    .map(encode_location)
+                    .collect::<serde_json::Value>()
+                    .to_string();
+
+                self.send_response(id, Ok(json::Value::Encoded(&encoded)));
+            }
+
+
+            "textDocument/completion" => {
+                let uri = params["textDocument"]["uri"].as_string();
+                let position = params["position"];
+                let position = Position::new(
+                    position["line"].as_number() as u32,
+                    position["character"].as_number() as u32,
+                );
+
+                trace!("uri = '{uri}', position = {position:?}");
+
+                let items = self.completion(&Url::from_str(uri).unwrap(), position);
+
+                let items = items.iter()
+                    .map(|item| {
+                        let mut encoded = serde_json::json!({ "label": item.label, "kind": item.kind });
+                        if let Some(detail) = &item.detail {
+                            encoded["detail"] = serde_json::json!(detail);
+                        }
+                        encoded
+                    })
+                    .collect::<Vec<_>>();
+                let encoded = serde_json::Value::Array(items).to_string();
+
+                self.send_response(id, Ok(json::Value::Encoded(&encoded)));
+            }
+
+
+            "textDocument/semanticTokens/full" => {
+                let uri = params["textDocument"]["uri"].as_string();
+                trace!("uri = '{uri}'");
+
+                let Some(data) = self.semantic_tokens(&Url::from_str(uri).unwrap())
+                else {
+                    self.send_response(id, Ok(json::Value::Null));
+                    return true;
+                };
+
+                let data = data.iter()
+                    .map(|v| json::Value::Number(*v as f64))
+                    .collect::<Vec<_>>();
+
+                self.send_response(id, Ok(json::Value::Object(&[
+                    ("data", json::Value::Array(&data)),
+                ])));
+            }
+
+
             _ => {
                 warn!("request not supported. method = '{method}'");
             }
@@ -1209,7 +736,9 @@ impl Lsp {
 
                 trace!("uri = '{path}', version = {version}");
 
-                self.on_change(version, Url::from_str(path).unwrap(), text);
+                let uri = Url::from_str(path).unwrap();
+                self.resolve_file(&uri).rope = text.into();
+                self.on_change(version, uri);
             }
 
 
@@ -1224,10 +753,11 @@ impl Lsp {
                 let version = version as u32;
 
 
-                let text = changes[0]["text"];
-                trace!("uri = '{path}', version = {version}, text = '{text}'");
+                trace!("uri = '{path}', version = {version}, changes = {}", changes.len());
 
-                self.on_change(version, Url::from_str(path).unwrap(), text.as_string());
+                let uri = Url::from_str(path).unwrap();
+                self.apply_changes(&uri, changes);
+                self.on_change(version, uri);
             }
 
 
@@ -1259,57 +789,96 @@ impl Lsp {
 
     
 
-    fn send_diagnostics(&mut self, diags: Vec<(SourceRange, String)>) {
+    /// Converts diagnostics into `Diagnostic` objects grouped by the
+    /// file they point into
+    fn encode_diagnostics(&mut self, diags: Vec<margarine::Diagnostic>) -> HashMap<Url, Vec<serde_json::Value>> {
         let mut file_diags = HashMap::new();
 
+        for diag in diags {
+            let Some(primary) = diag.primary()
+            else {
+                warn!("diagnostic '{}' has no span", diag.code);
+                continue;
+            };
 
-        let mut result = String::with_capacity(diags.len() * 64);
+            let (uri, range) = self.label_location(primary.span);
 
-        for (span, msg) in diags {
-            let (file, offset) = span.file(self.compiler.files.files());
-            let span = span.base(offset);
-            let path = Path::new(self.compiler.string_map.get(file.name()));
-            let path = path.with_extension("mar");
-            let uri = Url::from_file_path(path).unwrap();
+            let mut message = diag.message.clone();
+            if let Some(note) = &primary.message {
+                sti::write!(&mut message, "\n{note}");
+            }
 
-            if !file_diags.contains_key(&uri) {
-                file_diags.insert(uri.clone(), vec![]);
+            for note in &diag.notes {
+                sti::write!(&mut message, "\nnote: {note}");
             }
 
-            let vec = file_diags.get_mut(&uri).unwrap();
+            let related = diag.secondary().iter()
+                .map(|label| {
+                    let (uri, range) = self.label_location(label.span);
+                    serde_json::json!({
+                        "location": { "uri": uri, "range": range },
+                        "message": label.message.as_deref().unwrap_or(&diag.message),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let severity = match diag.severity {
+                margarine::Severity::Error => 1,
+                margarine::Severity::Warning => 2,
+            };
 
             trace!("file {}", uri);
-            let rope = &self.resolve_file(&uri).rope;
-            vec.push(Diagnostic::new_simple(to_range(rope, span), msg));
+            let encoded = serde_json::json!({
+                "range": range,
+                "severity": severity,
+                "code": diag.code,
+                "source": "margarine",
+                "message": message,
+                "relatedInformation": related,
+            });
+
+            file_diags.entry(uri).or_insert_with(Vec::new).push(encoded);
         }
 
+        file_diags
+    }
 
-        for (uri, _) in &self.files {
-            let diags = 
-            if let Some(diags) = file_diags.remove(uri) { diags }
-            else { vec![] };
-            trace!("margarine-lsp/send_diagnostics: uri = {uri}, len(diag) = {}", diags.len());
 
+    /// Publishes the diagnostics of every checked program for each
+    /// open file, files without any get theirs cleared
+    fn send_diagnostics(&mut self) {
+        let mut file_diags = HashMap::<&Url, Vec<&serde_json::Value>>::new();
+        for analysis in self.analyses.values() {
+            for (uri, diags) in &analysis.diagnostics {
+                file_diags.entry(uri).or_default().extend(diags);
+            }
+        }
 
-            sti::write!(&mut result, "{{\"uri\":\"{uri}\",\"diagnostics\":[");
 
-            for (i, d) in diags.iter().enumerate() {
-                if i != 0 { _ = sti::write!(&mut result, ","); }
-                sti::write!(&mut result, "{{");
-                sti::write!(&mut result, "\"range\":{{\"start\":{{\"line\":{},\"character\":{}}},\"end\":{{\"line\":{},\"character\":{}}}}}",
-                    d.range.start.line, d.range.start.character, d.range.end.line, d.range.end.character);
-                sti::write!(&mut result, ",\"message\":{:?}", d.message);
-                sti::write!(&mut result, "}}");
-            }
+        for uri in self.files.keys() {
+            let diags = file_diags.remove(uri).unwrap_or_default();
+            trace!("margarine-lsp/send_diagnostics: uri = {uri}, len(diag) = {}", diags.len());
+
 
-            sti::write!(&mut result, "]}}");
+            let result = serde_json::json!({ "uri": uri, "diagnostics": diags }).to_string();
 
             self.send_notification("textDocument/publishDiagnostics", json::Value::Encoded(&result));
+        }
+
+    }
 
-            result.clear();
 
-        }
+    /// The file and range a global diagnostic span points at
+    fn label_location(&mut self, span: SourceRange) -> (Url, Range) {
+        let (file, offset) = span.file(self.compiler.files.files());
+        let span = span.base(offset);
+        let path = Path::new(self.compiler.string_map.get(file.name()));
+        let path = path.with_extension("mar");
+        let uri = Url::from_file_path(path).unwrap();
 
+        let rope = &self.resolve_file(&uri).rope;
+        let range = to_range(rope, span);
+        (uri, range)
     }
 
 
@@ -1360,16 +929,7 @@ fn main() {
     let _a = tracing_init();
     set_panic_hook();
 
-    let arena = Box::leak(Box::new(Arena::new()));
-    let mut lsp = Lsp {
-        message: vec![],
-        initialized: false,
-        next_request_id: 1,
-        active_requests: HashMap::new(),
-        arena,
-        files: HashMap::new(),
-        compiler: Compiler::new(arena),
-    };
+    let mut lsp = Lsp::new(margarine::parse_env_preludes());
 
     
     if std::env::args().len() == 2 {
@@ -0,0 +1,269 @@
+use common::string_map::{StringIndex, StringMap};
+use margarine::{CompilationResult, SourceRange};
+use parser::nodes::{decl::{Decl, DeclId}, expr::{Expr, ExprId}};
+use ropey::Rope;
+use semantic_analysis::syms::{sym_map::SymbolId, SymbolKind};
+use tower_lsp::lsp_types::{Position, Range, Url};
+use tracing::trace;
+
+use crate::{hover::SourceIndex, position_to_offset, to_range, Lsp};
+
+
+/// The thing a name resolves to, two names refer to
+/// the same thing if their targets are equal
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    /// A local variable, identified by where it was bound
+    Local(SourceRange),
+
+    /// Anything that lives in a namespace
+    Sym(SymbolId),
+
+    /// A field or variant of a struct or an enum
+    Field(SymbolId, StringIndex),
+
+    /// A function declared in a trait
+    Method(SymbolId, StringIndex),
+}
+
+
+impl Lsp {
+    /// Where whatever is under `position` was declared
+    pub fn definition(&mut self, uri: &Url, position: Position) -> Option<(Url, Range)> {
+        trace!("margarine-lsp/definition: uri = '{uri}', position = {position:?}");
+
+        let root = self.resolve_file(uri).root;
+        self.with_analysis(root, |lsp, result, index| {
+            let offset = lsp.offset_at(result, uri, position)?;
+
+            let target = target_at(result, index, &lsp.compiler.string_map, offset)?;
+            let source = definition(result, target)?;
+            lsp.location(result, source)
+        })
+    }
+
+
+    /// Every use of whatever is under `position`, optionally
+    /// including its declaration
+    pub fn references(&mut self, uri: &Url, position: Position, include_decl: bool) -> Vec<(Url, Range)> {
+        trace!("margarine-lsp/references: uri = '{uri}', position = {position:?}");
+
+        let root = self.resolve_file(uri).root;
+        self.with_analysis(root, |lsp, result, index| lsp.references_in(result, index, uri, position, include_decl))
+    }
+
+
+    fn references_in(
+        &self, result: &mut CompilationResult, index: &SourceIndex,
+        uri: &Url, position: Position, include_decl: bool,
+    ) -> Vec<(Url, Range)> {
+        let Some(offset) = self.offset_at(result, uri, position)
+        else { return vec![] };
+
+        let Some(target) = target_at(result, index, &self.compiler.string_map, offset)
+        else { return vec![] };
+
+        let mut sources = vec![];
+        if include_decl {
+            sources.extend(definition(result, target));
+        }
+
+        let exprs = result.ast().exprs().kiter().map(|(id, _)| id).collect::<Vec<_>>();
+        for expr in exprs {
+            let Some((t, source)) = target_of(result, &self.compiler.string_map, expr)
+            else { continue };
+
+            if t == target { sources.push(source) }
+        }
+
+        sources.iter()
+            .filter_map(|source| self.location(result, *source))
+            .collect()
+    }
+
+
+    /// The global offset `position` in `uri` is at within the
+    /// checked program `result`
+    fn offset_at(&self, result: &CompilationResult, uri: &Url, position: Position) -> Option<u32> {
+        let file = self.files.get(uri)?;
+        let local_offset = position_to_offset(position, &file.rope)?;
+
+        let (_, base) = *result.file_offsets().iter().find(|f| f.0 == file.file_path)?;
+        Some(base + local_offset)
+    }
+
+
+    /// Converts a global source range into a location in whichever
+    /// file it's in, be it a module or a cached repository
+    fn location(&self, result: &CompilationResult, source: SourceRange) -> Option<(Url, Range)> {
+        let (name, base) = *result.file_offsets().iter().rev().find(|f| f.1 <= source.start())?;
+        let file = self.compiler.files.get(name)?;
+
+        let path = format!("{}.mar", self.compiler.string_map.get(name));
+        let path = std::fs::canonicalize(&path).ok()?;
+        let uri = Url::from_file_path(path).ok()?;
+
+        let rope = Rope::from_str(file.read());
+        Some((uri, to_range(&rope, source.base(base))))
+    }
+}
+
+
+/// The target of the expression under `offset` or, failing
+/// that, of the declaration whose name is under it
+fn target_at(result: &mut CompilationResult, index: &SourceIndex, string_map: &StringMap, offset: u32) -> Option<Target> {
+    let target = index.expr_at(offset).and_then(|expr| target_of(result, string_map, expr));
+    if let Some((target, _)) = target {
+        return Some(target);
+    }
+
+    let contains = |source: SourceRange| source.start() <= offset && offset <= source.end();
+
+    let ty_info = result.ty_info();
+    if let Some((sym, _)) = ty_info.sym_decls().iter().find(|(_, (_, source))| contains(*source)) {
+        return Some(Target::Sym(*sym));
+    }
+
+    for (id, (decl, _)) in result.ast().decls().kiter() {
+        match *decl {
+            Decl::Struct { fields, .. } => {
+                let Some((name, ..)) = fields.iter().find(|f| contains(f.2))
+                else { continue };
+
+                return Some(Target::Field(owner_of(result, id)?, *name));
+            },
+
+
+            Decl::Enum { mappings, .. } => {
+                let Some(mapping) = mappings.iter().find(|m| contains(m.range()))
+                else { continue };
+
+                return Some(Target::Field(owner_of(result, id)?, mapping.name()));
+            },
+
+
+            Decl::Trait { functions, .. } => {
+                let Some(sig) = functions.iter().find(|f| contains(f.source))
+                else { continue };
+
+                return Some(Target::Method(owner_of(result, id)?, sig.name));
+            },
+
+            _ => (),
+        }
+    }
+
+    None
+}
+
+
+/// The symbol `decl` declared
+fn owner_of(result: &CompilationResult, decl: DeclId) -> Option<SymbolId> {
+    result.ty_info().sym_decls().iter()
+        .find(|(_, (id, _))| *id == decl)
+        .map(|(sym, _)| *sym)
+}
+
+
+/// What `expr` refers to along with the source of the name
+/// that does the referring
+fn target_of(result: &mut CompilationResult, string_map: &StringMap, expr: ExprId) -> Option<(Target, SourceRange)> {
+    result.ty_info().exprs[expr]?;
+    let range = result.ast().range(expr);
+
+    match result.ast().expr(expr) {
+        Expr::Identifier(name, _) => {
+            let ty_info = result.ty_info();
+            let target =
+            if let Some(source) = ty_info.var(expr) { Target::Local(source) }
+            else if let Some(tr) = ty_info.trait_func(expr) { Target::Method(tr.sym()?, name) }
+            else if let Some(Some(sym)) = ty_info.ident(expr) { Target::Sym(sym) }
+            else { return None };
+
+            Some((target, name_source(string_map, range, name)))
+        },
+
+
+        Expr::AccessField { val, field_name, .. } => {
+            let source = name_source(string_map, range, field_name);
+            if let Some(tr) = result.ty_info().trait_func(expr) {
+                return Some((Target::Method(tr.sym()?, field_name), source));
+            }
+
+            result.ty_info().exprs[val]?;
+            let val_ty = result.ty_info().expr(val);
+            let val_sym = val_ty.sym(&mut result.syms).ok()?;
+            let ns = result.syms.sym_ns(val_sym);
+
+            let method = result.namespaces().get_ns(ns).get_sym(field_name)
+                .and_then(|sym| sym.ok())
+                .filter(|sym| matches!(result.syms.sym(*sym).kind(), SymbolKind::Function(_)));
+
+            match method {
+                Some(sym) => Some((Target::Sym(sym), source)),
+                None => Some((Target::Field(val_sym, field_name), source)),
+            }
+        },
+
+
+        Expr::CreateStruct { data_type, .. } => {
+            let ty = result.ty_info().expr(expr);
+            let sym = ty.sym(&mut result.syms).ok()?;
+            Some((Target::Sym(sym), data_type.range()))
+        },
+
+
+        _ => None,
+    }
+}
+
+
+/// The trailing `name` in `range`
+fn name_source(string_map: &StringMap, range: SourceRange, name: StringIndex) -> SourceRange {
+    let len = string_map.get(name).len() as u32;
+    if len == 0 || range.end() + 1 < range.start() + len { return range }
+
+    SourceRange::new(range.end() + 1 - len, range.end())
+}
+
+
+/// Where `target` was declared
+fn definition(result: &CompilationResult, target: Target) -> Option<SourceRange> {
+    let ty_info = result.ty_info();
+    let ast = result.ast();
+
+    match target {
+        Target::Local(source) => Some(source),
+
+        Target::Sym(sym) => {
+            let (decl, source) = ty_info.sym_decl(sym)?;
+
+            // modules point at their file rather than where they were imported
+            if let Decl::Module { body, .. } = ast.decl(decl) {
+                if body.range() != SourceRange::ZERO {
+                    let start = body.range().start();
+                    return Some(SourceRange::new(start, start));
+                }
+            }
+
+            Some(source)
+        },
+
+        Target::Field(sym, name) => {
+            let (decl, _) = ty_info.sym_decl(sym)?;
+            match ast.decl(decl) {
+                Decl::Struct { fields, .. } => fields.iter().find(|f| f.0 == name).map(|f| f.2),
+                Decl::Enum { mappings, .. } => mappings.iter().find(|m| m.name() == name).map(|m| m.range()),
+                _ => None,
+            }
+        },
+
+        Target::Method(sym, name) => {
+            let (decl, _) = ty_info.sym_decl(sym)?;
+            let Decl::Trait { functions, .. } = ast.decl(decl)
+            else { return None };
+
+            functions.iter().find(|f| f.name == name).map(|f| f.source)
+        },
+    }
+}
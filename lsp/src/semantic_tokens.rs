@@ -0,0 +1,412 @@
+use common::string_map::StringIndex;
+use lexer::{Literal, TokenKind};
+use margarine::{CompilationResult, FileData, SourceRange};
+use parser::{dt::{DataType, DataTypeKind}, nodes::{decl::{Decl, DeclGeneric, FunctionArgument, FunctionSignature}, expr::Expr, stmt::Stmt}};
+use ropey::Rope;
+use semantic_analysis::syms::SymbolKind;
+use tower_lsp::lsp_types::Url;
+use tracing::trace;
+
+use crate::{offset_to_position, Lsp};
+
+
+/// Indices into the `tokenTypes` legend sent in `initialize`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenType {
+    Error,
+    Comment,
+    Keyword,
+    Punctuation,
+    Operator,
+    String,
+    Number,
+    Type,
+    Parameter,
+    Variable,
+    Property,
+    Function,
+    Method,
+}
+
+
+impl TokenType {
+    pub const LEGEND : &[&str] = &[
+        "error",
+        "comment",
+        "keyword",
+        "punctuation",
+        "operator",
+        "string",
+        "number",
+        "type",
+        "parameter",
+        "variable",
+        "property",
+        "function",
+        "method",
+    ];
+}
+
+
+/// A classification the type checker made for the identifier
+/// called `name` somewhere within `range`
+struct Mark {
+    range: SourceRange,
+    name: StringIndex,
+    ty: TokenType,
+}
+
+
+impl Lsp {
+    /// The `textDocument/semanticTokens/full` data for `uri`
+    pub fn semantic_tokens(&mut self, uri: &Url) -> Option<Vec<u32>> {
+        trace!("margarine-lsp/semantic-tokens: uri = '{uri}'");
+
+        let root = self.resolve_file(uri).root;
+        self.with_analysis(root, |lsp, result, _| lsp.semantic_tokens_in(result, uri))
+    }
+
+
+    fn semantic_tokens_in(&mut self, result: &mut CompilationResult, uri: &Url) -> Option<Vec<u32>> {
+        let file = self.files.get(uri)?;
+
+        let (_, base) = *result.file_offsets().iter().find(|f| f.0 == file.file_path)?;
+
+        let fd = FileData::new(file.rope.to_string(), file.file_path, margarine::Extension::Mar);
+        let (tokens, _) = margarine::lex(&fd, &mut self.compiler.string_map, base);
+
+        let mut marks = vec![];
+        collect_marks(result, &mut marks);
+        marks.sort_by_key(|m| m.range.start());
+
+        // resolve every mark to the first matching identifier in its range
+        let mut resolved = vec![None; tokens.len()];
+        for mark in &marks {
+            let first = tokens.partition_point(|t| t.range().start() < mark.range.start());
+            for (i, token) in tokens.iter().enumerate().skip(first) {
+                if token.range().start() > mark.range.end() { break }
+                if token.kind() != TokenKind::Identifier(mark.name) { continue }
+
+                if resolved[i].is_none() { resolved[i] = Some(mark.ty) }
+                break;
+            }
+        }
+
+        let mut data = vec![];
+        let (mut prev_line, mut prev_char) = (0, 0);
+        for (token, resolved) in tokens.iter().zip(resolved) {
+            let ty = match token.kind() {
+                TokenKind::EndOfFile => continue,
+
+                TokenKind::Identifier(_) => resolved.unwrap_or(TokenType::Variable),
+                TokenKind::Keyword(_) => TokenType::Keyword,
+                TokenKind::Doc(_) => TokenType::Comment,
+                TokenKind::Error(_) => TokenType::Error,
+
+                TokenKind::Literal(Literal::Bool(_)) => TokenType::Keyword,
+                TokenKind::Literal(Literal::String(_)) => TokenType::String,
+                TokenKind::Literal(Literal::Char(_)) => TokenType::String,
+                | TokenKind::InterpolatedHead(_)
+                | TokenKind::InterpolatedMiddle(_)
+                | TokenKind::InterpolatedTail(_) => TokenType::String,
+                TokenKind::Literal(Literal::Integer(_)) => TokenType::Number,
+                TokenKind::Literal(Literal::Float(_)) => TokenType::Number,
+
+                | TokenKind::LeftParenthesis
+                | TokenKind::RightParenthesis
+                | TokenKind::LeftBracket
+                | TokenKind::RightBracket
+                | TokenKind::LeftSquare
+                | TokenKind::RightSquare
+                | TokenKind::Colon
+                | TokenKind::DoubleColon
+                | TokenKind::SemiColon
+                | TokenKind::Comma
+                | TokenKind::Dot
+                | TokenKind::Underscore
+                | TokenKind::At
+                | TokenKind::DollarSign => TokenType::Punctuation,
+
+                _ => TokenType::Operator,
+            };
+
+            let range = token.range().base(base);
+            for (line, start, len) in split_lines(&file.rope, range) {
+                let delta_char = if line == prev_line { start - prev_char } else { start };
+                data.extend_from_slice(&[line - prev_line, delta_char, len, ty as u32, 0]);
+
+                prev_line = line;
+                prev_char = start;
+            }
+        }
+
+        Some(data)
+    }
+}
+
+
+/// Splits an inclusive source range into `(line, start, length)`
+/// triples in UTF-16 code units since tokens can't span lines
+fn split_lines(rope: &Rope, range: SourceRange) -> Vec<(u32, u32, u32)> {
+    let Some(start) = offset_to_position(range.start(), rope)
+    else { return vec![] };
+
+    let Some(end) = offset_to_position(range.end(), rope)
+    else { return vec![] };
+
+    let mut lines = Vec::with_capacity((end.line - start.line + 1) as usize);
+    for line in start.line..=end.line {
+        let from = if line == start.line { start.character } else { 0 };
+        let to = if line == end.line { end.character + 1 }
+                 else {
+                    let text = rope.line(line as usize);
+                    let len : usize = text.chars().map(|c| c.len_utf16()).sum();
+                    (len as u32).saturating_sub(1)
+                 };
+
+        if to > from { lines.push((line, from, to - from)) }
+    }
+
+    lines
+}
+
+
+fn collect_marks(result: &mut CompilationResult, marks: &mut Vec<Mark>) {
+    let ast = result.ast();
+
+    // the bodies function arguments are visible in, used to tell
+    // parameters apart from other identifiers
+    let mut scopes : Vec<(SourceRange, &[FunctionArgument])> = vec![];
+
+    for (_, (decl, _)) in ast.decls().kiter() {
+        match *decl {
+            Decl::Struct { name, header, fields, generics, .. } => {
+                decl_header(name, header, generics, marks);
+                for (name, dt, range) in fields {
+                    marks.push(Mark { range: *range, name: *name, ty: TokenType::Property });
+                    data_type(*dt, marks);
+                }
+            },
+
+
+            Decl::Enum { name, header, mappings, generics, .. } => {
+                decl_header(name, header, generics, marks);
+                for mapping in mappings {
+                    marks.push(Mark { range: mapping.range(), name: mapping.name(), ty: TokenType::Property });
+                    data_type(*mapping.data_type(), marks);
+                }
+            },
+
+
+            Decl::Function { sig, body, .. } => {
+                signature(sig, TokenType::Function, marks);
+                scopes.push((body.range(), sig.arguments));
+            },
+
+
+            Decl::Impl { data_type: dt, .. } => data_type(dt, marks),
+
+
+            Decl::ImplTrait { trait_name, data_type: dt, gens, header, .. } => {
+                data_type(trait_name, marks);
+                data_type(dt, marks);
+                for g in gens {
+                    marks.push(Mark { range: header, name: g.name(), ty: TokenType::Type });
+                }
+            },
+
+
+            Decl::Trait { name, header, generics, supertraits, functions, defaults, types, consts, .. } => {
+                marks.push(Mark { range: header, name, ty: TokenType::Type });
+                for g in generics {
+                    marks.push(Mark { range: header, name: g.name(), ty: TokenType::Type });
+                }
+
+                for (name, range) in types {
+                    marks.push(Mark { range: *range, name: *name, ty: TokenType::Type });
+                }
+
+                for (name, dt, range) in consts {
+                    marks.push(Mark { range: *range, name: *name, ty: TokenType::Variable });
+                    data_type(*dt, marks);
+                }
+
+                for dt in supertraits {
+                    data_type(*dt, marks);
+                }
+
+                // default methods are marked as the functions they're parsed into
+                for sig in functions {
+                    let has_default = defaults.iter().any(|decl| {
+                        matches!(ast.decl(*decl), Decl::Function { sig: default, .. } if default.name == sig.name)
+                    });
+
+                    if !has_default {
+                        signature(*sig, TokenType::Method, marks);
+                    }
+                }
+            },
+
+
+            Decl::Alias { name, header, data_type: dt, .. } => {
+                marks.push(Mark { range: header, name, ty: TokenType::Type });
+                data_type(dt, marks);
+            },
+
+
+            Decl::Extern { functions, .. } => {
+                for func in functions {
+                    marks.push(Mark { range: func.range(), name: func.name(), ty: TokenType::Function });
+                    for arg in func.args() {
+                        marks.push(Mark { range: arg.range(), name: arg.name(), ty: TokenType::Parameter });
+                        data_type(arg.data_type(), marks);
+                    }
+
+                    data_type(func.return_type(), marks);
+                }
+            },
+
+
+            Decl::Module { name, header, .. } => {
+                marks.push(Mark { range: header, name, ty: TokenType::Type });
+            },
+
+
+            | Decl::Const { data_type: dt, .. }
+            | Decl::Static { data_type: dt, .. } => data_type(dt, marks),
+
+
+            _ => (),
+        }
+    }
+
+
+    for (_, (stmt, _)) in ast.stmts().kiter() {
+        match *stmt {
+            Stmt::Variable { hint: Some(hint), .. } => data_type(hint, marks),
+            _ => (),
+        }
+    }
+
+
+    // copied out since deciding on methods needs `syms` mutably
+    let exprs = ast.exprs().kiter()
+        .map(|(id, (expr, range))| (id, *expr, *range))
+        .collect::<Vec<_>>();
+
+    for (id, expr, range) in exprs {
+        match expr {
+            Expr::Identifier(name, gens) => {
+                gens.iter().flat_map(|g| g.iter()).for_each(|g| data_type(*g, marks));
+
+                let is_function = match result.ty_info().ident(id) {
+                    Some(Some(sym)) => matches!(result.syms.sym(sym).kind(), SymbolKind::Function(_)),
+                    _ => false,
+                };
+
+                let is_param = scopes.iter().any(|(scope, args)| {
+                    scope.start() <= range.start() && range.end() <= scope.end()
+                    && args.iter().any(|a| a.name() == name)
+                });
+
+                let ty = if is_function { TokenType::Function }
+                         else if is_param { TokenType::Parameter }
+                         else { TokenType::Variable };
+
+                marks.push(Mark { range, name, ty });
+            },
+
+
+            Expr::AccessField { val, field_name, gens } => {
+                gens.iter().flat_map(|g| g.iter()).for_each(|g| data_type(*g, marks));
+
+                let is_method = result.ty_info().trait_func(id).is_some()
+                    || (result.ty_info().exprs[id].is_some()
+                        && result.ty_info().expr(id).sym(&mut result.syms)
+                            .map(|sym| matches!(result.syms.sym(sym).kind(), SymbolKind::Function(_)))
+                            .unwrap_or(false));
+
+                let ty = if is_method { TokenType::Method } else { TokenType::Property };
+                let after_val = result.ast().range(val).end() + 1;
+                marks.push(Mark { range: SourceRange::new(after_val, range.end()), name: field_name, ty });
+            },
+
+
+            Expr::CreateStruct { data_type: dt, fields } => {
+                data_type(dt, marks);
+                for (name, range, _) in fields {
+                    marks.push(Mark { range: *range, name: *name, ty: TokenType::Property });
+                }
+            },
+
+
+            Expr::Closure { args, .. } => {
+                for (name, dt, _, range) in args {
+                    marks.push(Mark { range: *range, name: *name, ty: TokenType::Parameter });
+                    if let Some(dt) = dt { data_type(*dt, marks) }
+                }
+            },
+
+
+            Expr::WithinNamespace { namespace, namespace_source, .. } => {
+                marks.push(Mark { range: namespace_source, name: namespace, ty: TokenType::Type });
+            },
+
+
+            | Expr::WithinTypeNamespace { namespace: dt, .. }
+            | Expr::AsCast { data_type: dt, .. } => data_type(dt, marks),
+
+
+            _ => (),
+        }
+    }
+}
+
+
+fn decl_header(name: StringIndex, header: SourceRange, generics: &[DeclGeneric], marks: &mut Vec<Mark>) {
+    marks.push(Mark { range: header, name, ty: TokenType::Type });
+    for g in generics {
+        marks.push(Mark { range: header, name: g.name(), ty: TokenType::Type });
+        g.bounds().iter().for_each(|b| data_type(*b, marks));
+    }
+}
+
+
+fn signature(sig: FunctionSignature, ty: TokenType, marks: &mut Vec<Mark>) {
+    marks.push(Mark { range: sig.source, name: sig.name, ty });
+    for g in sig.generics {
+        marks.push(Mark { range: sig.source, name: g.name(), ty: TokenType::Type });
+        g.bounds().iter().for_each(|b| data_type(*b, marks));
+    }
+
+    for arg in sig.arguments {
+        marks.push(Mark { range: arg.range(), name: arg.name(), ty: TokenType::Parameter });
+        data_type(arg.data_type(), marks);
+    }
+
+    data_type(sig.return_type, marks);
+}
+
+
+fn data_type(dt: DataType, marks: &mut Vec<Mark>) {
+    match dt.kind() {
+        DataTypeKind::CustomType(name, gens) => {
+            marks.push(Mark { range: dt.range(), name, ty: TokenType::Type });
+            gens.iter().for_each(|g| data_type(*g, marks));
+        },
+
+        DataTypeKind::Within(_, inner) => data_type(*inner, marks),
+        DataTypeKind::List(inner) => data_type(*inner, marks),
+        DataTypeKind::Dyn(inner) => data_type(*inner, marks),
+        DataTypeKind::Tuple(fields) => fields.iter().for_each(|f| data_type(f.1, marks)),
+
+        DataTypeKind::Fn(args, ret) => {
+            args.iter().for_each(|a| data_type(a.data_type(), marks));
+            data_type(*ret, marks);
+        },
+
+        | DataTypeKind::Unit
+        | DataTypeKind::Never
+        | DataTypeKind::Hole => (),
+    }
+}
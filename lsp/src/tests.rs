@@ -0,0 +1,251 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use tower_lsp::lsp_types::{CompletionItemKind, Position, Range, Url};
+
+use crate::Lsp;
+
+
+/// Writes `files` into a fresh directory and opens each of them,
+/// the first one is the root of the program
+fn open(files: &[(&str, &str)]) -> (Lsp, Vec<Url>) {
+    static NEXT : AtomicU32 = AtomicU32::new(0);
+
+    let dir = std::env::temp_dir()
+        .join(format!("margarine-lsp-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir = std::fs::canonicalize(dir).unwrap();
+
+    let uris = files.iter()
+        .map(|(name, text)| {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, text).unwrap();
+            Url::from_file_path(path).unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let mut lsp = Lsp::new(vec![]);
+    for uri in &uris {
+        lsp.on_change(0, uri.clone());
+    }
+
+    (lsp, uris)
+}
+
+
+/// The position of the `nth` occurrence of `needle` in `text`,
+/// its column in UTF-16 code units
+#[track_caller]
+fn at(text: &str, needle: &str, nth: usize) -> Position {
+    let offset = text.match_indices(needle).nth(nth)
+        .unwrap_or_else(|| panic!("'{needle}' doesn't occur {} times", nth + 1)).0;
+
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = text[..offset].matches('\n').count();
+    let character = text[line_start..offset].encode_utf16().count();
+    Position::new(line as u32, character as u32)
+}
+
+
+#[test]
+fn hover_shows_signatures_and_docs() {
+    let source = "/// Adds two numbers\n\
+                  fn add(a: int, b: int): int { a + b }\n\
+                  fn main() { let total = add(1, 2); let twice = total * 2; }";
+    let (mut lsp, uris) = open(&[("main.mar", source)]);
+
+    let (text, range) = lsp.hover(&uris[0], at(source, "add", 1)).unwrap();
+    assert_eq!(text, "```margarine\nfn add(a: int, b: int): int\n```\n---\nAdds two numbers");
+    assert_eq!(range, Range::new(Position::new(2, 24), Position::new(2, 27)));
+
+    let (text, range) = lsp.hover(&uris[0], at(source, "total", 1)).unwrap();
+    assert_eq!(text, "```margarine\ntotal: int\n```");
+    assert_eq!(range, Range::new(Position::new(2, 47), Position::new(2, 52)));
+
+    let (text, _) = lsp.hover(&uris[0], at(source, "a + b", 0)).unwrap();
+    assert_eq!(text, "```margarine\na: int\n```");
+
+    // the innermost expression, not the call around it
+    let (text, range) = lsp.hover(&uris[0], at(source, "2)", 0)).unwrap();
+    assert_eq!(text, "```margarine\nint\n```");
+    assert_eq!(range, Range::new(Position::new(2, 31), Position::new(2, 32)));
+
+    assert!(lsp.hover(&uris[0], at(source, "fn", 0)).is_none());
+}
+
+
+#[test]
+fn semantic_token_legend_matches_token_types() {
+    use crate::semantic_tokens::TokenType as T;
+
+    let types = [
+        (T::Error, "error"), (T::Comment, "comment"), (T::Keyword, "keyword"),
+        (T::Punctuation, "punctuation"), (T::Operator, "operator"), (T::String, "string"),
+        (T::Number, "number"), (T::Type, "type"), (T::Parameter, "parameter"),
+        (T::Variable, "variable"), (T::Property, "property"), (T::Function, "function"),
+        (T::Method, "method"),
+    ];
+
+    assert_eq!(T::LEGEND.len(), types.len());
+    for (ty, name) in types {
+        assert_eq!(T::LEGEND[ty as usize], name);
+    }
+}
+
+
+#[test]
+fn semantic_tokens_are_relative_and_utf16() {
+    let source = "fn add(a: int): int { a }\n\
+                  fn main() { let s = \"héllo\"; add(1); }";
+    let (mut lsp, uris) = open(&[("main.mar", source)]);
+
+    let data = lsp.semantic_tokens(&uris[0]).unwrap();
+    assert_eq!(data.len() % 5, 0);
+
+    // (line, start, length, type) with the deltas undone
+    let mut tokens = vec![];
+    let (mut line, mut start) = (0, 0);
+    for token in data.chunks(5) {
+        if token[0] != 0 { start = 0 }
+        line += token[0];
+        start += token[1];
+        assert_eq!(token[4], 0, "no modifiers are sent");
+        tokens.push((line, start, token[2], crate::semantic_tokens::TokenType::LEGEND[token[3] as usize]));
+    }
+
+    assert_eq!(tokens, vec![
+        (0, 0, 2, "keyword"), (0, 3, 3, "function"), (0, 6, 1, "punctuation"),
+        (0, 7, 1, "parameter"), (0, 8, 1, "punctuation"), (0, 10, 3, "type"),
+        (0, 13, 1, "punctuation"), (0, 14, 1, "punctuation"), (0, 16, 3, "type"),
+        (0, 20, 1, "punctuation"), (0, 22, 1, "parameter"), (0, 24, 1, "punctuation"),
+
+        (1, 0, 2, "keyword"), (1, 3, 4, "function"), (1, 7, 1, "punctuation"),
+        (1, 8, 1, "punctuation"), (1, 10, 1, "punctuation"), (1, 12, 3, "keyword"),
+        (1, 16, 1, "variable"), (1, 18, 1, "operator"),
+        // `é` is two bytes but one UTF-16 code unit
+        (1, 20, 7, "string"), (1, 27, 1, "punctuation"),
+        (1, 29, 3, "function"), (1, 32, 1, "punctuation"), (1, 33, 1, "number"),
+        (1, 34, 1, "punctuation"), (1, 35, 1, "punctuation"), (1, 37, 1, "punctuation"),
+    ]);
+}
+
+
+#[test]
+fn definition_and_references_cross_files() {
+    let main = "mod util;\n\
+                fn main() { let x = util::double(2); let y = util::double(x); }";
+    let util = "pub fn double(n: int): int { n * 2 }";
+    let (mut lsp, uris) = open(&[("main.mar", main), ("main/util.mar", util)]);
+
+    let definition = lsp.definition(&uris[0], at(main, "double", 1));
+    // the header of the function
+    let header = Range::new(Position::new(0, 4), Position::new(0, 26));
+    assert_eq!(definition, Some((uris[1].clone(), header)));
+
+    let references = lsp.references(&uris[0], at(main, "double", 0), true);
+    assert_eq!(references, vec![
+        (uris[1].clone(), header),
+        (uris[0].clone(), Range::new(Position::new(1, 26), Position::new(1, 32))),
+        (uris[0].clone(), Range::new(Position::new(1, 51), Position::new(1, 57))),
+    ]);
+
+    // a parameter is declared by its whole `n: int`
+    let references = lsp.references(&uris[1], at(util, "n *", 0), true);
+    assert_eq!(references, vec![
+        (uris[1].clone(), Range::new(Position::new(0, 14), Position::new(0, 20))),
+        (uris[1].clone(), Range::new(Position::new(0, 29), Position::new(0, 30))),
+    ]);
+}
+
+
+/// The labels & kinds completed right after `needle`, sorted
+fn complete(lsp: &mut Lsp, uri: &Url, text: &str, needle: &str) -> Vec<(String, CompletionItemKind)> {
+    let mut position = at(text, needle, 0);
+    position.character += needle.encode_utf16().count() as u32;
+
+    let mut items = lsp.completion(uri, position).into_iter()
+        .map(|item| (item.label, item.kind))
+        .collect::<Vec<_>>();
+
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    items
+}
+
+
+#[test]
+fn completion_contexts() {
+    let pair = "struct Pair<T> { a: T, b: T }\n\
+                impl<T> Pair<T> {\n\
+                    fn new(a: T, b: T): Pair<T> { Pair { a: a, b: b } }\n\
+                    fn swap(self): Pair<T> { Pair { a: self.b, b: self.a } }\n\
+                }\n";
+
+    let field = format!("{pair}fn main() {{ let p = Pair::<int>::new(1, 2); p. }}");
+    let qualified = format!("{pair}fn main() {{ let p = Pair:: }}");
+    let generic = format!("{pair}fn main() {{ let p = Pair::<int>:: }}");
+    let scope = format!("{pair}fn main() {{ let count = 1; let p = co }}");
+
+    use CompletionItemKind as K;
+
+    let members = |kind| vec![("new".to_string(), kind), ("swap".to_string(), kind)];
+    let mut fields = vec![("a".to_string(), K::FIELD), ("b".to_string(), K::FIELD)];
+    fields.extend(members(K::METHOD));
+
+    let cases = [
+        (&field, "p.", fields),
+        (&qualified, "Pair::", members(K::FUNCTION)),
+        (&generic, "Pair::<int>::", members(K::FUNCTION)),
+    ];
+
+    for (source, needle, expected) in cases {
+        let (mut lsp, uris) = open(&[("main.mar", source)]);
+        assert_eq!(complete(&mut lsp, &uris[0], source, needle), expected, "{needle}");
+    }
+
+    // everything in scope, which includes all of `core`
+    let (mut lsp, uris) = open(&[("main.mar", &scope)]);
+    let items = complete(&mut lsp, &uris[0], &scope, "= co");
+    assert!(items.contains(&("count".to_string(), K::VARIABLE)), "{items:?}");
+    assert!(items.contains(&("Pair".to_string(), K::STRUCT)), "{items:?}");
+    assert!(items.contains(&("main".to_string(), K::FUNCTION)), "{items:?}");
+    assert!(!items.iter().any(|item| item.0 == "swap" || item.0 == "p"), "{items:?}");
+}
+
+
+/// Applies the `contentChanges` of a `didChange` to `uri`
+fn edit(lsp: &mut Lsp, uri: &Url, changes: &str) -> String {
+    let arena = crate::Arena::new();
+    let changes = crate::json::parse(&arena, changes.as_bytes()).unwrap();
+    lsp.apply_changes(uri, changes.as_array());
+    lsp.files[uri].rope.to_string()
+}
+
+
+#[test]
+fn incremental_edits_count_utf16() {
+    let source = "fn main() {\n    let s = \"héllo 😀 wörld\";\n}";
+    let (mut lsp, uris) = open(&[("main.mar", source)]);
+
+    // `é` is one code unit, `😀` is two
+    let text = edit(&mut lsp, &uris[0], r#"[
+        { "range": { "start": { "line": 1, "character": 23 }, "end": { "line": 1, "character": 24 } }, "text": "o" }
+    ]"#);
+    assert_eq!(text, "fn main() {\n    let s = \"héllo 😀 world\";\n}");
+
+    // changes apply one after another
+    let text = edit(&mut lsp, &uris[0], r#"[
+        { "range": { "start": { "line": 1, "character": 18 }, "end": { "line": 1, "character": 22 } }, "text": "→" },
+        { "range": { "start": { "line": 1, "character": 14 }, "end": { "line": 1, "character": 15 } }, "text": "e" }
+    ]"#);
+    assert_eq!(text, "fn main() {\n    let s = \"hello→world\";\n}");
+
+    // one that's out of bounds is skipped, the rest still apply
+    let text = edit(&mut lsp, &uris[0], r#"[
+        { "range": { "start": { "line": 7, "character": 0 }, "end": { "line": 7, "character": 1 } }, "text": "x" },
+        { "range": { "start": { "line": 2, "character": 1 }, "end": { "line": 2, "character": 1 } }, "text": "\n" }
+    ]"#);
+    assert_eq!(text, "fn main() {\n    let s = \"hello→world\";\n}\n");
+
+    let text = edit(&mut lsp, &uris[0], r#"[{ "text": "fn main() {}" }]"#);
+    assert_eq!(text, "fn main() {}");
+}
@@ -56,6 +56,27 @@ impl FileData {
 
     #[inline(always)]
     pub fn extension(&self) -> Extension { self.extension }
+
+
+    /// The name of the file, with its extension
+    pub fn file_name(&self, string_map: &StringMap) -> String {
+        let ext = self.extension.read(string_map);
+        let name = string_map.get(self.name);
+        if ext.is_empty() { name.to_string() } else { format!("{name}.{ext}") }
+    }
+
+
+    /// The 1-based line and column, in characters, of `offset`
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let text = self.read();
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) { offset -= 1 }
+
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+        (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+    }
 }
 
 
@@ -86,6 +86,57 @@ impl<'str> StringMap<'str> {
     pub const BUILTIN_LIST_ITER : StringIndex = StringIndex(66);
     pub const BUILTIN_LIST_ITER_NEXT : StringIndex = StringIndex(67);
     pub const BUILTIN_FLOAT_SQRT : StringIndex = StringIndex(68);
+
+    pub const I8    : StringIndex = StringIndex(69);
+    pub const I16   : StringIndex = StringIndex(70);
+    pub const I32   : StringIndex = StringIndex(71);
+    pub const U16   : StringIndex = StringIndex(72);
+    pub const U32   : StringIndex = StringIndex(73);
+    pub const U64   : StringIndex = StringIndex(74);
+    pub const USIZE : StringIndex = StringIndex(75);
+    pub const F32   : StringIndex = StringIndex(76);
+    pub const I64_ALIAS : StringIndex = StringIndex(77);
+    pub const U8_ALIAS  : StringIndex = StringIndex(78);
+    pub const F64_ALIAS : StringIndex = StringIndex(79);
+
+    pub const WRAPPING_ADD : StringIndex = StringIndex(80);
+    pub const WRAPPING_SUB : StringIndex = StringIndex(81);
+    pub const WRAPPING_MUL : StringIndex = StringIndex(82);
+    pub const CHECKED_ADD  : StringIndex = StringIndex(83);
+    pub const CHECKED_SUB  : StringIndex = StringIndex(84);
+    pub const CHECKED_MUL  : StringIndex = StringIndex(85);
+    pub const CHECKED_DIV  : StringIndex = StringIndex(86);
+
+    pub const CHAR         : StringIndex = StringIndex(87);
+    pub const CHARS        : StringIndex = StringIndex(88);
+    pub const CHAR_INDICES : StringIndex = StringIndex(89);
+    pub const STR_CHARS        : StringIndex = StringIndex(90);
+    pub const STR_CHAR_INDICES : StringIndex = StringIndex(91);
+    pub const OFFSET       : StringIndex = StringIndex(92);
+    pub const STR_CONCAT   : StringIndex = StringIndex(93);
+
+    pub const ADD_TRAIT    : StringIndex = StringIndex(94);
+    pub const ADD_FUNC     : StringIndex = StringIndex(95);
+    pub const SUB_TRAIT    : StringIndex = StringIndex(96);
+    pub const SUB_FUNC     : StringIndex = StringIndex(97);
+    pub const MUL_TRAIT    : StringIndex = StringIndex(98);
+    pub const MUL_FUNC     : StringIndex = StringIndex(99);
+    pub const DIV_TRAIT    : StringIndex = StringIndex(100);
+    pub const DIV_FUNC     : StringIndex = StringIndex(101);
+    pub const REM_TRAIT    : StringIndex = StringIndex(102);
+    pub const REM_FUNC     : StringIndex = StringIndex(103);
+    pub const ORD_TRAIT    : StringIndex = StringIndex(104);
+    pub const ORD_FUNC     : StringIndex = StringIndex(105);
+    pub const NEG_TRAIT    : StringIndex = StringIndex(106);
+    pub const NEG_FUNC     : StringIndex = StringIndex(107);
+    pub const INDEX_TRAIT  : StringIndex = StringIndex(108);
+    pub const INDEX_FUNC   : StringIndex = StringIndex(109);
+
+    pub const DYN          : StringIndex = StringIndex(110);
+    pub const ITERATOR_TRAIT : StringIndex = StringIndex(111);
+    pub const ITEM           : StringIndex = StringIndex(112);
+    pub const HASH_TRAIT     : StringIndex = StringIndex(113);
+    pub const TO_STR         : StringIndex = StringIndex(114);
  
     #[inline(always)]
     pub fn new(arena: &'str Arena) -> Self {
@@ -173,6 +224,52 @@ impl<'str> StringMap<'str> {
         assert_eq!(s.insert("$list_iter"), Self::BUILTIN_LIST_ITER);
         assert_eq!(s.insert("$list_iter_next"), Self::BUILTIN_LIST_ITER_NEXT);
         assert_eq!(s.insert("$float_sqrt"), Self::BUILTIN_FLOAT_SQRT);
+        assert_eq!(s.insert("i8"), Self::I8);
+        assert_eq!(s.insert("i16"), Self::I16);
+        assert_eq!(s.insert("i32"), Self::I32);
+        assert_eq!(s.insert("u16"), Self::U16);
+        assert_eq!(s.insert("u32"), Self::U32);
+        assert_eq!(s.insert("u64"), Self::U64);
+        assert_eq!(s.insert("usize"), Self::USIZE);
+        assert_eq!(s.insert("f32"), Self::F32);
+        assert_eq!(s.insert("i64"), Self::I64_ALIAS);
+        assert_eq!(s.insert("u8"), Self::U8_ALIAS);
+        assert_eq!(s.insert("f64"), Self::F64_ALIAS);
+        assert_eq!(s.insert("wrapping_add"), Self::WRAPPING_ADD);
+        assert_eq!(s.insert("wrapping_sub"), Self::WRAPPING_SUB);
+        assert_eq!(s.insert("wrapping_mul"), Self::WRAPPING_MUL);
+        assert_eq!(s.insert("checked_add"), Self::CHECKED_ADD);
+        assert_eq!(s.insert("checked_sub"), Self::CHECKED_SUB);
+        assert_eq!(s.insert("checked_mul"), Self::CHECKED_MUL);
+        assert_eq!(s.insert("checked_div"), Self::CHECKED_DIV);
+        assert_eq!(s.insert("char"), Self::CHAR);
+        assert_eq!(s.insert("Chars"), Self::CHARS);
+        assert_eq!(s.insert("CharIndices"), Self::CHAR_INDICES);
+        assert_eq!(s.insert("chars"), Self::STR_CHARS);
+        assert_eq!(s.insert("char_indices"), Self::STR_CHAR_INDICES);
+        assert_eq!(s.insert("offset"), Self::OFFSET);
+        assert_eq!(s.insert("$str_concat"), Self::STR_CONCAT);
+        assert_eq!(s.insert("Add"), Self::ADD_TRAIT);
+        assert_eq!(s.insert("add"), Self::ADD_FUNC);
+        assert_eq!(s.insert("Sub"), Self::SUB_TRAIT);
+        assert_eq!(s.insert("sub"), Self::SUB_FUNC);
+        assert_eq!(s.insert("Mul"), Self::MUL_TRAIT);
+        assert_eq!(s.insert("mul"), Self::MUL_FUNC);
+        assert_eq!(s.insert("Div"), Self::DIV_TRAIT);
+        assert_eq!(s.insert("div"), Self::DIV_FUNC);
+        assert_eq!(s.insert("Rem"), Self::REM_TRAIT);
+        assert_eq!(s.insert("rem"), Self::REM_FUNC);
+        assert_eq!(s.insert("Ord"), Self::ORD_TRAIT);
+        assert_eq!(s.insert("cmp"), Self::ORD_FUNC);
+        assert_eq!(s.insert("Neg"), Self::NEG_TRAIT);
+        assert_eq!(s.insert("neg"), Self::NEG_FUNC);
+        assert_eq!(s.insert("Index"), Self::INDEX_TRAIT);
+        assert_eq!(s.insert("index"), Self::INDEX_FUNC);
+        assert_eq!(s.insert("dyn"), Self::DYN);
+        assert_eq!(s.insert("Iterator"), Self::ITERATOR_TRAIT);
+        assert_eq!(s.insert("Item"), Self::ITEM);
+        assert_eq!(s.insert("Hash"), Self::HASH_TRAIT);
+        assert_eq!(s.insert("to_str"), Self::TO_STR);
         s
     }
 
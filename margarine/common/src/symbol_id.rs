@@ -40,56 +40,77 @@ impl SymbolId {
     pub const BUILTIN_LIST_ITER : Self = Self(40);
     pub const BUILTIN_LIST_ITER_NEXT : Self = Self(41);
     pub const BUILTIN_FLOAT_SQRT : Self = Self(42);
+    pub const I8    : Self = Self(43);
+    pub const I16   : Self = Self(44);
+    pub const I32   : Self = Self(45);
+    pub const U16   : Self = Self(46);
+    pub const U32   : Self = Self(47);
+    pub const U64   : Self = Self(48);
+    pub const USIZE : Self = Self(49);
+    pub const F32   : Self = Self(50);
+    pub const WRAPPING_ADD : Self = Self(51);
+    pub const WRAPPING_SUB : Self = Self(52);
+    pub const WRAPPING_MUL : Self = Self(53);
+    pub const CHECKED_ADD  : Self = Self(54);
+    pub const CHECKED_SUB  : Self = Self(55);
+    pub const CHECKED_MUL  : Self = Self(56);
+    pub const CHECKED_DIV  : Self = Self(57);
+    pub const CHAR         : Self = Self(58);
+    pub const TUPLE_PAIR   : Self = Self(59);
+    pub const CHARS        : Self = Self(60);
+    pub const CHAR_INDICES : Self = Self(61);
+    pub const STR_CHARS        : Self = Self(62);
+    pub const STR_CHAR_INDICES : Self = Self(63);
+    pub const CHARS_NEXT        : Self = Self(64);
+    pub const CHAR_INDICES_NEXT : Self = Self(65);
+    pub const STR_TO_STR        : Self = Self(66);
+    pub const STR_CONCAT        : Self = Self(67);
+    pub const ADD_TRAIT         : Self = Self(68);
+    pub const SUB_TRAIT         : Self = Self(69);
+    pub const MUL_TRAIT         : Self = Self(70);
+    pub const DIV_TRAIT         : Self = Self(71);
+    pub const REM_TRAIT         : Self = Self(72);
+    pub const ORD_TRAIT         : Self = Self(73);
+    pub const NEG_TRAIT         : Self = Self(74);
+    pub const INDEX_TRAIT       : Self = Self(75);
+    pub const DYN               : Self = Self(76);
+    pub const ITERATOR_TRAIT    : Self = Self(77);
+    pub const HASH_TRAIT        : Self = Self(78);
 
 
     pub fn supports_arith(self) -> bool {
-        matches!(self,
-            | Self::I64
-            | Self::F64
-            | Self::BYTE
-        )
+        self.is_num()
     }
 
 
     pub fn supports_bw(self) -> bool {
-        matches!(self,
-            | Self::I64
-            | Self::BYTE
-        )
+        self.is_int()
     }
 
 
     pub fn supports_ord(self) -> bool {
-        matches!(self,
-            | Self::I64
-            | Self::F64
-            | Self::BYTE
-        )
+        self.is_num() || self == Self::CHAR
     }
 
     pub fn supports_eq(self) -> bool {
-        self.is_float() || self.is_num()
+        self.is_float() || self.is_num() || self == Self::CHAR
     }
 
 
     pub fn is_num(self) -> bool {
-        matches!(self,
-            | Self::I64
-            | Self::F64
-            | Self::BYTE
-        )
+        self.is_int() || self.is_float()
     }
 
 
     pub fn is_int(self) -> bool {
-        matches!(self,
-            | Self::I64
-            | Self::BYTE
-        )
+        self.int_bits().is_some()
     }
 
     pub fn is_sint(self) -> bool {
         matches!(self,
+            | Self::I8
+            | Self::I16
+            | Self::I32
             | Self::I64
         )
     }
@@ -97,7 +118,34 @@ impl SymbolId {
 
     pub fn is_float(self) -> bool {
         matches!(self,
+            | Self::F32
             | Self::F64
         )
     }
+
+
+    /// The width of an integer type, `byte` is `u8` and `int` is `i64`
+    pub fn int_bits(self) -> Option<u32> {
+        Some(match self {
+            Self::I8  | Self::BYTE => 8,
+            Self::I16 | Self::U16 => 16,
+            Self::I32 | Self::U32 => 32,
+            Self::I64 | Self::U64 | Self::USIZE => 64,
+            _ => return None,
+        })
+    }
+
+
+    /// Whether `value` is representable by this integer type
+    pub fn int_fits(self, value: i128) -> bool {
+        let Some(bits) = self.int_bits()
+        else { return false };
+
+        if self.is_sint() {
+            let max = (1i128 << (bits - 1)) - 1;
+            (-max - 1..=max).contains(&value)
+        } else {
+            (0..1i128 << bits).contains(&value)
+        }
+    }
 }
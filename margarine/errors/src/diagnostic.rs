@@ -0,0 +1,57 @@
+use common::source::SourceRange;
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+
+/// A span of source code with an optional message
+/// attached to it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Label {
+    pub span: SourceRange,
+    pub message: Option<String>,
+}
+
+
+/// A fully resolved error report which doesn't depend on
+/// the compiler's state anymore. The first label, if any,
+/// is the primary span, the rest are secondary.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: String) -> Self {
+        Self { severity, code, message, labels: vec![], notes: vec![] }
+    }
+
+
+    pub fn primary(&self) -> Option<&Label> {
+        self.labels.first()
+    }
+
+
+    pub fn secondary(&self) -> &[Label] {
+        self.labels.get(1..).unwrap_or(&[])
+    }
+}
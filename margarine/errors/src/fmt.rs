@@ -4,35 +4,33 @@ use colourful::ColourBrush;
 use common::{num_size, string_map::{StringMap, StringIndex}, source::{SourceRange, FileData}};
 use display_plus::DisplayPlus;
 
+use crate::diagnostic::{Diagnostic, Label, Severity};
+
+/// Collects the diagnostic an `ErrorType` describes
 pub struct ErrorFormatter<'me> {
-    writer: &'me mut String,
     string_map: &'me StringMap<'me>,
-    files: &'me [FileData],
-    max_padding: usize,
+    code: &'static str,
+    diagnostics: Vec<Diagnostic>,
 }
 
 
 impl<'me> ErrorFormatter<'me> {
-    pub(crate) fn new(
-        writer: &'me mut String, 
-        string_map: &'me StringMap, 
-        files: &'me [FileData]
-    ) -> Self {
-        let padding : u32 = files.iter()
-            .map(|x| x.read().lines().map(|_| 1).sum())
-            .max().unwrap();
-        
+    pub(crate) fn new(string_map: &'me StringMap, code: &'static str) -> Self {
         Self {
-            writer,
             string_map,
-            files,
-            max_padding: num_size(padding + 1) as usize,
+            code,
+            diagnostics: vec![],
         }
     } 
 
     
     pub fn error<'fmt>(&'fmt mut self, msg: &str) -> CompilerError<'fmt, 'me> {
-        CompilerError::new(self, msg)
+        CompilerError::new(self, Severity::Error, msg)
+    }
+
+
+    pub fn warning<'fmt>(&'fmt mut self, msg: &str) -> CompilerError<'fmt, 'me> {
+        CompilerError::new(self, Severity::Warning, msg)
     }
 
 
@@ -44,44 +42,136 @@ impl<'me> ErrorFormatter<'me> {
     pub fn string_map(&self) -> &StringMap<'me> {
         self.string_map
     }
-}
 
 
-impl Write for ErrorFormatter<'_> {
-    fn write_str(&mut self, s: &str) -> std::fmt::Result {
-        self.writer.write_str(s)
+    /// The diagnostic the error reported, any reported after
+    /// the first are folded into it so none of them are lost
+    pub(crate) fn finish(self) -> Diagnostic {
+        let mut diagnostics = self.diagnostics.into_iter();
+        let Some(mut diagnostic) = diagnostics.next()
+        else { return Diagnostic::new(Severity::Error, self.code, "unknown error".to_string()) };
+
+        for extra in diagnostics {
+            diagnostic.notes.push(extra.message);
+            diagnostic.labels.extend(extra.labels);
+            diagnostic.notes.extend(extra.notes);
+        }
+
+        diagnostic
     }
 }
 
 
 pub struct CompilerError<'me, 'fmt> {
     fmt: &'me mut ErrorFormatter<'fmt>,
-    errored_before: bool,
+    diagnostic: Diagnostic,
 }
 
 
 impl<'me, 'fmt> CompilerError<'me, 'fmt> {
-    fn new(f: &'me mut ErrorFormatter<'fmt>, msg: &str) -> Self {
-        let _ = writeln!(f, "{}: {}", "error".red().bold(), msg.white().bold());
+    fn new(f: &'me mut ErrorFormatter<'fmt>, severity: Severity, msg: &str) -> Self {
+        let diagnostic = Diagnostic::new(severity, f.code, msg.to_string());
         Self {
             fmt: f,
-            errored_before: false,
+            diagnostic,
         }
     }
 
 
     pub fn highlight(&mut self, source: SourceRange) {
-        self.inner_highlight(source, None);
+        self.diagnostic.labels.push(Label { span: source, message: None });
     }
 
 
     pub fn highlight_with_note(&mut self, source: SourceRange, note: &str) {
-        self.inner_highlight(source, Some(note));
+        self.diagnostic.labels.push(Label { span: source, message: Some(note.to_string()) });
     }
 
 
+    pub fn note(&mut self, note: &str) {
+        self.diagnostic.notes.push(note.to_string());
+    }
+}
+
+
+impl Drop for CompilerError<'_, '_> {
+    fn drop(&mut self) {
+        let diagnostic = std::mem::replace(
+            &mut self.diagnostic,
+            Diagnostic::new(Severity::Error, "", String::new()),
+        );
+
+        self.fmt.diagnostics.push(diagnostic);
+    }
+}
+
+
+/// Renders `diagnostic` the way the command line prints it
+pub fn render(diagnostic: &Diagnostic, string_map: &StringMap, files: &[FileData]) -> String {
+    let padding : u32 = files.iter()
+        .map(|x| x.read().lines().map(|_| 1).sum())
+        .max().unwrap_or(0);
+
+    let mut writer = String::new();
+    let mut renderer = Renderer {
+        writer: &mut writer,
+        string_map,
+        files,
+        max_padding: num_size(padding + 1) as usize,
+        errored_before: false,
+    };
+
+    let severity = match diagnostic.severity {
+        Severity::Error => diagnostic.severity.as_str().red().bold(),
+        Severity::Warning => diagnostic.severity.as_str().orange().bold(),
+    };
+
+    let _ = writeln!(renderer, "{}{}: {}",
+        severity,
+        format!("[{}]", diagnostic.code).bold(),
+        diagnostic.message.white().bold(),
+    );
+
+    for label in &diagnostic.labels {
+        renderer.inner_highlight(label.span, label.message.as_deref());
+    }
+
+    for note in &diagnostic.notes {
+        let _ = writeln!(renderer, "{} {} {note}",
+            " ".repeated(renderer.max_padding),
+            "= note:".cyan(),
+        );
+    }
+
+    let _ = writeln!(renderer, 
+        "{}{} ",
+        " ".repeated(renderer.max_padding), 
+        "━┻━".orange(), 
+    );
+
+    writer
+}
+
+
+struct Renderer<'me> {
+    writer: &'me mut String,
+    string_map: &'me StringMap<'me>,
+    files: &'me [FileData],
+    max_padding: usize,
+    errored_before: bool,
+}
+
+
+impl Write for Renderer<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.writer.write_str(s)
+    }
+}
+
+
+impl Renderer<'_> {
     fn inner_highlight(&mut self, source: SourceRange, note: Option<&str>) {
-        let (file, offset) = source.file(self.fmt.files);
+        let (file, offset) = source.file(self.files);
         let source = SourceRange::new(source.start() - offset, source.end() - offset);
 
         let start_line = line_at(
@@ -97,23 +187,23 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
             start_line,
         ).unwrap();
 
-        let max_line_padding = self.fmt.max_padding;
-        let ext = file.extension().read(self.fmt.string_map);
-        let _ = writeln!(self.fmt, "{} {} {}{}{}:{}:{}",
+        let max_line_padding = self.max_padding;
+        let ext = file.extension().read(self.string_map);
+        let _ = writeln!(self, "{} {} {}{}{}:{}:{}",
             " ".repeated(max_line_padding),
             if self.errored_before {
                 "┣─▶"
             } else {
                 "┏─▶"
             }.orange(),
-            self.fmt.string_map.get(file.name()),
+            self.string_map.get(file.name()),
             if ext.is_empty() { "" } else { "." },
             ext,
             start_line.line + 1,
             characters_between(file.read(), start_line.offset, source.start() as usize).len()
         );
 
-        let _ = writeln!(self.fmt, 
+        let _ = writeln!(self, 
             "{} {} ",
             " ".repeated(max_line_padding), 
             "┃".orange(), 
@@ -123,12 +213,12 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
         {
             for line in file.read().lines().enumerate().take(end_line.line+1).skip(start_line.line) {
                 if line.0 != start_line.line {
-                    let _ = writeln!(self.fmt);
+                    let _ = writeln!(self);
                 }
                 
                 // The main line
                 let size = num_size(line.0 as u32 + 1) as usize;
-                let _ = writeln!(self.fmt, 
+                let _ = writeln!(self, 
                     "{}{} {} {}", 
                     (line.0+1).orange(), " ".repeated(max_line_padding - size), "┃".orange(), line.1,
                 );
@@ -136,7 +226,7 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
                 
                 // The lil' arrows
                 {                
-                    let _ = write!(self.fmt, 
+                    let _ = write!(self, 
                         "{} {} ",
                         " ".repeated(max_line_padding),
                         "┃".orange(), 
@@ -144,7 +234,7 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
 
                     if line.0 == start_line.line && line.0 == end_line.line {                        
                         let _ = write!(
-                            self.fmt, "{}{}",
+                            self, "{}{}",
                             " ".repeated(characters_between(
                                 line.1, 0, 
                                 source.start() as usize - start_line.offset
@@ -158,7 +248,7 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
                         );
                     } else if line.0 == start_line.line {
                         let _ = write!(
-                            self.fmt, "{}{}",
+                            self, "{}{}",
                             " ".repeated(characters_between(
                                 line.1, 0, 
                                 source.start() as usize - start_line.offset
@@ -181,7 +271,7 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
                         };
 
                         let _ = write!(
-                            self.fmt, "{}{}",
+                            self, "{}{}",
                             " ".repeated(beginning_buffer),
                             "▔".repeated(characters_between(
                                 line.1, 0, 
@@ -199,7 +289,7 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
                             num
                         };
                         let _ = write!(
-                            self.fmt, "{}{}",
+                            self, "{}{}",
                             " ".repeated(beginning_buffer),
                             "▔".repeated(line.1.len() - beginning_buffer).red(),
                         );
@@ -207,16 +297,16 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
                 }
             }
             if let Some(note) = note {
-                let _ = write!(self.fmt, " {note}");
+                let _ = write!(self, " {note}");
             }
             
-            let _ = writeln!(self.fmt);
+            let _ = writeln!(self);
 
         }
 
         
         /*
-        let _ = writeln!(self.fmt, 
+        let _ = writeln!(self, 
             "{}{} ",
             " ".repeated(max_line_padding), 
             "━┻━".orange(), 
@@ -229,17 +319,6 @@ impl<'me, 'fmt> CompilerError<'me, 'fmt> {
 }
 
 
-impl Drop for CompilerError<'_, '_> {
-    fn drop(&mut self) {
-        let _ = writeln!(self.fmt, 
-            "{}{} ",
-            " ".repeated(self.fmt.max_padding), 
-            "━┻━".orange(), 
-        );
-    }
-}
-
-
 
 #[derive(Clone, Copy, Debug)]
 struct LineAt {
@@ -1,8 +1,8 @@
 pub mod fmt;
+pub mod diagnostic;
  
-use std::fmt::Write;
-
 use common::{string_map::StringMap, source::FileData};
+pub use diagnostic::{Diagnostic, Label, Severity};
 use fmt::ErrorFormatter;
 use sti::define_key;
 
@@ -19,6 +19,9 @@ define_key!(pub ParserError(pub u32));
 define_key!(pub SemaError(pub u32));
 
 pub trait ErrorType<T> {
+    /// A stable identifier for this kind of error, e.g. `E0001`
+    fn code(&self) -> &'static str;
+
     fn display(&self, fmt: &mut ErrorFormatter, data: &mut T);
 }
 
@@ -30,14 +33,13 @@ impl From<SemaError> for ErrorId {
 }
 
 
-pub fn display<T>(e: &impl ErrorType<T>, string_map: &StringMap, file: &[FileData], data: &mut T) -> String {
-    let mut string = String::new();
-    if !string.is_empty() {
-        let _ = writeln!(string);
-    }
-
-    let mut fmt = ErrorFormatter::new(&mut string, string_map, file);
+pub fn diagnostic<T>(e: &impl ErrorType<T>, string_map: &StringMap, data: &mut T) -> Diagnostic {
+    let mut fmt = ErrorFormatter::new(string_map, e.code());
     e.display(&mut fmt, data);
+    fmt.finish()
+}
 
-    string
+
+pub fn display<T>(e: &impl ErrorType<T>, string_map: &StringMap, file: &[FileData], data: &mut T) -> String {
+    fmt::render(&diagnostic(e, string_map, data), string_map, file)
 }
@@ -22,10 +22,29 @@ pub enum Error {
     InvalidExponent(SourceRange),
     InvalidEscape { character: char, position: SourceRange },
     InvalidString(SourceRange),
+    UnterminatedChar(SourceRange),
 }
 
 
 impl ErrorType<()> for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidCharacter { .. } => "E0001",
+            Error::UnterminatedString { .. } => "E0002",
+            Error::CorruptUnicodeEscape { .. } => "E0003",
+            Error::InvalidUnicodeCharacter { .. } => "E0004",
+            Error::NumberTooLarge { .. } => "E0005",
+            Error::TooManyDots { .. } => "E0006",
+            Error::BasedFloatsArentSupported { .. } => "E0007",
+            Error::InvalidBaseForNumber { .. } => "E0008",
+            Error::InvalidExponent { .. } => "E0009",
+            Error::InvalidEscape { .. } => "E0010",
+            Error::InvalidString { .. } => "E0011",
+            Error::UnterminatedChar { .. } => "E0012",
+        }
+    }
+
+
     fn display(&self, fmt: &mut errors::fmt::ErrorFormatter, _: &mut ()) {
         match self {
             Error::InvalidCharacter { character, position } => {
@@ -95,6 +114,11 @@ impl ErrorType<()> for Error {
                 fmt.error("invalid escape sequence")
                     .highlight_with_note(*position, &format!("'\\{}' is not a valid escape", character));
             },
+
+            Error::UnterminatedChar(pos) => {
+                fmt.error("unterminated character literal")
+                    .highlight_with_note(*pos, "a character literal holds exactly one character")
+            },
         }
     }
 }
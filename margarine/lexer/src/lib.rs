@@ -13,7 +13,7 @@ pub mod errors;
 /// A wrapper around `Vec<Token>` with
 /// with the guarantee that it wont be
 /// empty.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TokenList {
     vec: Vec<Token>,
 }
@@ -28,6 +28,15 @@ impl TokenList {
             vec,
         }
     }
+
+
+    /// Moves every token from being lexed at source offset
+    /// `from` to being lexed at `to`
+    pub fn rebase(&mut self, from: u32, to: u32) {
+        for token in self.vec.iter_mut() {
+            token.source_range = token.source_range.base(from).offset(to);
+        }
+    }
 }
 
 
@@ -48,7 +57,7 @@ impl DerefMut for TokenList {
 
 
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     token_kind: TokenKind,
     source_range: SourceRange,
@@ -129,6 +138,16 @@ pub enum TokenKind {
     Literal(Literal),
     Keyword(Keyword),
     Identifier(StringIndex),
+    /// A loop label, e.g. `'outer`
+    Label(StringIndex),
+
+    /// The text of an interpolated string before its
+    /// first hole, e.g. `f"line {`
+    InterpolatedHead(StringIndex),
+    /// The text between two holes, e.g. `}: {`
+    InterpolatedMiddle(StringIndex),
+    /// The text after the last hole, e.g. `}!"`
+    InterpolatedTail(StringIndex),
 
     /// '<='
     LesserEquals,
@@ -183,6 +202,7 @@ pub enum Literal {
     Float(NonNaNF64),
     String(StringIndex),
     Bool(bool),
+    Char(char),
 }
 
 
@@ -212,6 +232,9 @@ pub enum Keyword {
     In,
     Trait,
     Import,
+    Const,
+    Static,
+    Dyn,
 }
 
 pub fn lex<'a, 'arena>(
@@ -236,6 +259,7 @@ pub fn lex<'a, 'arena>(
         errors: KVec::new(),
         source_offset,
         after_dot: false,
+        holes: Vec::new(),
     };
 
 
@@ -263,6 +287,9 @@ struct Lexer<'a, 's> {
     errors: KVec<LexerError, Error>,
     source_offset: u32,
     after_dot: bool,
+    /// The bracket depth inside each open
+    /// interpolation hole, innermost last
+    holes: Vec<u32>,
 }
 
 
@@ -298,8 +325,24 @@ impl Lexer<'_, '_> {
             b'(' => TokenKind::LeftParenthesis,
             b')' => TokenKind::RightParenthesis,
 
-            b'{' => TokenKind::LeftBracket,
-            b'}' => TokenKind::RightBracket,
+            b'{' => {
+                if let Some(depth) = self.holes.last_mut() { *depth += 1 }
+                TokenKind::LeftBracket
+            },
+
+            b'}' => match self.holes.last_mut() {
+                Some(0) => {
+                    self.holes.pop();
+                    self.interpolated_string(start as usize, false)
+                },
+
+                Some(depth) => {
+                    *depth -= 1;
+                    TokenKind::RightBracket
+                },
+
+                None => TokenKind::RightBracket,
+            },
 
             b'[' => TokenKind::LeftSquare,
             b']' => TokenKind::RightSquare,
@@ -432,6 +475,22 @@ impl Lexer<'_, '_> {
 
             b'"' => self.string(start as usize),
 
+            b'f' if self.reader.peek() == Some(b'"') => {
+                let _ = self.reader.next();
+                self.interpolated_string(start as usize, true)
+            },
+
+            b'\'' if self.is_char_literal() => self.char_literal(start as usize),
+
+            b'\'' if self.reader.peek().is_some_and(|x| x.is_ascii_alphabetic() || x == b'_') => {
+                let (value, _) = self.reader.consume_while_slice_from(start as usize + 1, |x| {
+                    x.is_ascii_alphanumeric() || *x == b'_'
+                });
+
+                let value = unsafe { core::str::from_utf8_unchecked(value) };
+                TokenKind::Label(self.string_map.insert(value))
+            },
+
             _ if val.is_ascii_alphabetic() || val == b'_' => self.identifier(start as usize),
 
 
@@ -513,6 +572,9 @@ impl Lexer<'_, '_> {
             "for"       => TokenKind::Keyword(Keyword::For),
             "in"        => TokenKind::Keyword(Keyword::In),
             "trait"      => TokenKind::Keyword(Keyword::Trait),
+            "const"     => TokenKind::Keyword(Keyword::Const),
+            "static"    => TokenKind::Keyword(Keyword::Static),
+            "dyn"       => TokenKind::Keyword(Keyword::Dyn),
 
 
             "true"      => TokenKind::Literal(Literal::Bool(true)),
@@ -625,6 +687,175 @@ impl Lexer<'_, '_> {
     }
 
 
+    /// Lexes the text of an interpolated string up to the
+    /// next hole or the closing quote. `{{` and `}}` stand
+    /// for literal brackets.
+    fn interpolated_string(&mut self, start: usize, is_head: bool) -> TokenKind {
+        let mut string = Vec::new();
+
+        let opens_hole = loop {
+            let Some(value) = self.reader.next()
+            else {
+                let err = Error::UnterminatedString(SourceRange::new(
+                    self.source_offset + start as u32,
+                    (self.source_offset + self.reader.offset() as u32).saturating_sub(1),
+                ));
+
+                return TokenKind::Error(self.errors.push(err));
+            };
+
+            match value {
+                b'"' => break false,
+
+                b'{' if self.reader.consume_if_eq(&b'{') => string.push(b'{'),
+                b'}' if self.reader.consume_if_eq(&b'}') => string.push(b'}'),
+                b'{' => break true,
+
+                b'\\' => {
+                    let value = match self.reader.next() {
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'\\') => '\\',
+                        Some(b'0') => '\0',
+                        Some(b'"') => '"',
+
+                        Some(b'u') => match self.unicode_escape_character() {
+                            Ok(v) => v,
+                            Err(e) => {
+                                self.skip_interpolated_string();
+                                return TokenKind::Error(self.errors.push(e));
+                            },
+                        },
+
+                        value => {
+                            let end = self.reader.offset() as u32 - 1;
+                            let source = SourceRange::new(self.source_offset + end - 1, self.source_offset + end);
+                            self.skip_interpolated_string();
+
+                            return TokenKind::Error(self.errors.push(Error::InvalidEscape {
+                                character: value.map_or(' ', |v| v as char),
+                                position: source,
+                            }));
+                        },
+                    };
+
+                    string.extend_from_slice(value.encode_utf8(&mut [0; 4]).as_bytes());
+                },
+
+                _ => string.push(value),
+            }
+        };
+
+        let Ok(string) = core::str::from_utf8(&string)
+        else {
+            let end = self.source_offset + self.reader.offset() as u32 - 1;
+            let source_range = SourceRange::new(self.source_offset + start as u32, end);
+            return TokenKind::Error(self.errors.push(Error::InvalidString(source_range)));
+        };
+
+        let index = self.string_map.insert(string);
+        if opens_hole { self.holes.push(0) }
+
+        match (is_head, opens_hole) {
+            (true, true) => TokenKind::InterpolatedHead(index),
+            (true, false) => TokenKind::Literal(Literal::String(index)),
+            (false, true) => TokenKind::InterpolatedMiddle(index),
+            (false, false) => TokenKind::InterpolatedTail(index),
+        }
+    }
+
+
+    /// Skips to the end of the interpolated string after an
+    /// error, holes and all, so the rest isn't lexed as code
+    fn skip_interpolated_string(&mut self) {
+        let mut depth = 0u32;
+        while let Some(value) = self.reader.next() {
+            match value {
+                b'\\' => { let _ = self.reader.next(); },
+                b'{' => depth += 1,
+                b'}' => depth = depth.saturating_sub(1),
+                b'"' if depth == 0 => break,
+                _ => (),
+            }
+        }
+    }
+
+
+    /// Whether the `'` just consumed starts a char literal
+    /// rather than a label
+    fn is_char_literal(&self) -> bool {
+        let rest = self.reader.as_slice();
+        match rest.first() {
+            Some(b'\\') => true,
+            Some(b'\'') | None => false,
+            Some(_) => {
+                let len = common::utf8::check_1(rest).map_or(1, |r| rest.len() - r.len());
+                rest.get(len) == Some(&b'\'')
+            },
+        }
+    }
+
+
+    fn char_literal(&mut self, start: usize) -> TokenKind {
+        let value = match self.reader.next() {
+            Some(b'\\') => match self.reader.next() {
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                Some(b't') => '\t',
+                Some(b'\\') => '\\',
+                Some(b'0') => '\0',
+                Some(b'\'') => '\'',
+                Some(b'"') => '"',
+
+                Some(b'u') => match self.unicode_escape_character() {
+                    Ok(v) => v,
+                    Err(e) => {
+                        self.reader.consume_while(|x| *x != b'\'' && *x != b'\n');
+                        self.reader.consume_if_eq(&b'\'');
+                        return TokenKind::Error(self.errors.push(e));
+                    },
+                },
+
+                value => {
+                    let end = self.reader.offset() as u32 - 1;
+                    let source = SourceRange::new(self.source_offset + end - 1, self.source_offset + end);
+                    self.reader.consume_if_eq(&b'\'');
+
+                    return TokenKind::Error(self.errors.push(Error::InvalidEscape {
+                        character: value.map_or(' ', |v| v as char),
+                        position: source,
+                    }));
+                },
+            },
+
+            _ => {
+                let slice = &self.reader.original_slice()[self.reader.offset()-1..];
+                let Ok(rest) = common::utf8::check_1(slice)
+                else {
+                    let end = self.source_offset + self.reader.offset() as u32;
+                    self.reader.consume_if_eq(&b'\'');
+                    let source = SourceRange::new(self.source_offset + start as u32, end);
+                    return TokenKind::Error(self.errors.push(Error::InvalidString(source)));
+                };
+
+                let len = slice.len() - rest.len();
+                let value = core::str::from_utf8(&slice[..len]).unwrap().chars().next().unwrap();
+                self.reader.consume(len - 1);
+                value
+            },
+        };
+
+        if !self.reader.consume_if_eq(&b'\'') {
+            let end = self.source_offset + self.reader.offset() as u32 - 1;
+            let source = SourceRange::new(self.source_offset + start as u32, end);
+            return TokenKind::Error(self.errors.push(Error::UnterminatedChar(source)));
+        }
+
+        TokenKind::Literal(Literal::Char(value))
+    }
+
+
     fn unicode_escape_character(&mut self) -> Result<char, Error> {
         if self.reader.peek() != Some(b'{') {
             let offset = self.source_offset + self.reader.offset() as u32;
@@ -226,6 +226,105 @@ fn identifiers() {
 }
 
 
+#[test]
+fn labels() {
+    let arena = Arena::new();
+    let mut symbol_table = StringMap::new(&arena);
+    let file = symbol_table.insert("test");
+    let data = "'outer: loop";
+    let file_data = FileData::new(data.to_string(), file, Extension::None);
+
+    let tokens = lex(&file_data, &mut symbol_table, 0);
+
+    compare_individually(&*tokens.0, vec![
+        Token {
+            token_kind: TokenKind::Label(symbol_table.insert("outer")),
+            source_range: SourceRange::new(0, 5),
+        },
+        token(TokenKind::Colon, 6, 6),
+        token(TokenKind::Keyword(Keyword::Loop), 8, 11),
+        token(TokenKind::EndOfFile, 11, 11),
+    ].as_slice())
+}
+
+
+#[test]
+fn chars() {
+    let arena = Arena::new();
+    let mut symbol_table = StringMap::new(&arena);
+    let file = symbol_table.insert("test");
+    let data = "'a' '\\n' '\\u{1F600}' 'é' 'outer";
+    let file_data = FileData::new(data.to_string(), file, Extension::None);
+
+    let tokens = lex(&file_data, &mut symbol_table, 0);
+
+    assert!(tokens.1.is_empty());
+    compare_individually(&*tokens.0, vec![
+        token(TokenKind::Literal(Literal::Char('a')), 0, 2),
+        token(TokenKind::Literal(Literal::Char('\n')), 4, 7),
+        token(TokenKind::Literal(Literal::Char('😀')), 9, 19),
+        token(TokenKind::Literal(Literal::Char('é')), 21, 24),
+        Token {
+            token_kind: TokenKind::Label(symbol_table.insert("outer")),
+            source_range: SourceRange::new(26, 31),
+        },
+        token(TokenKind::EndOfFile, 31, 31),
+    ].as_slice())
+}
+
+
+#[test]
+fn invalid_chars() {
+    let arena = Arena::new();
+    let mut symbol_table = StringMap::new(&arena);
+    let file = symbol_table.insert("test");
+    let data = "'\\q' '\\n";
+    let file_data = FileData::new(data.to_string(), file, Extension::None);
+
+    let tokens = lex(&file_data, &mut symbol_table, 0);
+    assert!(matches!(tokens.1.as_slice()[0], Error::InvalidEscape { character: 'q', .. }));
+    assert!(matches!(tokens.1.as_slice()[1], Error::UnterminatedChar(..)));
+}
+
+
+#[test]
+fn interpolated_strings() {
+    let arena = Arena::new();
+    let mut symbol_table = StringMap::new(&arena);
+    let file = symbol_table.insert("test");
+    let data = "f\"n {x}: {{{y}}}\" f\"e\"";
+    let file_data = FileData::new(data.to_string(), file, Extension::None);
+
+    let tokens = lex(&file_data, &mut symbol_table, 0);
+
+    assert!(tokens.1.is_empty());
+    compare_individually(&*tokens.0, vec![
+        token(TokenKind::InterpolatedHead(symbol_table.insert("n ")), 0, 4),
+        token(TokenKind::Identifier(symbol_table.insert("x")), 5, 5),
+        token(TokenKind::InterpolatedMiddle(symbol_table.insert(": {")), 6, 11),
+        token(TokenKind::Identifier(symbol_table.insert("y")), 12, 12),
+        token(TokenKind::InterpolatedTail(symbol_table.insert("}")), 13, 16),
+        token(TokenKind::Literal(Literal::String(symbol_table.insert("e"))), 18, 21),
+        token(TokenKind::EndOfFile, 21, 21),
+    ].as_slice())
+}
+
+
+#[test]
+fn invalid_interpolated_strings() {
+    let arena = Arena::new();
+    let mut symbol_table = StringMap::new(&arena);
+    let file = symbol_table.insert("test");
+    let data = "f\"\\q{x}\" f\"{x";
+    let file_data = FileData::new(data.to_string(), file, Extension::None);
+
+    let tokens = lex(&file_data, &mut symbol_table, 0);
+    assert!(matches!(tokens.1.as_slice()[0], Error::InvalidEscape { character: 'q', .. }));
+    assert!(tokens.1.len() == 1);
+    assert!(matches!(tokens.0[1].kind(), TokenKind::InterpolatedHead(_)));
+}
+
+
 #[test]
 fn string() {
     // valid string
@@ -400,6 +499,22 @@ fn exponent_not_based() {
 }
 
 
+#[test]
+fn rebase() {
+    let arena = Arena::new();
+    let mut symbol_table = StringMap::new(&arena);
+    let file = symbol_table.insert("test");
+    let data = "( )";
+    let file_data = FileData::new(data.to_string(), file, Extension::None);
+
+    let (mut tokens, _) = lex(&file_data, &mut symbol_table, 10);
+    tokens.rebase(10, 4);
+
+    let (expected, _) = lex(&file_data, &mut symbol_table, 4);
+    compare_individually(&tokens, &expected);
+}
+
+
 fn compare_individually<T: PartialEq + Debug>(list1: &[T], list2: &[T]) {
     assert_eq!(list1.len(), list2.len(), "list1: {list1:#?},\nlist2: {list2:#?}");
     for (index, (v1, v2)) in list1.iter().zip(list2.iter()).enumerate() {
@@ -1,7 +0,0 @@
-; ModuleID = 'main.bc'
-source_filename = "hello fresh"
-
-define i32 @owo(i69 %uwu) {
-entry:
-  ret i16 489
-}
@@ -1,9 +1,9 @@
-use std::{collections::HashSet, marker::PhantomData, ops::Deref, ptr::NonNull};
+use std::{collections::HashSet, marker::PhantomData, ops::Deref, ptr::{null_mut, NonNull}};
 
-use llvm_sys::{core::{LLVMAddCallSiteAttribute, LLVMAddCase, LLVMAppendBasicBlockInContext, LLVMBuildAShr, LLVMBuildAdd, LLVMBuildAlloca, LLVMBuildAnd, LLVMBuildBitCast, LLVMBuildBr, LLVMBuildCall2, LLVMBuildCallWithOperandBundles, LLVMBuildCondBr, LLVMBuildFAdd, LLVMBuildFCmp, LLVMBuildFDiv, LLVMBuildFMul, LLVMBuildFPCast, LLVMBuildFPToSI, LLVMBuildFPToUI, LLVMBuildFRem, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildICmp, LLVMBuildInBoundsGEP2, LLVMBuildIntCast2, LLVMBuildIntToPtr, LLVMBuildIsNull, LLVMBuildLShr, LLVMBuildLoad2, LLVMBuildMul, LLVMBuildNUWAdd, LLVMBuildNot, LLVMBuildOr, LLVMBuildPtrToInt, LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildSDiv, LLVMBuildSIToFP, LLVMBuildSRem, LLVMBuildSelect, LLVMBuildShl, LLVMBuildStore, LLVMBuildStructGEP2, LLVMBuildSub, LLVMBuildSwitch, LLVMBuildUDiv, LLVMBuildUIToFP, LLVMBuildURem, LLVMBuildUnreachable, LLVMBuildXor, LLVMConstAllOnes, LLVMConstNull, LLVMCreateOperandBundle, LLVMCreateTypeAttribute, LLVMDeleteBasicBlock, LLVMDisposeBuilder, LLVMDisposeOperandBundle, LLVMGetBasicBlockTerminator, LLVMGetEnumAttributeKindForName, LLVMGetFirstBasicBlock, LLVMGetGlobalParent, LLVMGetInsertBlock, LLVMGetIntrinsicDeclaration, LLVMGetLastInstruction, LLVMGetNextBasicBlock, LLVMGetNumSuccessors, LLVMGetParam, LLVMGetSuccessor, LLVMIntrinsicGetType, LLVMIsATerminatorInst, LLVMLookupIntrinsicID, LLVMPositionBuilderAtEnd}, prelude::{LLVMBasicBlockRef, LLVMOperandBundleRef}, LLVMBasicBlock, LLVMBuilder, LLVMIntPredicate, LLVMModule, LLVMRealPredicate, LLVMValue};
+use llvm_sys::{core::{LLVMAddCallSiteAttribute, LLVMAddCase, LLVMAppendBasicBlockInContext, LLVMBuildAShr, LLVMBuildAdd, LLVMBuildAlloca, LLVMBuildAnd, LLVMBuildBitCast, LLVMBuildBr, LLVMBuildCall2, LLVMBuildCallWithOperandBundles, LLVMBuildCondBr, LLVMBuildExtractValue, LLVMBuildFAdd, LLVMBuildFCmp, LLVMBuildFDiv, LLVMBuildFMul, LLVMBuildFPCast, LLVMBuildFPToSI, LLVMBuildFPToUI, LLVMBuildFRem, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildICmp, LLVMBuildInBoundsGEP2, LLVMBuildIntCast2, LLVMBuildIntToPtr, LLVMBuildIsNull, LLVMBuildLShr, LLVMBuildLoad2, LLVMBuildMul, LLVMBuildNUWAdd, LLVMBuildNot, LLVMBuildOr, LLVMBuildPtrToInt, LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildSDiv, LLVMBuildSIToFP, LLVMBuildSRem, LLVMBuildSelect, LLVMBuildShl, LLVMBuildStore, LLVMBuildStructGEP2, LLVMBuildSub, LLVMBuildSwitch, LLVMBuildUDiv, LLVMBuildUIToFP, LLVMBuildURem, LLVMBuildUnreachable, LLVMBuildXor, LLVMConstAllOnes, LLVMConstNull, LLVMCreateOperandBundle, LLVMCreateTypeAttribute, LLVMDeleteBasicBlock, LLVMDisposeBuilder, LLVMDisposeOperandBundle, LLVMGetBasicBlockTerminator, LLVMGetEnumAttributeKindForName, LLVMGetFirstBasicBlock, LLVMGetGlobalParent, LLVMGetInsertBlock, LLVMGetIntrinsicDeclaration, LLVMGetLastInstruction, LLVMGetNextBasicBlock, LLVMGetNumSuccessors, LLVMGetParam, LLVMGetSuccessor, LLVMIntrinsicGetType, LLVMIsATerminatorInst, LLVMLookupIntrinsicID, LLVMPositionBuilderAtEnd, LLVMSetCurrentDebugLocation2}, debuginfo::LLVMDIBuilderInsertDeclareAtEnd, prelude::{LLVMBasicBlockRef, LLVMOperandBundleRef}, LLVMBasicBlock, LLVMBuilder, LLVMIntPredicate, LLVMModule, LLVMRealPredicate, LLVMValue};
 use sti::{arena::Arena, define_key, vec::KVec};
 
-use crate::{cstr, ctx::ContextRef, tys::{func::FunctionType, integer::IntegerTy, ptr::PtrTy, strct::StructTy, Type, TypeKind}, values::{array::Array, bool::Bool, fp::FP, func::FunctionPtr, int::Integer, ptr::Ptr, strct::Struct, unit::Unit, Value}};
+use crate::{cstr, ctx::ContextRef, debug::{DebugInfo, Metadata}, tys::{func::FunctionType, integer::IntegerTy, ptr::PtrTy, strct::StructTy, Type, TypeKind}, values::{array::Array, bool::Bool, fp::FP, func::FunctionPtr, int::Integer, ptr::Ptr, strct::Struct, unit::Unit, Value}};
 
 
 define_key!(pub Local(u32));
@@ -156,6 +156,28 @@ impl<'ctx> Builder<'ctx> {
     }
 
 
+    /// Attaches `location` to the instructions built from now
+    /// on, `None` stops attaching one
+    pub fn set_debug_location(&self, location: Option<Metadata<'ctx>>) {
+        let location = location.map_or(null_mut(), |location| location.llvm_md().as_ptr());
+        unsafe { LLVMSetCurrentDebugLocation2(self.ptr.as_ptr(), location) };
+    }
+
+
+    /// Describes the storage of `local` as `variable`
+    pub fn debug_declare(&self, debug: &DebugInfo<'ctx>, local: Local, variable: Metadata<'ctx>, location: Metadata<'ctx>) {
+        let bb = unsafe { LLVMGetInsertBlock(self.ptr.as_ptr()) };
+        unsafe { LLVMDIBuilderInsertDeclareAtEnd(
+            debug.ptr.as_ptr(),
+            self.local_ptr(local).llvm_val().as_ptr(),
+            variable.llvm_md().as_ptr(),
+            debug.empty_expr.llvm_md().as_ptr(),
+            location.llvm_md().as_ptr(),
+            bb,
+        ) };
+    }
+
+
     pub fn unreachable(&self) {
         unsafe { LLVMBuildUnreachable(self.ptr.as_ptr()) };
         let bb = unsafe { LLVMAppendBasicBlockInContext(self.ctx.ptr.as_ptr(), self.func.llvm_val().as_ptr(), c"".as_ptr() as _) };
@@ -611,6 +633,67 @@ impl<'ctx> Builder<'ctx> {
     }
 
 
+    /// Computes `lhs <op> rhs` through the `llvm.*.with.overflow`
+    /// intrinsics, returning the wrapped result and whether it overflowed
+    pub fn int_with_overflow(
+        &self, op: OverflowOp, lhs: Integer<'ctx>, rhs: Integer<'ctx>, is_signed: bool,
+    ) -> (Integer<'ctx>, Bool<'ctx>) {
+        assert_eq!(lhs.ty().bit_size(), rhs.ty().bit_size(),
+                    "the two integers can't be used together as their bit-sizes are different");
+
+        let name: &[u8] = match (op, is_signed) {
+            (OverflowOp::Add, true) => b"llvm.sadd.with.overflow",
+            (OverflowOp::Add, false) => b"llvm.uadd.with.overflow",
+            (OverflowOp::Sub, true) => b"llvm.ssub.with.overflow",
+            (OverflowOp::Sub, false) => b"llvm.usub.with.overflow",
+            (OverflowOp::Mul, true) => b"llvm.smul.with.overflow",
+            (OverflowOp::Mul, false) => b"llvm.umul.with.overflow",
+        };
+
+        let mut overloads = [unsafe { lhs.ty().llvm_ty().as_ptr() }];
+        let intrinsic_id = unsafe { LLVMLookupIntrinsicID(name.as_ptr().cast(), name.len()) };
+        assert_ne!(intrinsic_id, 0, "LLVM does not provide the overflow intrinsics");
+
+        let intrinsic = unsafe {
+            LLVMGetIntrinsicDeclaration(
+                self.module.as_ptr(),
+                intrinsic_id,
+                overloads.as_mut_ptr(),
+                overloads.len(),
+            )
+        };
+        let intrinsic = NonNull::new(intrinsic).expect("failed to declare an overflow intrinsic");
+        let intrinsic_ty = unsafe {
+            LLVMIntrinsicGetType(
+                self.ctx.ptr.as_ptr(),
+                intrinsic_id,
+                overloads.as_mut_ptr(),
+                overloads.len(),
+            )
+        };
+        let intrinsic_ty = NonNull::new(intrinsic_ty).expect("failed to get an overflow intrinsic's type");
+        let mut args = unsafe { [lhs.llvm_val().as_ptr(), rhs.llvm_val().as_ptr()] };
+        let pair = unsafe {
+            LLVMBuildCall2(
+                self.ptr.as_ptr(),
+                intrinsic_ty.as_ptr(),
+                intrinsic.as_ptr(),
+                args.as_mut_ptr(),
+                args.len() as u32,
+                cstr!("ovf"),
+            )
+        };
+
+        let value = unsafe { LLVMBuildExtractValue(self.ptr.as_ptr(), pair, 0, cstr!("ovf_value")) };
+        let overflowed = unsafe { LLVMBuildExtractValue(self.ptr.as_ptr(), pair, 1, cstr!("ovf_flag")) };
+
+        unsafe {(
+            Integer::new(Value::new(NonNull::new(value).unwrap())),
+            Bool::new(Value::new(NonNull::new(overflowed).unwrap())),
+        )}
+    }
+
+
     pub fn cmp_int(&self, lhs: Integer<'ctx>, rhs: Integer<'ctx>, cmp: IntCmp) -> Bool<'ctx> {
         assert_eq!(lhs.ty().bit_size(), rhs.ty().bit_size(),
                     "the two integers can't be compared as their bit-sizes are different");
@@ -698,6 +781,11 @@ impl<'ctx> Builder<'ctx> {
         unsafe { Bool::new(*self.and(lhs.as_integer(), rhs.as_integer())) }
     }
 
+
+    pub fn bool_or(&self, lhs: Bool<'ctx>, rhs: Bool<'ctx>) -> Bool<'ctx> {
+        unsafe { Bool::new(*self.or(lhs.as_integer(), rhs.as_integer())) }
+    }
+
     /// Returns `condition` with LLVM's expected branch value attached.
     ///
     /// `expected` is the value LLVM should assume is most likely at runtime.
@@ -1095,6 +1183,15 @@ pub enum IntCmp {
 }
 
 
+/// The operations with an `llvm.*.with.overflow` intrinsic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowOp {
+    Add,
+    Sub,
+    Mul,
+}
+
+
 #[derive(Clone, Copy, Debug)]
 pub enum FPCmp {
     Eq,
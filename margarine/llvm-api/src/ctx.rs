@@ -140,8 +140,18 @@ impl<'me> ContextImpl<'me> {
     }
 
     pub fn emit_object(&self, module: Module<'me>, path: &Path) -> Result<(), String> {
+        self.emit_to_file(module, path, LLVMCodeGenFileType::LLVMObjectFile, "an object file")
+    }
+
+
+    pub fn emit_assembly(&self, module: Module<'me>, path: &Path) -> Result<(), String> {
+        self.emit_to_file(module, path, LLVMCodeGenFileType::LLVMAssemblyFile, "assembly")
+    }
+
+
+    fn emit_to_file(&self, module: Module<'me>, path: &Path, kind: LLVMCodeGenFileType, what: &str) -> Result<(), String> {
         let path = CString::new(path.to_string_lossy().as_bytes())
-            .map_err(|_| format!("object path contains a null byte: {}", path.display()))?;
+            .map_err(|_| format!("output path contains a null byte: {}", path.display()))?;
         let mut error = null_mut();
         let state = 
         unsafe {
@@ -149,7 +159,7 @@ impl<'me> ContextImpl<'me> {
                 self.target_machine.as_ptr(),
                 module.ptr.as_ptr(),
                 path.as_ptr(),
-                LLVMCodeGenFileType::LLVMObjectFile,
+                kind,
                 &mut error,
             )
         };
@@ -157,7 +167,7 @@ impl<'me> ContextImpl<'me> {
         if state != 0 {
             let message = NonNull::new(error)
                 .map(|error| unsafe { CStr::from_ptr(error.as_ptr()) }.to_string_lossy().into_owned())
-                .unwrap_or_else(|| "LLVM failed to emit an object file".to_string());
+                .unwrap_or_else(|| format!("LLVM failed to emit {what}"));
 
             if !error.is_null() {
                 unsafe { LLVMDisposeMessage(error) };
@@ -169,7 +179,6 @@ impl<'me> ContextImpl<'me> {
         }
     }
 
-
     pub fn emit_bitcode(&self, module: Module<'me>, path: &Path) -> Result<(), String> {
         let path = CString::new(path.to_string_lossy().as_bytes())
             .map_err(|_| format!("bitcode path contains a null byte: {}", path.display()))?;
@@ -295,7 +304,9 @@ impl<'me> ContextImpl<'me> {
 
 
     pub fn const_int(&self, ty: IntegerTy<'me>, val: i64, sign_extended: bool) -> Integer<'me> {
-        if val as u64 > 2u64.saturating_pow(ty.bit_size() as u32) {
+        let bits = ty.bit_size() as u32;
+        let fits_signed = sign_extended && bits < 64 && val >> (bits - 1) == -1;
+        if val as u64 > 2u64.saturating_pow(bits) && !fits_signed {
             panic!("the constant ({val}) is out of bounds of the integer size ({})", ty.bit_size());
         }
 
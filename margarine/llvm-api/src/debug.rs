@@ -0,0 +1,223 @@
+use std::{marker::PhantomData, ptr::{null_mut, NonNull}};
+
+use llvm_sys::{core::{LLVMAddModuleFlag, LLVMConstInt, LLVMIntTypeInContext, LLVMValueAsMetadata}, debuginfo::{LLVMCreateDIBuilder, LLVMDIBuilderCreateAutoVariable, LLVMDIBuilderCreateBasicType, LLVMDIBuilderCreateCompileUnit, LLVMDIBuilderCreateDebugLocation, LLVMDIBuilderCreateExpression, LLVMDIBuilderCreateFile, LLVMDIBuilderCreateFunction, LLVMDIBuilderCreateLexicalBlock, LLVMDIBuilderCreateParameterVariable, LLVMDIBuilderCreateStructType, LLVMDIBuilderCreateSubroutineType, LLVMDIBuilderFinalize, LLVMDIFlagPrototyped, LLVMDIFlagZero, LLVMDWARFEmissionKind, LLVMDWARFSourceLanguage, LLVMDebugMetadataVersion, LLVMDisposeDIBuilder, LLVMSetSubprogram}, LLVMModuleFlagBehavior, LLVMOpaqueDIBuilder, LLVMOpaqueMetadata};
+
+use crate::{ctx::ContextRef, module::Module, values::func::FunctionPtr};
+
+
+/// A node of the debug info metadata: a file, a scope, a type,
+/// a variable or a location
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata<'ctx> {
+    ptr: NonNull<LLVMOpaqueMetadata>,
+    phantom: PhantomData<&'ctx ()>,
+}
+
+
+impl<'ctx> Metadata<'ctx> {
+    fn new(ptr: *mut LLVMOpaqueMetadata) -> Self {
+        let ptr = NonNull::new(ptr).expect("failed to create debug info metadata");
+        Self { ptr, phantom: PhantomData }
+    }
+
+
+    pub fn llvm_md(self) -> NonNull<LLVMOpaqueMetadata> { self.ptr }
+}
+
+
+/// The `DW_ATE_*` encoding of a basic type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeEncoding {
+    Boolean  = 0x02,
+    Float    = 0x04,
+    Signed   = 0x05,
+    Unsigned = 0x08,
+    Utf      = 0x10,
+}
+
+
+/// Builds the DWARF debug info of a module, it has to be
+/// finalised before the module is verified or emitted
+pub struct DebugInfo<'ctx> {
+    pub(crate) ptr: NonNull<LLVMOpaqueDIBuilder>,
+    pub(crate) empty_expr: Metadata<'ctx>,
+    ctx: ContextRef<'ctx>,
+    compile_unit: Metadata<'ctx>,
+    subroutine_ty: Metadata<'ctx>,
+}
+
+
+impl<'ctx> DebugInfo<'ctx> {
+    pub fn new(ctx: ContextRef<'ctx>, module: Module<'ctx>, producer: &str, file: &str, directory: &str) -> Self {
+        let ptr = unsafe { LLVMCreateDIBuilder(module.ptr.as_ptr()) };
+        let ptr = NonNull::new(ptr).expect("failed to create a debug info builder");
+
+        let flag = |key: &str, value: u32| unsafe {
+            let i32_ty = LLVMIntTypeInContext(ctx.ptr.as_ptr(), 32);
+            let value = LLVMValueAsMetadata(LLVMConstInt(i32_ty, value as u64, 0));
+            LLVMAddModuleFlag(module.ptr.as_ptr(), LLVMModuleFlagBehavior::LLVMModuleFlagBehaviorWarning,
+                              key.as_ptr().cast(), key.len(), value);
+        };
+
+        flag("Debug Info Version", unsafe { LLVMDebugMetadataVersion() });
+        flag("Dwarf Version", 4);
+
+        let file = Metadata::new(unsafe { LLVMDIBuilderCreateFile(
+            ptr.as_ptr(),
+            file.as_ptr().cast(), file.len(),
+            directory.as_ptr().cast(), directory.len(),
+        ) });
+
+        // there's no DWARF language code for margarine, C is
+        // the one every debugger understands
+        let compile_unit = Metadata::new(unsafe { LLVMDIBuilderCreateCompileUnit(
+            ptr.as_ptr(),
+            LLVMDWARFSourceLanguage::LLVMDWARFSourceLanguageC,
+            file.ptr.as_ptr(),
+            producer.as_ptr().cast(), producer.len(),
+            0,
+            c"".as_ptr(), 0,
+            0,
+            c"".as_ptr(), 0,
+            LLVMDWARFEmissionKind::LLVMDWARFEmissionKindFull,
+            0, 0, 0,
+            c"".as_ptr(), 0,
+            c"".as_ptr(), 0,
+        ) });
+
+        let empty_expr = Metadata::new(unsafe { LLVMDIBuilderCreateExpression(ptr.as_ptr(), null_mut(), 0) });
+        let subroutine_ty = Metadata::new(unsafe {
+            LLVMDIBuilderCreateSubroutineType(ptr.as_ptr(), file.ptr.as_ptr(), null_mut(), 0, LLVMDIFlagZero)
+        });
+
+        Self { ptr, empty_expr, ctx, compile_unit, subroutine_ty }
+    }
+
+
+    pub fn compile_unit(&self) -> Metadata<'ctx> { self.compile_unit }
+
+
+    pub fn file(&self, name: &str, directory: &str) -> Metadata<'ctx> {
+        Metadata::new(unsafe { LLVMDIBuilderCreateFile(
+            self.ptr.as_ptr(),
+            name.as_ptr().cast(), name.len(),
+            directory.as_ptr().cast(), directory.len(),
+        ) })
+    }
+
+
+    /// Creates the subprogram of `func` and attaches it
+    pub fn function(
+        &self, func: FunctionPtr<'ctx>, name: &str, linkage_name: &str,
+        file: Metadata<'ctx>, line: u32, is_local: bool,
+    ) -> Metadata<'ctx> {
+        let subprogram = Metadata::new(unsafe { LLVMDIBuilderCreateFunction(
+            self.ptr.as_ptr(),
+            file.ptr.as_ptr(),
+            name.as_ptr().cast(), name.len(),
+            linkage_name.as_ptr().cast(), linkage_name.len(),
+            file.ptr.as_ptr(),
+            line,
+            self.subroutine_ty.ptr.as_ptr(),
+            is_local as i32,
+            1,
+            line,
+            LLVMDIFlagPrototyped,
+            0,
+        ) });
+
+        unsafe { LLVMSetSubprogram(func.llvm_val().as_ptr(), subprogram.ptr.as_ptr()) };
+        subprogram
+    }
+
+
+    pub fn lexical_block(&self, scope: Metadata<'ctx>, file: Metadata<'ctx>, line: u32, column: u32) -> Metadata<'ctx> {
+        Metadata::new(unsafe { LLVMDIBuilderCreateLexicalBlock(
+            self.ptr.as_ptr(), scope.ptr.as_ptr(), file.ptr.as_ptr(), line, column,
+        ) })
+    }
+
+
+    pub fn basic_type(&self, name: &str, size_in_bits: u64, encoding: TypeEncoding) -> Metadata<'ctx> {
+        Metadata::new(unsafe { LLVMDIBuilderCreateBasicType(
+            self.ptr.as_ptr(),
+            name.as_ptr().cast(), name.len(),
+            size_in_bits,
+            encoding as u32,
+            LLVMDIFlagZero,
+        ) })
+    }
+
+
+    /// A struct type without members, for the values
+    /// whose layout isn't described
+    pub fn opaque_type(&self, name: &str, size_in_bits: u64) -> Metadata<'ctx> {
+        Metadata::new(unsafe { LLVMDIBuilderCreateStructType(
+            self.ptr.as_ptr(),
+            self.compile_unit.ptr.as_ptr(),
+            name.as_ptr().cast(), name.len(),
+            null_mut(),
+            0,
+            size_in_bits,
+            0,
+            LLVMDIFlagZero,
+            null_mut(),
+            null_mut(), 0,
+            0,
+            null_mut(),
+            c"".as_ptr(), 0,
+        ) })
+    }
+
+
+    pub fn auto_variable(
+        &self, scope: Metadata<'ctx>, name: &str,
+        file: Metadata<'ctx>, line: u32, ty: Metadata<'ctx>,
+    ) -> Metadata<'ctx> {
+        Metadata::new(unsafe { LLVMDIBuilderCreateAutoVariable(
+            self.ptr.as_ptr(),
+            scope.ptr.as_ptr(),
+            name.as_ptr().cast(), name.len(),
+            file.ptr.as_ptr(),
+            line,
+            ty.ptr.as_ptr(),
+            1,
+            LLVMDIFlagZero,
+            0,
+        ) })
+    }
+
+
+    /// `arg_no` starts at 1
+    pub fn parameter_variable(
+        &self, scope: Metadata<'ctx>, name: &str, arg_no: u32,
+        file: Metadata<'ctx>, line: u32, ty: Metadata<'ctx>,
+    ) -> Metadata<'ctx> {
+        Metadata::new(unsafe { LLVMDIBuilderCreateParameterVariable(
+            self.ptr.as_ptr(),
+            scope.ptr.as_ptr(),
+            name.as_ptr().cast(), name.len(),
+            arg_no,
+            file.ptr.as_ptr(),
+            line,
+            ty.ptr.as_ptr(),
+            1,
+            LLVMDIFlagZero,
+        ) })
+    }
+
+
+    pub fn location(&self, line: u32, column: u32, scope: Metadata<'ctx>) -> Metadata<'ctx> {
+        Metadata::new(unsafe { LLVMDIBuilderCreateDebugLocation(
+            self.ctx.ptr.as_ptr(), line, column, scope.ptr.as_ptr(), null_mut(),
+        ) })
+    }
+
+
+    /// Resolves the metadata created so far, nothing
+    /// can be added after this
+    pub fn finalize(self) {
+        unsafe { LLVMDIBuilderFinalize(self.ptr.as_ptr()) };
+        unsafe { LLVMDisposeDIBuilder(self.ptr.as_ptr()) };
+    }
+}
@@ -5,6 +5,7 @@ pub mod module;
 pub mod builder;
 pub mod values;
 pub mod global;
+pub mod debug;
 
 #[macro_export]
 macro_rules! cstr { ($s: literal) => { concat!($s, "\0").as_ptr() as *const std::ffi::c_char }; }
@@ -5,6 +5,44 @@ use sti::arena::Arena;
 
 use crate::{cstr, info::Message, tys::{func::FunctionType, Type}, values::{func::FunctionPtr, global::GlobalPtr, Value}};
 
+/// The level of the default LLVM pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+    #[default]
+    O3,
+}
+
+
+impl OptLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OptLevel::O0 => "O0",
+            OptLevel::O1 => "O1",
+            OptLevel::O2 => "O2",
+            OptLevel::O3 => "O3",
+        }
+    }
+}
+
+
+impl std::str::FromStr for OptLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "0" => Ok(OptLevel::O0),
+            "1" => Ok(OptLevel::O1),
+            "2" => Ok(OptLevel::O2),
+            "3" => Ok(OptLevel::O3),
+            _ => Err(format!("unknown optimization level '{value}', expected 0, 1, 2 or 3")),
+        }
+    }
+}
+
+
 #[derive(Clone, Copy)]
 pub struct Module<'ctx> {
     pub(crate) ptr: NonNull<LLVMModule>,
@@ -119,21 +157,15 @@ impl<'ctx> Module<'ctx> {
     }
 
 
-    pub fn optimize(&self) -> Result<(), String> {
-        let level = std::env::var("MARGARINE_OPT_LEVEL").unwrap_or_else(|_| "O3".to_string());
-        if !matches!(level.as_str(), "O0" | "O1" | "O2" | "O3") {
-            return Err(format!(
-                "invalid MARGARINE_OPT_LEVEL '{level}'; expected O0, O1, O2, or O3"
-            ));
-        }
+    pub fn optimize(&self, level: OptLevel) -> Result<(), String> {
         // TBAA lets DSE cancel balanced RC clone/drop around borrowed walks,
         // but that pair often survives the first default pipeline. A second
         // instcombine/gvn/dse run finishes the same-value store.
         let pipeline = 
-        if level == "O0" {
-            format!("default<{level}>")
+        if level == OptLevel::O0 {
+            format!("default<{}>", level.as_str())
         } else {
-            format!("default<{level}>,function(instcombine,gvn,dse)")
+            format!("default<{}>,function(instcombine,gvn,dse)", level.as_str())
         };
         let pipeline = CString::new(pipeline)
             .expect("optimization pipeline cannot contain a null byte");
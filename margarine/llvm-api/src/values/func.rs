@@ -89,6 +89,17 @@ impl<'ctx> FunctionPtr<'ctx> {
     }
 
 
+    /// Marks a narrow integer parameter as sign or zero extended
+    /// by the caller, `param` being `None` for the return value
+    pub fn set_int_ext(self, ctx: ContextRef<'ctx>, param: Option<u32>, signed: bool) {
+        let (name, len) = if signed { (cstr!("signext"), 7) } else { (cstr!("zeroext"), 7) };
+        let attr_kind = unsafe { LLVMGetEnumAttributeKindForName(name, len) };
+        let attr = unsafe { LLVMCreateEnumAttribute(ctx.ptr.as_ptr(), attr_kind, 0) };
+        let index = param.map_or(LLVMAttributeReturnIndex, |param| param + 1);
+        unsafe { LLVMAddAttributeAtIndex(self.llvm_val().as_ptr(), index, attr) };
+    }
+
+
     pub fn set_sret(self, ctx: ContextRef<'ctx>, ty: Type<'ctx>) {
         let attr_kind = unsafe { LLVMGetEnumAttributeKindForName(cstr!("sret"), 4) };
         let attr = unsafe { LLVMCreateTypeAttribute(ctx.ptr.as_ptr(), attr_kind, ty.llvm_ty().as_ptr()) };
@@ -1,6 +1,6 @@
 use std::ops::Deref;
 
-use llvm_sys::core::{LLVMSetInitializer, LLVMSetLinkage};
+use llvm_sys::core::{LLVMSetGlobalConstant, LLVMSetInitializer, LLVMSetLinkage};
 
 use crate::tys::{ptr::PtrTy, TypeKind};
 
@@ -29,6 +29,12 @@ impl<'ctx> GlobalPtr<'ctx> {
     }
 
 
+    /// Marks the global as never written to
+    pub fn set_constant(self, constant: bool) {
+        unsafe { LLVMSetGlobalConstant(self.llvm_val().as_ptr(), constant as i32) }
+    }
+
+
     pub fn set_linkage(self, linkage: Linkage) {
         unsafe { LLVMSetLinkage(self.llvm_val().as_ptr(), linkage.llvm_linkage()) }
     }
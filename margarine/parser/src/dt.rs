@@ -39,6 +39,8 @@ pub enum DataTypeKind<'a> {
     Within(StringIndex, &'a DataType<'a>),
     CustomType(StringIndex, &'a [DataType<'a>]),
     Fn(&'a [FunctionTypeArgument<'a>], &'a DataType<'a>),
+    /// A trait object, `dyn Trait`
+    Dyn(&'a DataType<'a>),
 }
 
 
@@ -94,6 +96,12 @@ impl std::hash::Hash for DataTypeKind<'_> {
             },
 
 
+            DataTypeKind::Dyn(dt) => {
+                208.hash(state);
+                dt.kind().hash(state);
+            },
+
+
         }
     }
 }
@@ -84,6 +84,27 @@ pub enum Error {
 
 
 impl ErrorType<()> for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::ExpectedLiteralString { .. } => "E0101",
+            Error::ExpectedLiteralBool { .. } => "E0102",
+            Error::ExpectedIdentifier { .. } => "E0103",
+            Error::UnexpectedToken { .. } => "E0104",
+            Error::ExpectedXFoundY { .. } => "E0105",
+            Error::ExpectedXFoundYMulti { .. } => "E0106",
+            Error::DeclarationOnlyBlock { .. } => "E0107",
+            Error::FileDoesntExist { .. } => "E0108",
+            Error::HashMismatch { .. } => "E0109",
+            Error::InvalidHash { .. } => "E0110",
+            Error::ExternalFileError { .. } => "E0111",
+            Error::RepoDoesntExist { .. } => "E0112",
+            Error::TooManyEnumVariants { .. } => "E0113",
+            Error::InvalidCfg { .. } => "E0114",
+            Error::MissingCfgEnvironment { .. } => "E0115",
+        }
+    }
+
+
     fn display(&self, fmt: &mut errors::fmt::ErrorFormatter, _: &mut ()) {
         match self {
             Error::ExpectedLiteralString { source, token } => {
@@ -8,11 +8,20 @@ use dt::{DataType, DataTypeKind, FunctionTypeArgument};
 use errors::Error;
 use ::errors::{ParserError, ErrorId};
 use lexer::{Token, TokenKind, TokenList, Keyword, Literal};
-use nodes::{decl::{Attribute, AttributeValue, Decl, DeclId, EnumMapping, ExternFunction, FunctionArgument, FunctionSignature, UseItem, UseItemKind, Visibility}, expr::{Block, CallArgument, Expr, MatchMapping, UnaryOperator}, stmt::{Stmt, StmtId}, NodeId, AST};
+use nodes::{decl::{Attribute, AttributeValue, Decl, DeclId, EnumMapping, ExternFunction, FunctionArgument, FunctionSignature, UseItem, UseItemKind, Visibility}, expr::{Block, CallArgument, Expr, Fallback, MatchMapping, UnaryOperator}, stmt::{Stmt, StmtId}, NodeId, AST};
 use sti::{arena::Arena, vec::{KVec, Vec}};
 
 use crate::nodes::{decl::DeclGeneric, expr::{BinaryOperator, ExprId}, Pattern};
 
+
+/// Type names a number literal can be suffixed with
+const NUMBER_SUFFIXES: [StringIndex; 11] = [
+    StringMap::I8, StringMap::I16, StringMap::I32, StringMap::I64_ALIAS,
+    StringMap::U8_ALIAS, StringMap::U16, StringMap::U32, StringMap::U64,
+    StringMap::USIZE, StringMap::F32, StringMap::F64_ALIAS,
+];
+
+
 pub fn parse<'a>(
     tokens: TokenList, 
     file: u32,
@@ -234,6 +243,12 @@ impl<'out> Parser<'_, 'out, '_> {
                 DataTypeKind::Fn(list, self.arena.alloc_new(ret))
             )
 
+        } else if self.current_is(TokenKind::Keyword(Keyword::Dyn)) {
+            self.advance();
+
+            let ty = self.expect_type()?;
+            DataType::new(SourceRange::new(start, self.current_range().end()), DataTypeKind::Dyn(self.arena.alloc_new(ty)))
+
         } else if self.current_is(TokenKind::LeftParenthesis) { 
             self.advance();
             if self.current_is(TokenKind::RightParenthesis) {
@@ -807,7 +822,7 @@ impl<'out> Parser<'_, 'out, '_> {
             
 
             let name = self.expect_identifier()?;
-            bindings.push(name);
+            bindings.push(Pattern::new(self.current_range(), nodes::PatternKind::Variable(name)));
 
             if !self.peek_is(TokenKind::Comma) {
                 break
@@ -819,10 +834,7 @@ impl<'out> Parser<'_, 'out, '_> {
         }
 
         if bindings.len() == 1 {
-            return Ok(Pattern::new(
-                SourceRange::new(start, self.current_range().end()),
-                nodes::PatternKind::Variable(bindings[0])
-            ))
+            return Ok(bindings[0])
         }
 
 
@@ -831,6 +843,100 @@ impl<'out> Parser<'_, 'out, '_> {
             nodes::PatternKind::Tuple(bindings.leak_slice())
         ))
     }
+
+
+    /// A refutable pattern, alternatives are separated by `|`
+    fn parse_match_pattern(&mut self) -> Result<Pattern<'out>, ErrorId> {
+        let start = self.current_range().start();
+        let first = self.parse_match_pattern_alternative()?;
+        if !self.peek_is(TokenKind::BitwiseOr) {
+            return Ok(first);
+        }
+
+        let mut alternatives = Vec::new_in(self.arena);
+        alternatives.push(first);
+
+        while self.peek_is(TokenKind::BitwiseOr) {
+            self.advance();
+            self.advance();
+
+            alternatives.push(self.parse_match_pattern_alternative()?);
+        }
+
+        Ok(Pattern::new(
+            SourceRange::new(start, self.current_range().end()),
+            nodes::PatternKind::Or(alternatives.leak())
+        ))
+    }
+
+
+    fn parse_match_pattern_alternative(&mut self) -> Result<Pattern<'out>, ErrorId> {
+        let start = self.current_range().start();
+        let kind = match self.current_kind() {
+            TokenKind::Underscore => nodes::PatternKind::Wildcard,
+
+            TokenKind::Literal(Literal::Bool(true)) => nodes::PatternKind::Variant(StringMap::TRUE, None),
+            TokenKind::Literal(Literal::Bool(false)) => nodes::PatternKind::Variant(StringMap::FALSE, None),
+
+            TokenKind::Literal(lit @ (Literal::Integer(_) | Literal::String(_) | Literal::Char(_))) => nodes::PatternKind::Literal(lit),
+
+            TokenKind::Minus if matches!(self.peek_kind(), Some(TokenKind::Literal(Literal::Integer(_)))) => {
+                self.advance();
+                let TokenKind::Literal(Literal::Integer(int)) = self.current_kind()
+                else { unreachable!() };
+
+                nodes::PatternKind::Literal(Literal::Integer(int.wrapping_neg()))
+            },
+
+            TokenKind::LeftParenthesis => {
+                self.advance();
+                let items = self.parse_match_pattern_list()?;
+
+                // `(pattern)` is just `pattern`
+                if let [item] = items {
+                    return Ok(*item);
+                }
+
+                nodes::PatternKind::Tuple(items)
+            },
+
+            _ => {
+                let name = self.expect_identifier()?;
+                if !self.peek_is(TokenKind::LeftParenthesis) {
+                    nodes::PatternKind::Variable(name)
+                } else {
+                    self.advance();
+                    self.advance();
+
+                    let payload_start = self.current_range().start();
+                    let items = self.parse_match_pattern_list()?;
+
+                    // `variant(a, b)` is short for `variant((a, b))`
+                    let payload = match items {
+                        [item] => *item,
+                        _ => Pattern::new(
+                            SourceRange::new(payload_start, self.current_range().end()),
+                            nodes::PatternKind::Tuple(items),
+                        ),
+                    };
+
+                    nodes::PatternKind::Variant(name, Some(self.arena.alloc_new(payload)))
+                }
+            },
+        };
+
+        Ok(Pattern::new(SourceRange::new(start, self.current_range().end()), kind))
+    }
+
+
+    /// Comma separated patterns up to and including a `)`
+    fn parse_match_pattern_list(&mut self) -> Result<&'out [Pattern<'out>], ErrorId> {
+        let items = self.list(TokenKind::RightParenthesis, Some(TokenKind::Comma),
+            |parser, _| parser.parse_match_pattern())?;
+
+        self.expect(TokenKind::RightParenthesis)?;
+        Ok(items)
+    }
 }
 
 impl<'ta> Parser<'_, 'ta, '_> {
@@ -858,6 +964,8 @@ impl<'ta> Parser<'_, 'ta, '_> {
         match self.current_kind() {
             TokenKind::Keyword(Keyword::Let) | TokenKind::Keyword(Keyword::Var) => self.let_statement()?.into(),
             TokenKind::Keyword(Keyword::For) => self.for_statement()?.into(),
+            TokenKind::Label(_) if self.peek_n(2).is_some_and(|t| t.kind() == TokenKind::Keyword(Keyword::For))
+                => self.for_statement()?.into(),
 
             TokenKind::SemiColon => {
                 return Ok(Some(self.ast.add_expr(Expr::Unit, self.current_range()).into()))
@@ -983,6 +1091,8 @@ impl<'ta> Parser<'_, 'ta, '_> {
             TokenKind::Keyword(Keyword::Enum) => self.enum_declaration(visibility),
             TokenKind::Keyword(Keyword::Use) => self.using_declaration(visibility),
             TokenKind::Keyword(Keyword::Type) => self.alias_declaration(visibility),
+            TokenKind::Keyword(Keyword::Const) => self.global_declaration(visibility),
+            TokenKind::Keyword(Keyword::Static) => self.global_declaration(visibility),
             TokenKind::Keyword(Keyword::Impl) if visibility == Visibility::Private => self.impl_declaration(),
             TokenKind::Keyword(Keyword::Import) if visibility == Visibility::Private => self.import_declaration(),
             _ => return None,
@@ -1011,6 +1121,37 @@ impl<'ta> Parser<'_, 'ta, '_> {
         Ok(self.ast.add_decl(Decl::Alias { visibility, name, header: range, gens, data_type }, range))
     }
 
+    fn global_declaration(&mut self, visibility: Visibility) -> DeclResult<'ta> {
+        let start = self.current_range().start();
+        let is_const = match self.current_kind() {
+            TokenKind::Keyword(Keyword::Const) => true,
+            TokenKind::Keyword(Keyword::Static) => false,
+            _ => unreachable!(),
+        };
+        self.advance();
+
+        let name = self.expect_identifier()?;
+        self.advance();
+        self.expect(TokenKind::Colon)?;
+
+        self.advance();
+        let data_type = self.expect_type()?;
+        let header = SourceRange::new(start, self.current_range().end());
+
+        self.advance();
+        self.expect(TokenKind::Equals)?;
+
+        self.advance();
+        let expr = self.expression(&ParserSettings::default())?;
+
+        let range = SourceRange::new(start, self.current_range().end());
+        let decl =
+            if is_const { Decl::Const { visibility, name, header, data_type, expr } }
+            else { Decl::Static { visibility, name, header, data_type, expr } };
+
+        Ok(self.ast.add_decl(decl, range))
+    }
+
     fn struct_declaration(&mut self, visibility: Visibility) -> DeclResult<'ta> {
         let start = self.current_range().start();
         self.expect(TokenKind::Keyword(Keyword::Struct))?;
@@ -1173,6 +1314,17 @@ impl<'ta> Parser<'_, 'ta, '_> {
         self.advance();
 
         let generics = self.generic_decl()?;
+
+        let supertraits =
+        if self.current_is(TokenKind::Colon) {
+            self.advance();
+            self.list_multi(&[TokenKind::LeftBracket], Some(TokenKind::Plus), |parser, _| {
+                parser.expect_type()
+            })?
+        } else {
+            &[]
+        };
+
         let header = SourceRange::new(start, self.current_range().end());
 
         self.expect(TokenKind::LeftBracket)?;
@@ -1180,16 +1332,65 @@ impl<'ta> Parser<'_, 'ta, '_> {
 
         let mut set = ParserSettings::default();
         set.is_in_impl = true;
-        let functions = self.list(
+        let mut defaults = Vec::new_in(self.arena);
+        let mut functions = Vec::new_in(self.arena);
+        let mut types = Vec::new_in(self.arena);
+        let mut consts = Vec::new_in(self.arena);
+        self.list(
             TokenKind::RightBracket,
             None,
             |parser, _| {
-                parser.function_sig(&set)
+                let start = parser.current_range().start();
+
+                if parser.current_is(TokenKind::Keyword(Keyword::Type)) {
+                    parser.advance();
+                    let name = parser.expect_identifier()?;
+                    types.push((name, SourceRange::new(start, parser.current_range().end())));
+                    return Ok(());
+                }
+
+                if parser.current_is(TokenKind::Keyword(Keyword::Const)) {
+                    parser.advance();
+                    let name = parser.expect_identifier()?;
+                    parser.advance();
+                    parser.expect(TokenKind::Colon)?;
+                    parser.advance();
+
+                    let data_type = parser.expect_type()?;
+                    consts.push((name, data_type, SourceRange::new(start, parser.current_range().end())));
+                    return Ok(());
+                }
+
+                let sig = parser.function_sig(&set)?;
+                functions.push(sig);
+                if !parser.peek_is(TokenKind::LeftBracket) {
+                    return Ok(());
+                }
+
+                parser.advance();
+                let body_start = parser.current_range().start();
+                parser.advance();
+
+                let body = parser.parse_till(TokenKind::RightBracket, body_start, &ParserSettings::default())?;
+                let end = parser.current_range().end();
+
+                let decl = parser.ast.add_decl(
+                    Decl::Function { visibility, sig, body },
+                    SourceRange::new(start, end),
+                );
+
+                defaults.push(decl);
+                Ok(())
             }
         )?;
 
+        let defaults = defaults.leak();
+        let functions = functions.leak();
+        let types = types.leak();
+        let consts = consts.leak();
+
         Ok(self.ast.add_decl(
-            Decl::Trait { visibility, header, generics, functions, name },
+            Decl::Trait { visibility, header, generics, supertraits, functions, defaults, types, consts, name },
             SourceRange::new(start, self.current_range().end())
         ))
     }
@@ -1603,6 +1804,8 @@ impl<'ta> Parser<'_, 'ta, '_> {
 
     fn for_statement(&mut self) -> StmtResult<'ta> {
         let start = self.current_range().start();
+        let label = self.loop_label()?;
+
         self.expect(TokenKind::Keyword(Keyword::For))?;
         self.advance();
 
@@ -1627,7 +1830,8 @@ impl<'ta> Parser<'_, 'ta, '_> {
             Stmt::ForLoop {
                 binding,
                 expr,
-                body: block
+                body: block,
+                label,
             },
             SourceRange::new(start, self.current_range().end()),
         ))
@@ -2064,14 +2268,93 @@ impl<'ta> Parser<'_, 'ta, '_> {
     }
     
 
+    /// Lowers `f"a{x}b"` to `$str_concat($str_concat("a", x.__to_str__()), "b")`,
+    /// sema swaps in `ToStr::to_str` for types without `__to_str__`
+    fn interpolated_string(&mut self, head: StringIndex) -> ExprResult<'ta> {
+        let start = self.current_range().start();
+        let mut result = None;
+        let mut text = head;
+
+        loop {
+            if !self.string_map.get(text).is_empty() {
+                let lit = self.ast.add_expr(Expr::Literal(Literal::String(text)), self.current_range());
+                result = Some(self.str_concat(start, result, lit));
+            }
+
+            if !matches!(self.current_kind(), TokenKind::InterpolatedHead(_) | TokenKind::InterpolatedMiddle(_)) {
+                break;
+            }
+
+            self.advance();
+            let hole = self.expression(&ParserSettings::default())?;
+            self.advance();
+
+            let range = self.ast.range(hole);
+            let to_str = self.ast.add_expr(Expr::AccessField {
+                val: hole,
+                field_name: StringMap::TO_STR_FUNC,
+                gens: None,
+            }, range);
+            let to_str = self.ast.add_expr(Expr::CallFunction { lhs: to_str, args: &[] }, range);
+            result = Some(self.str_concat(start, result, to_str));
+
+            self.is_error_token()?;
+            text = match self.current_kind() {
+                  TokenKind::InterpolatedMiddle(text)
+                | TokenKind::InterpolatedTail(text) => text,
+
+                _ => return Err(ErrorId::Parser((self.file, self.errors.push(Error::ExpectedXFoundY {
+                    source: self.current_range(),
+                    found: self.current_kind(),
+                    expected: TokenKind::RightBracket,
+                })))),
+            };
+        }
+
+        // the head always opens a hole
+        Ok(result.unwrap())
+    }
+
+
+    fn str_concat(&mut self, start: u32, lhs: Option<ExprId>, rhs: ExprId) -> ExprId {
+        let Some(lhs) = lhs
+        else { return rhs };
+
+        let range = SourceRange::new(start, self.ast.range(rhs).end());
+        let func = self.ast.add_expr(Expr::Identifier(StringMap::STR_CONCAT, None), range);
+        let args = self.arena.alloc_new([
+            CallArgument { expr: lhs, is_inout: false },
+            CallArgument { expr: rhs, is_inout: false },
+        ]);
+
+        self.ast.add_expr(Expr::CallFunction { lhs: func, args }, range)
+    }
+
+
     fn atom(&mut self, settings: &ParserSettings) -> ExprResult<'ta> {
         self.is_error_token()?;
 
         match self.current_kind() {
-            TokenKind::Literal(l) => Ok(self.ast.add_expr(
-                Expr::Literal(l), 
-                self.current_range(),
-            )),
+            TokenKind::Literal(l) => {
+                let lit = self.ast.add_expr(Expr::Literal(l), self.current_range());
+
+                // `5u8` is short for `5 as u8`
+                let start = self.current_range().start();
+                let end = self.current_range().end();
+                if let Literal::Integer(_) | Literal::Float(_) = l
+                && let Some(suffix) = self.peek().filter(|t| t.range().start() == end + 1)
+                && let TokenKind::Identifier(ty) = suffix.kind()
+                && NUMBER_SUFFIXES.contains(&ty) {
+                    self.advance();
+                    let data_type = DataType::new(self.current_range(), DataTypeKind::CustomType(ty, &[]));
+                    return Ok(self.ast.add_expr(
+                        Expr::AsCast { lhs: lit, data_type },
+                        SourceRange::new(start, self.current_range().end()),
+                    ))
+                }
+
+                Ok(lit)
+            },
 
 
             TokenKind::LeftParenthesis => {
@@ -2118,6 +2401,9 @@ impl<'ta> Parser<'_, 'ta, '_> {
             TokenKind::LeftBracket => self.block_expression(),
 
 
+            TokenKind::InterpolatedHead(head) => self.interpolated_string(head),
+
+
             TokenKind::DollarSign => {
                 let start = self.current_range().start();
 
@@ -2159,6 +2445,23 @@ impl<'ta> Parser<'_, 'ta, '_> {
 
                         let gens = self.parse_generic_usage()?;
 
+                        // `Type::<T>::action`
+                        if self.peek_is(TokenKind::DoubleColon) {
+                            let namespace = DataType::new(
+                                SourceRange::new(start, self.current_range().end()),
+                                DataTypeKind::CustomType(v, gens.unwrap_or(&[])),
+                            );
+
+                            self.advance();
+                            self.advance();
+                            let action = self.atom(settings)?;
+
+                            return Ok(self.ast.add_expr(
+                                Expr::WithinTypeNamespace { namespace, action },
+                                SourceRange::new(start, self.current_range().end()),
+                            ))
+                        }
+
                         return Ok(self.ast.add_expr(
                             Expr::Identifier(v, gens),
                             SourceRange::new(start, self.current_range().end()),
@@ -2259,76 +2562,52 @@ impl<'ta> Parser<'_, 'ta, '_> {
 
 
             TokenKind::Keyword(Keyword::Break) => {
-                Ok(self.ast.add_expr(
-                    Expr::Break, 
-                    self.current_range(),
-                ))
-            }
+                let start = self.current_range().start();
+                let label = self.jump_label();
 
+                let value =
+                    if self.peek_ends_expression() { None }
+                    else {
+                        self.advance();
+                        Some(self.expression(&ParserSettings::default())?)
+                    };
 
-            TokenKind::Keyword(Keyword::Continue) => {
                 Ok(self.ast.add_expr(
-                    Expr::Continue, 
-                    self.current_range(),
+                    Expr::Break { label, value }, 
+                    SourceRange::new(start, self.current_range().end()),
                 ))
-            },
+            }
 
 
-            TokenKind::Keyword(Keyword::Loop) => {
+            TokenKind::Keyword(Keyword::Continue) => {
                 let start = self.current_range().start();
-                self.advance();
-
-                let body_start = self.current_range().start();
-                self.expect(TokenKind::LeftBracket)?;
-                self.advance();
-                let body = self.parse_till(TokenKind::RightBracket, body_start, &ParserSettings::default())?;
+                let label = self.jump_label();
 
                 Ok(self.ast.add_expr(
-                    Expr::Loop { body },
-                    SourceRange::new(start, self.current_range().end())
+                    Expr::Continue { label }, 
+                    SourceRange::new(start, self.current_range().end()),
                 ))
-            }
+            },
 
 
-            TokenKind::Keyword(Keyword::While) => {
+            TokenKind::Label(_) => {
                 let start = self.current_range().start();
-                self.advance();
-
-                let expr = self.expression(&ParserSettings {
-                    can_parse_struct_creation: false,
-                    ..Default::default()
-                })?;
-
-                self.advance();
+                let label = self.loop_label()?;
 
-                let body_start = self.current_range().start();
-                self.expect(TokenKind::LeftBracket)?;
-                self.advance();
-                let body = self.parse_till(TokenKind::RightBracket, body_start, &ParserSettings::default())?;
-
-                let source = SourceRange::new(start, self.current_range().end());
+                match self.current_kind() {
+                    TokenKind::Keyword(Keyword::Loop) => self.loop_expression(start, label),
+                    TokenKind::Keyword(Keyword::While) => self.while_expression(start, label),
 
-                let else_block = self.ast.add_expr(
-                    Expr::Break,
-                    source,
-                );
+                    _ => Err(ErrorId::Parser((
+                        self.file,
+                        self.errors.push(Error::UnexpectedToken(self.current_range())))
+                    )),
+                }
+            },
 
-                let body = self.ast.add_expr(Expr::Block { block: body }, body.range());
-                let if_node = self.ast.add_expr(
-                    Expr::If {
-                        condition: expr,
-                        body,
-                        else_block: Some(else_block),
-                    },
-                    source
-                );
 
-                Ok(self.ast.add_expr(
-                    Expr::Loop {
-                        body: Block::new(self.arena.alloc_new([if_node.into()]), source) },
-                    source,
-                ))
-            }
+            TokenKind::Keyword(Keyword::Loop) => self.loop_expression(self.current_range().start(), None),
+            TokenKind::Keyword(Keyword::While) => self.while_expression(self.current_range().start(), None),
 
 
             TokenKind::LeftSquare => {
@@ -2360,6 +2639,112 @@ impl<'ta> Parser<'_, 'ta, '_> {
 
 
 
+    fn loop_expression(&mut self, start: u32, label: Option<StringIndex>) -> ExprResult<'ta> {
+        self.expect(TokenKind::Keyword(Keyword::Loop))?;
+        self.advance();
+
+        let body_start = self.current_range().start();
+        self.expect(TokenKind::LeftBracket)?;
+        self.advance();
+        let body = self.parse_till(TokenKind::RightBracket, body_start, &ParserSettings::default())?;
+
+        Ok(self.ast.add_expr(
+            Expr::Loop { body, label, is_while: false },
+            SourceRange::new(start, self.current_range().end())
+        ))
+    }
+
+
+    fn while_expression(&mut self, start: u32, label: Option<StringIndex>) -> ExprResult<'ta> {
+        self.expect(TokenKind::Keyword(Keyword::While))?;
+        self.advance();
+
+        let pattern =
+            if self.current_is(TokenKind::Keyword(Keyword::Let)) { Some(self.let_pattern()?) }
+            else { None };
+
+        let expr = self.expression(&ParserSettings {
+            can_parse_struct_creation: false,
+            ..Default::default()
+        })?;
+
+        self.advance();
+
+        let body_start = self.current_range().start();
+        self.expect(TokenKind::LeftBracket)?;
+        self.advance();
+        let body = self.parse_till(TokenKind::RightBracket, body_start, &ParserSettings::default())?;
+
+        let source = SourceRange::new(start, self.current_range().end());
+
+        let else_block = self.ast.add_expr(
+            Expr::Break { label: None, value: None },
+            source,
+        );
+
+        let body = self.ast.add_expr(Expr::Block { block: body }, body.range());
+        let if_node = match pattern {
+            Some(pattern) => self.if_let(pattern, expr, body, Some(else_block), source),
+            None => self.ast.add_expr(
+                Expr::If {
+                    condition: expr,
+                    body,
+                    else_block: Some(else_block),
+                },
+                source
+            ),
+        };
+
+        Ok(self.ast.add_expr(
+            Expr::Loop {
+                body: Block::new(self.arena.alloc_new([if_node.into()]), source),
+                label,
+                is_while: true,
+            },
+            source,
+        ))
+    }
+
+
+    /// Parses the `'label:` in front of a loop if there's one
+    fn loop_label(&mut self) -> Result<Option<StringIndex>, ErrorId> {
+        let TokenKind::Label(label) = self.current_kind()
+        else { return Ok(None) };
+
+        self.advance();
+        self.expect(TokenKind::Colon)?;
+        self.advance();
+
+        Ok(Some(label))
+    }
+
+
+    /// Parses the label after a `break` or `continue` if there's one
+    fn jump_label(&mut self) -> Option<StringIndex> {
+        let Some(TokenKind::Label(label)) = self.peek_kind()
+        else { return None };
+
+        self.advance();
+        Some(label)
+    }
+
+
+    /// Whether the next token can't start an expression, used
+    /// to tell `break` apart from `break value`
+    fn peek_ends_expression(&self) -> bool {
+        matches!(
+            self.peek_kind(),
+            None
+            | Some(TokenKind::SemiColon)
+            | Some(TokenKind::Comma)
+            | Some(TokenKind::RightBracket)
+            | Some(TokenKind::RightParenthesis)
+            | Some(TokenKind::RightSquare)
+            | Some(TokenKind::EndOfFile)
+        )
+    }
+
+
     fn match_expression(&mut self) -> ExprResult<'ta> {
         let start = self.current_range().start();
         self.expect(TokenKind::Keyword(Keyword::Match))?;
@@ -2381,30 +2766,17 @@ impl<'ta> Parser<'_, 'ta, '_> {
         let mappings = self.list(TokenKind::RightBracket, Some(TokenKind::Comma),
         |parser, _| {
             let start = parser.current_range().start();
-            let name = match parser.current_kind() {
-                TokenKind::Literal(Literal::Bool(true)) => StringMap::TRUE,
-                TokenKind::Literal(Literal::Bool(false)) => StringMap::FALSE,
-                _ => parser.expect_identifier()?,
-            };
+            let pattern = parser.parse_match_pattern()?;
             parser.advance();
 
-            let (bind_to, binding_range) =
-                if parser.current_is(TokenKind::LeftParenthesis) {
+            let guard =
+                if parser.current_is(TokenKind::Keyword(Keyword::If)) {
                     parser.advance();
 
-                    let binding_start = parser.current_range().start();
-
-                    let name = parser.expect_identifier()?;
-                    let binding_range = SourceRange::new(binding_start, parser.current_range().end());
+                    let guard = parser.expression(&ParserSettings::default())?;
                     parser.advance();
-
-                    parser.expect(TokenKind::RightParenthesis)?;
-                    parser.advance();
-                    (name, binding_range)
-
-                } else {
-                    (parser.string_map.insert("_"), parser.current_range())
-                };
+                    Some(guard)
+                } else { None };
 
             let source_range = SourceRange::new(start, parser.current_range().start());
 
@@ -2413,7 +2785,7 @@ impl<'ta> Parser<'_, 'ta, '_> {
 
             let expr = parser.expression(&ParserSettings::default())?;
 
-            Ok(MatchMapping::new(name, bind_to, binding_range, source_range, expr))
+            Ok(MatchMapping::new(pattern, guard, source_range, expr))
         })?;
 
         self.expect(TokenKind::RightBracket)?;
@@ -2448,6 +2820,10 @@ impl<'ta> Parser<'_, 'ta, '_> {
         self.expect(TokenKind::Keyword(Keyword::If))?;
         self.advance();
 
+        let pattern =
+            if self.current_is(TokenKind::Keyword(Keyword::Let)) { Some(self.let_pattern()?) }
+            else { None };
+
         let settings = ParserSettings { can_parse_struct_creation: false, ..Default::default()};
         let condition = self.expression(&settings)?;
         self.advance();
@@ -2472,17 +2848,62 @@ impl<'ta> Parser<'_, 'ta, '_> {
 
         
         let body = self.ast.add_expr(Expr::Block { block: body }, body.range());
+        let source = SourceRange::new(start, self.current_range().end());
+
+        if let Some(pattern) = pattern {
+            return Ok(self.if_let(pattern, condition, body, else_block, source));
+        }
+
         Ok(self.ast.add_expr(
             Expr::If {
                 condition, 
                 body,
                 else_block,
             },
-            SourceRange::new(start, self.current_range().end())
+            source
         ))
     }
 
 
+    /// Parses the `let <pattern> =` of an `if let`
+    /// or a `while let`
+    fn let_pattern(&mut self) -> Result<Pattern<'ta>, ErrorId> {
+        self.advance();
+
+        let pattern = self.parse_match_pattern()?;
+        self.advance();
+
+        self.expect(TokenKind::Equals)?;
+        self.advance();
+
+        Ok(pattern)
+    }
+
+
+    /// Desugars `if let pattern = value { body } else { else_block }`
+    /// into `match value { pattern => body, _ => else_block }`
+    fn if_let(
+        &mut self,
+        pattern: Pattern<'ta>, value: ExprId,
+        body: ExprId, else_block: Option<ExprId>,
+        source: SourceRange,
+    ) -> ExprId {
+        let (else_block, fallback) = match else_block {
+            Some(else_block) => (else_block, Fallback::Else),
+            None => (self.ast.add_expr(Expr::Unit, self.ast.range(body)), Fallback::MissingElse),
+        };
+
+        let wildcard = Pattern::new(self.ast.range(else_block), nodes::PatternKind::Wildcard);
+
+        let mappings = self.arena.alloc_new([
+            MatchMapping::new(pattern, None, pattern.source(), body),
+            MatchMapping::fallback_arm(wildcard, else_block, fallback),
+        ]);
+
+        self.ast.add_expr(Expr::Match { value, mappings }, source)
+    }
+
+
     fn parse_function_call_args(
         &mut self, 
         associated: Option<ExprId>
@@ -2655,6 +3076,33 @@ mod tests {
         assert!(errors.is_empty(), "parse errors: {errors:?}");
     }
 
+    #[test]
+    fn generic_type_namespaces() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new("fn f() { Pair::<int>::new(1, 2) }".to_string(), file_name, Extension::None);
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (_, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let (namespace, action) = ast.exprs().iter()
+            .find_map(|(expr, _)| match expr {
+                Expr::WithinTypeNamespace { namespace, action } => Some((*namespace, *action)),
+                _ => None,
+            })
+            .unwrap();
+
+        let DataTypeKind::CustomType(name, gens) = namespace.kind()
+        else { panic!("expected a named type") };
+
+        assert_eq!(sm.get(name), "Pair");
+        assert_eq!(gens.len(), 1);
+        assert!(matches!(ast.expr(action), Expr::Identifier(name, None) if sm.get(name) == "new"));
+    }
+
     #[test]
     fn binary_operator_display() {
         assert_eq!(format!("{}", BinaryOperator::BitshiftLeft), "<<");
@@ -2680,6 +3128,238 @@ mod tests {
     }
 
 
+    #[test]
+    fn match_patterns_nest() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new(
+            "fn f(x: int) { match x { ok(some((a, _))) if a > 0 => a, 1 | -2 => 0, \"s\" => 1, _ => 2 } }".to_string(),
+            file_name,
+            Extension::None,
+        );
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (_, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let mappings = ast.exprs().iter()
+            .find_map(|(expr, _)| match expr {
+                Expr::Match { mappings, .. } => Some(*mappings),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(mappings.len(), 4);
+        assert!(mappings[0].guard().is_some());
+
+        let nodes::PatternKind::Variant(_, Some(some)) = mappings[0].pattern().kind()
+        else { panic!("expected a variant") };
+        let nodes::PatternKind::Variant(_, Some(tuple)) = some.kind()
+        else { panic!("expected a nested variant") };
+        assert!(matches!(tuple.kind(), nodes::PatternKind::Tuple([_, wildcard]) if wildcard.kind() == nodes::PatternKind::Wildcard));
+
+        assert!(matches!(
+            mappings[1].pattern().kind(),
+            nodes::PatternKind::Or([a, b])
+                if a.kind() == nodes::PatternKind::Literal(Literal::Integer(1))
+                && b.kind() == nodes::PatternKind::Literal(Literal::Integer(-2))
+        ));
+        assert!(matches!(mappings[2].pattern().kind(), nodes::PatternKind::Literal(Literal::String(_))));
+        assert_eq!(mappings[3].pattern().kind(), nodes::PatternKind::Wildcard);
+    }
+
+
+    #[test]
+    fn if_let_and_while_let_desugar_to_match() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new(
+            "fn f(x: int) { if let some(a) = x { a } else { 0 }; while let some(b) = x { } }".to_string(),
+            file_name,
+            Extension::None,
+        );
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (_, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let matches = ast.exprs().iter()
+            .filter_map(|(expr, _)| match expr {
+                Expr::Match { mappings, .. } => Some(*mappings),
+                _ => None,
+            })
+            .collect::<std::vec::Vec<_>>();
+
+        assert_eq!(matches.len(), 2);
+        for mappings in matches {
+            assert_eq!(mappings.len(), 2);
+            assert!(matches!(mappings[0].pattern().kind(), nodes::PatternKind::Variant(_, Some(_))));
+            assert_eq!(mappings[1].pattern().kind(), nodes::PatternKind::Wildcard);
+            assert_eq!(mappings[1].fallback(), Some(nodes::expr::Fallback::Else));
+        }
+
+        assert!(ast.exprs().iter().any(|(expr, _)| matches!(expr, Expr::Loop { .. })));
+        assert!(!ast.exprs().iter().any(|(expr, _)| matches!(expr, Expr::If { .. })));
+    }
+
+
+    #[test]
+    fn loop_labels_and_break_values() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new(
+            "fn f(x: int) { 'rows: for i in x { 'cols: while true { continue 'rows; } }; let v = 'l: loop { break 'l 5 }; }".to_string(),
+            file_name,
+            Extension::None,
+        );
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (_, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let rows = sm.insert("rows");
+        let cols = sm.insert("cols");
+        let l = sm.insert("l");
+
+        let exprs = ast.exprs();
+        assert!(exprs.iter().any(|(expr, _)| matches!(expr, Expr::Loop { label: Some(x), is_while: true, .. } if *x == cols)));
+        assert!(exprs.iter().any(|(expr, _)| matches!(expr, Expr::Loop { label: Some(x), is_while: false, .. } if *x == l)));
+        assert!(exprs.iter().any(|(expr, _)| matches!(expr, Expr::Continue { label: Some(x) } if *x == rows)));
+        assert!(exprs.iter().any(|(expr, _)| matches!(expr, Expr::Break { label: Some(x), value: Some(_) } if *x == l)));
+    }
+
+
+    #[test]
+    fn number_literal_suffixes() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new(
+            "fn f() { let a = 255u8; let b = 1.5f32; let c = 0xffu16 + 3; let d = 7; }".to_string(),
+            file_name,
+            Extension::None,
+        );
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (_, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let suffixes = ast.exprs().iter()
+            .filter_map(|(expr, _)| match expr {
+                Expr::AsCast { data_type, .. } => match data_type.kind() {
+                    DataTypeKind::CustomType(name, _) => Some(sm.get(name).to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<std::vec::Vec<_>>();
+
+        assert_eq!(suffixes, ["u8", "f32", "u16"]);
+    }
+
+
+    #[test]
+    fn interpolated_strings_lower_to_to_str() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new(
+            "fn f(a: int, b: str) { let s = f\"{a}: {b.len()}!\"; let t = f\"{a}\"; }".to_string(),
+            file_name,
+            Extension::None,
+        );
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (_, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let exprs = ast.exprs();
+        let to_strs = exprs.iter()
+            .filter(|(expr, _)| matches!(expr, Expr::AccessField { field_name: StringMap::TO_STR_FUNC, .. }))
+            .count();
+        let concats = exprs.iter()
+            .filter(|(expr, _)| matches!(expr, Expr::Identifier(StringMap::STR_CONCAT, None)))
+            .count();
+
+        // `{a}` + ": " + `{b.len()}` + "!", the second string is just its hole
+        assert_eq!(to_strs, 3);
+        assert_eq!(concats, 3);
+    }
+
+
+    #[test]
+    fn trait_supertraits_and_default_methods() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new(
+            "trait Ord: Eq + Into<int> { fn cmp(self, other: Self): int fn lt(self, other: Self): bool { self.cmp(other) < 0 } }".to_string(),
+            file_name,
+            Extension::None,
+        );
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (body, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let NodeId::Decl(id) = body[0]
+        else { panic!("expected a declaration") };
+
+        let Decl::Trait { supertraits, functions, defaults, .. } = ast.decl(id)
+        else { panic!("expected a trait") };
+
+        assert_eq!(supertraits.len(), 2);
+        assert_eq!(functions.len(), 2);
+        assert_eq!(defaults.len(), 1);
+
+        let Decl::Function { sig, body, .. } = ast.decl(defaults[0])
+        else { panic!("expected a default method") };
+
+        assert_eq!(sm.get(sig.name), "lt");
+        assert_eq!(body.len(), 1);
+    }
+
+
+    #[test]
+    fn trait_associated_items() {
+        let arena = Arena::new();
+        let mut sm = StringMap::new(&arena);
+        let file_name = sm.insert("test");
+        let file = FileData::new(
+            "trait Buffer { type Item const CAPACITY: int fn get(self, index: int): Self::Item }".to_string(),
+            file_name,
+            Extension::None,
+        );
+        let (tokens, _) = lex(&file, &mut sm, 0);
+        let mut ast = AST::new(&arena);
+        let cfg_env = std::collections::HashMap::new();
+        let (body, _, _, errors) = parse(tokens, 0, &arena, &mut sm, &mut ast, &cfg_env);
+        assert!(errors.is_empty(), "parse errors: {errors:?}");
+
+        let NodeId::Decl(id) = body[0]
+        else { panic!("expected a declaration") };
+
+        let Decl::Trait { functions, types, consts, .. } = ast.decl(id)
+        else { panic!("expected a trait") };
+
+        assert_eq!(functions.len(), 1);
+        assert_eq!(types.len(), 1);
+        assert_eq!(sm.get(types[0].0), "Item");
+        assert_eq!(consts.len(), 1);
+        assert_eq!(sm.get(consts[0].0), "CAPACITY");
+        assert!(matches!(functions[0].return_type.kind(), DataTypeKind::Within(_, _)));
+    }
+
+
     #[test]
     fn disabled_cfg_items_are_omitted_before_import_collection() {
         let arena = Arena::new();
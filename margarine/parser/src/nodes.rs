@@ -6,6 +6,7 @@ pub mod err;
 
 use common::{source::{FileData, SourceRange}, string_map::StringIndex, ImmutableData};
 use errors::ErrorId;
+use lexer::Literal;
 use sti::{arena::Arena, key::Key, slice::KSlice, vec::KVec};
 
 use self::{decl::{Decl, DeclId}, expr::{Expr, ExprId}, stmt::{Stmt, StmtId}};
@@ -66,6 +67,7 @@ impl<'a> AST<'a> {
     pub fn expr(&self, expr: ExprId) -> Expr<'a> { self.exprs[expr].0 }
     pub fn decl(&self, decl: DeclId) -> Decl<'a> { self.decls[decl].0 }
     pub fn set_decl(&mut self, decl_id: DeclId, decl: Decl<'a>) { self.decls[decl_id].0 = decl }
+    pub fn set_expr(&mut self, expr_id: ExprId, expr: Expr<'a>) { self.exprs[expr_id].0 = expr }
 
 
     pub fn stmts(&self) -> &KSlice<StmtId, (Stmt<'a>, SourceRange)> {
@@ -112,8 +114,18 @@ pub struct Pattern<'out> {
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PatternKind<'out> {
+    /// A binding or, when matching on an enum which has
+    /// a variant of the same name, that variant
     Variable(StringIndex),
-    Tuple(&'out [StringIndex]),
+    Tuple(&'out [Pattern<'out>]),
+    /// `variant` or `variant(payload)`
+    Variant(StringIndex, Option<&'out Pattern<'out>>),
+    /// `_`
+    Wildcard,
+    /// An integer or a string
+    Literal(Literal),
+    /// `a | b`
+    Or(&'out [Pattern<'out>]),
 }
 
 
@@ -3,7 +3,7 @@ use errors::ErrorId;
 use lexer::Literal;
 use sti::define_key;
 
-use crate::{nodes::NodeId, Block, DataType};
+use crate::{nodes::{expr::ExprId, NodeId}, Block, DataType};
 
 define_key!(pub DeclId(u32));
 
@@ -112,7 +112,15 @@ pub enum Decl<'a> {
         name: StringIndex,
         header: SourceRange,
         generics: &'a [DeclGeneric<'a>],
+        /// The traits every implementor must also implement
+        supertraits: &'a [DataType<'a>],
         functions: &'a [FunctionSignature<'a>],
+        /// `Decl::Function`s for the methods that have a default body
+        defaults: &'a [DeclId],
+        /// Associated types, `type Name`
+        types: &'a [(StringIndex, SourceRange)],
+        /// Associated constants, `const NAME: T`
+        consts: &'a [(StringIndex, DataType<'a>, SourceRange)],
     },
 
     Alias {
@@ -123,6 +131,24 @@ pub enum Decl<'a> {
         data_type: DataType<'a>,
     },
 
+    /// `const NAME: T = expr;`, evaluated at compile time
+    Const {
+        visibility: Visibility,
+        name: StringIndex,
+        header: SourceRange,
+        data_type: DataType<'a>,
+        expr: ExprId,
+    },
+
+    /// `static NAME: T = expr;`, initialised once before `main`
+    Static {
+        visibility: Visibility,
+        name: StringIndex,
+        header: SourceRange,
+        data_type: DataType<'a>,
+        expr: ExprId,
+    },
+
     Attribute {
         attr: Attribute<'a>,
         decl: DeclId,
@@ -6,7 +6,7 @@ use sti::define_key;
 
 use crate::DataType;
 
-use super::NodeId;
+use super::{NodeId, Pattern};
 
 define_key!(pub ExprId(u32));
 
@@ -49,7 +49,7 @@ pub enum Expr<'a> {
 
     Match {
         value: ExprId,
-        mappings: &'a [MatchMapping],
+        mappings: &'a [MatchMapping<'a>],
     },
 
     Block {
@@ -90,11 +90,19 @@ pub enum Expr<'a> {
 
     Loop {
         body: Block<'a>,
+        label: Option<StringIndex>,
+        /// desugared from a `while`, which can't break with a value
+        is_while: bool,
     },
     
     Return(ExprId),
-    Continue,
-    Break,
+    Continue {
+        label: Option<StringIndex>,
+    },
+    Break {
+        label: Option<StringIndex>,
+        value: Option<ExprId>,
+    },
 
     Tuple(&'a [ExprId]),
 
@@ -119,31 +127,50 @@ pub struct CallArgument {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, ImmutableData)]
-pub struct MatchMapping {
-    variant: StringIndex,
-    binding: StringIndex,
-    binding_range: SourceRange,
+pub struct MatchMapping<'a> {
+    pattern: Pattern<'a>,
+    guard: Option<ExprId>,
     range: SourceRange,
     expr: ExprId,
+    /// Set on the `_` arm an `if let` or
+    /// `while let` is desugared with
+    fallback: Option<Fallback>,
+}
+
+
+/// What the desugared `_` arm of an `if let` runs
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Fallback {
+    /// the `else` block, or the `break` of a `while let`
+    Else,
+    /// a unit, the `if let` had no `else`
+    MissingElse,
 }
 
 
-impl MatchMapping {
+impl<'a> MatchMapping<'a> {
     pub fn new(
-        variant: StringIndex, 
-        binding: StringIndex, 
-        binding_range: SourceRange,
+        pattern: Pattern<'a>,
+        guard: Option<ExprId>,
         source_range: SourceRange, 
         expression: ExprId,
     ) -> Self { 
         Self { 
-            variant, 
-            binding, 
+            pattern,
+            guard,
             expr: expression,
             range: source_range, 
-            binding_range,
+            fallback: None,
         } 
     }
+
+
+    pub fn fallback_arm(pattern: Pattern<'a>, expression: ExprId, fallback: Fallback) -> Self {
+        Self {
+            fallback: Some(fallback),
+            ..Self::new(pattern, None, pattern.source(), expression)
+        }
+    }
 }
 
 
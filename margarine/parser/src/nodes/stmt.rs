@@ -1,3 +1,4 @@
+use common::string_map::StringIndex;
 use sti::define_key;
 
 use crate::{nodes::{decl::Attribute, NodeId, Pattern}, Block, DataType};
@@ -27,6 +28,7 @@ pub enum Stmt<'a> {
         binding: Pattern<'a>,
         expr: ExprId,
         body: Block<'a>,
+        label: Option<StringIndex>,
     },
 
     Attribute {
@@ -1,10 +1,10 @@
-use common::{buffer::Buffer, source::SourceRange, string_map::{StringIndex, StringMap}, Once};
+use common::{buffer::Buffer, hashables::NonNaNF64, source::SourceRange, string_map::{StringIndex, StringMap}, Once};
 use lexer::Literal;
 use errors::ErrorId;
-use parser::{dt::{DataType, DataTypeKind}, nodes::{decl::{AttributeValue, Decl, DeclId, FunctionSignature, UseItem, UseItemKind, Visibility}, expr::{BinaryOperator, Expr, ExprId, UnaryOperator}, stmt::{Stmt, StmtId}, NodeId, Pattern, PatternKind}};
-use sti::{alloc::GlobalAlloc, key::Key, vec::{KVec, Vec}};
+use parser::{dt::{DataType, DataTypeKind}, nodes::{decl::{AttributeValue, Decl, DeclId, FunctionSignature, UseItem, UseItemKind, Visibility}, expr::{BinaryOperator, Block, Expr, ExprId, Fallback, UnaryOperator}, stmt::{Stmt, StmtId}, NodeId, Pattern, PatternKind}};
+use sti::{alloc::GlobalAlloc, key::Key, vec::Vec};
 
-use crate::{errors::Error, namespace::{Namespace, NamespaceId, SymbolGetResult}, scope::{FunctionScope, GenericsScope, Scope, ScopeId, ScopeKind, VariableScope}, syms::{containers::{Container, ContainerKind}, func::{FunctionArgument, FunctionKind, FunctionTy}, sym_map::{BoundedGeneric, Generic, GenericKind, SymbolId, SymbolMap, TraitImplEntry}, ty::Type, Symbol, SymbolKind, Trait}, AnalysisResult, TyChecker};
+use crate::{c_header, errors::Error, lints::BindingKind, namespace::{Namespace, NamespaceId, SymbolGetResult}, scope::{FunctionScope, GenericsScope, Scope, ScopeId, ScopeKind, VariableScope}, syms::{containers::{Container, ContainerKind}, func::{FunctionArgument, FunctionKind, FunctionTy}, sym_map::{BoundedGeneric, Generic, GenericKind, SymbolId, SymbolMap, TraitImplEntry}, ty::Type, Global, Symbol, SymbolKind, Trait}, patterns::Pat, AnalysisResult, TyChecker};
 
 impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str> {
     pub fn block(&mut self, path: StringIndex, scope: ScopeId, body: &[NodeId]) -> AnalysisResult {
@@ -29,13 +29,22 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
         // Compute types & functions
         self.compute_types(path, scope, namespace, body, None);
         self.validate_types(path, scope, namespace, body, None);
+        self.resolve_pending_dyns();
 
         // Analyze all nodes
         let mut last_node = None;
         let mut has_never = false;
-        for node in body.iter() {
+        for (i, node) in body.iter().enumerate() {
             let eval = self.node(path, &mut scope, namespace, *node);
-            if eval.ty.is_never(&mut self.syms) { has_never = true };
+            if eval.ty.is_never(&mut self.syms) && !has_never {
+                has_never = true;
+
+                let rest = body[i+1..].iter().filter(|n| !matches!(n, NodeId::Decl(_)));
+                if let (Some(first), Some(last)) = (rest.clone().next(), rest.last()) {
+                    let source = SourceRange::new(self.ast.range(*first).start(), self.ast.range(*last).end());
+                    self.unreachable_code(source, self.ast.range(*node));
+                }
+            };
             last_node = Some(eval);
         }
 
@@ -66,34 +75,13 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 | Decl::Alias { visibility, name, header, gens: generics, .. }
                 | Decl::Trait { visibility, name, header, generics, .. }
                 | Decl::Function { visibility, sig: FunctionSignature { name, source: header, generics, .. }, .. }=> {
-                    if let Some(sym) = ns.get_sym(name) {
-                        let err = Error::NameIsAlreadyDefined {
-                            source: header, name };
-                        let err = self.error(*n, err);
-                        ns = self.namespaces.get_ns_mut(ns_id);
-
-                        if sym.is_ok() { ns.set_err_sym(name, err) }
-
-                        continue
-                    }
-
-                    if matches!(name, StringMap::ITER_NEXT_FUNC)
-                        && !matches!(decl, Decl::Function { .. }) {
-                        self.error(*n, Error::NameIsReservedForFunctions { source: header });
-                    }
-
-                    let path = self.string_map.concat(path, name);
-                    let pend = self.syms.pending(&mut self.namespaces, Some(ns_id), path, generics.len() + gen_count);
-                    ns = self.namespaces.get_ns_mut(ns_id);
+                    self.collect_name(path, ns_id, id, visibility, name, header, generics.len() + gen_count);
+                },
 
-                    let result = ns.add_sym(
-                        &mut self.errors, *n, range, name,
-                        pend, visibility
-                    );
 
-                    if let Err(e) = result {
-                        self.set_error(id, e);
-                    }
+                | Decl::Const { visibility, name, header, .. }
+                | Decl::Static { visibility, name, header, .. } => {
+                    self.collect_name(path, ns_id, id, visibility, name, header, 0);
                 },
 
 
@@ -115,6 +103,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
                         let path = self.string_map.concat(path, f.name());
                         let pend = self.syms.pending(&mut self.namespaces, Some(ns_id), path, f.gens().len());
+                        self.type_info.set_sym_decl(pend, id, f.range());
                         ns = self.namespaces.get_ns_mut(ns_id);
 
 
@@ -138,6 +127,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
                     let sym = self.syms.pending(&mut self.namespaces, Some(ns_id), path, 0);
                     self.syms.add_sym(sym, Symbol::new(name, &[], SymbolKind::Namespace));
+                    self.type_info.set_sym_decl(sym, id, header);
 
                     let module_ns = self.syms.as_ns(sym);
 
@@ -172,6 +162,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     if matches!(attr.identifier(), Some(name) if self.string_map.get(name) == "silent") {
                         self.silent_ranges.push(self.ast.range(decl));
                     }
+                    self.lint_attr(attr, self.ast.range(decl));
                     self.collect_names(path, ns_id, &[decl.into()], gen_count);
                 },
 
@@ -185,6 +176,46 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
     }
 
 
+    fn collect_name(
+        &mut self, path: StringIndex, ns_id: NamespaceId, id: DeclId,
+        visibility: Visibility, name: StringIndex, header: SourceRange, gen_count: usize,
+    ) {
+        let n = NodeId::Decl(id);
+        let range = self.ast.range(n);
+        let ns = self.namespaces.get_ns_mut(ns_id);
+
+        if let Some(sym) = ns.get_sym(name) {
+            let err = Error::NameIsAlreadyDefined {
+                source: header, name };
+            let err = self.error(n, err);
+            let ns = self.namespaces.get_ns_mut(ns_id);
+
+            if sym.is_ok() { ns.set_err_sym(name, err) }
+
+            return
+        }
+
+        if matches!(name, StringMap::ITER_NEXT_FUNC)
+            && !matches!(self.ast.decl(id), Decl::Function { .. }) {
+            self.error(n, Error::NameIsReservedForFunctions { source: header });
+        }
+
+        let path = self.string_map.concat(path, name);
+        let pend = self.syms.pending(&mut self.namespaces, Some(ns_id), path, gen_count);
+        self.type_info.set_sym_decl(pend, id, header);
+        let ns = self.namespaces.get_ns_mut(ns_id);
+
+        let result = ns.add_sym(
+            &mut self.errors, n, range, name,
+            pend, visibility
+        );
+
+        if let Err(e) = result {
+            self.set_error(id, e);
+        }
+    }
+
+
     pub fn collect_impls(&mut self, path: StringIndex, scope: ScopeId, ns_id: NamespaceId, nodes: &[NodeId]) {
         for &n in nodes {
             let NodeId::Decl(id) = n
@@ -279,6 +310,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         generics: gens,
                         declaration: Some(id),
                         bound_error: None,
+                        types: &[],
                     });
                 },
 
@@ -368,8 +400,10 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 let result = scope.find_sym_from(item.name(), &self.scopes, &mut self.syms, &self.namespaces, ns_id);
                 let import_sym = self.convert_symbol_get_result(node, item.name(), item.range(), result);
                 let ns = self.namespaces.get_ns_mut(ns_id);
-                if let Err(e) = ns.add_sym(&mut self.errors, node, item.range(), alias, import_sym, visibility) {
-                    ns.set_err_sym(item.name(), e);
+                match ns.add_sym(&mut self.errors, node, item.range(), alias, import_sym, visibility) {
+                    Ok(()) if visibility != Visibility::Public => self.declare_import(ns_id, alias, item.range()),
+                    Ok(()) => (),
+                    Err(e) => ns.set_err_sym(item.name(), e),
                 }
             },
 
@@ -581,6 +615,30 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 }
 
 
+                | Decl::Const { name, header, data_type, .. }
+                | Decl::Static { name, header, data_type, .. } => {
+                    let ns = self.namespaces.get_ns(ns);
+                    let Ok(tsi) = ns.get_sym(name).unwrap()
+                    else { continue };
+
+                    let ty = self.dt_to_gen(*id, self.scopes.get(scope), data_type, &[]);
+                    let is_const = matches!(decl, Decl::Const { .. });
+
+                    let sym_name = self.string_map.concat(path, name);
+                    let kind = SymbolKind::Global(Global { ty, decl: *id, is_const });
+                    self.syms.add_sym(tsi, Symbol::new(sym_name, &[], kind));
+
+                    if is_const { continue }
+
+                    // the initialiser runs as a startup function
+                    let init = self.syms.pending(&mut self.namespaces, None, sym_name, 0);
+                    let ret = Generic::new(header, GenericKind::Sym(SymbolId::UNIT, &[]));
+                    let func = FunctionTy::new(&[], ret, FunctionKind::StaticInit(tsi), None, &[]);
+                    self.syms.add_sym(init, Symbol::new(sym_name, &[], SymbolKind::Function(func)));
+                    self.startups.push(init);
+                },
+
+
                 Decl::Enum { name, mappings, generics, .. } => {
                     let generics = 
                     match self.resolve_generics(scope, *n, generics) {
@@ -737,7 +795,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 }
 
 
-                Decl::Trait { name, generics, functions, header, .. } => {
+                Decl::Trait { name, generics, supertraits, functions, defaults, header, types, consts, .. } => {
                     let Some(Ok(sym)) = self.namespaces.get_ns(ns).get_sym(name)
                     else { continue };
 
@@ -750,12 +808,24 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         },
                     };
 
+                    // `Self` is bound by the trait itself, so default bodies
+                    // can call its methods & `Self::Name` can be projected
+                    let self_bound = {
+                        let mut args = Buffer::new(self.output, trait_gens.len());
+                        for g in trait_gens {
+                            args.push(Generic::new(header, GenericKind::Generic(*g)));
+                        }
+
+                        let bound = Generic::new(header, GenericKind::Sym(sym, args.leak()));
+                        BoundedGeneric::new(StringMap::SELF_TY, self.output.alloc_new([bound]))
+                    };
+
                     let mut scope = self.scopes.push(
                         Scope::new(
                             scope, 
                             ScopeKind::AliasDecl(
                                 StringMap::SELF_TY, 
-                                Generic::new(header, GenericKind::Generic(BoundedGeneric::new(StringMap::SELF_TY, &[]))))));
+                                Generic::new(header, GenericKind::Generic(self_bound)))));
 
                     if !trait_gens.is_empty() {
                         let mut vec = Buffer::new(&*self.output, trait_gens.len());
@@ -769,6 +839,24 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         scope = self.scopes.push(Scope::new(scope, ScopeKind::Generics(gscope)));
                     }
 
+                    let mut supers = Buffer::new(self.output, supertraits.len());
+                    for st in supertraits {
+                        supers.push(self.dt_to_gen(*id, self.scopes.get(scope), *st, trait_gens));
+                    }
+
+                    let mut assoc_types = Buffer::new(self.output, types.len());
+                    for (name, _) in types {
+                        assoc_types.push(*name);
+                    }
+
+                    let mut assoc_consts = Buffer::new(self.output, consts.len());
+                    for (name, dt, _) in consts {
+                        assoc_consts.push((*name, self.dt_to_gen(*id, self.scopes.get(scope), *dt, trait_gens)));
+                    }
+
+                    let trait_path = self.string_map.concat(path, name);
+                    let mut default_syms = Buffer::new(self.output, defaults.len());
+
                     let mut funcs = sti::vec::Vec::with_cap_in(self.output, functions.len());
                     for f in functions {
                         let mut args = Buffer::new(self.output, f.arguments.len());
@@ -801,14 +889,65 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         }
 
                         let ret = self.dt_to_gen(*id, self.scopes.get(scope), f.return_type, all_gens);
+                        let args = args.leak();
+
+                        funcs.push((f.name, FunctionTy::new(args, ret, FunctionKind::Trait, None, gens)));
 
-                        funcs.push((f.name, FunctionTy::new(args.leak(), ret, FunctionKind::Trait, None, gens)));
+                        let default = defaults.iter().copied().find(|decl| {
+                            matches!(self.ast.decl(*decl), Decl::Function { sig, .. } if sig.name == f.name)
+                        });
+
+                        let Some(decl) = default
+                        else { continue };
+
+                        let mut default_gens = Buffer::new(&*self.output, 1 + all_gens.len());
+                        default_gens.push(self_bound);
+                        for g in all_gens { default_gens.push(*g); }
+                        let default_gens = default_gens.leak();
+
+                        let func_path = self.string_map.concat(trait_path, f.name);
+                        let func_sym = self.syms.pending(&mut self.namespaces, None, func_path, default_gens.len());
+                        self.type_info.set_sym_decl(func_sym, decl, f.source);
+
+                        let func = FunctionTy::new(args, ret, FunctionKind::UserDefined, Some(decl), gens);
+                        self.syms.add_sym(func_sym, Symbol::new(func_path, default_gens, SymbolKind::Function(func)));
+                        default_syms.push((f.name, func_sym));
                     }
 
+                    let funcs = funcs.leak();
+                    let assoc_consts = assoc_consts.leak();
                     self.syms.add_sym(sym, Symbol::new(name, trait_gens, SymbolKind::Trait(Trait {
-                        funcs: funcs.leak(),
+                        funcs,
+                        supertraits: supers.leak(),
+                        defaults: default_syms.leak(),
+                        types: assoc_types.leak(),
+                        consts: assoc_consts,
                         synthesis: crate::syms::TraitSynthesis::None,
                     })));
+
+                    // `Self::Name` can only be checked now that the trait is computed
+                    let mut assocs = vec![];
+                    for (_, func) in funcs.iter() {
+                        for arg in func.args() {
+                            arg.symbol().collect_assoc(&mut assocs);
+                        }
+                        func.ret().collect_assoc(&mut assocs);
+                    }
+
+                    for (_, ty) in assoc_consts.iter() {
+                        ty.collect_assoc(&mut assocs);
+                    }
+
+                    for assoc in assocs {
+                        let GenericKind::Assoc(base, name) = assoc.kind()
+                        else { unreachable!() };
+                        let GenericKind::Generic(base) = base.kind()
+                        else { continue };
+
+                        if self.syms.traits_declare_assoc(base.bounds.iter().filter_map(|b| b.sym()), name) == Some(false) {
+                            self.error(*id, Error::AssocTypeNotFound { source: assoc.range(), name });
+                        }
+                    }
                 }
 
 
@@ -843,6 +982,47 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     self.compute_types(path, scope, ns, &body, Some((sym, gens, None)));
                 }
 
+
+                // the rest of the body is computed when it's analysed but
+                // associated types are needed wherever the impl is used
+                Decl::ImplTrait { body, .. } => {
+                    let Some(&(_, ty, gens)) = self.type_info.impls.get(id)
+                    else { continue };
+
+                    let Some(sym) = ty.sym()
+                    else { continue };
+
+                    let scope = self.scopes.push(Scope::new(scope, ScopeKind::AliasDecl(StringMap::SELF_TY, ty)));
+
+                    let mut types = Buffer::new(self.output, body.len());
+                    for node in body.iter() {
+                        let NodeId::Decl(decl) = node
+                        else { continue };
+                        let Decl::Alias { name, gens: alias_gens, data_type, header, .. } = self.ast.decl(*decl)
+                        else { continue };
+                        if !alias_gens.is_empty() { continue }
+
+                        let assoc = self.dt_to_gen(*decl, self.scopes.get(scope), data_type, gens);
+
+                        let mut assocs = vec![];
+                        assoc.collect_assoc(&mut assocs);
+                        let is_recursive = assocs.iter().any(|assoc| matches!(assoc.kind(), GenericKind::Assoc(base, _) if *base == ty));
+                        if is_recursive {
+                            self.error(*decl, Error::RecursiveAlias(header));
+                            continue;
+                        }
+
+                        types.push((name, assoc));
+                    }
+
+                    let types = types.leak();
+                    if let Some(entry) = self.syms.traits(sym).values_mut()
+                        .flatten()
+                        .find(|entry| entry.declaration == Some(*id)) {
+                        entry.types = types;
+                    }
+                }
+
                 Decl::Attribute { decl, .. } => {
                     self.compute_types(path, scope, ns, &[decl.into()], impl_block);
                 },
@@ -881,6 +1061,180 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
     }
 
 
+    /// Type checks the body of the function `func_id` declared by `n`
+    fn function_body(
+        &mut self, scope: ScopeId, n: DeclId, func_id: SymbolId,
+        sig: FunctionSignature<'_>, body: Block<'_>,
+    ) {
+        // we need a scope that'd fake the generics
+        let sym = self.syms.sym(func_id);
+        let SymbolKind::Function(func) = sym.kind()
+        else { unreachable!() };
+
+        let generics = self.generic_placeholders(n, sig.source, sym.generics());
+
+        // fake args
+        let gscope = GenericsScope::new(generics);
+        let mut scope = Scope::new(scope, ScopeKind::Generics(gscope));
+
+        for (a, decl_arg) in func.args().iter().zip(sig.arguments.iter()) {
+            let ty = a.symbol().to_ty(generics, &mut self.syms);
+
+            let vs = VariableScope::new(a.name(), ty, true, decl_arg.range());
+            let parent = self.scopes.push(scope);
+            self.declare_binding(parent, vs, BindingKind::Other);
+            scope = Scope::new(Some(parent), ScopeKind::VariableScope(vs))
+        }
+
+        let ret = func.ret().to_ty(generics, &mut self.syms);
+
+        // func scope
+        let fs = FunctionScope::new(ret, sig.return_type.range());
+        scope = Scope::new(Some(self.scopes.push(scope)), ScopeKind::Function(fs));
+
+        let scope = self.scopes.push(scope);
+
+        // GO GO GO
+        let flow = self.control_flow.suspend();
+        let mut anal = self.block(sym.name(), scope, &*body);
+        self.control_flow.restore(flow);
+
+        if let Some(&NodeId::Expr(tail)) = body.last() {
+            anal = self.coerce_literal(tail, anal, ret);
+        }
+
+        if !anal.ty.eq(&mut self.syms, ret) {
+            let item = 
+            body.last()
+                .map(|n| self.ast.range(*n))
+                .unwrap_or(body.range());
+
+            self.error(n, Error::FunctionBodyAndReturnMismatch {
+                header: sig.source, item,
+                return_type: ret, body_type: anal.ty });
+        }
+    }
+
+
+    /// Creates a placeholder type for each generic which implements
+    /// the generic's bounds and their supertraits
+    fn generic_placeholders(
+        &mut self, n: DeclId, source: SourceRange,
+        generics: &[BoundedGeneric<'out>],
+    ) -> &'out [(BoundedGeneric<'out>, Type)] {
+        let mut vec = Buffer::new(&*self.output, generics.len());
+        for g in generics {
+            let ty = self.syms.pending(&mut self.namespaces, None, g.name(), 0);
+            let kind = SymbolKind::Container(Container::new(&[], ContainerKind::Generic));
+
+            self.syms.add_sym(ty, Symbol::new(g.name(), &[], kind));
+            let receiver = Generic::new(source, GenericKind::Sym(ty, &[]));
+            for b in g.bounds.iter() {
+                let Some(trait_id) = b.sym()
+                else { continue };
+
+                let bound_error = self.validate_trait_bound(n.into(), *b, &[]);
+                self.syms.traits(ty).entry(trait_id).or_default().push(TraitImplEntry {
+                    namespace: NamespaceId::MAX,
+                    trait_ty: *b,
+                    receiver,
+                    generics: &[],
+                    declaration: None,
+                    bound_error,
+                    types: &[],
+                });
+
+                self.supertrait_bounds(ty, receiver, *b);
+            }
+            vec.push((*g, self.syms.get_ty(ty, &[])));
+        }
+
+        vec.leak()
+    }
+
+
+    /// Makes the placeholder `ty` implement every supertrait of `bound`
+    fn supertrait_bounds(&mut self, ty: SymbolId, receiver: Generic<'out>, bound: Generic<'out>) {
+        let Some(trait_id) = bound.sym()
+        else { return };
+
+        let trait_sym = self.syms.sym(trait_id);
+        let SymbolKind::Trait(tr) = trait_sym.kind()
+        else { return };
+
+        let mut repls = std::vec::Vec::with_capacity(trait_sym.generics().len() + 1);
+        repls.push((StringMap::SELF_TY, receiver));
+        for (g, arg) in trait_sym.generics().iter().zip(bound.gens().unwrap_or(&[])) {
+            repls.push((g.name(), *arg));
+        }
+
+        for supertrait in tr.supertraits {
+            let supertrait = supertrait.rec_replace_all(self.output, &repls);
+            let Some(supertrait_id) = supertrait.sym()
+            else { continue };
+
+            let impls = self.syms.traits(ty).entry(supertrait_id).or_default();
+            // supertraits may be cyclic
+            if impls.iter().any(|entry| entry.trait_ty == supertrait) {
+                continue;
+            }
+
+            impls.push(TraitImplEntry {
+                namespace: NamespaceId::MAX,
+                trait_ty: supertrait,
+                receiver,
+                generics: &[],
+                declaration: None,
+                bound_error: None,
+                types: &[],
+            });
+
+            self.supertrait_bounds(ty, receiver, supertrait);
+        }
+    }
+
+
+    /// Instantiates a trait's default method into the namespace
+    /// of an impl that doesn't provide its own
+    fn impl_default_method(
+        &mut self, ns_id: NamespaceId, path: StringIndex, name: StringIndex,
+        default: SymbolId, trait_ty: Generic<'out>, receiver: Generic<'out>,
+        impl_gens: &'out [BoundedGeneric<'out>],
+    ) {
+        let SymbolKind::Function(func) = self.syms.sym(default).kind()
+        else { unreachable!() };
+
+        let trait_gens = trait_ty.sym().map(|sym| self.syms.sym(sym).generics()).unwrap_or(&[]);
+        let mut repls = std::vec::Vec::with_capacity(trait_gens.len() + 1);
+        repls.push((StringMap::SELF_TY, receiver));
+        for (g, arg) in trait_gens.iter().zip(trait_ty.gens().unwrap_or(&[])) {
+            repls.push((g.name(), *arg));
+        }
+
+        let mut args = Buffer::new(self.output, func.args().len());
+        for arg in func.args() {
+            let symbol = arg.symbol().rec_replace_all(self.output, &repls);
+            args.push(FunctionArgument::new_inout(arg.name(), symbol, arg.is_inout()));
+        }
+
+        let ret = func.ret().rec_replace_all(self.output, &repls);
+
+        let method_gens = func.declared_generics();
+        let mut gens = Buffer::new(&*self.output, impl_gens.len() + method_gens.len());
+        for g in impl_gens { gens.push(*g); }
+        for g in method_gens { gens.push(*g); }
+        let gens = gens.leak();
+
+        let func_path = self.string_map.concat(path, name);
+        let func_sym = self.syms.pending(&mut self.namespaces, Some(ns_id), func_path, gens.len());
+
+        let func = FunctionTy::new(args.leak(), ret, FunctionKind::UserDefined, func.decl(), method_gens);
+        self.syms.add_sym(func_sym, Symbol::new(func_path, gens, SymbolKind::Function(func)));
+        self.namespaces.get_ns_mut(ns_id).add_sym_unchecked(name, func_sym, Visibility::Public);
+        self.type_info.default_methods.insert(func_sym, (receiver, trait_ty));
+    }
+
+
     pub fn decl(&mut self, scope: &mut ScopeId, ns: NamespaceId, n: DeclId) {
         let decl = self.ast.decl(n);
         match decl {
@@ -893,87 +1247,74 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             Decl::Error(_) => unreachable!(),
 
 
-            Decl::Trait { .. } => (),
-
-            
-            Decl::Function { sig, body, .. } => {
-                let ns = self.namespaces.get_ns(ns);
-                let Some(Ok(func_id)) = ns.get_sym(sig.name)
+            Decl::Trait { name, supertraits, .. } => {
+                let Some(Ok(sym)) = self.namespaces.get_ns(ns).get_sym(name)
                 else { return };
 
+                let SymbolKind::Trait(tr) = self.syms.sym(sym).kind()
+                else { return };
 
-                // we need a scope that'd fake the generics
-                let sym = self.syms.sym(func_id);
-                let SymbolKind::Function(func) = sym.kind()
-                else { unreachable!() };
+                for (supertrait, dt) in tr.supertraits.iter().zip(supertraits.iter()) {
+                    let is_trait = supertrait.sym().is_some_and(|sym| {
+                        matches!(self.syms.sym(sym).kind(), SymbolKind::Trait(_) | SymbolKind::Error(_))
+                    });
 
-                let generics = sym.generics();
-                let generics = {
-                    let mut vec = Buffer::new(&*self.output, generics.len());
-                    for g in generics {
-                        let ty = self.syms.pending(&mut self.namespaces, None, g.name(), 0);
-                        let kind = SymbolKind::Container(Container::new(&[], ContainerKind::Generic));
-
-                        self.syms.add_sym(ty, Symbol::new(g.name(), &[], kind));
-                        for b in g.bounds.iter() {
-                            let Some(trait_id) = b.sym()
-                            else { continue };
-
-                            let bound_error = self.validate_trait_bound(n.into(), *b, &[]);
-                            self.syms.traits(ty).entry(trait_id).or_default().push(TraitImplEntry {
-                                namespace: NamespaceId::MAX,
-                                trait_ty: *b,
-                                receiver: Generic::new(sig.source, GenericKind::Sym(ty, &[])),
-                                generics: &[],
-                                declaration: None,
-                                bound_error,
-                            });
-                        }
-                        vec.push((*g, self.syms.get_ty(ty, &[])));
+                    if !is_trait {
+                        self.error(n, Error::SupertraitIsNotATrait(dt.range()));
                     }
-
-                    vec.leak()
-                };
-
-                if sig.name == self.string_map.insert("main") {
-                    self.startups.push(func_id);
                 }
-                
-                // fake args
-                let gscope = GenericsScope::new(generics);
-                let mut scope = Scope::new(*scope, ScopeKind::Generics(gscope));
 
-                for a in func.args() {
-                    let ty = a.symbol().to_ty(generics, &mut self.syms);
+                for (_, func_id) in tr.defaults {
+                    let SymbolKind::Function(func) = self.syms.sym(*func_id).kind()
+                    else { unreachable!() };
 
-                    let vs = VariableScope::new(a.name(), ty, true);
-                    scope = Scope::new(Some(self.scopes.push(scope)), ScopeKind::VariableScope(vs))
+                    let decl = func.decl().unwrap();
+                    let Decl::Function { sig, body, .. } = self.ast.decl(decl)
+                    else { unreachable!() };
+
+                    self.function_body(*scope, decl, *func_id, sig, body);
                 }
+            },
 
-                let ret = func.ret().to_ty(generics, &mut self.syms);
 
-                // func scope
-                let fs = FunctionScope::new(ret, sig.return_type.range());
-                scope = Scope::new(Some(self.scopes.push(scope)), ScopeKind::Function(fs));
+            | Decl::Const { name, header, expr, .. }
+            | Decl::Static { name, header, expr, .. } => {
+                let ns = self.namespaces.get_ns(ns);
+                let Some(Ok(sym_id)) = ns.get_sym(name)
+                else { return };
 
-                let scope = self.scopes.push(scope);
+                let sym = self.syms.sym(sym_id);
+                let SymbolKind::Global(global) = sym.kind()
+                else { return };
+
+                let ty = global.ty.to_ty(&[], &mut self.syms);
 
-                // GO GO GO
                 let flow = self.control_flow.suspend();
-                let anal = self.block(sym.name(), scope, &*body);
+                let anal = self.expr(sym.name(), *scope, expr);
+                let anal = self.coerce_literal(expr, anal, ty);
                 self.control_flow.restore(flow);
 
-                if !anal.ty.eq(&mut self.syms, ret) {
-                    let item = 
-                    body.last()
-                        .map(|n| self.ast.range(*n))
-                        .unwrap_or(body.range());
+                if !anal.ty.eq(&mut self.syms, ty) {
+                    self.error(n, Error::GlobalValueAndTypeDiffer {
+                        value_type: anal.ty, declared_type: ty, source: header });
+                }
+
+                if global.is_const {
+                    self.pending_consts.push((sym_id, expr));
+                }
+            },
 
-                    self.error(n, Error::FunctionBodyAndReturnMismatch {
-                        header: sig.source, item,
-                        return_type: ret, body_type: anal.ty });
+            
+            Decl::Function { sig, body, .. } => {
+                let ns = self.namespaces.get_ns(ns);
+                let Some(Ok(func_id)) = ns.get_sym(sig.name)
+                else { return };
+
+                if sig.name == self.string_map.insert("main") {
+                    self.startups.push(func_id);
                 }
 
+                self.function_body(*scope, n, func_id, sig, body);
             },
 
 
@@ -1031,20 +1372,63 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
                 
 
+                if !tr.supertraits.is_empty() {
+                    let placeholders = self.generic_placeholders(n, header, gens);
+                    let receiver = ty.to_ty(placeholders, &mut self.syms);
+
+                    let mut bindings = std::vec::Vec::with_capacity(trait_sym.generics().len() + 1);
+                    for (g, arg) in trait_sym.generics().iter().zip(trait_ty.gens().unwrap_or(&[])) {
+                        bindings.push((*g, arg.to_ty(placeholders, &mut self.syms)));
+                    }
+                    bindings.push((BoundedGeneric::new(StringMap::SELF_TY, &[]), receiver));
+
+                    for supertrait in tr.supertraits {
+                        let Some(supertrait_id) = supertrait.sym()
+                        else { continue };
+
+                        if !matches!(self.syms.sym(supertrait_id).kind(), SymbolKind::Trait(_)) {
+                            continue;
+                        }
+
+                        let supertrait_ty = supertrait.to_ty(&bindings, &mut self.syms);
+                        if self.syms.type_implements_trait_generic(receiver, supertrait_ty) {
+                            continue;
+                        }
+
+                        let error = self.error(n, Error::TypeDoesntImplTrait {
+                            source: header, ty: receiver, tr: supertrait_id });
+
+                        if let Some(entry) = self.syms.traits(sym)
+                            .get_mut(&trait_sym_id)
+                            .and_then(|impls| impls.iter_mut().find(|entry| entry.declaration == Some(n)))
+                        {
+                            entry.bound_error.get_or_insert(error);
+                        }
+                    }
+                }
+
                 let path = trait_sym.name();
 
-                let ns_id = self.syms.traits(sym).get(&trait_sym_id)
+                let entry = *self.syms.traits(sym).get(&trait_sym_id)
                     .and_then(|impls| impls.iter().find(|entry| entry.declaration == Some(n)))
-                    .unwrap().namespace;
+                    .unwrap();
+                let ns_id = entry.namespace;
                 let scope = Scope::new(*scope, ScopeKind::ImplicitNamespace(ns_id));
                 let scope = self.scopes.push(scope);
 
                 let scope = Scope::new(scope, ScopeKind::AliasDecl(StringMap::SELF_TY, ty));
                 let mut scope = self.scopes.push(scope);
 
-                self.collect_names(path, ns_id, &body, gens.len());
-                self.collect_impls(path, scope, ns_id, &body);
-                self.compute_types(path, scope, ns_id, &body, Some((sym, gens, Some(trait_sym.name()))));
+                // associated types were computed with the enclosing block
+                let items = body.iter()
+                    .copied()
+                    .filter(|node| !matches!(node, NodeId::Decl(decl)
+                        if matches!(self.ast.decl(*decl), Decl::Alias { gens, .. } if gens.is_empty())))
+                    .collect::<std::vec::Vec<_>>();
+
+                self.collect_names(path, ns_id, &items, gens.len());
+                self.collect_impls(path, scope, ns_id, &items);
+                self.compute_types(path, scope, ns_id, &items, Some((sym, gens, Some(trait_sym.name()))));
 
 
                 for node in body.iter() {
@@ -1058,7 +1442,14 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 for (name, ft) in tr.funcs.iter() {
                     let Some(sym) = ns.get_sym(*name)
                     else {
-                        missing.push(*name);
+                        match tr.defaults.iter().find(|(default, _)| default == name) {
+                            Some((_, default)) => {
+                                self.impl_default_method(ns_id, path, *name, *default, trait_ty, ty, gens);
+                                ns = self.namespaces.get_ns_mut(ns_id);
+                            },
+
+                            None => { missing.push(*name); },
+                        }
                         continue;
                     };
 
@@ -1090,7 +1481,8 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     }
 
                     for (arg, trait_arg) in f.args().iter().zip(ft.args()) {
-                        let arg_ty = arg.symbol().rec_replace(self.output, StringMap::SELF_TY, ty);
+                        let arg_ty = arg.symbol().rec_replace(self.output, StringMap::SELF_TY, ty)
+                            .replace_assoc(self.output, ty, entry.types);
                         let mut farg = trait_arg.symbol().rec_replace(self.output, StringMap::SELF_TY, ty);
 
                         if let Some(trait_args) = trait_ty.gens() {
@@ -1099,6 +1491,8 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                             }
                         }
 
+                        let farg = farg.replace_assoc(self.output, ty, entry.types);
+
                         if arg.is_inout() != trait_arg.is_inout() || arg_ty != farg {
                             let decl = f.decl().unwrap();
 
@@ -1108,7 +1502,8 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         }
                     }
 
-                    let arg = f.ret().rec_replace(self.output, StringMap::SELF_TY, ty);
+                    let arg = f.ret().rec_replace(self.output, StringMap::SELF_TY, ty)
+                        .replace_assoc(self.output, ty, entry.types);
                     let mut farg = ft.ret().rec_replace(self.output, StringMap::SELF_TY, ty);
 
                     if let Some(trait_args) = trait_ty.gens() {
@@ -1117,6 +1512,8 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         }
                     }
 
+                    let farg = farg.replace_assoc(self.output, ty, entry.types);
+
                     if arg != farg {
                         let decl = f.decl().unwrap();
 
@@ -1131,6 +1528,43 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 if !missing.is_empty() {
                     self.error(n, Error::MissingFuncs { source: header, fields: missing });
                 }
+
+
+                let mut missing = sti::vec![];
+                for name in tr.types.iter() {
+                    if !entry.types.iter().any(|(n, _)| n == name) {
+                        missing.push(*name);
+                    }
+                }
+
+                for (name, const_ty) in tr.consts.iter() {
+                    let ns = self.namespaces.get_ns(ns_id);
+                    let Some(sym) = ns.get_sym(*name)
+                    else { missing.push(*name); continue };
+
+                    let Ok(sym_id) = sym
+                    else { continue };
+
+                    let SymbolKind::Global(global) = self.syms.sym(sym_id).kind()
+                    else { missing.push(*name); continue };
+
+                    let mut expected = const_ty.rec_replace(self.output, StringMap::SELF_TY, ty);
+                    if let Some(trait_args) = trait_ty.gens() {
+                        for (t_gen, t_arg) in trait_sym.generics().iter().zip(trait_args.iter()) {
+                            expected = expected.rec_replace(self.output, t_gen.name(), *t_arg);
+                        }
+                    }
+
+                    let expected = expected.replace_assoc(self.output, ty, entry.types);
+                    if global.ty != expected {
+                        let source = self.ast.range(global.decl);
+                        self.error(global.decl, Error::InvalidArgument { source });
+                    }
+                }
+
+                if !missing.is_empty() {
+                    self.error(n, Error::MissingAssocItems { source: header, items: missing });
+                }
             },
 
 
@@ -1243,6 +1677,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         let Decl::Function { 
                             sig: FunctionSignature {
                                 name,
+                                arguments,
                                 ..
                             }, 
                             .. 
@@ -1257,9 +1692,99 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         let Some(Ok(func)) = self.namespaces.get_ns(ns).get_sym(name)
                         else { return; };
 
+                        // the table is keyed on the arguments so they
+                        // have to be comparable & hashable
+                        let sym = self.syms.sym(func);
+                        let SymbolKind::Function(func_ty) = sym.kind()
+                        else { return; };
+
+                        for (arg, decl_arg) in func_ty.args().iter().zip(arguments.iter()) {
+                            if arg.is_inout() {
+                                self.error(n, Error::CachedInOutArg(decl_arg.range()));
+                                continue;
+                            }
+
+                            if !self.is_hashable(sym.generics(), arg.symbol()) {
+                                self.error(n, Error::UnhashableCachedArg(decl_arg.range()));
+                            }
+                        }
+
                         self.syms.cached_fn(func);
                     }
 
+
+                    Some("export") => {
+                        let name = match attr.params {
+                            [param] => match param.value {
+                                AttributeValue::Literal(Literal::String(name))
+                                if c_header::is_c_identifier(self.string_map.get(name)) => Some(name),
+                                _ => None,
+                            },
+                            _ => None,
+                        };
+
+                        let Some(name) = name
+                        else {
+                            let value = attr.params.first().map(|param| param.range).unwrap_or(attr.range);
+                            self.error(n, Error::InvalidValueForAttr {
+                                attr: (attr.range, attr_name.unwrap()),
+                                value,
+                                expected: "'a string literal holding a C identifier'",
+                            });
+                            return;
+                        };
+
+                        let mut decl_id = decl_id;
+                        while let Decl::Attribute { decl, .. } = self.ast.decl(decl_id) {
+                            decl_id = decl;
+                        }
+
+                        let Decl::Function { sig, .. } = self.ast.decl(decl_id)
+                        else {
+                            let range = self.ast.range(decl_id);
+                            self.error(n, Error::InvalidValueForAttr {
+                                attr: (attr.range, attr_name.unwrap()), value: range, expected: "'a non-generic function'" });
+                            return;
+                        };
+
+                        if !sig.generics.is_empty() {
+                            self.error(n, Error::InvalidValueForAttr {
+                                attr: (attr.range, attr_name.unwrap()), value: sig.source, expected: "'a non-generic function'" });
+                            return;
+                        }
+
+                        let Some(Ok(func)) = self.namespaces.get_ns(ns).get_sym(sig.name)
+                        else { return; };
+
+                        let SymbolKind::Function(func_ty) = self.syms.sym(func).kind()
+                        else { return; };
+
+                        let mut exportable = true;
+                        for (arg, decl_arg) in func_ty.args().iter().zip(sig.arguments.iter()) {
+                            if arg.is_inout() || !c_header::is_exportable(&self.syms, arg.symbol(), false) {
+                                self.error(n, Error::UnexportableType(decl_arg.range()));
+                                exportable = false;
+                            }
+                        }
+
+                        if !c_header::is_exportable(&self.syms, func_ty.ret(), true) {
+                            self.error(n, Error::UnexportableType(sig.return_type.range()));
+                            exportable = false;
+                        }
+
+                        if c_header::is_reserved(self.string_map.get(name))
+                        || self.exports.iter().any(|(_, other)| *other == name) {
+                            self.error(n, Error::DuplicateExport(attr.params[0].range));
+                            exportable = false;
+                        }
+
+                        if exportable {
+                            self.exports.push((func, name));
+                        }
+                    }
+
+                    Some("allow" | "warn" | "deny") => self.validate_lint_attr(n, attr),
+
                     _ => {
                         self.error(n, Error::UnknownAttr(attr.range));
                     }
@@ -1269,6 +1794,34 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
     }
 
 
+    /// Whether a value of type `ty` can key a `@cached` function's
+    /// table, `generics` are the function's own
+    fn is_hashable(&mut self, generics: &[BoundedGeneric<'out>], ty: Generic<'out>) -> bool {
+        let implements = |traits: &mut dyn Iterator<Item=SymbolId>| {
+            let traits = traits.collect::<std::vec::Vec<_>>();
+            traits.contains(&SymbolId::EQ_TRAIT) && traits.contains(&SymbolId::HASH_TRAIT)
+        };
+
+        match ty.kind() {
+            GenericKind::Sym(sym, _) => {
+                if sym.is_int() || sym == SymbolId::CHAR || sym == SymbolId::BOOL { return true }
+                // codegen hashes the bytes, comparing still goes through `Eq`
+                if sym == SymbolId::STR { return self.syms.traits(sym).contains_key(&SymbolId::EQ_TRAIT) }
+                implements(&mut self.syms.traits(sym).keys().copied())
+            },
+
+            GenericKind::Generic(generic) => {
+                let Some(generic) = generics.iter().find(|g| g.name == generic.name)
+                else { return false };
+
+                implements(&mut generic.bounds.iter().filter_map(|b| b.sym()))
+            },
+
+            _ => false,
+        }
+    }
+
+
     pub fn resolve_pattern(
         &mut self, id: NodeId, scope: &mut ScopeId, 
         pattern: Pattern, rhs: AnalysisResult, rhs_range: SourceRange, mutable: bool
@@ -1287,10 +1840,16 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
         //
         let mut result = Once::new();
 
+        let kind = match id {
+            NodeId::Stmt(stmt) if matches!(self.ast.stmt(stmt), Stmt::Variable { .. }) => BindingKind::Variable,
+            _ => BindingKind::Other,
+        };
+
         (|| {
             match pattern.kind() {
                 PatternKind::Variable(name) => {
-                    let vs = VariableScope::new(name, rhs.ty, mutable);
+                    let vs = VariableScope::new(name, rhs.ty, mutable, pattern.source());
+                    self.declare_binding(*scope, vs, kind);
                     let vs = Scope::new(*scope, ScopeKind::VariableScope(vs));
                     *scope = self.scopes.push(vs);
                 },
@@ -1336,12 +1895,21 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     let gens = ty.gens(&mut self.syms);
                     let gens = self.syms.get_gens(gens);
                     for (&item, (_, ty)) in items.iter().zip(gens.iter()) {
-                        let vs = VariableScope::new(item, *ty, mutable);
-                        let vs = Scope::new(*scope, ScopeKind::VariableScope(vs));
-                        *scope = self.scopes.push(vs);
+                        let item_range = item.source();
+                        if let Err(e) = self.resolve_pattern(id, scope, item, AnalysisResult::new(*ty), item_range, mutable) {
+                            result.set(e);
+                        }
                     }
 
                 },
+
+
+                PatternKind::Wildcard => (),
+
+
+                PatternKind::Variant(..)
+                | PatternKind::Literal(_)
+                | PatternKind::Or(_) => unreachable!("refutable patterns are only parsed in 'match'"),
             }
 
         })();
@@ -1365,6 +1933,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 let mut validate_hint = || {
                     if let Some(hint) = hint {
                         let hint = self.dt_to_ty(*scope, id, hint);
+                        rhs_anal = self.coerce_literal(rhs, rhs_anal, hint);
                         if let Some(err) = hint.as_err(&mut self.syms) {
                             rhs_anal.ty.eq(&mut self.syms, hint);
                             rhs_anal.ty = hint;
@@ -1411,12 +1980,14 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             Stmt::UpdateValue { lhs, rhs  } => {
                 let lhs_anal = self.expr(path, *scope, lhs);
                 let rhs_anal = self.expr(path, *scope, rhs);
+                let rhs_anal = self.coerce_literal(rhs, rhs_anal, lhs_anal.ty);
 
                 if !lhs_anal.ty.eq(&mut self.syms, rhs_anal.ty) {
                     self.error(id, Error::ValueUpdateTypeMismatch { lhs: lhs_anal.ty, rhs: rhs_anal.ty, source });
                 }
 
                 let range = self.ast.range(lhs);
+                self.mark_mutated(lhs);
                 if lhs_anal.is_captured && self.is_assignable_place(lhs) {
 
                     self.error(id, Error::CannotMutateCapturedValue { source: range });
@@ -1430,7 +2001,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             },
 
 
-            Stmt::ForLoop { binding, expr, body } => {
+            Stmt::ForLoop { binding, expr, body, label } => {
                 let iter_anal = self.expr(path, *scope, expr);
 
                 // check if the exprs type is an iterable
@@ -1453,7 +2024,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         AnalysisResult::new(err_ty), range, true
                     );
 
-                    self.control_flow.enter_loop();
+                    self.control_flow.enter_loop(label, false);
                     let _ = self.block(path, scope, &body);
                     self.control_flow.exit_loop();
 
@@ -1466,6 +2037,27 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
                 let func = self.syms.sym_ns(sym);
                 let ns = self.namespaces.get_ns(func);
+
+                // `Iterator` impls win over an inherent `__next__`, which is
+                // only the fallback for types that don't implement the trait
+                if self.syms.traits(sym).contains_key(&SymbolId::ITERATOR_TRAIT) {
+                    let binding_ty = self.syms.project(iter_anal.ty, StringMap::ITEM);
+
+                    let scope = Scope::new(*scope, ScopeKind::Loop);
+                    let mut scope = self.scopes.push(scope);
+
+                    let _ = self.resolve_pattern(
+                        id.into(), &mut scope, binding, 
+                        AnalysisResult::new(binding_ty), source, true
+                    );
+
+                    self.control_flow.enter_loop(label, false);
+                    let _ = self.block(path, scope, &body);
+                    self.control_flow.exit_loop();
+
+                    return;
+                }
+
                 let Some(sym) = ns.get_sym(StringMap::ITER_NEXT_FUNC)
                 else { 
                     let range = self.ast.range(expr);
@@ -1480,7 +2072,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         AnalysisResult::new(err_ty), range, true
                     );
 
-                    self.control_flow.enter_loop();
+                    self.control_flow.enter_loop(label, false);
                     let _ = self.block(path, scope, &body);
                     self.control_flow.exit_loop();
 
@@ -1516,7 +2108,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 );
 
 
-                self.control_flow.enter_loop();
+                self.control_flow.enter_loop(label, false);
                 let _ = self.block(path, scope, &body);
                 self.control_flow.exit_loop();
 
@@ -1531,6 +2123,8 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         });
                     }
                     self.silent_ranges.push(self.ast.range(id));
+                } else if self.lint_attr(attr, self.ast.range(id)) {
+                    self.validate_lint_attr(id, attr);
                 } else {
                     self.error(id, Error::UnknownAttr(attr.range));
                 }
@@ -1546,12 +2140,21 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
     }
 
 
-    fn is_assignable_place(&self, expr: ExprId) -> bool {
+    fn is_assignable_place(&mut self, expr: ExprId) -> bool {
         match self.ast.expr(expr) {
             Expr::Identifier(_, _) => true,
 
-            Expr::AccessField { val, .. }
-            | Expr::IndexList { list: val, .. } => self.is_assignable_place(val),
+            Expr::AccessField { val, .. } => self.is_assignable_place(val),
+
+            Expr::IndexList { list, .. } => {
+                // `Index` gives back a value, not a place
+                let is_value = self.type_info.exprs[list].is_some_and(|info| {
+                    matches!(info.ty.sym(&mut self.syms), Ok(sym) if sym != SymbolId::LIST
+                        && sym != SymbolId::NEVER && !self.syms.is_err_sym(sym))
+                });
+
+                !is_value && self.is_assignable_place(list)
+            },
 
             Expr::Unwrap(val)
             | Expr::OrReturn(val) => self.is_assignable_place(val),
@@ -1574,6 +2177,28 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
         }
     }
 
+    /// The element type indexing into `ty` through
+    /// `Index<T>` gives, if it implements it
+    fn index_output(&mut self, ty: Type) -> Option<Type> {
+        let sym = ty.sym(&mut self.syms).ok()?;
+        let impls = self.syms.traits(sym).get(&SymbolId::INDEX_TRAIT)?.clone();
+
+        for entry in impls {
+            let mut bindings = std::vec::Vec::with_capacity(entry.generics.len());
+            if !self.syms.match_impl_type(entry.receiver, ty, entry.generics, &mut bindings) {
+                continue;
+            }
+
+            let Some(&[output]) = entry.trait_ty.gens()
+            else { continue };
+
+            return Some(output.to_ty(&bindings, &mut self.syms));
+        }
+
+        None
+    }
+
+
     fn trait_method_sym(
         &mut self,
         trait_id: SymbolId,
@@ -1845,6 +2470,49 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
     }
 
 
+    /// Types `Ty::NAME` when `NAME` is an associated constant of
+    /// one of the traits `Ty` implements
+    fn assoc_const(
+        &mut self,
+        scope: ScopeId,
+        id: ExprId,
+        sym_id: SymbolId,
+        name: StringIndex,
+    ) -> Option<Type> {
+        let receiver_ty = self.scopes.get(scope).find_qualified_type(&self.scopes)?;
+
+        let mut trait_ids = self.syms.traits(sym_id).keys().copied().collect::<std::vec::Vec<_>>();
+        trait_ids.sort();
+
+        for trait_id in trait_ids {
+            let trait_sym = self.syms.sym(trait_id);
+            let SymbolKind::Trait(tr) = trait_sym.kind() else { continue };
+            let Some(&(_, const_ty)) = tr.consts.iter().find(|x| x.0 == name) else { continue };
+
+            let impls = self.syms.traits(sym_id)[&trait_id].clone();
+            for entry in impls {
+                let mut bindings = std::vec::Vec::with_capacity(entry.generics.len());
+                if !self.syms.match_impl_type(entry.receiver, receiver_ty, entry.generics, &mut bindings) {
+                    continue;
+                }
+
+                let mut ty = const_ty.rec_replace(self.output, StringMap::SELF_TY, entry.receiver);
+                if let Some(trait_args) = entry.trait_ty.gens() {
+                    for (t_gen, t_arg) in trait_sym.generics().iter().zip(trait_args.iter()) {
+                        ty = ty.rec_replace(self.output, t_gen.name(), *t_arg);
+                    }
+                }
+
+                self.type_info.set_acc(id, entry.trait_ty);
+                self.type_info.set_ident(id, Some(sym_id));
+                return Some(ty.to_ty(&bindings, &mut self.syms));
+            }
+        }
+
+        None
+    }
+
+
     pub fn expr(&mut self, path: StringIndex, scope: ScopeId, id: ExprId) -> AnalysisResult {
         let range = self.ast.range(id);
 
@@ -1859,6 +2527,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     lexer::Literal::Float(_)   => AnalysisResult::new(Type::F64),
                     lexer::Literal::String(_)  => AnalysisResult::new(Type::STR),
                     lexer::Literal::Bool(_)    => AnalysisResult::new(Type::BOOL),
+                    lexer::Literal::Char(_)    => AnalysisResult::new(Type::CHAR),
                 }
             },
 
@@ -1867,6 +2536,16 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
 
             Expr::Identifier(ident, gens) => {
+                self.type_info.set_ident_scope(id, scope);
+
+                if let Some(sym_id) = self.scopes.get(scope).find_super(&self.scopes)
+                && let Some(ty) = self.assoc_const(scope, id, sym_id, ident) {
+                    if gens.is_some() {
+                        return Err(self.error(id, Error::GenericLenMismatch { source: range, found: gens.map(|gs| gs.len()).unwrap_or(0), expected: 0 }))
+                    }
+
+                    return Ok(AnalysisResult::new(ty));
+                }
 
                 let mut variable = || {
                     let sym_id = self.scopes.get(scope).find_super(&self.scopes)?;
@@ -1906,6 +2585,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
                 match variable {
                     Ok((variable, is_captured)) => {
+                        self.type_info.set_var(id, variable.source());
                         if gens.is_some() {
                             return Err(self.error(id, Error::GenericLenMismatch { source: range, found: gens.map(|gs| gs.len()).unwrap_or(0), expected: 0 }))
                         }
@@ -1926,6 +2606,18 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         let sym = self.syms.sym(sym_id);
 
                         match sym.kind() {
+                            SymbolKind::Global(global) => {
+                                self.type_info.set_ident(id, Some(sym_id));
+                                if gens.is_some() {
+                                    return Err(self.error(id, Error::GenericLenMismatch { source: range, found: gens.map(|gs| gs.len()).unwrap_or(0), expected: 0 }))
+                                }
+
+                                let mut anal = AnalysisResult::new(global.ty.to_ty(&[], &mut self.syms));
+                                anal.is_mut = false;
+                                return Ok(anal)
+                            },
+
+
                             SymbolKind::Function(func) => {
                                 self.type_info.set_ident(id, Some(sym_id));
 
@@ -2010,10 +2702,13 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     if let Some(ty) = arg.1 { self.dt_to_ty(scope, id, ty) }
                     else { self.syms.new_var(id, arg.0, arg.3) };
 
+                    let vs = VariableScope::new(arg.0, ty, true, arg.3);
+                    self.declare_binding(active_scope, vs, BindingKind::Other);
+
                     active_scope =
                     self.scopes.push(Scope::new(
                         Some(active_scope),
-                        ScopeKind::VariableScope(VariableScope::new(arg.0, ty, true))
+                        ScopeKind::VariableScope(vs)
                     ));
 
                     sargs.push((arg.0, ty, arg.2, arg.3));
@@ -2023,6 +2718,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 // process the body
                 let flow = self.control_flow.suspend();
                 let ret = self.expr(path, active_scope, body);
+                let ret = self.coerce_literal(body, ret, ret_var);
                 self.control_flow.restore(flow);
 
 
@@ -2073,13 +2769,13 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 let lhs_anal = self.expr(path, scope, lhs);
                 let rhs_anal = self.expr(path, scope, rhs);
 
-                if !lhs_anal.ty.is_int(&mut self.syms) {
+                if !lhs_anal.ty.eq(&mut self.syms, Type::I64) {
                     let range = self.ast.range(lhs);
                     return Err(self.error(id, Error::InvalidRange { source: range, ty: lhs_anal.ty }));
                 }
 
 
-                if !rhs_anal.ty.is_int(&mut self.syms) {
+                if !rhs_anal.ty.eq(&mut self.syms, Type::I64) {
                     let range = self.ast.range(rhs);
                     return Err(self.error(id, Error::InvalidRange { source: range, ty: rhs_anal.ty }));
                 }
@@ -2092,6 +2788,8 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             Expr::BinaryOp { operator, lhs, rhs } => {
                 let lhs_anal = self.expr(path, scope, lhs);
                 let rhs_anal = self.expr(path, scope, rhs);
+                let rhs_anal = self.coerce_literal(rhs, rhs_anal, lhs_anal.ty);
+                let lhs_anal = self.coerce_literal(lhs, lhs_anal, rhs_anal.ty);
 
                 lhs_anal.ty.eq(&mut self.syms, rhs_anal.ty);
 
@@ -2148,26 +2846,29 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
 
 
-                let can_trait =
-                   lhs_anal.ty.eq(&mut self.syms, rhs_anal.ty)
-                && operator.is_ecomp();
-
+                // builtin scalars only compare through `Eq`, everything
+                // else goes through the operator's trait
+                let is_scalar = lhs_sym.is_num() || lhs_sym == SymbolId::BOOL || lhs_sym == SymbolId::CHAR;
+                let tr = crate::operator_trait(operator)
+                    .filter(|&(tr, _)| !is_scalar || tr == SymbolId::EQ_TRAIT);
 
-                if !can_trait {
+                let can_trait = lhs_anal.ty.eq(&mut self.syms, rhs_anal.ty);
+                let Some((tr, _)) = tr.filter(|_| can_trait)
+                else {
                     return Err(self.error(id, Error::InvalidBinaryOp {
                         operator, lhs: lhs_anal.ty, rhs: rhs_anal.ty, source: range }));
-
-                }
+                };
 
 
                 let lhs_sym = lhs_anal.ty.sym(&mut self.syms).map_err(|e| self.error(id, e))?;
                 let traits = self.syms.traits(lhs_sym);
 
-                if traits.contains_key(&SymbolId::EQ_TRAIT) {
-                    return Ok(AnalysisResult::new(Type::BOOL));
+                if traits.contains_key(&tr) {
+                    let ty = if operator.is_arith() { lhs_anal.ty } else { Type::BOOL };
+                    return Ok(AnalysisResult::new(ty));
                 }
 
-                return Err(self.error(id, Error::TypeDoesntImplTrait { source: range, ty: lhs_anal.ty, tr: SymbolId::EQ_TRAIT }));
+                return Err(self.error(id, Error::TypeDoesntImplTrait { source: range, ty: lhs_anal.ty, tr }));
             },
 
 
@@ -2180,7 +2881,12 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
                 match operator {
                     UnaryOperator::Not if sym == SymbolId::BOOL => (),
-                    UnaryOperator::Neg if sym.is_num() && sym != SymbolId::BYTE => (),
+                    UnaryOperator::Neg if sym.is_sint() || sym.is_float() => (),
+                    UnaryOperator::Neg if !sym.is_num() => {
+                        if !self.syms.traits(sym).contains_key(&SymbolId::NEG_TRAIT) {
+                            return Err(self.error(id, Error::TypeDoesntImplTrait { source: range, ty: rhs_anal.ty, tr: SymbolId::NEG_TRAIT }));
+                        }
+                    },
                     
                     _ => return Err(self.error(id, Error::InvalidUnaryOp { operator, rhs: rhs_anal.ty, source: range }))
                 }
@@ -2239,73 +2945,60 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             Expr::Match { value, mappings  } => {
                 let anal = self.expr(path, scope, value);
 
-                let sym = anal.ty.sym(&mut self.syms).map_err(|e| self.error(id, e))?;
-
                 if let Some(err) = anal.ty.as_err(&mut self.syms) {
                     return Err(err);
                 }
 
-                let sym = self.syms.sym(sym);
-
-                let SymbolKind::Container(cont) = sym.kind()
-                else {
-                    let range = self.ast.range(value);
-                    return Err(self.error(id, Error::MatchValueIsntEnum { source: range, typ: anal.ty }));
-                };
+                let ret_ty = self.syms.new_var(id, StringMap::RESULT, range);
+                let mut errored = None;
+                let mut pattern_error = None;
+                let mut has_value_branch = false;
+                let mut arms = std::vec::Vec::with_capacity(mappings.len());
+                for m in mappings.iter() {
+                    // the arm is still checked if its pattern is invalid
+                    // so its body gets type info
+                    let mut bindings = vec![];
+                    let pat = match self.lower_pattern(id, m.pattern(), anal.ty, &mut bindings) {
+                        Ok(pat) => pat,
+                        Err(e) => {
+                            let e = self.error(id, e);
+                            pattern_error.get_or_insert(e);
+                            Pat::Any
+                        },
+                    };
 
-                // check if the value is an enum
-                if !matches!(cont.kind(), ContainerKind::Enum) {
-                    let range = self.ast.range(value);
-                    return Err(self.error(id, Error::MatchValueIsntEnum { source: range, typ: anal.ty }));
-                }
+                    arms.push((pat, m.guard().is_some()));
 
-                // check the mapping names
-                for (i, m) in mappings.iter().enumerate() {
-                    let exists = cont.fields().iter().any(|x| {
-                        let name = x.0;
-                        m.variant() == name
-                    });
+                    let mut scope = scope;
+                    for (name, ty, source) in bindings {
+                        let vs = VariableScope::new(name, ty, true, source);
+                        self.declare_binding(scope, vs, BindingKind::Other);
 
-                    if !exists {
-                        return Err(self.error(id, Error::InvalidMatch {
-                            name: m.variant(), range: m.range(), value: anal.ty }));
+                        let vs = Scope::new(Some(scope), ScopeKind::VariableScope(vs));
+                        scope = self.scopes.push(vs);
                     }
 
-                    for o in mappings.iter().skip(i+1) {
-                        if o.variant() == m.variant() {
-                            return Err(self.error(id, Error::DuplicateMatch {
-                                declared_at: m.range(), error_point: o.range() }));
+                    if let Some(guard) = m.guard() {
+                        let guard_anal = self.expr(path, scope, guard);
+                        if !guard_anal.ty.is_err(&mut self.syms)
+                        && !guard_anal.ty.eq(&mut self.syms, Type::BOOL) {
+                            let range = self.ast.range(guard);
+                            self.error(guard, Error::InvalidType {
+                                source: range, found: guard_anal.ty, expected: Type::BOOL });
                         }
                     }
-                }
-
-                
-                let mut missings = Vec::new_in(self.temp);
-                for sm in cont.fields().iter() {
-                    let name = sm.0;
-                    if !mappings.iter().any(|x| x.variant() == name) {
-                        missings.push(name);
-                    }
-                }
-
-                if !missings.is_empty() {
-                    return Err(self.error(id, Error::MissingMatch { name: KVec::from_slice(&missings), range }));
-                }
-
 
-                // ty chck
-                let ret_ty = self.syms.new_var(id, StringMap::RESULT, range);
-                let mut errored = None;
-                let mut has_value_branch = false;
-                for (m, f) in mappings.iter().zip(cont.fields().iter()) {
-                    let gens = anal.ty.gens(&mut self.syms);
-                    let gens = self.syms.get_gens(gens);
-                    let vs = VariableScope::new(m.binding(), f.1.to_ty(gens, &mut self.syms), true);
+                    let anal = self.expr(path, scope, m.expr());
 
-                    let scope = Scope::new(Some(scope), ScopeKind::VariableScope(vs));
-                    let scope = self.scopes.push(scope);
+                    // an `if let` without an `else` is a unit like an `if`
+                    if m.fallback() == Some(Fallback::MissingElse) {
+                        if !ret_ty.eq(&mut self.syms, Type::UNIT) {
+                            let body = self.ast.range(mappings[0].expr());
+                            return Err(self.error(id, Error::IfMissingElse { body: (body, ret_ty) }));
+                        }
+                        continue;
+                    }
 
-                    let anal = self.expr(path, scope, m.expr());
                     if anal.ty.is_err(&mut self.syms) {
                         if errored.is_none() {
                             errored = anal.ty.as_err(&mut self.syms);
@@ -2336,6 +3029,20 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     ret_ty.eq(&mut self.syms, ty);
                 }
 
+                if let Some(err) = pattern_error {
+                    return Err(err);
+                }
+
+                let tree = self.decision_tree(anal.ty, arms);
+                // the `_` an `if let` adds isn't the user's to remove
+                let arms = mappings.iter()
+                    .map(|m| m.fallback().is_none().then(|| m.pattern().source()))
+                    .collect::<std::vec::Vec<_>>();
+                if let Some(err) = self.check_decision_tree(id, anal.ty, &tree, &arms, range) {
+                    return Err(err);
+                }
+
+                self.type_info.set_match(id, tree);
                 AnalysisResult::new(ret_ty)
             },
 
@@ -2408,8 +3115,9 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
                 //dbg!(sym);
                 for f in fields.iter() {
-                    let expr = self.expr(path, scope, f.2);
                     let g = sym_fields.iter().find(|x| x.0 == f.0).unwrap();
+                    let expr = self.expr(path, scope, f.2);
+                    let expr = self.coerce_literal(f.2, expr, g.1);
                     //dbg!(g);
                     //dbg!(expr);
 
@@ -2486,6 +3194,16 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 };
 
 
+                // interpolation holes call `__to_str__`, types without
+                // one fall back to `ToStr::to_str` like the builtin numbers
+                let field_name =
+                if field_name == StringMap::TO_STR_FUNC
+                && self.namespaces.get_ns(self.syms.sym_ns(sym_id)).get_sym(field_name).is_none()
+                && self.find_trait_method_candidate(scope, id, range, Some(expr.ty), sym_id, field_name)?.is_none() {
+                    self.ast.set_expr(id, Expr::AccessField { val, field_name: StringMap::TO_STR, gens: expr_gens });
+                    StringMap::TO_STR
+                } else { field_name };
+
 
                 let ns = self.syms.sym_ns(sym_id);
                 let ns = self.namespaces.get_ns(ns);
@@ -2556,6 +3274,11 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 let Some((t, trait_ty, func, g, impl_generics)) = candidate
                 else {
                     let e = match e {
+                        // interpolation holes are lowered to `__to_str__` calls
+                        Error::FieldDoesntExist { field: StringMap::TO_STR_FUNC, typ, .. } => Error::NoStringConversion {
+                            ty: typ, source: self.ast.range(val),
+                        },
+
                         Error::FieldDoesntExist { source, field, typ, .. } => Error::FieldDoesntExist {
                             source, field, typ,
                             suggested: self.suggest_trait_imports(sym_id, expr.ty, field_name),
@@ -2655,26 +3378,19 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                         source: range, sig_len: func.args().len(), call_len: args.len() + if is_accessor { 1 } else { 0 } }));
                 }
 
-                // find out the args
-                let func_args = {
-                    let mut vec = sti::vec::Vec::with_cap_in(&*pool, func.args().len());
-                    for g in func.args() {
-                        vec.push((g.symbol().to_ty(gens, &mut self.syms), g.is_inout()));
-                    }
-
-                    vec
-                };
-
-                let ret = func.ret().to_ty(gens, &mut self.syms);
-
-                // ty check args
-                for (i, ((source, anal, expr, explicit_inout), (fa, formal_inout))) in args_anals.iter().copied().zip(func_args.iter().copied()).enumerate() {
+                // ty check args, each formal is found out after the ones
+                // before it so associated types of inferred generics resolve
+                for (i, ((source, anal, expr, explicit_inout), arg)) in args_anals.iter().copied().zip(func.args()).enumerate() {
+                    let fa = arg.symbol().to_ty(gens, &mut self.syms);
+                    let formal_inout = arg.is_inout();
+                    let anal = self.coerce_literal(expr, anal, fa);
                     if !anal.ty.eq(&mut self.syms, fa) {
                         self.error(expr, Error::InvalidType {
                             source, found: anal.ty, expected: fa });
                     }
 
                     let is_inout = explicit_inout || (formal_inout && is_accessor && i == 0);
+                    if is_inout { self.mark_mutated(expr) }
                     if is_inout && !formal_inout {
                         self.error(expr, Error::InOutValueWithoutInOutBinding { source });
                     } else if formal_inout && !is_inout {
@@ -2686,6 +3402,8 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     }
                 }
 
+                let ret = func.ret().to_ty(gens, &mut self.syms);
+
                 for (sym_g, (func_g, value)) in sym.generics().iter().zip(gens.iter()) {
                     assert_eq!(sym_g.name(), func_g.name());
 
@@ -2717,6 +3435,31 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     }
                 }
 
+                // `wrapping_*` & `checked_*` only make sense for integers
+                let builtin = self.type_info.ident(lhs_expr).flatten()
+                    .and_then(|sym| match self.syms.sym(sym).kind() {
+                        SymbolKind::Function(func) => Some(func.kind()),
+                        _ => None,
+                    })
+                    .filter(|kind| kind.is_int_builtin());
+
+                if let Some(builtin) = builtin
+                && let Some(&(_, value)) = gens.first()
+                && !value.is_err(&mut self.syms)
+                && !value.is_never(&mut self.syms)
+                && let Ok(value_sym) = value.sym(&mut self.syms)
+                && !value_sym.is_int() {
+                    let operator = match builtin {
+                        FunctionKind::WrappingAdd | FunctionKind::CheckedAdd => BinaryOperator::Add,
+                        FunctionKind::WrappingSub | FunctionKind::CheckedSub => BinaryOperator::Sub,
+                        FunctionKind::WrappingMul | FunctionKind::CheckedMul => BinaryOperator::Mul,
+                        _ => BinaryOperator::Div,
+                    };
+
+                    let err = self.error(id, Error::InvalidBinaryOp { operator, lhs: value, rhs: value, source: range });
+                    return Ok(AnalysisResult::new(self.error_type(err)))
+                }
+
                 self.type_info.set_func_call(id, (sym_id, f_gens));
                 AnalysisResult::new(ret)
             },
@@ -2761,21 +3504,28 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     return Err(err);
                 }
 
+                let scope = Scope::new(scope, ScopeKind::QualifiedTypeNamespace(ty, None));
+                let scope = self.scopes.push(scope);
+                let scope = Scope::new(scope, ScopeKind::ImplicitTrait(sym));
+                let scope = self.scopes.push(scope);
+
                 self.expr(path, scope, action)
             },
 
 
-            Expr::Loop { body } => {
+            Expr::Loop { body, label, is_while } => {
                 let scope = Scope::new(Some(scope), ScopeKind::Loop);
                 let scope = self.scopes.push(scope);
-                self.control_flow.enter_loop();
+                self.control_flow.enter_loop(label, !is_while);
                 self.block(path, scope, &*body);
 
                 let loop_context = self.control_flow.exit_loop();
-                if loop_context.has_break {
-                    AnalysisResult::new(Type::UNIT)
-                } else {
+                if !loop_context.has_break {
                     AnalysisResult::new(Type::NEVER)
+                } else if let Some((value, _)) = loop_context.value {
+                    AnalysisResult::new(value)
+                } else {
+                    AnalysisResult::new(Type::UNIT)
                 }
             },
 
@@ -2784,6 +3534,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 let Some(func) = self.scopes.get(scope).find_curr_func(&self.scopes)
                 else { return Err(self.error(id, Error::OutsideOfAFunction { source: range })) };
                 let ret_anal = self.expr(path, scope, ret);
+                let ret_anal = self.coerce_literal(ret, ret_anal, func.ret);
                 if ret_anal.ty.is_err(&mut self.syms) { return Ok(AnalysisResult::new(ret_anal.ty)) }
                 if ret_anal.ty.is_never(&mut self.syms) { return Ok(AnalysisResult::never()) }
 
@@ -2797,19 +3548,60 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             },
 
 
-            Expr::Continue => {
+            Expr::Continue { label } => {
                 if self.scopes.get(scope).find_loop(&self.scopes).is_none() { 
                     return Err(self.error(id, Error::ContinueOutsideOfLoop(range)))
                 }
 
+                if let Some(label) = label
+                && self.control_flow.target(Some(label)).is_none() {
+                    return Err(self.error(id, Error::UndeclaredLabel { label, source: range }))
+                }
+
                 AnalysisResult::new(Type::NEVER)
             },
 
 
-            Expr::Break => {
-                if self.scopes.get(scope).find_loop(&self.scopes).is_none()
-                || !self.control_flow.mark_break() {
-                    return Err(self.error(id, Error::BreakOutsideOfLoop(range)))
+            Expr::Break { label, value } => {
+                let value_anal = value.map(|value| self.expr(path, scope, value));
+
+                let target =
+                    if self.scopes.get(scope).find_loop(&self.scopes).is_none() { None }
+                    else { self.control_flow.target(label) };
+
+                let Some(target) = target
+                else {
+                    return Err(match label {
+                        Some(label) => self.error(id, Error::UndeclaredLabel { label, source: range }),
+                        None => self.error(id, Error::BreakOutsideOfLoop(range)),
+                    })
+                };
+
+                if value.is_some() && !self.control_flow.loops[target].takes_value {
+                    return Err(self.error(id, Error::BreakWithValueOutsideOfLoop { source: range }))
+                }
+
+                self.control_flow.loops[target].has_break = true;
+
+                let (ty, source) = match (value, value_anal) {
+                    (Some(value), Some(anal)) => (anal.ty, self.ast.range(value)),
+                    _ => (Type::UNIT, range),
+                };
+
+                if ty.is_err(&mut self.syms) { return Ok(AnalysisResult::new(ty)) }
+                if ty.is_never(&mut self.syms) { return Ok(AnalysisResult::never()) }
+
+                match self.control_flow.loops[target].value {
+                    None => self.control_flow.loops[target].value = Some((ty, source)),
+
+                    Some((initial_typ, initial_source)) => {
+                        if initial_typ.ne(&mut self.syms, ty) {
+                            return Err(self.error(id, Error::BreakValuesDiffer {
+                                initial_source, initial_typ,
+                                break_source: source, break_typ: ty,
+                            }))
+                        }
+                    },
                 }
 
                 AnalysisResult::new(Type::NEVER)
@@ -2854,6 +3646,7 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 let mut errored = None;
                 for e in exprs {
                     let expr = self.expr(path, scope, *e);
+                    let expr = self.coerce_literal(*e, expr, ty);
                     if !ty.eq(&mut self.syms, expr.ty) {
                         let range = self.ast.range(*e);
                         let e = self.error(*e, Error::InvalidType { source: range, found: expr.ty, expected: ty });
@@ -2868,16 +3661,24 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             }
 
 
-            Expr::IndexList { list, index } => {
+            Expr::IndexList { list, index: index_expr } => {
                 let list = self.expr(path, scope, list);
-                let index = self.expr(path, scope, index);
+                let index = self.expr(path, scope, index_expr);
 
                 let sym = list.ty.sym(&mut self.syms).map_err(|e| self.error(id, e))?;
 
                 if sym == SymbolId::NEVER || self.syms.is_err_sym(sym) { return Ok(AnalysisResult::new(list.ty)) }
 
                 if sym != SymbolId::LIST {
-                    return Err(self.error(id, Error::IndexOnNonList(range, list.ty)));
+                    let Some(ty) = self.index_output(list.ty)
+                    else { return Err(self.error(id, Error::IndexOnNonList(range, list.ty))) };
+
+                    let index = self.coerce_literal(index_expr, index, Type::I64);
+                    if !index.ty.eq(&mut self.syms, Type::I64) {
+                        return Err(self.error(id, Error::InvalidType { source: range, found: index.ty, expected: Type::I64 }))
+                    }
+
+                    return Ok(AnalysisResult::new(ty));
                 }
 
                 if !index.ty.is_int(&mut self.syms) {
@@ -2899,6 +3700,9 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 let anal = self.expr(path, scope, lhs);
                 let ty = self.dt_to_ty(scope, id, data_type);
 
+                // `5u8` & `5 as u8` type the literal instead of casting it
+                let anal = self.coerce_literal(lhs, anal, ty);
+
                 if anal.ty.eq(&mut self.syms, ty) {
                     return Ok(AnalysisResult::new(ty))
                 }
@@ -2911,8 +3715,26 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                     return Ok(AnalysisResult::new(ty))
                 }
 
+                // a value becomes a `dyn` of any trait its type implements
+                if ty.sym(&mut self.syms) == Ok(SymbolId::DYN) {
+                    let gens = ty.gens(&mut self.syms);
+                    let trait_ty = self.syms.get_gens(gens)[0].1;
+                    let Ok(trait_id) = trait_ty.sym(&mut self.syms)
+                    else { return Ok(AnalysisResult::new(ty)) };
+
+                    if self.syms.trait_implementation(anal.ty, trait_ty).is_none() {
+                        self.error(id, Error::TypeDoesntImplTrait {
+                            source: range, ty: anal.ty, tr: trait_id });
+                    }
+
+                    return Ok(AnalysisResult::new(ty))
+                }
+
                 match (anal.ty.sym(&mut self.syms), ty.sym(&mut self.syms)) {
-                    (Ok(SymbolId::BOOL), Ok(SymbolId::I64)) => (),
+                    (Ok(SymbolId::BOOL), Ok(to)) if to.is_int() => (),
+                    (Ok(SymbolId::CHAR), Ok(to)) if to.is_int() => (),
+                    // only bytes are always a valid char
+                    (Ok(SymbolId::BYTE), Ok(SymbolId::CHAR)) => (),
                     _ => {
                         if !(anal.ty.is_num(&mut self.syms) && ty.is_num(&mut self.syms)) {
                             self.error(id, Error::InvalidCast {
@@ -3044,6 +3866,63 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
     }
 
 
+    /// The number literal `expr` is made of, looking through
+    /// parentheses & negation
+    fn number_literal(&self, expr: ExprId) -> Option<Literal> {
+        match self.ast.expr(expr) {
+            Expr::Literal(lit @ (Literal::Integer(_) | Literal::Float(_))) => Some(lit),
+            Expr::Paren(inner) => self.number_literal(inner),
+            Expr::UnaryOp { operator: UnaryOperator::Neg, rhs } => match self.number_literal(rhs)? {
+                Literal::Integer(v) => Some(Literal::Integer(v.wrapping_neg())),
+                Literal::Float(v) => Some(Literal::Float(NonNaNF64::new(-v.inner()))),
+                _ => unreachable!(),
+            },
+
+            _ => None,
+        }
+    }
+
+
+    /// Gives an unsuffixed number literal the type its context
+    /// expects, `let x: u8 = 5` or `x + 1` where `x: i32`
+    pub(crate) fn coerce_literal(&mut self, expr: ExprId, anal: AnalysisResult, expected: Type) -> AnalysisResult {
+        let Some(lit) = self.number_literal(expr)
+        else { return anal };
+
+        if anal.ty.is_err(&mut self.syms) { return anal }
+
+        let Ok(sym) = expected.instantiate_shallow(&mut self.syms).sym(&mut self.syms)
+        else { return anal };
+
+        match lit {
+            Literal::Integer(value) if sym.is_int() => {
+                if !sym.int_fits(value.into()) {
+                    let source = self.ast.range(expr);
+                    let err = self.error(expr, Error::LiteralOutOfRange { value, ty: expected, source });
+                    return AnalysisResult::new(self.error_type(err));
+                }
+            },
+
+            Literal::Float(_) if sym.is_float() => (),
+
+            _ => return anal,
+        }
+
+        self.retype_literal(expr, expected);
+        AnalysisResult::new(expected)
+    }
+
+
+    fn retype_literal(&mut self, expr: ExprId, ty: Type) {
+        self.type_info.set_expr(expr, ty);
+        match self.ast.expr(expr) {
+            Expr::Paren(inner) => self.retype_literal(inner, ty),
+            Expr::UnaryOp { rhs, .. } => self.retype_literal(rhs, ty),
+            _ => (),
+        }
+    }
+
+
     fn suggest_trait_imports(
         &mut self,
         sym_id: SymbolId,
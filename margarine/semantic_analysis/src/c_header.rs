@@ -0,0 +1,191 @@
+use std::fmt::Write;
+
+use common::string_map::{StringIndex, StringMap};
+
+use crate::syms::{containers::ContainerKind, sym_map::{Generic, GenericKind, SymbolId, SymbolMap}, SymbolKind};
+
+
+/// Whether a value of type `ty` can cross the C ABI of an
+/// `@export`ed function, `unit` only works as a return type
+pub fn is_exportable(syms: &SymbolMap, ty: Generic, is_ret: bool) -> bool {
+    let GenericKind::Sym(sym, &[]) = ty.kind()
+    else { return false };
+
+    if sym == SymbolId::UNIT { return is_ret }
+    if c_primitive(sym).is_some() { return true }
+
+    let Some(symbol) = syms.sym_ok(sym)
+    else { return false };
+
+    let SymbolKind::Container(cont) = symbol.kind()
+    else { return false };
+
+    cont.kind() == ContainerKind::Struct
+    && symbol.generics().is_empty()
+    && !cont.fields().is_empty()
+    && cont.fields().iter().all(|(_, field)| is_exportable(syms, *field, false))
+}
+
+
+/// Whether `name` can name a C function
+pub fn is_c_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+
+/// Whether the generated code already defines `name`, `main`
+/// and the runtime's `margarine…` symbols
+pub fn is_reserved(name: &str) -> bool {
+    name == "main" || name.starts_with("margarine")
+}
+
+
+/// The include guard of the header for the output `stem`, prefixed
+/// so a stem starting with a digit still makes a valid macro
+pub fn include_guard(stem: &str) -> String {
+    let stem = stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>();
+    format!("MARGARINE_{stem}_H")
+}
+
+
+/// The C spelling of a primitive type
+fn c_primitive(sym: SymbolId) -> Option<&'static str> {
+    Some(match sym {
+        SymbolId::I8 => "int8_t",
+        SymbolId::I16 => "int16_t",
+        SymbolId::I32 => "int32_t",
+        SymbolId::I64 => "int64_t",
+        SymbolId::BYTE => "uint8_t",
+        SymbolId::U16 => "uint16_t",
+        SymbolId::U32 => "uint32_t",
+        SymbolId::U64 => "uint64_t",
+        SymbolId::USIZE => "size_t",
+        SymbolId::F32 => "float",
+        SymbolId::F64 => "double",
+        SymbolId::BOOL => "bool",
+        SymbolId::CHAR => "uint32_t",
+        _ => return None,
+    })
+}
+
+
+/// The C spelling of an exportable type, `names` holds the
+/// C names of the structs
+fn c_type<'a>(names: &'a [(SymbolId, String)], ty: Generic) -> &'a str {
+    let sym = ty.sym().unwrap();
+    if sym == SymbolId::UNIT { return "void" }
+
+    c_primitive(sym).unwrap_or_else(|| &names.iter().find(|(s, _)| *s == sym).unwrap().1)
+}
+
+
+/// Names each struct after the last segment of its path, or
+/// the whole path if another struct shares that segment
+fn c_struct_names(string_map: &StringMap, syms: &SymbolMap, structs: &[SymbolId]) -> Vec<(SymbolId, String)> {
+    let path = |sym: SymbolId| string_map.get(syms.sym(sym).name());
+    let last = |sym: SymbolId| path(sym).rsplit("::").next().unwrap();
+
+    structs.iter().map(|sym| {
+        let name =
+        if structs.iter().filter(|other| last(**other) == last(*sym)).count() > 1 { path(*sym) }
+        else { last(*sym) };
+
+        let name = name.replace("::", "_")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        (*sym, name)
+    }).collect()
+}
+
+
+/// Pushes the structs `ty` is built out of, dependencies first
+fn collect_structs(syms: &SymbolMap, ty: Generic, structs: &mut Vec<SymbolId>) {
+    let sym = ty.sym().unwrap();
+    if sym == SymbolId::UNIT || c_primitive(sym).is_some() || structs.contains(&sym) { return }
+
+    let SymbolKind::Container(cont) = syms.sym(sym).kind()
+    else { unreachable!() };
+
+    for (_, field) in cont.fields() {
+        collect_structs(syms, *field, structs);
+    }
+
+    structs.push(sym);
+}
+
+
+/// A C header declaring `exports` by their exported names along
+/// with the structs they pass around, `guard` is the include guard
+pub fn generate(
+    string_map: &StringMap, syms: &SymbolMap,
+    exports: &[(SymbolId, StringIndex)], guard: &str,
+) -> String {
+    let mut structs = vec![];
+    for (func, _) in exports {
+        let SymbolKind::Function(func) = syms.sym(*func).kind()
+        else { unreachable!() };
+
+        for arg in func.args() {
+            collect_structs(syms, arg.symbol(), &mut structs);
+        }
+
+        collect_structs(syms, func.ret(), &mut structs);
+    }
+
+    let names = c_struct_names(string_map, syms, &structs);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "// generated by margarine, don't edit");
+    let _ = writeln!(out, "#ifndef {guard}");
+    let _ = writeln!(out, "#define {guard}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#include <stdbool.h>");
+    let _ = writeln!(out, "#include <stddef.h>");
+    let _ = writeln!(out, "#include <stdint.h>");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#ifdef __cplusplus");
+    let _ = writeln!(out, "extern \"C\" {{");
+    let _ = writeln!(out, "#endif");
+
+    for (sym, name) in &names {
+        let SymbolKind::Container(cont) = syms.sym(*sym).kind()
+        else { unreachable!() };
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "typedef struct {name} {{");
+        for (field, ty) in cont.fields() {
+            let _ = writeln!(out, "    {} {};", c_type(&names, *ty), string_map.get(*field));
+        }
+        let _ = writeln!(out, "}} {name};");
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "// initialises the statics, call it before anything else");
+    let _ = writeln!(out, "void margarineInitStatics(void);");
+    let _ = writeln!(out, "// frees what `@cached` functions kept, call it when done");
+    let _ = writeln!(out, "void margarineFreeCaches(void);");
+
+    for (func, name) in exports {
+        let SymbolKind::Function(func) = syms.sym(*func).kind()
+        else { unreachable!() };
+
+        let args = func.args().iter()
+            .map(|arg| format!("{} {}", c_type(&names, arg.symbol()), string_map.get(arg.name())))
+            .collect::<Vec<_>>();
+        let args = if args.is_empty() { "void".to_string() } else { args.join(", ") };
+
+        let _ = writeln!(out, "{} {}({args});", c_type(&names, func.ret()), string_map.get(*name));
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#ifdef __cplusplus");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out, "#endif");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#endif");
+    out
+}
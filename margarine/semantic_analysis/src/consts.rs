@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use common::string_map::StringIndex;
+use errors::ErrorId;
+use parser::nodes::{decl::Decl, expr::{BinaryOperator, Expr, ExprId, UnaryOperator}};
+use lexer::Literal;
+
+use crate::{errors::Error, syms::{sym_map::SymbolId, SymbolKind}, TyChecker};
+
+
+/// The compile-time value of a `const`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    /// Wide enough to hold both `i64` and `u64`
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+    Str(StringIndex),
+    Tuple(Vec<ConstValue>),
+    List(Vec<ConstValue>),
+}
+
+
+impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str> {
+    /// Evaluates the initialiser of every `const`, a `const`
+    /// may use others regardless of the order they're declared in
+    pub(crate) fn eval_consts(&mut self) {
+        let pending = core::mem::take(&mut self.pending_consts);
+        let mut values = HashMap::with_capacity(pending.len());
+        let mut visiting = vec![];
+
+        for &(sym, _) in &pending {
+            let _ = self.eval_const(&pending, &mut values, &mut visiting, sym);
+        }
+
+        for (sym, value) in values {
+            match value {
+                Ok(value) => { self.type_info.consts.insert(sym, value); },
+
+                // uses of the const report the error
+                Err(e) => {
+                    let SymbolKind::Global(global) = self.syms.sym(sym).kind()
+                    else { unreachable!() };
+
+                    if self.type_info.decl(global.decl).is_none() {
+                        self.set_error(global.decl, e);
+                    }
+                },
+            }
+        }
+    }
+
+
+    fn eval_const(
+        &mut self, pending: &[(SymbolId, ExprId)],
+        values: &mut HashMap<SymbolId, Result<ConstValue, ErrorId>>,
+        visiting: &mut Vec<SymbolId>, sym: SymbolId,
+    ) -> Result<ConstValue, ErrorId> {
+        if let Some(value) = values.get(&sym) { return value.clone() }
+
+        let SymbolKind::Global(global) = self.syms.sym(sym).kind()
+        else { unreachable!() };
+
+        // the const's declaration errored
+        let Some(&(_, expr)) = pending.iter().find(|c| c.0 == sym)
+        else { return Err(self.type_info.decl(global.decl).unwrap()) };
+
+        if visiting.contains(&sym) {
+            let Decl::Const { name, header, .. } = self.ast.decl(global.decl)
+            else { unreachable!() };
+
+            let err = self.error(global.decl, Error::RecursiveConst { name, source: header });
+            values.insert(sym, Err(err));
+            return Err(err);
+        }
+
+        visiting.push(sym);
+        let value = self.const_expr(pending, values, visiting, expr);
+        visiting.pop();
+
+        values.insert(sym, value.clone());
+        value
+    }
+
+
+    /// Evaluates `expr`, erroring if it can't be evaluated
+    /// at compile time
+    fn const_expr(
+        &mut self, pending: &[(SymbolId, ExprId)],
+        values: &mut HashMap<SymbolId, Result<ConstValue, ErrorId>>,
+        visiting: &mut Vec<SymbolId>, expr: ExprId,
+    ) -> Result<ConstValue, ErrorId> {
+        // the error has already been reported
+        if let Some(err) = self.type_info.expr(expr).as_err(&mut self.syms) { return Err(err) }
+
+        let source = self.ast.range(expr);
+        let mut eval = |slf: &mut Self, expr| slf.const_expr(pending, values, visiting, expr);
+
+        let value = match self.ast.expr(expr) {
+            Expr::Literal(lit) => match lit {
+                Literal::Integer(v) => ConstValue::Int(v.into()),
+                Literal::Float(v) => ConstValue::Float(v.inner()),
+                Literal::String(v) => ConstValue::Str(v),
+                Literal::Bool(v) => ConstValue::Bool(v),
+                Literal::Char(v) => ConstValue::Int(v as i128),
+            },
+
+
+            Expr::Paren(inner) => return eval(self, inner),
+
+
+            | Expr::WithinNamespace { action, .. }
+            | Expr::WithinTypeNamespace { action, .. } => return eval(self, action),
+
+
+            Expr::Identifier(..) => {
+                let sym = self.type_info.ident(expr).flatten()
+                    .filter(|&sym| matches!(self.syms.sym(sym).kind(), SymbolKind::Global(g) if g.is_const));
+
+                let Some(sym) = sym
+                else {
+                    return Err(self.error(expr, Error::NonConstantExpression { source }));
+                };
+
+                return self.eval_const(pending, values, visiting, sym);
+            },
+
+
+            Expr::Tuple(items) => {
+                let mut vec = Vec::with_capacity(items.len());
+                for &item in items { vec.push(eval(self, item)?) }
+                ConstValue::Tuple(vec)
+            },
+
+
+            Expr::CreateList { exprs } => {
+                let mut vec = Vec::with_capacity(exprs.len());
+                for &item in exprs { vec.push(eval(self, item)?) }
+                ConstValue::List(vec)
+            },
+
+
+            Expr::UnaryOp { operator, rhs } => {
+                let rhs = eval(self, rhs)?;
+                let sym = self.type_info.expr(expr).sym(&mut self.syms);
+
+                match (operator, rhs) {
+                    (UnaryOperator::Neg, ConstValue::Int(v)) => match v.checked_neg() {
+                        Some(v) => ConstValue::Int(v),
+                        None => return Err(self.error(expr, Error::ConstEvaluationFailed { reason: "the negation overflows", source })),
+                    },
+
+                    (UnaryOperator::Neg, ConstValue::Float(v)) => ConstValue::Float(-v),
+                    (UnaryOperator::Not, ConstValue::Bool(v)) => ConstValue::Bool(!v),
+                    (UnaryOperator::Not, ConstValue::Int(v)) if let Ok(sym) = sym => ConstValue::Int(truncate(!v, sym)),
+
+                    _ => return Err(self.error(expr, Error::NonConstantExpression { source })),
+                }
+            },
+
+
+            Expr::BinaryOp { operator, lhs, rhs } => {
+                // the operands' type, the result may be a `bool`
+                let int = self.type_info.expr(lhs).sym(&mut self.syms).ok()
+                    .filter(|sym| sym.is_int());
+
+                let lhs = eval(self, lhs)?;
+                let rhs = eval(self, rhs)?;
+
+                match binary_op(operator, &lhs, &rhs, int) {
+                    Ok(Some(v)) => v,
+                    Ok(None) => return Err(self.error(expr, Error::NonConstantExpression { source })),
+
+                    Err(reason) => return Err(self.error(expr, Error::ConstEvaluationFailed { reason, source })),
+                }
+            },
+
+
+            Expr::AsCast { lhs, .. } => {
+                let value = eval(self, lhs)?;
+                let ty = self.type_info.expr(expr);
+
+                let sym = ty.sym(&mut self.syms);
+
+                match value {
+                    ConstValue::Int(v) if ty.is_float(&mut self.syms) => ConstValue::Float(v as f64),
+                    ConstValue::Float(v) if ty.is_int(&mut self.syms) => ConstValue::Int(v as i128),
+                    ConstValue::Int(v) if let Ok(sym) = sym => ConstValue::Int(truncate(v, sym)),
+                    ConstValue::Int(_) | ConstValue::Float(_) => value,
+
+                    _ => return Err(self.error(expr, Error::NonConstantExpression { source })),
+                }
+            },
+
+
+            _ => return Err(self.error(expr, Error::NonConstantExpression { source })),
+        };
+
+        if let ConstValue::Int(v) = value
+        && let Ok(sym) = self.type_info.expr(expr).sym(&mut self.syms)
+        && sym.is_int() && !sym.int_fits(v) {
+            return Err(self.error(expr, Error::ConstEvaluationFailed { reason: "the value overflows its type", source }));
+        }
+
+        Ok(value)
+    }
+}
+
+
+/// Wraps `value` to the width and signedness of the integer type `sym`
+fn truncate(value: i128, sym: SymbolId) -> i128 {
+    let Some(bits) = sym.int_bits()
+    else { return value };
+
+    let shift = 128 - bits;
+    if sym.is_sint() { (value << shift) >> shift }
+    else { ((value as u128) << shift >> shift) as i128 }
+}
+
+
+/// Folds `lhs <operator> rhs`, `Ok(None)` if the operator
+/// can't be folded for these values.
+///
+/// Integers of the type `int` are folded exactly, an unsigned
+/// operand is never negative so `/ % < >>` behave as they do on
+/// `int` and the caller catches results that don't fit it
+fn binary_op(operator: BinaryOperator, lhs: &ConstValue, rhs: &ConstValue, int: Option<SymbolId>) -> Result<Option<ConstValue>, &'static str> {
+    use BinaryOperator as B;
+    use ConstValue as C;
+
+    const OVERFLOW: &str = "the operation overflows";
+
+    Ok(Some(match (lhs, rhs) {
+        (&C::Int(l), &C::Int(r)) => match operator {
+            B::Add => C::Int(l.checked_add(r).ok_or(OVERFLOW)?),
+            B::Sub => C::Int(l.checked_sub(r).ok_or(OVERFLOW)?),
+            B::Mul => C::Int(l.checked_mul(r).ok_or(OVERFLOW)?),
+            B::Div if r == 0 => return Err("division by zero"),
+            B::Div => C::Int(l.checked_div(r).ok_or(OVERFLOW)?),
+            B::Rem if r == 0 => return Err("remainder by zero"),
+            B::Rem => C::Int(l.checked_rem(r).ok_or(OVERFLOW)?),
+
+            B::BitshiftLeft | B::BitshiftRight => {
+                let bits = int.and_then(|sym| sym.int_bits()).unwrap_or(64);
+                let r = u32::try_from(r).ok().filter(|&r| r < bits)
+                    .ok_or("the shift amount is out of range")?;
+
+                // bits shifted out are dropped
+                if operator == B::BitshiftRight { C::Int(l >> r) }
+                else { C::Int(int.map_or(l << r, |sym| truncate(l << r, sym))) }
+            },
+
+            B::BitwiseAnd => C::Int(l & r),
+            B::BitwiseOr => C::Int(l | r),
+            B::BitwiseXor => C::Int(l ^ r),
+
+            B::Eq => C::Bool(l == r),
+            B::Ne => C::Bool(l != r),
+            B::Gt => C::Bool(l > r),
+            B::Ge => C::Bool(l >= r),
+            B::Lt => C::Bool(l < r),
+            B::Le => C::Bool(l <= r),
+        },
+
+
+        (&C::Float(l), &C::Float(r)) => match operator {
+            B::Add => C::Float(l + r),
+            B::Sub => C::Float(l - r),
+            B::Mul => C::Float(l * r),
+            B::Div => C::Float(l / r),
+            B::Rem => C::Float(l % r),
+
+            B::Eq => C::Bool(l == r),
+            B::Ne => C::Bool(l != r),
+            B::Gt => C::Bool(l > r),
+            B::Ge => C::Bool(l >= r),
+            B::Lt => C::Bool(l < r),
+            B::Le => C::Bool(l <= r),
+
+            _ => return Ok(None),
+        },
+
+
+        (&C::Bool(l), &C::Bool(r)) => match operator {
+            B::BitwiseAnd => C::Bool(l & r),
+            B::BitwiseOr => C::Bool(l | r),
+            B::BitwiseXor => C::Bool(l ^ r),
+            B::Eq => C::Bool(l == r),
+            B::Ne => C::Bool(l != r),
+
+            _ => return Ok(None),
+        },
+
+
+        (&C::Str(l), &C::Str(r)) => match operator {
+            B::Eq => C::Bool(l == r),
+            B::Ne => C::Bool(l != r),
+
+            _ => return Ok(None),
+        },
+
+
+        _ => return Ok(None),
+    }))
+}
@@ -0,0 +1,151 @@
+use common::{buffer::Buffer, source::SourceRange, string_map::{StringIndex, StringMap}};
+use errors::ErrorId;
+use parser::nodes::{decl::Visibility, NodeId};
+
+use crate::{errors::Error, namespace::Namespace, syms::{func::{FunctionArgument, FunctionKind, FunctionTy}, sym_map::{Generic, GenericKind, SymbolId, TraitImplEntry}, Symbol, SymbolKind}, TyChecker};
+
+
+impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str> {
+    /// Validates `dyn tr` & makes `dyn tr` implement `tr` along
+    /// with its supertraits.
+    /// Traits that aren't computed yet are checked once the
+    /// surrounding block's types are
+    pub(crate) fn dyn_type(
+        &mut self, node: NodeId, source: SourceRange, tr: Option<SymbolId>,
+    ) -> Result<(), ErrorId> {
+        let Some(tr) = tr
+        else { return Err(self.error(node, Error::DynIsNotATrait(source))) };
+
+        if let Some(sym) = self.syms.sym_ok(tr) {
+            match sym.kind() {
+                SymbolKind::Trait(_) => (),
+                SymbolKind::Error(e) => return Err(e),
+                _ => return Err(self.error(node, Error::DynIsNotATrait(source))),
+            }
+        }
+
+        if let Some(violation) = self.dyn_traits.get(&tr) {
+            return match *violation {
+                Some((tr, func)) => Err(self.error(node, Error::TraitNotObjectSafe { source, tr, func })),
+                None => Ok(()),
+            };
+        }
+
+        let Some(traits) = self.syms.dyn_traits(tr)
+        else {
+            self.pending_dyns.push((node, source, tr));
+            return Ok(());
+        };
+
+        let violation = self.object_safety_violation(&traits);
+        self.dyn_traits.insert(tr, violation);
+
+        match violation {
+            Some((tr, func)) => Err(self.error(node, Error::TraitNotObjectSafe { source, tr, func })),
+            None => {
+                self.dyn_impls(tr, &traits);
+                Ok(())
+            },
+        }
+    }
+
+
+    /// Checks the `dyn` types whose trait wasn't computed
+    /// at the time they were named
+    pub(crate) fn resolve_pending_dyns(&mut self) {
+        let pending = core::mem::take(&mut self.pending_dyns);
+        for (node, source, tr) in pending {
+            // a trait that's still pending is an error reported elsewhere
+            if self.syms.dyn_traits(tr).is_none() { continue }
+            let _ = self.dyn_type(node, source, Some(tr));
+        }
+    }
+
+
+    /// The first method that can't be called through a vtable.
+    /// A method needs to take `self` by value and can't otherwise
+    /// mention `Self` or have generics of its own
+    fn object_safety_violation(&mut self, traits: &[Generic<'out>]) -> Option<(SymbolId, StringIndex)> {
+        for tr in traits {
+            let Some(tr) = tr.sym()
+            else { continue };
+            let SymbolKind::Trait(trait_sym) = self.syms.sym(tr).kind()
+            else { continue };
+
+            for (name, func) in trait_sym.funcs {
+                let is_self = |g: Generic| matches!(g.kind(), GenericKind::Generic(g) if g.name == StringMap::SELF_TY);
+                let mentions_self = |g: Generic| {
+                    let mut gens = vec![];
+                    g.collect_generics(&mut gens);
+                    gens.iter().any(|g| g.name == StringMap::SELF_TY)
+                };
+
+                let has_receiver = func.args().first()
+                    .is_some_and(|arg| !arg.is_inout() && is_self(arg.symbol()));
+
+                if !has_receiver
+                || func.args()[1..].iter().any(|arg| mentions_self(arg.symbol()))
+                || mentions_self(func.ret())
+                || !func.declared_generics().is_empty() {
+                    return Some((tr, *name));
+                }
+            }
+        }
+
+        None
+    }
+
+
+    /// Registers an implementation of each trait in `traits` for
+    /// `dyn tr`. Their methods are `FunctionKind::DynMethod`s with
+    /// slots in the order of `SymbolMap::dyn_traits`
+    fn dyn_impls(&mut self, tr: SymbolId, traits: &[Generic<'out>]) {
+        let trait_sym = self.syms.sym(tr);
+        let trait_gens = trait_sym.generics();
+        let receiver = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::DYN, self.output.alloc_new([traits[0]])));
+        let path = self.string_map.concat_with(StringMap::DYN, trait_sym.name(), " ");
+
+        let mut slot = 0;
+        for supertrait in traits {
+            let Some(supertrait_id) = supertrait.sym()
+            else { continue };
+            let supertrait_sym = self.syms.sym(supertrait_id);
+            let SymbolKind::Trait(supertrait_data) = supertrait_sym.kind()
+            else { continue };
+
+            let mut repls = std::vec::Vec::with_capacity(supertrait_sym.generics().len() + 1);
+            repls.push((StringMap::SELF_TY, receiver));
+            for (g, arg) in supertrait_sym.generics().iter().zip(supertrait.gens().unwrap_or(&[])) {
+                repls.push((g.name(), *arg));
+            }
+
+            let ns = self.namespaces.push(Namespace::new(path), None);
+            for (name, func) in supertrait_data.funcs {
+                let mut args = Buffer::new(self.output, func.args().len());
+                for arg in func.args() {
+                    let symbol = arg.symbol().rec_replace_all(self.output, &repls);
+                    args.push(FunctionArgument::new_inout(arg.name(), symbol, arg.is_inout()));
+                }
+
+                let ret = func.ret().rec_replace_all(self.output, &repls);
+
+                let func_path = self.string_map.concat(path, *name);
+                let func_sym = self.syms.pending(&mut self.namespaces, Some(ns), func_path, trait_gens.len());
+                let func = FunctionTy::new(args.leak(), ret, FunctionKind::DynMethod(slot), None, &[]);
+                self.syms.add_sym(func_sym, Symbol::new(func_path, trait_gens, SymbolKind::Function(func)));
+                self.namespaces.get_ns_mut(ns).add_sym_unchecked(*name, func_sym, Visibility::Public);
+                slot += 1;
+            }
+
+            self.syms.traits(SymbolId::DYN).entry(supertrait_id).or_default().push(TraitImplEntry {
+                namespace: ns,
+                trait_ty: supertrait.rec_replace_all(self.output, &[(StringMap::SELF_TY, receiver)]),
+                receiver,
+                generics: trait_gens,
+                declaration: None,
+                bound_error: None,
+                types: &[],
+            });
+        }
+    }
+}
@@ -119,9 +119,8 @@ pub enum Error {
         branch_typ: Type,
     },
 
-    DuplicateMatch {
-        declared_at: SourceRange,
-        error_point: SourceRange,
+    UnreachableMatch {
+        source: SourceRange,
     },
     
     InvalidMatch {
@@ -131,7 +130,7 @@ pub enum Error {
     },
      
     MissingMatch {
-        name: Vec<StringIndex>,
+        missing: Vec<StringIndex>,
         range: SourceRange,
     },
 
@@ -273,10 +272,177 @@ pub enum Error {
     },
 
     InvalidArgument { source: SourceRange },
+
+    OrPatternBindingMismatch {
+        name: StringIndex,
+        source: SourceRange,
+    },
+
+    UndeclaredLabel {
+        label: StringIndex,
+        source: SourceRange,
+    },
+
+    BreakWithValueOutsideOfLoop {
+        source: SourceRange,
+    },
+
+    BreakValuesDiffer {
+        initial_source: SourceRange,
+        initial_typ: Type,
+        break_source: SourceRange,
+        break_typ: Type,
+    },
+
+    GlobalValueAndTypeDiffer {
+        value_type: Type,
+        declared_type: Type,
+        source: SourceRange,
+    },
+
+    NonConstantExpression {
+        source: SourceRange,
+    },
+
+    RecursiveConst {
+        name: StringIndex,
+        source: SourceRange,
+    },
+
+    ConstEvaluationFailed {
+        reason: &'static str,
+        source: SourceRange,
+    },
+
+    LiteralOutOfRange {
+        value: i64,
+        ty: Type,
+        source: SourceRange,
+    },
+
+    NoStringConversion {
+        ty: Type,
+        source: SourceRange,
+    },
+
+    SupertraitIsNotATrait(SourceRange),
+
+    DynIsNotATrait(SourceRange),
+
+    TraitNotObjectSafe {
+        source: SourceRange,
+        tr: SymbolId,
+        func: StringIndex,
+    },
+
+    AssocTypeNotFound {
+        source: SourceRange,
+        name: StringIndex,
+    },
+
+    MissingAssocItems {
+        source: SourceRange,
+        items: sti::vec::Vec<StringIndex>,
+    },
+
+    UnhashableCachedArg(SourceRange),
+    CachedInOutArg(SourceRange),
+
+    UnexportableType(SourceRange),
+    DuplicateExport(SourceRange),
 }
 
 
 impl<'a> ErrorType<SymbolMap<'_>> for Error {
+    fn code(&self) -> &'static str {
+        match self {
+            Error::IteratorFunctionInvalidSig { .. } => "E0201",
+            Error::InvalidCast { .. } => "E0202",
+            Error::InvalidValueForAttr { .. } => "E0203",
+            Error::UnknownAttr { .. } => "E0204",
+            Error::UnknownAttrParam { .. } => "E0205",
+            Error::NameIsAlreadyDefined { .. } => "E0206",
+            Error::PrivateSymbol { .. } => "E0207",
+            Error::RecursiveAlias { .. } => "E0208",
+            Error::UnknownType { .. } => "E0209",
+            Error::FunctionBodyAndReturnMismatch { .. } => "E0210",
+            Error::OutsideOfAFunction { .. } => "E0211",
+            Error::InvalidType { .. } => "E0212",
+            Error::DuplicateField { .. } => "E0213",
+            Error::DuplicateArg { .. } => "E0214",
+            Error::VariableValueAndHintDiffer { .. } => "E0215",
+            Error::VariableValueNotTuple { .. } => "E0216",
+            Error::VariableTupleAndHintTupleSizeMismatch { .. } => "E0217",
+            Error::VariableNotFound { .. } => "E0218",
+            Error::InvalidBinaryOp { .. } => "E0219",
+            Error::InvalidUnaryOp { .. } => "E0220",
+            Error::IfMissingElse { .. } => "E0221",
+            Error::IfBodyAndElseMismatch { .. } => "E0222",
+            Error::MatchValueIsntEnum { .. } => "E0223",
+            Error::MatchBranchesDifferInReturnType { .. } => "E0224",
+            // E0225 was `DuplicateMatch`, retired for `UnreachableMatch`
+            Error::InvalidMatch { .. } => "E0226",
+            Error::MissingMatch { .. } => "E0227",
+            Error::ValueIsntAnIterator { .. } => "E0228",
+            Error::StructCreationOnNonStruct { .. } => "E0229",
+            Error::FieldAccessOnNonEnumOrStruct { .. } => "E0230",
+            Error::FieldDoesntExist { .. } => "E0231",
+            Error::MissingFields { .. } => "E0232",
+            Error::MissingFuncs { .. } => "E0233",
+            Error::FunctionArgsMismatch { .. } => "E0234",
+            Error::NamespaceNotFound { .. } => "E0235",
+            Error::ValueUpdateTypeMismatch { .. } => "E0236",
+            Error::ContinueOutsideOfLoop { .. } => "E0237",
+            Error::BreakOutsideOfLoop { .. } => "E0238",
+            Error::CantUnwrapOnGivenType { .. } => "E0239",
+            Error::CantTryOnGivenType { .. } => "E0240",
+            Error::FunctionDoesntReturnAnOption { .. } => "E0241",
+            Error::FunctionDoesntReturnAResult { .. } => "E0242",
+            Error::FunctionReturnsAResultButTheErrIsntTheSame { .. } => "E0243",
+            Error::ReturnAndFuncTypDiffer { .. } => "E0244",
+            Error::AssignIsNotLHSValue { .. } => "E0245",
+            Error::CannotMutateCapturedValue { .. } => "E0246",
+            Error::AssignmentToImmutableVariable { .. } => "E0247",
+            Error::InOutValueWithoutInOutBinding { .. } => "E0248",
+            Error::InOutBindingWithoutInOutValue { .. } => "E0249",
+            Error::InOutValueIsNotAssignable { .. } => "E0250",
+            Error::UnableToInfer { .. } => "E0251",
+            Error::InvalidRange { .. } => "E0252",
+            Error::ImplOnGeneric { .. } => "E0253",
+            Error::GenericLenMismatch { .. } => "E0254",
+            Error::CantUseHoleHere { .. } => "E0255",
+            Error::NameIsReservedForFunctions { .. } => "E0256",
+            Error::IndexOnNonList { .. } => "E0257",
+            Error::CallOnNonFunction { .. } => "E0258",
+            Error::CallOnField { .. } => "E0259",
+            Error::ImplTraitOnNonTrait { .. } => "E0260",
+            Error::TypeDoesntImplTrait { .. } => "E0261",
+            Error::AmbiguousTraitMethod { .. } => "E0262",
+            Error::InvalidArgument { .. } => "E0263",
+            Error::OrPatternBindingMismatch { .. } => "E0264",
+            Error::UndeclaredLabel { .. } => "E0265",
+            Error::BreakWithValueOutsideOfLoop { .. } => "E0266",
+            Error::BreakValuesDiffer { .. } => "E0267",
+            Error::GlobalValueAndTypeDiffer { .. } => "E0268",
+            Error::NonConstantExpression { .. } => "E0269",
+            Error::RecursiveConst { .. } => "E0270",
+            Error::ConstEvaluationFailed { .. } => "E0271",
+            Error::LiteralOutOfRange { .. } => "E0272",
+            Error::NoStringConversion { .. } => "E0273",
+            Error::SupertraitIsNotATrait { .. } => "E0274",
+            Error::DynIsNotATrait { .. } => "E0275",
+            Error::TraitNotObjectSafe { .. } => "E0276",
+            Error::AssocTypeNotFound { .. } => "E0277",
+            Error::MissingAssocItems { .. } => "E0278",
+            Error::UnhashableCachedArg(_) => "E0279",
+            Error::CachedInOutArg(_) => "E0280",
+            Error::UnexportableType(_) => "E0281",
+            Error::DuplicateExport(_) => "E0282",
+            Error::UnreachableMatch { .. } => "E0283",
+        }
+    }
+
+
     fn display(&self, fmt: &mut errors::fmt::ErrorFormatter, types: &mut SymbolMap) {
         match self {
             Error::NameIsAlreadyDefined { source, name } => {
@@ -363,12 +529,9 @@ impl<'a> ErrorType<SymbolMap<'_>> for Error {
             },
 
             
-            Error::DuplicateMatch { declared_at, error_point } => {
-                let mut error = fmt.error("duplicate match variant");
-                error
-                    .highlight_with_note(*declared_at, "the variant is first declared here");
-                error.highlight_with_note(*error_point, "..but it's redeclared here");
-            
+            Error::UnreachableMatch { source } => {
+                fmt.error("unreachable match arm")
+                    .highlight_with_note(*source, "every value this matches is handled by an earlier arm");
             },
 
             
@@ -552,10 +715,10 @@ impl<'a> ErrorType<SymbolMap<'_>> for Error {
             },
 
             
-            Error::MissingMatch { name, range } => {
-                let mut msg = format!("missing variants: ");
+            Error::MissingMatch { missing, range } => {
+                let mut msg = format!("missing cases: ");
                 let mut is_first = true;
-                for n in name.iter() {
+                for n in missing.iter() {
                     if !is_first {
                         let _ = write!(msg, ", ");
                     }
@@ -798,7 +961,7 @@ impl<'a> ErrorType<SymbolMap<'_>> for Error {
             },
 
             Error::InvalidRange { source, ty } => {
-                let msg = format!("range bounds can only be integers but you provided '{}'", ty.display(fmt.string_map(), types));
+                let msg = format!("range bounds must be 'i64' but you provided '{}'", ty.display(fmt.string_map(), types));
 
                 fmt.error("invalid range bound")
                     .highlight_with_note(*source, &*msg);
@@ -876,6 +1039,157 @@ impl<'a> ErrorType<SymbolMap<'_>> for Error {
                 fmt.error("invalid argument for this trait function")
                     .highlight(*source);
             },
+
+
+            Error::OrPatternBindingMismatch { name, source } => {
+                let msg = format!("'{}' isn't bound in this alternative", fmt.string(*name));
+                fmt.error("every alternative of a pattern must bind the same names")
+                    .highlight_with_note(*source, &msg);
+            },
+
+
+            Error::UndeclaredLabel { label, source } => {
+                let msg = format!("there's no enclosing loop labelled '{}'", fmt.string(*label));
+                fmt.error("use of undeclared label")
+                    .highlight_with_note(*source, &msg);
+            },
+
+
+            Error::BreakWithValueOutsideOfLoop { source } => {
+                fmt.error("'break' with a value outside of 'loop'")
+                    .highlight_with_note(*source, "only 'loop' can produce a value, 'while' & 'for' can't");
+            },
+
+
+            Error::BreakValuesDiffer { initial_source, initial_typ, break_source, break_typ } => {
+                let msg1 = format!("..breaks with '{}'",
+                    initial_typ.display(fmt.string_map(), types),
+                );
+
+                let msg2 = format!("..but this breaks with '{}'",
+                    break_typ.display(fmt.string_map(), types),
+                );
+
+                let mut err = fmt.error("loop breaks with values of different types");
+                err.highlight_with_note(*initial_source, &msg1);
+                err.highlight_with_note(*break_source, &msg2);
+            },
+
+
+            Error::GlobalValueAndTypeDiffer { value_type, declared_type, source } => {
+                let msg = format!("the value is '{}' but the declared type is '{}'",
+                    value_type.display(fmt.string_map(), types),
+                    declared_type.display(fmt.string_map(), types),
+                );
+
+                fmt.error("value & declared type differ")
+                    .highlight_with_note(*source, &msg);
+            },
+
+
+            Error::NonConstantExpression { source } => {
+                fmt.error("expression can't be evaluated at compile time")
+                    .highlight_with_note(*source, "a 'const' can only use literals, operators, tuples, lists & other consts");
+            },
+
+
+            Error::RecursiveConst { name, source } => {
+                let msg = format!("'{}' depends on its own value", fmt.string(*name));
+                fmt.error("recursive const")
+                    .highlight_with_note(*source, &msg);
+            },
+
+
+            Error::ConstEvaluationFailed { reason, source } => {
+                fmt.error("failed to evaluate const")
+                    .highlight_with_note(*source, reason);
+            },
+
+
+            Error::LiteralOutOfRange { value, ty, source } => {
+                let msg = format!("'{value}' doesn't fit in '{}'", ty.display(fmt.string_map(), types));
+                fmt.error("literal out of range")
+                    .highlight_with_note(*source, &msg);
+            },
+
+
+            Error::NoStringConversion { ty, source } => {
+                let msg = format!("'{}' has no '__to_str__' method and doesn't implement 'ToStr'", ty.display(fmt.string_map(), types));
+                fmt.error("value can't be converted to a string")
+                    .highlight_with_note(*source, &msg);
+            },
+
+
+            Error::SupertraitIsNotATrait(src) => {
+                fmt.error("supertraits must be traits")
+                    .highlight(*src)
+            },
+
+
+            Error::DynIsNotATrait(src) => {
+                fmt.error("'dyn' must be followed by a trait")
+                    .highlight(*src)
+            },
+
+
+            Error::TraitNotObjectSafe { source, tr, func } => {
+                let msg = format!(
+                    "'{}' must take 'self' by value, not mention 'Self' otherwise and have no generics of its own",
+                    fmt.string(*func),
+                );
+
+                let tr = Type::Ty(*tr, GenListId::EMPTY).display(fmt.string_map(), types);
+                fmt.error(&format!("'{tr}' can't be used as a 'dyn' type"))
+                    .highlight_with_note(*source, &msg)
+            },
+
+
+            Error::AssocTypeNotFound { source, name } => {
+                let msg = format!("there's no associated type named '{}'", fmt.string(*name));
+                fmt.error("associated type not found")
+                    .highlight_with_note(*source, &msg)
+            },
+
+
+            Error::MissingAssocItems { source, items } => {
+                let mut msg = format!("missing associated items: ");
+                let mut is_first = true;
+                for (_, n) in items {
+                    if !is_first {
+                        let _ = write!(msg, ", ");
+                    }
+
+                    is_first = false;
+                    let _ = write!(msg, "{}", fmt.string(*n));
+                }
+
+                fmt.error("missing associated items")
+                    .highlight_with_note(*source, &msg)
+            },
+
+
+            Error::UnhashableCachedArg(source) => {
+                fmt.error("argument of a cached function isn't hashable")
+                    .highlight_with_note(*source, "must be an integer, a 'char', 'bool' or 'str' or implement both 'Eq' and 'Hash'")
+            },
+
+
+            Error::CachedInOutArg(source) => {
+                fmt.error("cached functions can't take inout arguments")
+                    .highlight_with_note(*source, "the cached result wouldn't replay the mutation")
+            },
+
+
+            Error::UnexportableType(source) => {
+                fmt.error("type can't cross the C ABI")
+                    .highlight_with_note(*source, "exported functions take & return numbers, 'bool', 'char' and structs of those")
+            },
+
+
+            Error::DuplicateExport(source) => {
+                fmt.error("symbol is exported more than once")
+                    .highlight_with_note(*source, "every '@export' needs a name of its own, 'main' & 'margarine…' are taken")
+            },
         }
     }
 }
@@ -3,11 +3,14 @@ use std::collections::HashMap;
 use common::{buffer::Buffer, source::SourceRange, string_map::{StringIndex, StringMap}};
 use errors::Error;
 use ::errors::{ErrorId, SemaError};
-use parser::{dt::{DataType, DataTypeKind}, nodes::{decl::{DeclGeneric, DeclId}, expr::ExprId, stmt::StmtId, NodeId, AST}};
+use parser::{dt::{DataType, DataTypeKind}, nodes::{decl::{DeclGeneric, DeclId}, expr::{BinaryOperator, ExprId}, stmt::StmtId, NodeId, AST}};
 use scope::{Scope, ScopeId, ScopeMap};
 use sti::{arena::Arena, ext::FromIn, key::Key, vec::{KVec, Vec}};
 use syms::{ty::Type, sym_map::{Generic, GenericKind, GenListId, SymbolId, SymbolMap}};
 use namespace::{Namespace, NamespaceId, NamespaceMap};
+use lints::{Level, LintState, Warning};
+use patterns::Decision;
+use consts::ConstValue;
 
 use crate::{namespace::SymbolGetResult, scope::ScopeKind, syms::{containers::Container, func::{FunctionArgument, FunctionTy}, sym_map::{BoundedGeneric, ClosureId, VarId}, Symbol, SymbolKind}};
 
@@ -17,6 +20,11 @@ pub mod errors;
 pub mod analysis;
 pub mod syms;
 pub mod llvm_codegen;
+pub mod lints;
+pub mod patterns;
+pub mod consts;
+pub mod dyn_traits;
+pub mod c_header;
 
 pub struct SemaErrors {
     pub errors: KVec<SemaError, Error>,
@@ -40,7 +48,7 @@ pub struct TyChecker<'me, 'out, 'temp, 'ast, 'str> {
     output      : &'out Arena,
     temp        : &'temp Arena,
     string_map  : &'me mut StringMap<'str>,
-    ast         : &'me AST<'ast>,
+    ast         : &'me mut AST<'ast>,
 
     pub scopes      : ScopeMap<'out>,
     pub namespaces  : NamespaceMap,
@@ -48,13 +56,25 @@ pub struct TyChecker<'me, 'out, 'temp, 'ast, 'str> {
     pub type_info   : TyInfo<'out>,
     pub startups: Vec<SymbolId>,
     pub tests   : Vec<(SymbolId, bool)>,
+    /// every `@export`ed function along with its C name
+    pub exports : Vec<(SymbolId, StringIndex)>,
     pub link_files: Vec<DeclId>,
     pub root_namespace: Option<NamespaceId>,
 
     pub errors     : SemaErrors,
     pub silent_ranges: std::vec::Vec<SourceRange>,
+    pub warnings   : std::vec::Vec<(Warning, Level)>,
+    lints       : LintState,
     control_flow: ControlFlowState,
     tuple_syms: std::vec::Vec<SymbolId>,
+    /// every `const` along with its initialiser, evaluated once
+    /// analysis is done
+    pending_consts: std::vec::Vec<(SymbolId, ExprId)>,
+    /// every trait used as `dyn` along with the method that
+    /// keeps it from being one, if any
+    dyn_traits: HashMap<SymbolId, Option<(SymbolId, StringIndex)>>,
+    /// `dyn` types named before their trait was computed
+    pending_dyns: std::vec::Vec<(NodeId, SourceRange, SymbolId)>,
     base_scope  : ScopeId,
 }
 
@@ -66,13 +86,18 @@ struct ControlFlowState {
 
 
 struct LoopContext {
+    label: Option<StringIndex>,
+    /// only `loop` can break with a value
+    takes_value: bool,
     has_break: bool,
+    /// the type of the first `break` along with its source
+    value: Option<(Type, SourceRange)>,
 }
 
 
 impl ControlFlowState {
-    fn enter_loop(&mut self) {
-        self.loops.push(LoopContext { has_break: false });
+    fn enter_loop(&mut self, label: Option<StringIndex>, takes_value: bool) {
+        self.loops.push(LoopContext { label, takes_value, has_break: false, value: None });
     }
 
 
@@ -81,12 +106,14 @@ impl ControlFlowState {
     }
 
 
-    fn mark_break(&mut self) -> bool {
-        let Some(loop_context) = self.loops.last_mut()
-        else { return false };
-
-        loop_context.has_break = true;
-        true
+    /// The index of the loop a `break` or `continue` to
+    /// `label` jumps out of, the innermost one if there's
+    /// no label
+    fn target(&self, label: Option<StringIndex>) -> Option<usize> {
+        match label {
+            Some(label) => self.loops.iter().rposition(|l| l.label == Some(label)),
+            None => self.loops.len().checked_sub(1),
+        }
     }
 
 
@@ -110,6 +137,14 @@ pub struct TyInfo<'out> {
     idents: HashMap<ExprId, Option<SymbolId>>,
     trait_funcs: HashMap<ExprId, Generic<'out>>,
     impls: HashMap<DeclId, (Generic<'out>, Generic<'out>, &'out [BoundedGeneric<'out>])>,
+    /// Trait default methods instantiated into an impl, mapped to
+    /// the impl's receiver and trait
+    default_methods: HashMap<SymbolId, (Generic<'out>, Generic<'out>)>,
+    vars: HashMap<ExprId, SourceRange>,
+    sym_decls: HashMap<SymbolId, (DeclId, SourceRange)>,
+    ident_scopes: HashMap<ExprId, ScopeId>,
+    matches: HashMap<ExprId, Decision>,
+    consts: HashMap<SymbolId, ConstValue>,
 }
 
 
@@ -149,8 +184,13 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             scopes: ScopeMap::new(),
             errors,
             silent_ranges: std::vec::Vec::new(),
+            warnings: std::vec::Vec::new(),
+            lints: LintState::default(),
             control_flow: ControlFlowState::default(),
             tuple_syms: std::vec::Vec::new(),
+            pending_consts: std::vec::Vec::new(),
+            dyn_traits: HashMap::new(),
+            pending_dyns: std::vec::Vec::new(),
             type_info: TyInfo {
                 exprs: KVec::new(),
                 stmts: KVec::new(),
@@ -159,10 +199,17 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 idents: HashMap::new(),
                 trait_funcs: HashMap::new(),
                 impls: HashMap::new(),
+                default_methods: HashMap::new(),
+                vars: HashMap::new(),
+                sym_decls: HashMap::new(),
+                ident_scopes: HashMap::new(),
+                matches: HashMap::new(),
+                consts: HashMap::new(),
             },
             ast,
             startups: Vec::new(),
             tests: Vec::new(),
+            exports: Vec::new(),
             link_files: Vec::new(),
             root_namespace: None,
             temp,
@@ -187,16 +234,37 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             let _ = add_sym!(I64);
             let _ = add_sym!(BYTE);
             let _ = add_sym!(F64);
+            let _ = add_sym!(I8);
+            let _ = add_sym!(I16);
+            let _ = add_sym!(I32);
+            let _ = add_sym!(U16);
+            let _ = add_sym!(U32);
+            let _ = add_sym!(U64);
+            let _ = add_sym!(USIZE);
+            let _ = add_sym!(F32);
+            let _ = add_sym!(CHAR);
             let _ = add_sym!(BOOL);
             let _ = add_sym!(PTR);
             let _ = add_sym!(OPTION);
             let _ = add_sym!(RESULT);
             let _ = add_sym!(STR);
+            let _ = add_sym!(CHARS);
+            let _ = add_sym!(CHAR_INDICES);
             let _ = add_sym!(RANGE);
             let _ = add_sym!(BUILTIN_TYPE_ID);
             let _ = add_sym!(BUILTIN_SIZE_OF);
             let _ = add_sym!(EQ_TRAIT);
             let _ = add_sym!(DESTROY_TRAIT);
+            let _ = add_sym!(ADD_TRAIT);
+            let _ = add_sym!(SUB_TRAIT);
+            let _ = add_sym!(MUL_TRAIT);
+            let _ = add_sym!(DIV_TRAIT);
+            let _ = add_sym!(REM_TRAIT);
+            let _ = add_sym!(ORD_TRAIT);
+            let _ = add_sym!(NEG_TRAIT);
+            let _ = add_sym!(INDEX_TRAIT);
+            let _ = add_sym!(ITERATOR_TRAIT);
+            let _ = add_sym!(HASH_TRAIT);
             let _ = add_sym!(RC);
             let _ = add_sym!(BUILTIN_RC);
             let _ = add_sym!(RC_GET);
@@ -211,12 +279,25 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             let _ = add_sym!(PTR_DROP);
             let _ = add_sym!(PTR_WRITE_UNINIT);
             let _ = add_sym!(LIST_CONCAT);
+            let _ = add_sym!(STR_CONCAT);
             let _ = add_sym!(LIST_SLICE);
             let _ = add_sym!(LIST_LEN);
             let _ = add_sym!(LIST_ITER);
             let _ = add_sym!(BUILTIN_LIST_ITER);
             let _ = add_sym!(BUILTIN_LIST_ITER_NEXT);
             let _ = add_sym!(BUILTIN_FLOAT_SQRT);
+            let _ = add_sym!(WRAPPING_ADD);
+            let _ = add_sym!(WRAPPING_SUB);
+            let _ = add_sym!(WRAPPING_MUL);
+            let _ = add_sym!(CHECKED_ADD);
+            let _ = add_sym!(CHECKED_SUB);
+            let _ = add_sym!(CHECKED_MUL);
+            let _ = add_sym!(CHECKED_DIV);
+
+            // `int`, `byte` & `float` under their sized names
+            namespace.add_sym_unchecked(StringMap::I64_ALIAS, SymbolId::I64, parser::nodes::decl::Visibility::Public);
+            namespace.add_sym_unchecked(StringMap::U8_ALIAS, SymbolId::BYTE, parser::nodes::decl::Visibility::Public);
+            namespace.add_sym_unchecked(StringMap::F64_ALIAS, SymbolId::F64, parser::nodes::decl::Visibility::Public);
 
             {
                 let ns = analyzer.namespaces.get_ns(analyzer.syms.sym_ns(SymbolId::OPTION));
@@ -255,6 +336,9 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             }
         }
 
+        analyzer.eval_consts();
+        analyzer.warnings = analyzer.finish_lints();
+
         analyzer
     }
 
@@ -415,7 +499,34 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
                 Generic::new(dt.range(), GenericKind::Sym(SymbolId::LIST, gens))
             },
 
+            DataTypeKind::Dyn(ty) => {
+                let tr = self.dt_to_gen_ex(node, scope, *ty, gens, used_gens);
+                if let Err(err) = self.dyn_type(node.into(), dt.range(), tr.sym()) {
+                    return self.error_generic(dt.range(), err);
+                }
+
+                let gens = self.output.alloc_new([tr]);
+                Generic::new(dt.range(), GenericKind::Sym(SymbolId::DYN, gens))
+            },
+
             DataTypeKind::Within(ns_name, ty) => {
+                // `T::Item`
+                if let DataTypeKind::CustomType(name, generics) = ty.kind()
+                && generics.is_empty() {
+                    let base =
+                    if ns_name == StringMap::SELF_TY
+                    && let Some(sym) = scope.find_self(&self.scopes) {
+                        Some(sym)
+                    } else if let Some((i, g)) = gens.iter().enumerate().find(|x| x.1.name() == ns_name) {
+                        used_gens[i] = true;
+                        Some(Generic::new(dt.range(), GenericKind::Generic(*g)))
+                    } else { None };
+
+                    if let Some(base) = base {
+                        return self.assoc_generic(node, base, name, dt.range());
+                    }
+                }
+
                 let ns = scope.find_sym(
                     ns_name, &self.scopes,
                     &mut self.syms, &self.namespaces
@@ -468,6 +579,29 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
 
 
 
+    /// `base::name` where `base` is `Self` or a generic
+    fn assoc_generic(
+        &mut self, node: impl Into<NodeId>, base: Generic<'out>,
+        name: StringIndex, source: SourceRange,
+    ) -> Generic<'out> {
+        let declared = match base.kind() {
+            GenericKind::Generic(g) => self.syms.traits_declare_assoc(g.bounds.iter().filter_map(|b| b.sym()), name),
+            GenericKind::Sym(sym, _) => {
+                let traits: std::vec::Vec<_> = self.syms.traits(sym).keys().copied().collect();
+                self.syms.traits_declare_assoc(traits.into_iter(), name)
+            },
+            GenericKind::Assoc(..) => Some(false),
+        };
+
+        if declared == Some(false) {
+            let err = self.error(node, Error::AssocTypeNotFound { source, name });
+            return self.error_generic(source, err);
+        }
+
+        Generic::new(source, GenericKind::Assoc(self.output.alloc_new(base), name))
+    }
+
+
     fn dt_to_ty(&mut self, scope_id: ScopeId, id: impl Into<NodeId> + Copy,
                 dt: DataType) -> Type {
         match dt.kind() {
@@ -479,6 +613,31 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             DataTypeKind::Within(ns_name, ty) => {
                 let scope = self.scopes.get(scope_id);
 
+                // `T::Item`
+                if let DataTypeKind::CustomType(name, generics) = ty.kind()
+                && generics.is_empty()
+                && (ns_name == StringMap::SELF_TY || scope.find_gen(ns_name, &self.scopes).is_some()) {
+                    let base = DataType::new(dt.range(), DataTypeKind::CustomType(ns_name, &[]));
+                    let base = self.dt_to_ty(scope_id, id, base);
+                    if base.is_err(&mut self.syms) { return base }
+
+                    let sym = match base.sym(&mut self.syms) {
+                        Ok(sym) => sym,
+                        Err(e) => {
+                            let err = self.error(id, e);
+                            return self.error_type(err);
+                        },
+                    };
+
+                    let traits: std::vec::Vec<_> = self.syms.traits(sym).keys().copied().collect();
+                    if self.syms.traits_declare_assoc(traits.into_iter(), name) == Some(false) {
+                        let err = self.error(id, Error::AssocTypeNotFound { source: dt.range(), name });
+                        return self.error_type(err);
+                    }
+
+                    return self.syms.project(base, name);
+                }
+
                 let result = scope.find_sym(ns_name, &self.scopes, &mut self.syms, &self.namespaces);
                 let ns = self.convert_symbol_get_result(id, ns_name, ty.range(), result);
                 let ns = self.syms.sym_ns(ns);
@@ -538,6 +697,18 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
             },
 
 
+            DataTypeKind::Dyn(ty) => {
+                let tr = self.dt_to_ty(scope_id, id, *ty);
+                let sym = tr.sym(&mut self.syms).ok();
+                if let Err(err) = self.dyn_type(id.into(), dt.range(), sym) {
+                    return self.error_type(err);
+                }
+
+                let gens = self.syms.add_gens(self.output.alloc_new([(BoundedGeneric::T, tr)]));
+                Type::Ty(SymbolId::DYN, gens)
+            },
+
+
             DataTypeKind::Fn(args, ret) => {
                 let fields = {
                     let mut fields = Buffer::new(&*self.output, args.len());
@@ -621,6 +792,12 @@ impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str>
     fn tuple_sym(&mut self, range: SourceRange, fields: &[Option<StringIndex>]) -> SymbolId {
         while self.tuple_syms.len() <= fields.len() {
             let arity = self.tuple_syms.len();
+            // pairs are seeded by the symbol map for the builtins
+            if arity == 2 {
+                self.tuple_syms.push(SymbolId::TUPLE_PAIR);
+                continue;
+            }
+
             let pending = self.syms.pending(&mut self.namespaces, None, StringMap::INVALID_IDENT, arity);
             let (fields, gens) = {
                 let mut sym_fields = Buffer::new(self.output, arity);
@@ -720,6 +897,32 @@ impl<'out> TyInfo<'out> {
     }
 
 
+    pub fn set_var(&mut self, expr: ExprId, source: SourceRange) {
+        self.vars.insert(expr, source);
+    }
+
+
+    pub fn set_sym_decl(&mut self, sym: SymbolId, decl: DeclId, source: SourceRange) {
+        self.sym_decls.insert(sym, (decl, source));
+    }
+
+
+    pub fn set_ident_scope(&mut self, expr: ExprId, scope: ScopeId) {
+        self.ident_scopes.insert(expr, scope);
+    }
+
+
+    /// The value of the `const` `sym`
+    pub fn const_value(&self, sym: SymbolId) -> Option<&ConstValue> {
+        self.consts.get(&sym)
+    }
+
+
+    pub fn set_match(&mut self, expr: ExprId, tree: Decision) {
+        self.matches.insert(expr, tree);
+    }
+
+
     pub fn expr(&self, expr: ExprId) -> Type {
         self.exprs[expr].unwrap().ty
     }
@@ -741,4 +944,57 @@ impl<'out> TyInfo<'out> {
         self.funcs.get(&expr).copied()
     }
 
+    pub fn trait_func(&self, expr: ExprId) -> Option<Generic<'out>> {
+        self.trait_funcs.get(&expr).copied()
+    }
+
+    /// Where the local variable `expr` refers to was bound
+    pub fn var(&self, expr: ExprId) -> Option<SourceRange> {
+        self.vars.get(&expr).copied()
+    }
+
+    /// The declaration that introduced `sym` along with
+    /// the source of its name
+    pub fn sym_decl(&self, sym: SymbolId) -> Option<(DeclId, SourceRange)> {
+        self.sym_decls.get(&sym).copied()
+    }
+
+    pub fn sym_decls(&self) -> &HashMap<SymbolId, (DeclId, SourceRange)> {
+        &self.sym_decls
+    }
+
+    /// The scope the identifier `expr` was resolved in
+    pub fn ident_scope(&self, expr: ExprId) -> Option<ScopeId> {
+        self.ident_scopes.get(&expr).copied()
+    }
+
+    /// The decision tree the `match` expression `expr` was
+    /// lowered into
+    pub fn match_tree(&self, expr: ExprId) -> Option<&Decision> {
+        self.matches.get(&expr)
+    }
+
+}
+
+
+/// The core trait & its method an operator on a
+/// non-builtin type is dispatched through
+pub(crate) fn operator_trait(operator: BinaryOperator) -> Option<(SymbolId, StringIndex)> {
+    Some(match operator {
+        BinaryOperator::Add => (SymbolId::ADD_TRAIT, StringMap::ADD_FUNC),
+        BinaryOperator::Sub => (SymbolId::SUB_TRAIT, StringMap::SUB_FUNC),
+        BinaryOperator::Mul => (SymbolId::MUL_TRAIT, StringMap::MUL_FUNC),
+        BinaryOperator::Div => (SymbolId::DIV_TRAIT, StringMap::DIV_FUNC),
+        BinaryOperator::Rem => (SymbolId::REM_TRAIT, StringMap::REM_FUNC),
+
+          BinaryOperator::Gt
+        | BinaryOperator::Ge
+        | BinaryOperator::Lt
+        | BinaryOperator::Le => (SymbolId::ORD_TRAIT, StringMap::ORD_FUNC),
+
+          BinaryOperator::Eq
+        | BinaryOperator::Ne => (SymbolId::EQ_TRAIT, StringMap::EQ_FUNC),
+
+        _ => return None,
+    })
 }
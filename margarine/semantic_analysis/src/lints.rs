@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+
+use common::{source::SourceRange, string_map::{StringIndex, StringMap}};
+use errors::ErrorType;
+use parser::nodes::{decl::Attribute, expr::{Expr, ExprId}, NodeId};
+
+use crate::{errors::Error, namespace::NamespaceId, scope::{ScopeId, ScopeKind, VariableScope}, TyChecker};
+
+
+/// A kind of warning whose level can be changed with
+/// `@allow`, `@warn` and `@deny`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedImports,
+    UnusedMut,
+    UnreachableCode,
+    Shadowing,
+}
+
+
+impl Lint {
+    pub const ALL : &[Lint] = &[
+        Lint::UnusedVariables,
+        Lint::UnusedImports,
+        Lint::UnusedMut,
+        Lint::UnreachableCode,
+        Lint::Shadowing,
+    ];
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedImports => "unused_imports",
+            Lint::UnusedMut => "unused_mut",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::Shadowing => "shadowing",
+        }
+    }
+
+
+    /// The lints `name` refers to, either a single lint
+    /// or one of the `unused` & `warnings` groups
+    pub fn from_name(name: &str) -> Option<&'static [Lint]> {
+        match name {
+            "unused" => Some(&Self::ALL[..4]),
+            "warnings" => Some(Self::ALL),
+            _ => {
+                let index = Self::ALL.iter().position(|lint| lint.name() == name)?;
+                Some(&Self::ALL[index..index+1])
+            },
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+
+impl Level {
+    /// The level set by the attribute called `name`
+    pub fn from_attr(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Warning {
+    UnusedVariable {
+        name: StringIndex,
+        source: SourceRange,
+    },
+
+    UnusedImport {
+        name: StringIndex,
+        source: SourceRange,
+    },
+
+    UnusedMut {
+        name: StringIndex,
+        source: SourceRange,
+    },
+
+    UnreachableCode {
+        source: SourceRange,
+        cause: SourceRange,
+    },
+
+    ShadowedBinding {
+        name: StringIndex,
+        source: SourceRange,
+        previous: SourceRange,
+    },
+}
+
+
+impl Warning {
+    pub fn lint(&self) -> Lint {
+        match self {
+            Warning::UnusedVariable { .. } => Lint::UnusedVariables,
+            Warning::UnusedImport { .. } => Lint::UnusedImports,
+            Warning::UnusedMut { .. } => Lint::UnusedMut,
+            Warning::UnreachableCode { .. } => Lint::UnreachableCode,
+            Warning::ShadowedBinding { .. } => Lint::Shadowing,
+        }
+    }
+
+
+    pub fn source(&self) -> SourceRange {
+        match self {
+            | Warning::UnusedVariable { source, .. }
+            | Warning::UnusedImport { source, .. }
+            | Warning::UnusedMut { source, .. }
+            | Warning::UnreachableCode { source, .. }
+            | Warning::ShadowedBinding { source, .. } => *source,
+        }
+    }
+}
+
+
+impl ErrorType<()> for Warning {
+    fn code(&self) -> &'static str {
+        match self {
+            Warning::UnusedVariable { .. } => "W0001",
+            Warning::UnusedImport { .. } => "W0002",
+            Warning::UnusedMut { .. } => "W0003",
+            Warning::UnreachableCode { .. } => "W0004",
+            Warning::ShadowedBinding { .. } => "W0005",
+        }
+    }
+
+
+    fn display(&self, fmt: &mut errors::fmt::ErrorFormatter, _: &mut ()) {
+        let lint = self.lint().name();
+        match self {
+            Warning::UnusedVariable { name, source } => {
+                let name = fmt.string(*name).to_string();
+                let mut w = fmt.warning(&format!("unused variable '{name}'"));
+                w.highlight_with_note(*source, &format!("if this is intentional, prefix it with an underscore: '_{name}'"));
+                w.note(&format!("'@allow({lint})' silences this"));
+            },
+
+
+            Warning::UnusedImport { name, source } => {
+                let name = fmt.string(*name).to_string();
+                let mut w = fmt.warning(&format!("unused import '{name}'"));
+                w.highlight(*source);
+                w.note(&format!("'@allow({lint})' silences this"));
+            },
+
+
+            Warning::UnusedMut { name, source } => {
+                let name = fmt.string(*name).to_string();
+                let mut w = fmt.warning(&format!("variable '{name}' is never mutated"));
+                w.highlight_with_note(*source, "this could be declared with 'let' instead of 'var'");
+                w.note(&format!("'@allow({lint})' silences this"));
+            },
+
+
+            Warning::UnreachableCode { source, cause } => {
+                let mut w = fmt.warning("unreachable code");
+                w.highlight(*source);
+                w.highlight_with_note(*cause, "any code following this expression is unreachable");
+                w.note(&format!("'@allow({lint})' silences this"));
+            },
+
+
+            Warning::ShadowedBinding { name, source, previous } => {
+                let name = fmt.string(*name).to_string();
+                let mut w = fmt.warning(&format!("'{name}' shadows an earlier binding"));
+                w.highlight(*source);
+                w.highlight_with_note(*previous, "previously bound here");
+                w.note(&format!("'@allow({lint})' silences this"));
+            },
+        }
+    }
+}
+
+
+/// What introduced a local
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BindingKind {
+    /// A `let` or `var` statement
+    Variable,
+    /// Function & closure arguments, loop and match bindings
+    Other,
+}
+
+
+/// Everything the lint pass needs which isn't already
+/// recorded in `TyInfo`
+#[derive(Debug, Default)]
+pub struct LintState {
+    bindings: Vec<(VariableScope, BindingKind)>,
+    mutated: HashSet<(SourceRange, StringIndex)>,
+    imports: Vec<(NamespaceId, StringIndex, SourceRange)>,
+    levels: Vec<(SourceRange, Lint, Level)>,
+    warnings: Vec<Warning>,
+}
+
+
+impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str> {
+    /// Records a local about to be pushed on top of `scope`
+    pub(crate) fn declare_binding(&mut self, scope: ScopeId, var: VariableScope, kind: BindingKind) {
+        if self.is_ignored_name(var.name()) { return }
+
+        // only look as far as the start of the enclosing function
+        let previous = self.scopes.get(scope).over(&self.scopes, |scope| match scope.kind() {
+            ScopeKind::VariableScope(v) if v.name() == var.name() => Some(Some(v.source())),
+            ScopeKind::Generics(_) => Some(None),
+            _ => None,
+        });
+
+        if let Some(Some(previous)) = previous
+        && previous != var.source() {
+            self.lints.warnings.push(Warning::ShadowedBinding { name: var.name(), source: var.source(), previous });
+        }
+
+        self.lints.bindings.push((var, kind));
+    }
+
+
+    /// Records that the local `place` is rooted at gets mutated
+    pub(crate) fn mark_mutated(&mut self, place: ExprId) {
+        let mut expr = place;
+        loop {
+            match self.ast.expr(expr) {
+                Expr::Identifier(name, _) => {
+                    if let Some(source) = self.type_info.var(expr) {
+                        self.lints.mutated.insert((source, name));
+                    }
+
+                    return;
+                },
+
+                | Expr::AccessField { val, .. }
+                | Expr::IndexList { list: val, .. }
+                | Expr::Unwrap(val)
+                | Expr::OrReturn(val) => expr = val,
+
+                _ => return,
+            }
+        }
+    }
+
+
+    pub(crate) fn declare_import(&mut self, ns: NamespaceId, name: StringIndex, source: SourceRange) {
+        if self.is_ignored_name(name) { return }
+        self.lints.imports.push((ns, name, source));
+    }
+
+
+    pub(crate) fn unreachable_code(&mut self, source: SourceRange, cause: SourceRange) {
+        self.lints.warnings.push(Warning::UnreachableCode { source, cause });
+    }
+
+
+    /// Applies `attr` to everything within `range` if it's
+    /// one of `@allow`, `@warn` or `@deny`, returning whether
+    /// it was
+    pub(crate) fn lint_attr(&mut self, attr: Attribute, range: SourceRange) -> bool {
+        let Some(level) = attr.identifier().and_then(|name| Level::from_attr(self.string_map.get(name)))
+        else { return false };
+
+        for param in attr.params {
+            let lints = param.identifier().and_then(|name| Lint::from_name(self.string_map.get(name)));
+            for lint in lints.unwrap_or(&[]) {
+                self.lints.levels.push((range, *lint, level));
+            }
+        }
+
+        true
+    }
+
+
+    /// Reports `@allow`, `@warn` & `@deny` params that
+    /// aren't lints
+    pub(crate) fn validate_lint_attr(&mut self, node: impl Into<NodeId> + Copy, attr: Attribute) {
+        if attr.params.is_empty() {
+            self.error(node, Error::InvalidValueForAttr {
+                attr: (attr.range, attr.identifier().unwrap()),
+                value: attr.range,
+                expected: "'a lint name'",
+            });
+        }
+
+        for param in attr.params {
+            let is_lint = param.identifier()
+                .is_some_and(|name| Lint::from_name(self.string_map.get(name)).is_some());
+
+            if !is_lint {
+                self.error(node, Error::UnknownAttrParam { param: param.range, attr: attr.range });
+            }
+        }
+    }
+
+
+    /// Runs the lints that need the whole program analysed and
+    /// resolves every warning's level, dropping the allowed ones
+    pub(crate) fn finish_lints(&mut self) -> Vec<(Warning, Level)> {
+        let used = self.type_info.vars.iter()
+            .filter_map(|(expr, source)| match self.ast.expr(*expr) {
+                Expr::Identifier(name, _) => Some((*source, name)),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        let mut warnings = std::mem::take(&mut self.lints.warnings);
+
+        for (var, kind) in &self.lints.bindings {
+            let key = (var.source(), var.name());
+            if !used.contains(&key) {
+                warnings.push(Warning::UnusedVariable { name: var.name(), source: var.source() });
+            } else if *kind == BindingKind::Variable && var.is_mutable() && !self.lints.mutated.contains(&key) {
+                warnings.push(Warning::UnusedMut { name: var.name(), source: var.source() });
+            }
+        }
+
+        for &(ns, name, source) in &self.lints.imports {
+            if !self.namespaces.is_used(ns, name) {
+                warnings.push(Warning::UnusedImport { name, source });
+            }
+        }
+
+        // bodies can be analysed more than once
+        warnings.sort_by_key(|w| (w.source().range(), w.code()));
+        warnings.dedup();
+
+        warnings.into_iter()
+            .map(|warning| {
+                let level = self.lint_level(warning.lint(), warning.source());
+                (warning, level)
+            })
+            .filter(|(_, level)| *level != Level::Allow)
+            .collect()
+    }
+
+
+    /// The level set by the innermost attribute around `source`
+    fn lint_level(&self, lint: Lint, source: SourceRange) -> Level {
+        let mut level = Level::Warn;
+        let mut innermost = u32::MAX;
+
+        for &(range, l, lvl) in &self.lints.levels {
+            if l != lint || source.start() < range.start() || range.end() < source.end() { continue }
+
+            let size = range.end() - range.start();
+            if size <= innermost {
+                innermost = size;
+                level = lvl;
+            }
+        }
+
+        level
+    }
+
+
+    fn is_ignored_name(&self, name: StringIndex) -> bool {
+        name == StringMap::SELF || self.string_map.get(name).starts_with('_')
+    }
+}
@@ -1,13 +1,13 @@
 use core::str;
 use std::{collections::HashMap, fmt, hash::Hash, path::Path};
 
-use common::{string_map::{StringIndex, StringMap}, Swap};
+use common::{source::{FileData, SourceRange}, string_map::{StringIndex, StringMap}};
 use errors::ErrorId;
-use llvm_api::{builder::{Builder, FPCmp, IntCmp, Local, Loop}, ctx::{Context, ContextRef}, module::Module, tys::{func::FunctionType, integer::IntegerTy, strct::StructTy, Type as LLVMType, TypeKind}, values::{bool::Bool, func::{AllocKind, FunctionPtr, Linkage}, int::Integer, ptr::Ptr, strct::Struct, Value}};
+use llvm_api::{builder::{Builder, FPCmp, IntCmp, Local, Loop, OverflowOp}, ctx::{Context, ContextRef}, debug::{DebugInfo, Metadata, TypeEncoding}, module::{Module, OptLevel}, tys::{func::FunctionType, integer::IntegerTy, strct::StructTy, Type as LLVMType, TypeKind}, values::{bool::Bool, func::{AllocKind, FunctionPtr, Linkage}, global::GlobalPtr, int::Integer, ptr::Ptr, strct::Struct, Value}};
 use parser::nodes::{decl::Decl, expr::{BinaryOperator, Expr, ExprId, UnaryOperator}, stmt::StmtId, NodeId, Pattern, PatternKind, AST};
 use sti::{arena::Arena, ext::FromIn, hash::fxhash::FxHasher64};
 
-use crate::{namespace::NamespaceMap, syms::{self, containers::ContainerKind, sym_map::{BoundedGeneric, GenListId, Generic, SymbolId, SymbolMap}, ty::{Type, TypeHash}, SymbolKind}, TyInfo};
+use crate::{consts::ConstValue, namespace::NamespaceMap, patterns::{Ctor, Decision, Step}, syms::{self, containers::ContainerKind, sym_map::{BoundedGeneric, GenListId, Generic, SymbolId, SymbolMap}, ty::{Type, TypeHash}, Global, SymbolKind}, TyInfo};
 
 pub struct Conversion<'me, 'out, 'ast, 'str, 'ctx> {
     string_map: &'me mut StringMap<'str>,
@@ -17,11 +17,21 @@ pub struct Conversion<'me, 'out, 'ast, 'str, 'ctx> {
     target: CompilationTarget,
 
     ty_info: &'me TyInfo<'out>,
+    files: &'me [FileData],
+    overflow_checks: bool,
     ty_mappings: HashMap<TypeHash, TypeMapping<'ctx>>,
+    /// the layout `@export`ed types have in C when it isn't
+    /// margarine's, see `c_ty`
+    c_tys: HashMap<TypeHash, LLVMType<'ctx>>,
     errors: [Vec<Vec<String>>; 3],
 
     externs: HashMap<StringIndex, (FunctionType<'ctx>, FunctionPtr<'ctx>, ExternAbi<'ctx>)>,
     funcs: HashMap<TypeHash, Function<'ctx>>,
+    /// the storage of every `static` & `const`
+    globals: HashMap<SymbolId, GlobalPtr<'ctx>>,
+    /// the `const`s that aren't plain data and have yet
+    /// to be built by `margarineInitStatics`
+    const_inits: Vec<(SymbolId, Type)>,
 
     func_counter: u32,
     current_function_name: Option<StringIndex>,
@@ -76,6 +86,23 @@ pub struct Conversion<'me, 'out, 'ast, 'str, 'ctx> {
     // ptr2 is the environment ptr
     func_ref: StructTy<'ctx>,
 
+    // ptr1 is an rc allocation of the value
+    // ptr2 is the vtable
+    dyn_ref: StructTy<'ctx>,
+    /// the vtable of every (type, `dyn` type) pair: the drop
+    /// function followed by the trait methods
+    vtables: HashMap<(TypeHash, TypeHash), GlobalPtr<'ctx>>,
+    /// the result tables of the `@cached` functions
+    caches: Vec<Cache<'ctx>>,
+
+    /// the DWARF builder, only with `debug_info` on
+    debug: Option<DebugInfo<'ctx>>,
+    /// the debug info file of every `FileData`, by index
+    debug_files: Vec<Option<Metadata<'ctx>>>,
+    debug_types: HashMap<TypeHash, Metadata<'ctx>>,
+    /// the innermost scope of the function being built
+    debug_scope: Option<Metadata<'ctx>>,
+
 
     str_ty: StructTy<'ctx>,
 
@@ -114,6 +141,20 @@ struct Function<'ctx> {
 }
 
 
+/// The results a `@cached` function has returned so far,
+/// chained per bucket
+#[derive(Debug)]
+struct Cache<'ctx> {
+    /// struct(buckets: ptr, capacity: i64, length: i64)
+    table: GlobalPtr<'ctx>,
+    table_ty: StructTy<'ctx>,
+    /// struct(next: ptr, hash: i64, arguments.., result)
+    node_ty: StructTy<'ctx>,
+    args: Vec<Type>,
+    ret: Type,
+}
+
+
 #[derive(Debug)]
 enum FunctionKind {
     Code,
@@ -137,6 +178,82 @@ pub struct CompilationSettings<'out> {
     pub cache: String,
     pub arena: &'out Arena,
     pub tests: bool,
+    /// trap on integer overflow and on division by zero
+    pub overflow_checks: bool,
+    /// emit DWARF debug info into the object
+    pub debug_info: bool,
+    /// dump the call stack before panicking, native targets only
+    pub backtrace: bool,
+    /// the level the LLVM pipeline runs at
+    pub opt_level: OptLevel,
+    /// the artifacts written next to `output` besides the object
+    pub emit: Vec<Emit>,
+    /// the kind of artifact being built
+    pub crate_type: CrateType,
+}
+
+
+/// What the object gets linked into
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CrateType {
+    /// an executable
+    #[default]
+    Bin,
+    /// a `.a` archive for C programs to link against
+    StaticLib,
+    /// a shared library for C programs to load
+    CDylib,
+}
+
+
+impl CrateType {
+    /// Libraries skip `main` and come with a `.h` header
+    pub fn is_library(self) -> bool {
+        matches!(self, CrateType::StaticLib | CrateType::CDylib)
+    }
+}
+
+
+impl std::str::FromStr for CrateType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "bin" => Ok(CrateType::Bin),
+            "staticlib" => Ok(CrateType::StaticLib),
+            "cdylib" => Ok(CrateType::CDylib),
+            _ => Err(format!("unknown crate type '{value}', expected 'bin', 'staticlib' or 'cdylib'")),
+        }
+    }
+}
+
+
+/// An artifact `--emit` asks for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Emit {
+    /// `.ll` files of the module before & after optimising
+    LlvmIr,
+    /// a `.bc` file of the optimised module
+    LlvmBc,
+    /// a `.s` file of the target assembly
+    Asm,
+    /// the `.o` file, it's always written since it's what gets linked
+    Obj,
+}
+
+
+impl std::str::FromStr for Emit {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "llvm-ir" => Ok(Emit::LlvmIr),
+            "llvm-bc" => Ok(Emit::LlvmBc),
+            "asm" => Ok(Emit::Asm),
+            "obj" => Ok(Emit::Obj),
+            _ => Err(format!("unknown emit kind '{value}', expected 'llvm-ir', 'llvm-bc', 'asm' or 'obj'")),
+        }
+    }
 }
 
 
@@ -269,17 +386,42 @@ impl CompilationTarget {
 struct Env<'a, 'ctx> {
     vars: Vec<(StringIndex, Local, Type, bool)>,
     inouts: Vec<(Local, Local)>,
-    loop_id: Option<(Loop, usize)>,
+    /// the enclosing loops along with their label, cleanup start
+    /// and the local a `break value` is stored into
+    loops: Vec<(Option<StringIndex>, Loop, usize, Option<Local>)>,
     gens: &'a [(BoundedGeneric<'a>, Type)],
     info: HashMap<ExprId, Value<'ctx>>,
     ret_llvm_ty: Option<TypeMapping<'ctx>>,
 }
 
 
+impl Env<'_, '_> {
+    /// The loop a `break`/`continue` with `label` jumps out of
+    fn loop_target(&self, label: Option<StringIndex>) -> (Option<StringIndex>, Loop, usize, Option<Local>) {
+        *self.loops.iter()
+            .rfind(|l| label.is_none() || l.0 == label)
+            .unwrap()
+    }
+}
+
+
+/// The state threaded through a `match`'s decision tree
+struct MatchArms<'ctx> {
+    root: Ptr<'ctx>,
+    root_ty: Type,
+    /// the index of the selected arm
+    arm: Local,
+    guards: Vec<Option<ExprId>>,
+    /// the locals each arm's bindings are copied into
+    bindings: Vec<Vec<(StringIndex, Local, Type)>>,
+}
+
+
 pub fn run<'a>(
     string_map: &mut StringMap, syms: &mut SymbolMap<'a>, nss: &mut NamespaceMap,
     ast: &mut AST<'a>, ty_info: &mut TyInfo<'a>, errors: [Vec<Vec<String>>; 3], 
-    _file_count: u32, startups: &[SymbolId], tests: &[SymbolId], settings: &CompilationSettings,
+    files: &[FileData], startups: &[SymbolId], tests: &[SymbolId], exports: &[(SymbolId, StringIndex)],
+    settings: &CompilationSettings,
 ) {
     let target = settings.compilation_target;
     let ctx = Context::new(ast.arena, &target.llvm_target_triple());
@@ -319,6 +461,39 @@ pub fn run<'a>(
         panic_fn.set_linkage(Linkage::External);
         panic_fn.set_noreturn(ctx.as_ctx_ref());
 
+        // every panic, the runtime's included, goes through
+        // a wrapper that prints the call stack first
+        let panic_fn =
+        if settings.backtrace && !matches!(target, CompilationTarget::Wasm32UnknownUnknown) {
+            let i32_ty = ctx.integer(32);
+            let backtrace_fn_ty = i32_ty.fn_ty(ctx.arena, &[*ptr, *i32_ty], false);
+            let backtrace_fn = module.function("backtrace", backtrace_fn_ty);
+            backtrace_fn.set_linkage(Linkage::External);
+
+            let symbols_fn_ty = void.fn_ty(ctx.arena, &[*ptr, *i32_ty, *i32_ty], false);
+            let symbols_fn = module.function("backtrace_symbols_fd", symbols_fn_ty);
+            symbols_fn.set_linkage(Linkage::External);
+
+            let wrapper = module.function("margarinePanic.backtrace", panic_fn_ty);
+            wrapper.set_linkage(Linkage::Internal);
+            wrapper.set_noreturn(ctx.as_ctx_ref());
+
+            let builder = wrapper.builder(ctx.as_ctx_ref(), panic_fn_ty);
+            let frames = builder.alloca(*ctx.array(*ptr, BACKTRACE_DEPTH));
+            let depth = builder.const_int(i32_ty, BACKTRACE_DEPTH as i64, false);
+            let depth = builder.call(backtrace_fn, backtrace_fn_ty, &[*frames, *depth]);
+
+            let stderr = builder.const_int(i32_ty, 2, false);
+            builder.call(symbols_fn, symbols_fn_ty, &[*frames, depth, *stderr]);
+
+            let message = builder.local_get(builder.arg(0).unwrap());
+            let len = builder.local_get(builder.arg(1).unwrap());
+            builder.call(panic_fn, panic_fn_ty, &[message, len]);
+            builder.unreachable();
+
+            wrapper
+        } else { panic_fn };
+
         let i32_ty = ctx.integer(32);
         let abort_fn_ty = void.fn_ty(ctx.arena, &[*i32_ty], false);
         let abort_fn = module.function("margarineAbort", abort_fn_ty);
@@ -406,6 +581,9 @@ pub fn run<'a>(
         let func_ref = ctx.structure("funcRef");
         func_ref.set_fields(&[*ctx.ptr(), *ctx.ptr()], false);
 
+        let dyn_ref = ctx.structure("dynRef");
+        dyn_ref.set_fields(&[*ctx.ptr(), *ctx.ptr()], false);
+
 
         let collection_header = ctx.structure("collectionHeader");
         collection_header.set_fields(&[*usize_ty], false);
@@ -497,6 +675,20 @@ pub fn run<'a>(
 
 
 
+        // the compile unit is named after the entry file, the
+        // other files are relative to the working directory
+        let debug = settings.debug_info.then(|| {
+            let directory = std::env::current_dir()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
+            let entry = files.first()
+                .map(|file| file.file_name(string_map))
+                .unwrap_or_default();
+
+            DebugInfo::new(ctx.as_ctx_ref(), module, "margarine", &entry, &directory)
+        });
+
+
         let mut conv = Conversion {
             string_map,
             target,
@@ -504,10 +696,21 @@ pub fn run<'a>(
             ns: nss,
             ast,
             ty_info,
+            files,
+            overflow_checks: settings.overflow_checks,
             errors,
             funcs: HashMap::new(),
             externs: HashMap::new(),
             ty_mappings: HashMap::new(),
+            c_tys: HashMap::new(),
+            globals: HashMap::new(),
+            const_inits: Vec::new(),
+            vtables: HashMap::new(),
+            caches: Vec::new(),
+            debug,
+            debug_files: vec![None; files.len()],
+            debug_types: HashMap::new(),
+            debug_scope: None,
             func_counter: 0,
             current_function_name: None,
             panic_fn: (panic_fn, panic_fn_ty),
@@ -523,6 +726,7 @@ pub fn run<'a>(
             usize: usize_ty,
             ctx: ctx.as_ctx_ref(),
             func_ref,
+            dyn_ref,
             module,
             collection_header,
             collection_ty,
@@ -559,6 +763,15 @@ pub fn run<'a>(
             register!(I64, ctx.integer(64));
             register!(BYTE, ctx.integer(8));
             register!(F64, ctx.f64());
+            register!(I8, ctx.integer(8));
+            register!(I16, ctx.integer(16));
+            register!(I32, ctx.integer(32));
+            register!(U16, ctx.integer(16));
+            register!(U32, ctx.integer(32));
+            register!(U64, ctx.integer(64));
+            register!(USIZE, usize_ty);
+            register!(F32, ctx.f32());
+            register!(CHAR, ctx.integer(32));
             register!(UNIT, ctx.unit());
         }
 
@@ -571,8 +784,17 @@ pub fn run<'a>(
         let main_fn_ty = i32_ty.fn_ty(ctx.arena, &[], false);
         let main_fn = module.function("main", main_fn_ty);
 
-        // create IR
+        // create IR, exports first so their C names stay free
+        for (sym, name) in exports.iter() {
+            conv.export_func(*sym, *name);
+        }
+
+        let is_library = settings.crate_type.is_library();
         for sym in startups.iter() {
+            let is_static = matches!(conv.syms.sym(*sym).kind(),
+                SymbolKind::Function(f) if matches!(f.kind(), syms::func::FunctionKind::StaticInit(_)));
+            if is_library && !is_static { continue }
+
             let _ = conv.get_func(Type::Ty(*sym, GenListId::EMPTY));
         }
 
@@ -580,10 +802,28 @@ pub fn run<'a>(
             let _ = conv.get_func(Type::Ty(*sym, GenListId::EMPTY));
         }
 
-        // build main
-        let builder = main_fn.builder(ctx.as_ctx_ref(), main_fn_ty);
+        // statics are initialised by their own function so
+        // the test runner can call it before each test
+        let statics_fn_ty = ctx.void().fn_ty(ctx.arena, &[], false);
+        let statics_fn = module.function("margarineInitStatics", statics_fn_ty);
+        let mut statics_builder = statics_fn.builder(ctx.as_ctx_ref(), statics_fn_ty);
+
+        // before the statics since their initialisers may use them
+        conv.init_consts(&mut statics_builder);
+
+        // build main, a library leaves it for its embedder
+        // which calls `margarineInitStatics` instead
+        let builder = (!is_library).then(|| main_fn.builder(ctx.as_ctx_ref(), main_fn_ty));
+        if let Some(builder) = &builder {
+            builder.call(statics_fn, statics_fn_ty, &[]);
+        }
 
         for sym_id in startups {
+            let is_static = matches!(conv.syms.sym(*sym_id).kind(),
+                SymbolKind::Function(f) if matches!(f.kind(), syms::func::FunctionKind::StaticInit(_)));
+            let Some(builder) = (if is_static { Some(&statics_builder) } else { builder.as_ref() })
+            else { continue };
+
             let hash = Type::Ty(*sym_id, GenListId::EMPTY).hash(&*conv.syms);
             if let Some(func) = conv.funcs.get(&hash) {
                 let args: Vec<_> = func.func_ty.args().into_iter().map(|ty| builder.const_zero(ty)).collect();
@@ -591,8 +831,24 @@ pub fn run<'a>(
             }
         }
 
-        builder.call(abort_fn, abort_fn_ty, &[*ctx.const_int(i32_ty, 0, false)]);
-        builder.unreachable();
+        statics_builder.ret_void();
+
+        // a library's embedder calls `margarineFreeCaches` itself
+        let (free_caches_fn, free_caches_fn_ty) = conv.free_caches();
+        if !is_library {
+            free_caches_fn.set_linkage(Linkage::Internal);
+        }
+
+        if let Some(builder) = builder {
+            builder.call(free_caches_fn, free_caches_fn_ty, &[]);
+
+            builder.call(abort_fn, abort_fn_ty, &[*ctx.const_int(i32_ty, 0, false)]);
+            builder.unreachable();
+        }
+
+        if let Some(debug) = conv.debug.take() {
+            debug.finalize();
+        }
 
         module = conv.module;
     }
@@ -600,7 +856,17 @@ pub fn run<'a>(
 
     module.validate()
         .unwrap_or_else(|error| panic!("generated invalid LLVM module: {error}"));
-    module.optimize()
+
+    let emit_ir = |module: Module, path: String| {
+        std::fs::write(&path, module.dump_to_str().as_str())
+            .unwrap_or_else(|error| panic!("failed to write '{path}': {error}"));
+    };
+
+    if settings.emit.contains(&Emit::LlvmIr) {
+        emit_ir(module, format!("{}.unoptimized.ll", settings.output));
+    }
+
+    module.optimize(settings.opt_level)
         .unwrap_or_else(|error| panic!("failed to optimize LLVM module: {error}"));
 
     if matches!(target, CompilationTarget::Wasm32UnknownUnknown) {
@@ -613,14 +879,38 @@ pub fn run<'a>(
             .unwrap_or_else(|error| panic!("generated invalid LLVM module after fuel instrumentation: {error}"));
     }
 
-    ctx.emit_bitcode(module, Path::new(&format!("{}.bc", settings.output)))
-        .unwrap_or_else(|error| panic!("failed to emit bitcode: {error}"));
+    if settings.emit.contains(&Emit::LlvmIr) {
+        emit_ir(module, format!("{}.ll", settings.output));
+    }
+
+    if settings.emit.contains(&Emit::LlvmBc) {
+        ctx.emit_bitcode(module, Path::new(&format!("{}.bc", settings.output)))
+            .unwrap_or_else(|error| panic!("failed to emit bitcode: {error}"));
+    }
+
+    if settings.emit.contains(&Emit::Asm) {
+        ctx.emit_assembly(module, Path::new(&format!("{}.s", settings.output)))
+            .unwrap_or_else(|error| panic!("failed to emit assembly: {error}"));
+    }
+
     ctx.emit_object(module, Path::new(&format!("{}.o", settings.output)))
         .unwrap_or_else(|error| panic!("failed to emit object file: {error}"));
+
+    if settings.crate_type.is_library() {
+        let stem = Path::new(&settings.output).file_name().and_then(|name| name.to_str()).unwrap_or("margarine");
+        let guard = crate::c_header::include_guard(stem);
+
+        let path = format!("{}.h", settings.output);
+        std::fs::write(&path, crate::c_header::generate(string_map, syms, exports, &guard))
+            .unwrap_or_else(|error| panic!("failed to write '{path}': {error}"));
+    }
 }
 
 
 
+/// how many frames a backtrace shows at most
+const BACKTRACE_DEPTH: usize = 64;
+
 const COLLECTION_LENGTH_BITS: u32 = 59;
 const COLLECTION_DEPTH_SHIFT: u32 = COLLECTION_LENGTH_BITS;
 const COLLECTION_LENGTH_MASK: i64 = (1i64 << COLLECTION_LENGTH_BITS) - 1;
@@ -651,7 +941,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
         };
         let invalid = unsafe { Bool::new(*builder.or(negative.as_integer(), out_of_range.as_integer())) };
         builder.iff(invalid, |builder| {
-            self.emit_panic(builder, "integer does not fit the target address space");
+            self.emit_panic_at(builder, "integer does not fit the target address space", None);
         });
         builder.int_cast(value, *self.usize, false).as_integer()
     }
@@ -1615,11 +1905,13 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             .map(|s| s.display(self.string_map, self.syms).to_string())
             .unwrap_or_else(|| elem_repr.name().to_string());
 
-        let func_ptr = self.module.function(&format!("<collection_drop>::{}", name), func_ty);
+        let name = format!("<collection_drop>::{}", name);
+        let func_ptr = self.module.function(&name, func_ty);
 
         self.collection_drop_funcs.insert(entry_key, (func_ptr, func_ty));
         self.collection_drop(env, builder, collection, elem_repr, elem_ty);
 
+        let previous_function_name = self.current_function_name.replace(self.string_map.insert(&name));
         let mut builder = func_ptr.builder(self.ctx, func_ty);
         let builder = &mut builder;
 
@@ -1705,6 +1997,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
         builder.call(self.dealloc_fn.0, self.dealloc_fn.1, &[*header_ptr, total_size]);
         builder.ret_void();
 
+        self.current_function_name = previous_function_name;
     }
 
 
@@ -1866,6 +2159,21 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     );
                     let external_fn = self.module.function(self.string_map.get(path), external_fn_ty);
                     external_fn.set_linkage(Linkage::External);
+
+                    // C passes integers narrower than 32 bits extended
+                    // to a full register
+                    let first_arg = matches!(external_abi, ExternAbi::SRet(_)) as u32;
+                    for (i, (arg, ty)) in sym_func.args().iter().zip(&args).enumerate() {
+                        if let Some(signed) = self.int_ext(*ty) && !arg.is_inout() {
+                            external_fn.set_int_ext(self.ctx, Some(first_arg + i as u32), signed);
+                        }
+                    }
+
+                    if let Some(signed) = self.int_ext(ret)
+                    && matches!(external_abi, ExternAbi::Direct) {
+                        external_fn.set_int_ext(self.ctx, None, signed);
+                    }
+
                     if let ExternAbi::SRet(ret) = external_abi {
                         external_fn.set_sret(self.ctx, ret);
                     }
@@ -1958,25 +2266,47 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
                 assert!(self.funcs.insert(hash, func).is_none());
 
+                // callers go through the table, the body
+                // only runs when it misses
+                let is_cached = sym_func.cached && !is_never;
+                let body_ptr =
+                if is_cached {
+                    let body_ptr = self.module.function(&format!("{name}.uncached"), func_ty);
+                    body_ptr.set_linkage(Linkage::Internal);
+                    self.cached_function(name, func_ptr, body_ptr, func_ty, &args, ret);
+                    body_ptr
+                } else {
+                    func_ptr
+                };
+
                 let previous_function_name = self.current_function_name.replace(name_idx);
-                let mut builder = func_ptr.builder(self.ctx, func_ty);
+                let mut builder = body_ptr.builder(self.ctx, func_ty);
+
+                let env_gens = match self.ty_info.default_methods.get(&sym_id).copied() {
+                    Some((receiver, trait_ty)) => self.default_method_gens(receiver, trait_ty, gens, sym_func),
+                    None => self.syms.get_gens(gens_id),
+                };
 
                 let mut env = Env {
                     vars: Vec::new(),
                     inouts: Vec::new(),
-                    loop_id: None,
-                    gens: self.syms.get_gens(gens_id),
+                    loops: vec![],
+                    gens: env_gens,
                     info: HashMap::new(),
                     ret_llvm_ty: Some(llvm_ret),
                 };
 
-                let Decl::Function { body, .. } = self.ast.decl(sym_func.decl().unwrap())
+                let Decl::Function { sig, body, .. } = self.ast.decl(sym_func.decl().unwrap())
                 else { unreachable!() };
 
+                let linkage_name = if is_cached { format!("{name}.uncached") } else { name.to_string() };
+                let previous_debug_scope = self.debug_function(&builder, body_ptr, name, &linkage_name, sig.source);
+
                 for (i, arg) in sym_func.args().iter().enumerate() {
                     let arg_ty = arg.symbol().to_ty(gens, self.syms);
                     let arg_ty = arg_ty.resolve(&[], self.syms);
                     let param = builder.arg(i).unwrap();
+                    let source = sig.arguments.get(i).map_or(sig.source, |arg| arg.range());
                     if arg.is_inout() {
                         let llvm_ty = self.to_llvm_ty(arg_ty);
                         let local = builder.local(llvm_ty.repr);
@@ -1984,6 +2314,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                         builder.local_set(local, value);
                         env.alloc_var(arg.name(), local, arg_ty, true);
                         env.inouts.push((param, local));
+                        self.debug_variable(&builder, arg.name(), Some(i as u32 + 1), local, arg_ty, source);
                     } else {
                         // Own every by-value parameter: the caller keeps its own reference and
                         // releases it after the call, so the callee must hold its own copy to
@@ -1994,12 +2325,14 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                         let owned = self.emit_copy(&mut builder, value, arg_ty);
                         builder.local_set(param, owned);
                         env.alloc_var(arg.name(), param, arg_ty, false);
+                        self.debug_variable(&builder, arg.name(), Some(i as u32 + 1), param, arg_ty, source);
                     }
                 }
 
 
                 let result = self.block(&mut env, &mut builder, &*body);
                 self.current_function_name = previous_function_name;
+                self.debug_scope = previous_debug_scope;
 
                 if let Some(e) = self.ty_info.decl(sym_func.decl().unwrap()) {
                     self.error(&mut env, &mut builder, e);
@@ -2028,6 +2361,68 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
+            syms::func::FunctionKind::StaticInit(sym) => {
+                let func_ty = llvm_ret.repr.fn_ty(
+                    self.ctx.arena,
+                    &llvm_args,
+                    false,
+                );
+                let func_ptr = self.module.function(&format!("{name}.init"), func_ty);
+
+                let func = Function {
+                    sym: ty,
+                    name: name_idx,
+                    kind: FunctionKind::Code,
+                    error: None,
+                    func_ty,
+                    func_ptr,
+                };
+
+                assert!(self.funcs.insert(hash, func).is_none());
+
+                let SymbolKind::Global(global) = self.syms.sym(sym).kind()
+                else { unreachable!() };
+
+                let Decl::Static { expr, .. } = self.ast.decl(global.decl)
+                else { unreachable!() };
+
+                let previous_function_name = self.current_function_name.replace(name_idx);
+                let mut builder = func_ptr.builder(self.ctx, func_ty);
+                let previous_debug_scope = self.debug_function(
+                    &builder, func_ptr, name, &format!("{name}.init"), self.ast.range(global.decl));
+
+                let mut env = Env {
+                    vars: Vec::new(),
+                    inouts: Vec::new(),
+                    loops: vec![],
+                    gens: &[],
+                    info: HashMap::new(),
+                    ret_llvm_ty: Some(llvm_ret),
+                };
+
+                let result = match self.ty_info.decl(global.decl) {
+                    Some(e) => Err(e),
+                    None => self.expr(&mut env, &mut builder, expr),
+                };
+
+                self.current_function_name = previous_function_name;
+                self.debug_scope = previous_debug_scope;
+
+                match result {
+                    Ok(value) => {
+                        let global = self.global_ptr(&builder, sym);
+                        builder.store(global.as_ptr(), value);
+                        self.drop_all_locals(&mut env, &mut builder);
+                        builder.ret(*builder.const_unit());
+                    },
+
+                    Err(e) => self.error(&mut env, &mut builder, e),
+                }
+
+                return Ok(&self.funcs[&hash]);
+            },
+
+
             syms::func::FunctionKind::FloatSqrt => {
                 let func_ty = llvm_ret.repr.fn_ty(
                     self.ctx.arena,
@@ -2056,6 +2451,126 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 return Ok(&self.funcs[&hash]);
             },
 
+
+            // wrapping_*<T>(lhs: T, rhs: T): T
+            | syms::func::FunctionKind::WrappingAdd
+            | syms::func::FunctionKind::WrappingSub
+            | syms::func::FunctionKind::WrappingMul => {
+                let func_ty = llvm_ret.repr.fn_ty(
+                    self.ctx.arena,
+                    &llvm_args,
+                    false,
+                );
+                let func_ptr = self.module.function(name, func_ty);
+
+                let func = Function {
+                    sym: ty,
+                    name: name_idx,
+                    kind: FunctionKind::Code,
+                    error: None,
+                    func_ty,
+                    func_ptr,
+                };
+
+                assert!(self.funcs.insert(hash, func).is_none());
+
+                let builder = func_ptr.builder(self.ctx, func_ty);
+                let lhs = builder.local_get(builder.arg(0).unwrap()).as_integer();
+                let rhs = builder.local_get(builder.arg(1).unwrap()).as_integer();
+
+                let result = match sym_func.kind() {
+                    syms::func::FunctionKind::WrappingAdd => builder.add_int(lhs, rhs),
+                    syms::func::FunctionKind::WrappingSub => builder.sub_int(lhs, rhs),
+                    _ => builder.mul_int(lhs, rhs),
+                };
+
+                builder.ret(*result);
+
+                return Ok(&self.funcs[&hash]);
+            },
+
+
+            // checked_*<T>(lhs: T, rhs: T): Option<T>
+            | syms::func::FunctionKind::CheckedAdd
+            | syms::func::FunctionKind::CheckedSub
+            | syms::func::FunctionKind::CheckedMul
+            | syms::func::FunctionKind::CheckedDiv => {
+                let func_ty = llvm_ret.repr.fn_ty(
+                    self.ctx.arena,
+                    &llvm_args,
+                    false,
+                );
+                let func_ptr = self.module.function(name, func_ty);
+
+                let func = Function {
+                    sym: ty,
+                    name: name_idx,
+                    kind: FunctionKind::Code,
+                    error: None,
+                    func_ty,
+                    func_ptr,
+                };
+
+                assert!(self.funcs.insert(hash, func).is_none());
+
+                let mut builder = func_ptr.builder(self.ctx, func_ty);
+                let int_ty = gens[0].1;
+                let signed = int_ty.sym(self.syms).unwrap().is_sint();
+                let lhs = builder.local_get(builder.arg(0).unwrap()).as_integer();
+                let rhs = builder.local_get(builder.arg(1).unwrap()).as_integer();
+
+                // division is only done once it's known not to trap
+                let (value, failed) = match sym_func.kind() {
+                    syms::func::FunctionKind::CheckedAdd => {
+                        let (value, overflowed) = builder.int_with_overflow(OverflowOp::Add, lhs, rhs, signed);
+                        (Some(value), overflowed)
+                    },
+
+                    syms::func::FunctionKind::CheckedSub => {
+                        let (value, overflowed) = builder.int_with_overflow(OverflowOp::Sub, lhs, rhs, signed);
+                        (Some(value), overflowed)
+                    },
+
+                    syms::func::FunctionKind::CheckedMul => {
+                        let (value, overflowed) = builder.int_with_overflow(OverflowOp::Mul, lhs, rhs, signed);
+                        (Some(value), overflowed)
+                    },
+
+                    _ => {
+                        let zero = builder.const_int(rhs.ty(), 0, false);
+                        let is_zero = builder.cmp_int(rhs, zero, IntCmp::Eq);
+                        if signed {
+                            let overflows = self.is_div_overflow(&mut builder, lhs, rhs);
+                            (None, builder.bool_or(is_zero, overflows))
+                        } else {
+                            (None, is_zero)
+                        }
+                    },
+                };
+
+                let result = builder.alloca(llvm_ret.repr);
+
+                let none_tag = *builder.const_int(self.i32, 1, false);
+                let some_tag = *builder.const_int(self.i32, 0, false);
+                let unit = *builder.const_unit();
+                let none = self.create_enum(&mut builder, ret, none_tag, unit, Type::UNIT);
+
+                builder.ite(failed,
+                    |builder| {
+                        builder.store(result, none);
+                    },
+                    |builder| {
+                        let value = value.unwrap_or_else(|| builder.div_int(lhs, rhs, signed));
+                        let some = self.create_enum(builder, ret, some_tag, *value, int_ty);
+                        builder.store(result, some);
+                    },
+                );
+
+                builder.ret(builder.load(result, llvm_ret.repr));
+
+                return Ok(&self.funcs[&hash]);
+            },
+
             syms::func::FunctionKind::TypeId => {
                 let func_ty = llvm_ret.repr.fn_ty(
                     self.ctx.arena, 
@@ -2273,7 +2788,9 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 let elem_size_val = self.const_usize(&builder, llvm_elem.repr.size_of(self.module).unwrap());
 
                 let count = builder.local_get(builder.arg(0).unwrap()).as_integer();
+                let previous_function_name = self.current_function_name.replace(name_idx);
                 let count = self.int_to_usize(&mut builder, count);
+                self.current_function_name = previous_function_name;
                 let total_size = builder.mul_int(count, elem_size_val);
 
                 let ptr = builder.call(self.alloc_fn.0, self.alloc_fn.1, &[*total_size]);
@@ -2311,7 +2828,9 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
                 let ptr = builder.local_get(builder.arg(0).unwrap()).as_ptr();
                 let count = builder.local_get(builder.arg(1).unwrap()).as_integer();
+                let previous_function_name = self.current_function_name.replace(name_idx);
                 let count = self.int_to_usize(&mut builder, count);
+                self.current_function_name = previous_function_name;
                 let total_size = builder.mul_int(count, elem_size_val);
 
                 builder.call(self.dealloc_fn.0, self.dealloc_fn.1, &[*ptr, *total_size]);
@@ -2523,7 +3042,9 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
                 let ptr = builder.local_get(builder.arg(0).unwrap()).as_ptr();
                 let off = builder.local_get(builder.arg(1).unwrap()).as_integer();
+                let previous_function_name = self.current_function_name.replace(name_idx);
                 let off = self.int_to_usize(&mut builder, off);
+                self.current_function_name = previous_function_name;
 
                 let gep = builder.gep(ptr, llvm_elem.repr, off);
                 builder.ret(*gep);
@@ -2682,9 +3203,12 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
-            // $list_concat([T], [T]): [T]
-            syms::func::FunctionKind::ListConcat => {
-                let func_ty = llvm_ret.repr.fn_ty(
+            // str::chars(self): Chars
+            // str::char_indices(self): CharIndices
+            | syms::func::FunctionKind::StrChars
+            | syms::func::FunctionKind::StrCharIndices => {
+                let func_ty =
+                llvm_ret.repr.fn_ty(
                     self.ctx.arena,
                     &llvm_args,
                     false,
@@ -2696,7 +3220,6 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     name: name_idx,
                     kind: FunctionKind::Code,
                     error: None,
-
                     func_ty,
                     func_ptr,
                 };
@@ -2704,34 +3227,192 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 assert!(self.funcs.insert(hash, func).is_none());
 
                 let mut builder = func_ptr.builder(self.ctx, func_ty);
-
-                let left = builder.local_get(builder.arg(0).unwrap());
-                let left = self.emit_copy(&mut builder, left, args[0]);
-                let right = builder.local_get(builder.arg(1).unwrap());
-                let right = self.emit_copy(&mut builder, right, args[1]);
-                let elem_ty = gens[0].1;
-                let elem_repr = self.to_llvm_ty(elem_ty).repr;
-                let mut env = Env::default();
-                let result =
-                self.collection_concat(
-                    &mut env,
-                    &mut builder,
-                    left.as_struct(),
-                    right.as_struct(),
-                    elem_repr,
-                    Some(elem_ty),
+                let str_value = builder.local_get(builder.arg(0).unwrap());
+                let str_value = self.emit_copy(&mut builder, str_value, args[0]).as_struct();
+                let bytes = builder.field_load(str_value, 0);
+                let iter =
+                builder.call(
+                    self.collection_iter_new.0,
+                    self.collection_iter_new.1,
+                    &[bytes],
                 );
 
-                builder.ret(*result);
-
+                let offset = builder.const_int(self.i64, 0, false);
+                let chars = builder.struct_instance(llvm_ret.strct.as_struct(), [iter, *offset]);
+                builder.ret(*chars);
 
                 return Ok(&self.funcs[&hash]);
             },
 
 
-            // $list_slice([T], int): Option<([T], [T])>
-            syms::func::FunctionKind::ListSlice => {
-                let func_ty = llvm_ret.repr.fn_ty(
+            // Chars::__next__(&self): Option<char>
+            // CharIndices::__next__(&self): Option<(int, char)>
+            | syms::func::FunctionKind::CharsNext
+            | syms::func::FunctionKind::CharIndicesNext => {
+                let func_ty =
+                llvm_ret.repr.fn_ty(
+                    self.ctx.arena,
+                    &llvm_args,
+                    false,
+                );
+                let func_ptr = self.module.function(name, func_ty);
+
+                let func = Function {
+                    sym: ty,
+                    name: name_idx,
+                    kind: FunctionKind::Code,
+                    error: None,
+                    func_ty,
+                    func_ptr,
+                };
+
+                assert!(self.funcs.insert(hash, func).is_none());
+
+                let mut builder = func_ptr.builder(self.ctx, func_ty);
+                let chars_ty = self.to_llvm_ty(args[0]).strct.as_struct();
+                let chars_ptr = builder.local_get(builder.arg(0).unwrap()).as_ptr();
+                let iter_ptr = builder.field_ptr(chars_ptr, chars_ty, 0);
+                let offset_ptr = builder.field_ptr(chars_ptr, chars_ty, 1);
+
+                let byte_ty = self.to_llvm_ty(Type::BYTE).repr;
+                let (next_fn, next_fn_ty) = self.create_collection_iter(byte_ty);
+                let lead = builder.call(next_fn, next_fn_ty, &[*iter_ptr]).as_ptr();
+
+                let value_ty = ret.gens(self.syms);
+                let value_ty = self.syms.get_gens(value_ty)[0].1;
+                let is_indices = matches!(sym_func.kind(), syms::func::FunctionKind::CharIndicesNext);
+                let result = builder.alloca(llvm_ret.repr);
+
+                let none_tag = *builder.const_int(self.i32, 1, false);
+                let some_tag = *builder.const_int(self.i32, 0, false);
+                let unit = *builder.const_unit();
+                let none = self.create_enum(&mut builder, ret, none_tag, unit, Type::UNIT);
+                let is_end = builder.ptr_is_null(lead);
+
+                builder.ite(is_end,
+                    |builder| {
+                        builder.store(result, none);
+                    },
+                    |builder| {
+                        let (char, width) = self.decode_utf8(builder, iter_ptr, lead);
+                        let offset = builder.load(offset_ptr, *self.i64).as_integer();
+                        builder.store(offset_ptr, *builder.add_int(offset, width));
+
+                        let value =
+                        if is_indices {
+                            let pair_ty = self.to_llvm_ty(value_ty).strct.as_struct();
+                            *builder.struct_instance(pair_ty, [*offset, *char])
+                        } else { *char };
+
+                        let some = self.create_enum(builder, ret, some_tag, value, value_ty);
+                        builder.store(result, some);
+                    },
+                );
+
+                builder.ret(builder.load(result, llvm_ret.repr));
+
+                return Ok(&self.funcs[&hash]);
+            },
+
+
+            // $list_concat([T], [T]): [T]
+            syms::func::FunctionKind::ListConcat => {
+                let func_ty = llvm_ret.repr.fn_ty(
+                    self.ctx.arena,
+                    &llvm_args,
+                    false,
+                );
+                let func_ptr = self.module.function(name, func_ty);
+
+                let func = Function {
+                    sym: ty,
+                    name: name_idx,
+                    kind: FunctionKind::Code,
+                    error: None,
+
+                    func_ty,
+                    func_ptr,
+                };
+
+                assert!(self.funcs.insert(hash, func).is_none());
+
+                let mut builder = func_ptr.builder(self.ctx, func_ty);
+
+                let left = builder.local_get(builder.arg(0).unwrap());
+                let left = self.emit_copy(&mut builder, left, args[0]);
+                let right = builder.local_get(builder.arg(1).unwrap());
+                let right = self.emit_copy(&mut builder, right, args[1]);
+                let elem_ty = gens[0].1;
+                let elem_repr = self.to_llvm_ty(elem_ty).repr;
+                let mut env = Env::default();
+                let result =
+                self.collection_concat(
+                    &mut env,
+                    &mut builder,
+                    left.as_struct(),
+                    right.as_struct(),
+                    elem_repr,
+                    Some(elem_ty),
+                );
+
+                builder.ret(*result);
+
+
+                return Ok(&self.funcs[&hash]);
+            },
+
+
+            // $str_concat(str, str): str
+            // str::__to_str__(self): str
+            | syms::func::FunctionKind::StrConcat
+            | syms::func::FunctionKind::StrToStr => {
+                let func_ty =
+                llvm_ret.repr.fn_ty(
+                    self.ctx.arena,
+                    &llvm_args,
+                    false,
+                );
+                let func_ptr = self.module.function(name, func_ty);
+
+                let func = Function {
+                    sym: ty,
+                    name: name_idx,
+                    kind: FunctionKind::Code,
+                    error: None,
+                    func_ty,
+                    func_ptr,
+                };
+
+                assert!(self.funcs.insert(hash, func).is_none());
+
+                let mut builder = func_ptr.builder(self.ctx, func_ty);
+                let left = builder.local_get(builder.arg(0).unwrap());
+                let left = self.emit_copy(&mut builder, left, args[0]);
+
+                if matches!(sym_func.kind(), syms::func::FunctionKind::StrToStr) {
+                    builder.ret(left);
+                    return Ok(&self.funcs[&hash]);
+                }
+
+                let right = builder.local_get(builder.arg(1).unwrap());
+                let right = self.emit_copy(&mut builder, right, args[1]);
+
+                let left = builder.field_load(left.as_struct(), 0).as_struct();
+                let right = builder.field_load(right.as_struct(), 0).as_struct();
+                let byte_repr = self.to_llvm_ty(Type::BYTE).repr;
+                let mut env = Env::default();
+                let bytes = self.collection_concat(&mut env, &mut builder, left, right, byte_repr, Some(Type::BYTE));
+
+                let str = builder.struct_instance(self.str_ty, [*bytes]);
+                builder.ret(*str);
+
+                return Ok(&self.funcs[&hash]);
+            },
+
+
+            // $list_slice([T], int): Option<([T], [T])>
+            syms::func::FunctionKind::ListSlice => {
+                let func_ty = llvm_ret.repr.fn_ty(
                     self.ctx.arena,
                     &llvm_args,
                     false,
@@ -2890,6 +3571,54 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
+            // calls the receiver's shim in `slot`, which
+            // takes the receiver's allocation instead
+            syms::func::FunctionKind::DynMethod(slot) => {
+                let func_ty = llvm_ret.repr.fn_ty(
+                    self.ctx.arena,
+                    &llvm_args,
+                    false,
+                );
+                let func_ptr = self.module.function(name, func_ty);
+
+                let func = Function {
+                    sym: ty,
+                    name: name_idx,
+                    kind: FunctionKind::Code,
+                    error: None,
+
+                    func_ty,
+                    func_ptr,
+                };
+
+                assert!(self.funcs.insert(hash, func).is_none());
+
+                let builder = func_ptr.builder(self.ctx, func_ty);
+                let receiver = builder.local_get(builder.arg(0).unwrap()).as_struct();
+                let data = builder.field_load(receiver, 0);
+                let vtable = builder.field_load(receiver, 1).as_ptr();
+
+                let index = builder.const_int(self.i64, slot as i64 + 1, false);
+                let shim = builder.gep(vtable, *self.ctx.ptr(), index);
+                let shim = builder.load(shim, *self.ctx.ptr()).as_func();
+
+                let mut shim_args = llvm_args.to_vec();
+                shim_args[0] = *self.ctx.ptr();
+                let shim_ty = llvm_ret.repr.fn_ty(self.ctx.arena, &shim_args, false);
+
+                let mut call_args = Vec::with_capacity(llvm_args.len());
+                call_args.push(data);
+                for i in 1..llvm_args.len() {
+                    call_args.push(builder.local_get(builder.arg(i).unwrap()));
+                }
+
+                let result = builder.call(shim, shim_ty, &call_args);
+                builder.ret(result);
+
+                return Ok(&self.funcs[&hash]);
+            },
+
+
             syms::func::FunctionKind::Closure(_) => unreachable!(),
             syms::func::FunctionKind::Trait => unreachable!(),
         }
@@ -2928,6 +3657,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             SymbolId::UNIT => Some(*self.ctx.unit()),
             SymbolId::I64 => Some(*self.i64),
             SymbolId::F64 => Some(*self.ctx.f64()),
+            SymbolId::DYN => Some(*self.dyn_ref),
             _ => None,
         };
 
@@ -3058,6 +3788,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
 
             SymbolKind::Alias(_) => unreachable!(),
+            SymbolKind::Global(_) => unreachable!(),
             SymbolKind::Namespace => unreachable!(),
             SymbolKind::Trait(_) => unreachable!(),
         };
@@ -3078,6 +3809,10 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
         let mut has_ret : Option<(Value<'ctx>, Type)> = None;
         let len = env.vars.len();
+        let previous_debug_scope = match block.first() {
+            Some(&n) => self.debug_lexical_block(self.ast.range(n)),
+            None => self.debug_scope,
+        };
 
 
         for (_, &n) in block.iter().enumerate() {
@@ -3085,6 +3820,8 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 self.emit_drop(env, builder, value, ty);
             }
 
+            self.debug_location(builder, self.ast.range(n));
+
             match n {
                 NodeId::Decl(_) => (),
 
@@ -3101,6 +3838,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
                 NodeId::Err(error_id) => {
                     env.vars.truncate(len);
+                    self.debug_scope = previous_debug_scope;
                     return Err(error_id);
                 },
             }
@@ -3108,6 +3846,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
         self.drop_locals(env, builder, len);
         env.vars.truncate(len);
+        self.debug_scope = previous_debug_scope;
         match has_ret {
             Some((value, ty)) => Ok((value, ty)),
             None => Ok((*builder.const_unit(), Type::UNIT)),
@@ -3162,7 +3901,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
-            parser::nodes::stmt::Stmt::ForLoop { binding, expr, body } => {
+            parser::nodes::stmt::Stmt::ForLoop { binding, expr, body, label } => {
                 out_if_err!();
                 let iter_value = self.expr(env, builder, expr)?;
                 let iter_sym = self.ty_info.expr(expr).resolve(&[env.gens], self.syms);
@@ -3172,10 +3911,32 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     let ns = self.syms.sym_ns(sym);
                     let ns = self.ns.get_ns(ns);
 
-                    let Ok(sym) = ns.get_sym(StringMap::ITER_NEXT_FUNC).unwrap()
-                    else { unreachable!() };
+                    // `Iterator` impls win over an inherent `__next__`
+                    let iterator = Type::Ty(SymbolId::ITERATOR_TRAIT, GenListId::EMPTY);
+                    let (sym, gens) = match self.syms.trait_implementation(iter_sym, iterator) {
+                        Some((ns, bindings)) => {
+                            let Ok(sym) = self.ns.get_ns(ns).get_sym(StringMap::ITER_NEXT_FUNC).unwrap()
+                            else { unreachable!() };
+
+                            let func_gens = self.syms.sym(sym).generics();
+                            let mut gens = sti::vec::Vec::with_cap_in(self.syms.arena(), func_gens.len());
+                            for generic in func_gens {
+                                let (_, ty) = bindings.iter().find(|(binding, _)| binding.name == generic.name).unwrap();
+                                gens.push((*generic, *ty));
+                            }
+
+                            (sym, self.syms.add_gens(gens.leak()))
+                        },
+
+                        None => {
+                            let Ok(sym) = ns.get_sym(StringMap::ITER_NEXT_FUNC).unwrap()
+                            else { unreachable!() };
+
+                            (sym, iter_sym.gens(self.syms))
+                        },
+                    };
 
-                    let func = Type::Ty(sym, iter_sym.gens(self.syms));
+                    let func = Type::Ty(sym, gens);
                     let func = func.resolve(&[], self.syms);
 
                     let ret_ty = self.syms.sym(sym);
@@ -3183,7 +3944,6 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     else { unreachable!() };
                     let iter_fn_is_inout = ret_ty.args().first().is_some_and(|arg| arg.is_inout());
 
-                    let gens = iter_sym.gens(self.syms);
                     let gens = self.syms.get_gens(gens);
                     let ret_ty = ret_ty.ret().to_ty(gens, self.syms);
 
@@ -3210,7 +3970,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     let null = builder.ptr_null();
                     let call_ret_value = builder.call(func_ptr, func_ty, &[iter_expr, *null]).as_struct();
 
-                    let lo = env.loop_id.swap(Some((l, env.vars.len())));
+                    env.loops.push((label, l, env.vars.len(), None));
 
                     let tag = builder.field_load(call_ret_value, 0).as_integer();
                     let none_case = builder.const_int(tag.as_integer().ty(), 1, false);
@@ -3231,7 +3991,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                         self.error(env, builder, e);
                     };
 
-                    env.loop_id = lo;
+                    env.loops.pop();
                     if let Some((_, local, ty, _)) = env.vars.last().copied() {
                         let value = builder.local_get(local);
                         self.emit_drop(env, builder, value, ty);
@@ -3281,11 +4041,266 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
     }
 
 
+    /// Panics with `message`, followed by the enclosing
+    /// function & where `source` is, when there's one
+    fn emit_panic_at(&mut self, builder: &mut Builder<'ctx>, message: &str, source: Option<SourceRange>) {
+        let func = self.current_function_name.map(|func| format!(" in '{}'", self.string_map.get(func)));
+        let location = source.map(|source| format!(" at {}", self.location(source)));
+        let message = format!("{message}{}{}", func.unwrap_or_default(), location.unwrap_or_default());
+
+        self.emit_panic(builder, &message);
+    }
+
+
+    /// Like `emit_panic_at` but for a runtime `str` message, a
+    /// call to the runtime's `margarinePanic` from the program
+    fn emit_str_panic_at(&mut self, builder: &mut Builder<'ctx>, message: Value<'ctx>, source: SourceRange) {
+        let suffix = match self.current_function_name {
+            Some(func) => format!(" in '{}' at {}", self.string_map.get(func), self.location(source)),
+            None => format!(" at {}", self.location(source)),
+        };
+
+        let byte_ty = self.ctx.integer(8);
+        let message = builder.field_load(message.as_struct(), 0).as_struct();
+        let message_len = self.collection_length(builder, message);
+        let suffix_len = builder.const_int(self.i64, suffix.len() as _, false);
+        let len = builder.add_int_nuw(message_len, suffix_len);
+
+        let size = builder.int_cast(len, *self.usize, false);
+        let data = builder.call(self.alloc_fn.0, self.alloc_fn.1, &[size]).as_ptr();
+        let output_index = builder.alloca(*self.i64);
+        let zero = builder.const_int(self.i64, 0, false);
+        builder.store(output_index, *zero);
+        self.collection_flatten(builder, message, zero, message_len, data, output_index, *byte_ty, Some(Type::BYTE));
+
+        let suffix_ty = self.ctx.array(*byte_ty, suffix.len());
+        let suffix_data = self.module.add_global(*suffix_ty, "panic_message");
+        suffix_data.set_initialiser(*self.ctx.const_str(&suffix));
+        let suffix_bytes = builder.load(suffix_data.as_ptr(), *suffix_ty);
+        builder.store(builder.gep(data, *byte_ty, message_len), suffix_bytes);
+
+        builder.call(self.panic_fn.0, self.panic_fn.1, &[*data, *len]);
+    }
+
+
+    /// Panics with `message`, followed by the enclosing
+    /// function & where `source` is, if `cond` holds
+    fn panic_if(&mut self, builder: &mut Builder<'ctx>, cond: Bool<'ctx>, message: &str, source: SourceRange) {
+        let cond = builder.expect(cond, false);
+
+        builder.ite(cond,
+            |builder| {
+                self.emit_panic_at(builder, message, Some(source));
+            },
+            |_| {},
+        );
+    }
+
+
+    /// The `file:line:column` of `source`
+    fn location(&self, source: SourceRange) -> String {
+        let (file, offset) = source.file(self.files);
+        let (line, column) = file.line_column((source.start() - offset) as usize);
+        format!("{}:{line}:{column}", file.file_name(self.string_map))
+    }
+
+
+    /// The debug info file, line & column of `source`
+    fn debug_position(&mut self, source: SourceRange) -> (Metadata<'ctx>, u32, u32) {
+        let (file, offset) = source.file(self.files);
+        let (line, column) = file.line_column((source.start() - offset) as usize);
+        let index = self.files.iter().position(|f| core::ptr::eq(f, file)).unwrap();
+
+        let debug = self.debug.as_ref().unwrap();
+        let string_map = &*self.string_map;
+        let file = *self.debug_files[index].get_or_insert_with(|| {
+            let path = file.file_name(string_map);
+            let path = Path::new(&path);
+            let directory = path.parent().map(|dir| dir.display().to_string()).unwrap_or_default();
+            let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            debug.file(&name, &directory)
+        });
+
+        (file, line as u32, column as u32)
+    }
+
+
+    /// Attaches the location of `source` to the
+    /// instructions `builder` builds next
+    fn debug_location(&mut self, builder: &Builder<'ctx>, source: SourceRange) {
+        let Some(scope) = self.debug_scope
+        else { return };
+
+        let (_, line, column) = self.debug_position(source);
+        let location = self.debug.as_ref().unwrap().location(line, column, scope);
+        builder.set_debug_location(Some(location));
+    }
+
+
+    /// Attaches a subprogram to `func` & makes it the debug scope,
+    /// returns the scope to restore once `func` is built
+    fn debug_function(
+        &mut self, builder: &Builder<'ctx>, func: FunctionPtr<'ctx>,
+        name: &str, linkage_name: &str, source: SourceRange,
+    ) -> Option<Metadata<'ctx>> {
+        if self.debug.is_none() { return None }
+
+        let (file, line, _) = self.debug_position(source);
+        let subprogram = self.debug.as_ref().unwrap().function(func, name, linkage_name, file, line, false);
+        let previous = self.debug_scope.replace(subprogram);
+        self.debug_location(builder, source);
+        previous
+    }
+
+
+    /// Opens a lexical block at `source`, returns
+    /// the scope to restore once it ends
+    fn debug_lexical_block(&mut self, source: SourceRange) -> Option<Metadata<'ctx>> {
+        let scope = self.debug_scope?;
+        let (file, line, column) = self.debug_position(source);
+        let block = self.debug.as_ref().unwrap().lexical_block(scope, file, line, column);
+        self.debug_scope.replace(block)
+    }
+
+
+    /// Describes `local` to the debugger as the variable `name`,
+    /// `arg` is the 1-based index of a parameter
+    fn debug_variable(
+        &mut self, builder: &Builder<'ctx>, name: StringIndex, arg: Option<u32>,
+        local: Local, ty: Type, source: SourceRange,
+    ) {
+        let Some(scope) = self.debug_scope
+        else { return };
+
+        let (file, line, column) = self.debug_position(source);
+        let ty = self.debug_type(ty);
+        let name = self.string_map.get(name);
+
+        let debug = self.debug.as_ref().unwrap();
+        let variable = match arg {
+            Some(arg) => debug.parameter_variable(scope, name, arg, file, line, ty),
+            None => debug.auto_variable(scope, name, file, line, ty),
+        };
+
+        builder.debug_declare(debug, local, variable, debug.location(line, column, scope));
+    }
+
+
+    /// The debug info type of `ty`, only the scalars
+    /// describe their layout
+    fn debug_type(&mut self, ty: Type) -> Metadata<'ctx> {
+        let hash = ty.hash(self.syms);
+        if let Some(ty) = self.debug_types.get(&hash) { return *ty }
+
+        let repr = self.to_llvm_ty(ty).repr;
+        let bits = repr.size_of(self.module).unwrap_or(0) as u64 * 8;
+        let name = ty.display(self.string_map, self.syms).to_string();
+        let sym = ty.sym(self.syms).unwrap();
+
+        let debug = self.debug.as_ref().unwrap();
+        let debug_ty = match sym {
+            SymbolId::BOOL => debug.basic_type(&name, bits, TypeEncoding::Boolean),
+            SymbolId::CHAR => debug.basic_type(&name, bits, TypeEncoding::Utf),
+            _ if sym.is_float() => debug.basic_type(&name, bits, TypeEncoding::Float),
+            _ if sym.is_sint() => debug.basic_type(&name, bits, TypeEncoding::Signed),
+            _ if sym.is_int() => debug.basic_type(&name, bits, TypeEncoding::Unsigned),
+            _ => debug.opaque_type(&name, bits),
+        };
+
+        self.debug_types.insert(hash, debug_ty);
+        debug_ty
+    }
+
+
+    /// `lhs <operator> rhs` for the integer operators that can overflow,
+    /// trapping on overflow & on a zero divisor if overflow checks are on
+    fn int_arith(
+        &mut self, builder: &mut Builder<'ctx>, operator: BinaryOperator,
+        lhs: Integer<'ctx>, rhs: Integer<'ctx>, signed: bool, source: SourceRange,
+    ) -> Integer<'ctx> {
+        if !self.overflow_checks {
+            return match operator {
+                BinaryOperator::Add => builder.add_int(lhs, rhs),
+                BinaryOperator::Sub => builder.sub_int(lhs, rhs),
+                BinaryOperator::Mul => builder.mul_int(lhs, rhs),
+                BinaryOperator::Div => builder.div_int(lhs, rhs, signed),
+                BinaryOperator::Rem => builder.rem_int(lhs, rhs, signed),
+                BinaryOperator::BitshiftLeft => builder.shl(lhs, rhs),
+                BinaryOperator::BitshiftRight => builder.shr(lhs, rhs, signed),
+
+                _ => unreachable!(),
+            };
+        }
+
+        let (op, verb) = match operator {
+            BinaryOperator::Add => (OverflowOp::Add, "add"),
+            BinaryOperator::Sub => (OverflowOp::Sub, "subtract"),
+            BinaryOperator::Mul => (OverflowOp::Mul, "multiply"),
+
+            BinaryOperator::Div | BinaryOperator::Rem => {
+                let is_div = operator == BinaryOperator::Div;
+                let zero = builder.const_int(rhs.ty(), 0, false);
+                let is_zero = builder.cmp_int(rhs, zero, IntCmp::Eq);
+
+                let message =
+                if is_div { "attempt to divide by zero" }
+                else { "attempt to calculate the remainder with a divisor of zero" };
+                self.panic_if(builder, is_zero, message, source);
+
+                if signed {
+                    let overflows = self.is_div_overflow(builder, lhs, rhs);
+                    let message =
+                    if is_div { "attempt to divide with overflow" }
+                    else { "attempt to calculate the remainder with overflow" };
+                    self.panic_if(builder, overflows, message, source);
+                }
+
+                return if is_div { builder.div_int(lhs, rhs, signed) }
+                else { builder.rem_int(lhs, rhs, signed) };
+            },
+
+            BinaryOperator::BitshiftLeft | BinaryOperator::BitshiftRight => {
+                let is_left = operator == BinaryOperator::BitshiftLeft;
+                let bits = builder.const_int(rhs.ty(), rhs.ty().bit_size() as i64, false);
+                let too_far = builder.cmp_int(rhs, bits, IntCmp::UnsignedGe);
+
+                let message =
+                if is_left { "attempt to shift left with overflow" }
+                else { "attempt to shift right with overflow" };
+                self.panic_if(builder, too_far, message, source);
+
+                return if is_left { builder.shl(lhs, rhs) }
+                else { builder.shr(lhs, rhs, signed) };
+            },
+
+            _ => unreachable!(),
+        };
+
+        let (value, overflowed) = builder.int_with_overflow(op, lhs, rhs, signed);
+        self.panic_if(builder, overflowed, &format!("attempt to {verb} with overflow"), source);
+        value
+    }
+
+
+    /// Whether the signed `lhs / rhs` is `MIN / -1`,
+    /// the one division that doesn't fit its type
+    fn is_div_overflow(&self, builder: &mut Builder<'ctx>, lhs: Integer<'ctx>, rhs: Integer<'ctx>) -> Bool<'ctx> {
+        let ty = lhs.ty();
+        let min = builder.const_int(ty, i64::MIN >> (64 - ty.bit_size()), true);
+        let minus_one = builder.const_int(ty, -1, true);
+
+        let is_min = builder.cmp_int(lhs, min, IntCmp::Eq);
+        let is_minus_one = builder.cmp_int(rhs, minus_one, IntCmp::Eq);
+        builder.bool_and(is_min, is_minus_one)
+    }
+
+
     fn check_list_index(
         &mut self,
         builder: &mut Builder<'ctx>,
         list: Struct<'ctx>,
         index: Integer<'ctx>,
+        source: SourceRange,
     ) -> Integer<'ctx> {
         let len = self.collection_length(builder, list);
         let is_lt_len = builder.cmp_int(index, len, IntCmp::SignedLt);
@@ -3296,7 +4311,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
         builder.ite(is_in_bounds,
             |_| {},
             |builder| {
-                self.emit_panic(builder, "list index out of bounds");
+                self.emit_panic_at(builder, "list index out of bounds", Some(source));
             },
         );
         index
@@ -3343,6 +4358,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             parser::nodes::expr::Expr::IndexList { list, index } => {
                 let list_slot = self.resolve_mut_lvalue_ptr(env, builder, list);
                 let index_val = self.expr(env, builder, index).unwrap().as_integer();
+                let index_val = self.index_to_i64(env, builder, index, index_val);
 
                 let elem_ty = self.ty_info.expr(list);
                 let elem_ty = elem_ty.gens(self.syms);
@@ -3353,7 +4369,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 let list_ty = self.ty_info.expr(list).resolve(&[env.gens], self.syms);
 
                 let collection = builder.load(list_slot, *self.collection_ty).as_struct();
-                let index_val = self.check_list_index(builder, collection, index_val);
+                let index_val = self.check_list_index(builder, collection, index_val, self.ast.range(expr));
                 let length = self.collection_length(builder, collection);
 
                 let zero = builder.const_int(self.i64, 0, false);
@@ -3472,6 +4488,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
 
             parser::nodes::expr::Expr::Unwrap(expr) => {
+                let source = self.ast.range(expr);
                 let ty = self.ty_info.expr(expr);
                 if ty.is_err(self.syms) { unreachable!() }
                 let ty = ty.resolve(&[env.gens], self.syms);
@@ -3493,7 +4510,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
 
                         |builder| {
-                            self.emit_panic(builder, "attempted to unwrap a none value");
+                            self.emit_panic_at(builder, "attempted to unwrap a none value", Some(source));
                         }, 
                         );
 
@@ -3545,7 +4562,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
 
                                 |builder| {
-                                    self.emit_panic(builder, "attempted to unwrap a none value");
+                                    self.emit_panic_at(builder, "attempted to unwrap a none value", Some(source));
                                 }, 
                                 );
 
@@ -3571,7 +4588,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
 
                                 |builder| {
-                                    self.emit_panic(builder, "attempted to unwrap an invalid enum variant");
+                                    self.emit_panic_at(builder, "attempted to unwrap an invalid enum variant", Some(source));
                                 }, 
                                 );
 
@@ -3780,25 +4797,18 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             parser::nodes::expr::Expr::Unit => *builder.const_unit(),
             parser::nodes::expr::Expr::Literal(literal) => {
                 match literal {
-                    lexer::Literal::Integer(v) => *builder.const_int(self.i64, v, true),
-                    lexer::Literal::Float(f) => *builder.const_f64(f.inner()),
+                    lexer::Literal::Integer(v) => {
+                        let ty = self.to_llvm_ty(result_ty).repr.as_integer();
+                        *builder.const_int(ty, v, true)
+                    },
 
+                    lexer::Literal::Float(f) => self.const_float(builder, result_ty, f.inner()),
 
-                    lexer::Literal::String(string_index) => {
-                        let string = self.string_map.get(string_index);
-                        let len = builder.const_int(self.i64, string.len() as _, false);
 
-                        let byte_ty = self.ctx.integer(8);
-                        let byte_arr_ty = self.ctx.array(*self.ctx.integer(8), string.len());
-                        let bytes = *self.ctx.const_str(string);
-                        let global = self.module.add_global(*byte_arr_ty, "str_literal");
-                        global.set_initialiser(bytes);
-                        let bytes = builder.load(global.as_ptr(), *byte_arr_ty);
- 
-                        let (collection, data) = self.collection_flat(builder, len, *byte_ty);
-                        builder.store_tbaa(data, bytes, self.tbaa_element);
-                        *builder.struct_instance(self.str_ty, [*collection])
-                    },
+                    lexer::Literal::String(string_index) => self.str_literal(builder, string_index),
+
+
+                    lexer::Literal::Char(v) => *builder.const_int(self.i32, v as i64, false),
 
 
                     lexer::Literal::Bool(v) => {
@@ -3818,9 +4828,42 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
                 let ty = ty.resolve(&[env.gens], self.syms);
 
-                let func =
-                // its a trait func
-                if let Some(trait_ty) = self.ty_info.trait_funcs.get(&expr).copied() {
+                if let Some(Some(sym)) = self.ty_info.idents.get(&expr).copied()
+                && let SymbolKind::Global(global) = self.syms.sym(sym).kind() {
+                    let value = self.global(builder, sym, global, ty)?;
+                    return Ok((value, ty))
+                }
+
+                // an associated constant, `Ty::NAME`
+                if let Some(trait_ty) = self.ty_info.trait_funcs.get(&expr).copied()
+                && let Some(trait_id) = trait_ty.sym()
+                && let SymbolKind::Trait(tr) = self.syms.sym(trait_id).kind()
+                && tr.consts.iter().any(|(n, _)| *n == name) {
+                    let sym = self.ty_info.idents.get(&expr).unwrap().unwrap();
+                    let receiver = Type::Ty(sym, GenListId::EMPTY).resolve(&[env.gens], self.syms);
+                    let receiver_sym = receiver.sym(self.syms).unwrap();
+
+                    let impls = self.syms.traits(receiver_sym)[&trait_id].clone();
+                    let entry = impls.iter()
+                        .find(|entry| {
+                            let mut bindings = vec![];
+                            self.syms.match_impl_type(entry.receiver, receiver, entry.generics, &mut bindings)
+                        })
+                        .unwrap();
+
+                    let Ok(sym) = self.ns.get_ns(entry.namespace).get_sym(name).unwrap()
+                    else { unreachable!() };
+
+                    let SymbolKind::Global(global) = self.syms.sym(sym).kind()
+                    else { unreachable!() };
+
+                    let value = self.global(builder, sym, global, ty)?;
+                    return Ok((value, ty))
+                }
+
+                let func =
+                // its a trait func
+                if let Some(trait_ty) = self.ty_info.trait_funcs.get(&expr).copied() {
                     let sym = self.ty_info.idents.get(&expr).unwrap().unwrap();
                     let sym = Type::Ty(sym, GenListId::EMPTY);
                     let sym = sym.resolve(&[env.gens], self.syms);
@@ -3907,20 +4950,53 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     },
 
                     _ => {
+                        let ty = sym;
                         let sym = sym.sym(self.syms).unwrap();
-                        if sym.is_int() {
+                        if !sym.is_num() && sym != SymbolId::CHAR {
+                            let (tr, func) = crate::operator_trait(operator).unwrap();
+                            let func = self.resolve_trait_method(ty, tr, func)
+                                .expect("semantic analysis must ensure the operator's trait is implemented");
+
+                            let (func_ptr, func_ty) = (func.func_ptr, func.func_ty);
+                            let value =
+                            self.call_function(
+                                env,
+                                builder,
+                                (func_ptr, builder.ptr_null()),
+                                func_ty,
+                                &[
+                                    (lhs_val, ty, None),
+                                    (rhs_val, ty, None),
+                                ]
+                            );
+
+                            if !operator.is_ocomp() { value }
+                            else {
+                                // `Ord::cmp` is negative, zero or positive
+                                let zero = builder.const_int(self.i64, 0, false);
+                                let cmp = match operator {
+                                    BinaryOperator::Gt => IntCmp::SignedGt,
+                                    BinaryOperator::Ge => IntCmp::SignedGe,
+                                    BinaryOperator::Lt => IntCmp::SignedLt,
+                                    _ => IntCmp::SignedLe,
+                                };
+
+                                *builder.cmp_int(value.as_integer(), zero, cmp)
+                            }
+
+                        } else if sym.is_int() || sym == SymbolId::CHAR {
                             let l = lhs_val.as_integer();
                             let r = rhs_val.as_integer();
                             let signed = sym.is_sint();
 
                             match operator {
-                              BinaryOperator::Add => *builder.add_int(l, r),
-                              BinaryOperator::Sub => *builder.sub_int(l, r),
-                              BinaryOperator::Mul => *builder.mul_int(l, r),
-                              BinaryOperator::Div => *builder.div_int(l, r, signed),
-                              BinaryOperator::Rem => *builder.rem_int(l, r, signed),
-                              BinaryOperator::BitshiftLeft => *builder.shl(l, r),
-                              BinaryOperator::BitshiftRight => *builder.shr(l, r, signed),
+                              BinaryOperator::Add | BinaryOperator::Sub | BinaryOperator::Mul
+                              | BinaryOperator::Div | BinaryOperator::Rem
+                              | BinaryOperator::BitshiftLeft | BinaryOperator::BitshiftRight => {
+                                  let source = self.ast.range(expr);
+                                  *self.int_arith(builder, operator, l, r, signed, source)
+                              },
+
                               BinaryOperator::BitwiseAnd => *builder.and(l, r),
                               BinaryOperator::BitwiseOr => *builder.or(l, r),
                               BinaryOperator::BitwiseXor => *builder.xor(l, r),
@@ -3974,9 +5050,19 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
             parser::nodes::expr::Expr::UnaryOp { operator, rhs } => {
                 let rhs = self.expr(env, builder, rhs)?;
-                out_if_err!();
+                let ty = out_if_err!().resolve(&[env.gens], self.syms);
+                let sym = ty.sym(self.syms).unwrap();
                 
                 match operator {
+                    UnaryOperator::Neg if !sym.is_num() => {
+                        let func = self.resolve_trait_method(ty, SymbolId::NEG_TRAIT, StringMap::NEG_FUNC)
+                            .expect("semantic analysis must ensure neg is implemented");
+
+                        let (func_ptr, func_ty) = (func.func_ptr, func.func_ty);
+                        self.call_function(env, builder, (func_ptr, builder.ptr_null()), func_ty, &[(rhs, ty, None)])
+                    },
+
+
                     UnaryOperator::Not => {
                         let buf = builder.alloca_store(rhs);
                         let tag_ptr = builder.field_ptr(buf, rhs.ty().as_struct(), 0);
@@ -3992,10 +5078,19 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
                     UnaryOperator::Neg => {
                         if rhs.ty().kind() == TypeKind::Integer {
-                            let c = builder.const_int(rhs.ty().as_integer(), -1, true);
-                            *builder.mul_int(rhs.as_integer(), c)
+                            let source = self.ast.range(expr);
+                            let zero = builder.const_int(rhs.ty().as_integer(), 0, false);
+
+                            if self.overflow_checks {
+                                let (value, overflowed) = builder.int_with_overflow(OverflowOp::Sub, zero, rhs.as_integer(), true);
+                                self.panic_if(builder, overflowed, "attempt to negate with overflow", source);
+                                *value
+                            } else {
+                                *builder.sub_int(zero, rhs.as_integer())
+                            }
                         } else {
-                            let c = builder.const_f64(-1.0);
+                            let ty = self.ty_info.expr(expr).resolve(&[env.gens], self.syms);
+                            let c = self.const_float(builder, ty, -1.0).as_fp();
                             *builder.mul_fp(rhs.as_fp(), c)
                         }
                     },
@@ -4065,74 +5160,55 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 let match_value = self.expr(env, builder, value)?;
                 let ty = out_if_err!().resolve(&[env.gens], self.syms);
 
-                let sym = self.ty_info.expr(value);
-                let sym = sym.resolve(&[env.gens], self.syms);
-
-                let gens = sym.gens(self.syms);
-                let sym_id = sym.sym(self.syms).unwrap();
-                let sym_data = self.syms.sym(sym_id);
-
-                let SymbolKind::Container(cont) = sym_data.kind()
-                else { unreachable!() };
-
-                let value_ty = match_value.as_struct();
-                let value_tag = builder.field_load(value_ty, 0).as_integer();
-
-                let enum_llvm_ty = self.to_llvm_ty(sym);
-                let val_alloca = builder.alloca_store(match_value);
-                let val_data_ptr = builder.field_ptr(val_alloca, enum_llvm_ty.strct.as_struct(), 1);
-
-                let iter = cont.fields().iter().map(|sf| {
-                    let name = sf.0;
-                    (sf, mappings.iter().find(|x| x.variant() == name).unwrap())
-                });
+                let value_ty = self.ty_info.expr(value).resolve(&[env.gens], self.syms);
+                let ty_info = self.ty_info;
+                let tree = ty_info.match_tree(expr).unwrap();
 
                 let ret_ty = self.to_llvm_ty(ty);
                 let ret_local = builder.local(ret_ty.repr);
 
-                builder.switch(value_tag, iter,
-                |builder, (field, mapping)| {
-                    // initialize the binding
-                    let gens = self.syms.get_gens(gens);
-                    let field_ty = field.1.to_ty(gens, self.syms);
-                    let field_ty = field_ty.resolve(&[env.gens], self.syms);
-                    let field_ty_llvm = self.to_llvm_ty(field_ty);
-
-                    let local = builder.local(field_ty_llvm.repr);
-                    let binding = 
-                    if field_ty.sym(self.syms).unwrap() == SymbolId::UNIT {
-                        *builder.const_unit()
-                    } else {
-                        let payload = builder.load(val_data_ptr, field_ty_llvm.repr);
-                        self.emit_copy(builder, payload, field_ty)
-                    };
+                let mut arms = MatchArms {
+                    root: builder.alloca_store(match_value),
+                    root_ty: value_ty,
+                    arm: builder.local(*self.i32),
+                    guards: mappings.iter().map(|m| m.guard()).collect(),
+                    bindings: vec![vec![]; mappings.len()],
+                };
 
-                    builder.local_set(local, binding);
-                    self.emit_drop(env, builder, match_value, sym);
+                // pick an arm and copy out its bindings, after
+                // that the matched value isn't needed anymore
+                self.decision(env, builder, tree, &mut arms);
+                self.emit_drop(env, builder, match_value, value_ty);
+
+                let arm = builder.local_get(arms.arm).as_integer();
+                builder.switch(arm, mappings.iter().zip(&arms.bindings),
+                |builder, (mapping, bindings)| {
+                    let vars_len = env.vars.len();
+                    for &(name, local, ty) in bindings {
+                        env.vars.push((name, local, ty, false));
+                    }
 
                     let match_body_is_never = self.ty_info.expr(mapping.expr())
                         .is_never(self.syms);
 
-                    env.vars.push((mapping.binding(), local, field_ty, false));
-
                     // run the body
                     let ret_val = self.expr(env, builder, mapping.expr());
+                    env.vars.truncate(vars_len);
 
                     let ret_val = match ret_val {
                         Ok(value) => value,
                         Err(error) => {
                             self.error(env, builder, error);
-                            let binding = env.vars.pop().unwrap();
-                            debug_assert_eq!(binding, (mapping.binding(), local, field_ty, false));
                             return;
                         },
                     };
 
-                    let binding = env.vars.pop().unwrap();
-                    debug_assert_eq!(binding, (mapping.binding(), local, field_ty, false));
                     if !match_body_is_never {
-                        let binding = builder.local_get(local);
-                        self.emit_drop(env, builder, binding, field_ty);
+                        for &(_, local, ty) in bindings {
+                            let binding = builder.local_get(local);
+                            self.emit_drop(env, builder, binding, ty);
+                        }
+
                         builder.local_set(ret_local, ret_val);
                     }
                 });
@@ -4142,18 +5218,46 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
+            parser::nodes::expr::Expr::IndexList { list, index } 
+            if self.ty_info.expr(list).resolve(&[env.gens], self.syms).sym(self.syms) != Ok(SymbolId::LIST) => {
+                let list_ty = self.ty_info.expr(list).resolve(&[env.gens], self.syms);
+                let list_value = self.expr(env, builder, list)?;
+                let index_value = self.expr(env, builder, index)?;
+                let elem_ty = out_if_err!().resolve(&[env.gens], self.syms);
+
+                let trait_gens = self.syms.sym(SymbolId::INDEX_TRAIT).generics();
+                let trait_gens = self.syms.add_gens(self.syms.arena().alloc_new([(trait_gens[0], elem_ty)]));
+                let func = self.resolve_trait_method_ex(list_ty, Type::Ty(SymbolId::INDEX_TRAIT, trait_gens), StringMap::INDEX_FUNC)
+                    .expect("semantic analysis must ensure index is implemented");
+
+                let (func_ptr, func_ty) = (func.func_ptr, func.func_ty);
+                self.call_function(
+                    env,
+                    builder,
+                    (func_ptr, builder.ptr_null()),
+                    func_ty,
+                    &[
+                        (list_value, list_ty, None),
+                        (index_value, Type::I64, None),
+                    ]
+                )
+            },
+
+
             parser::nodes::expr::Expr::IndexList { list, index } => {
                 let list_ty = self.ty_info.expr(list).resolve(&[env.gens], self.syms);
                 let (list_value, list_is_temporary) = 
                 match self.ast.expr(list) {
-                    parser::nodes::expr::Expr::Identifier(name, _) => {
+                    parser::nodes::expr::Expr::Identifier(name, _)
+                    if !matches!(self.ty_info.idents.get(&list), Some(Some(_))) => {
                         let local = env.find_var(name).unwrap();
                         (builder.local_get(local), false)
                     },
                     _ => (self.expr(env, builder, list)?, true),
                 };
-                let index = self.expr(env, builder, index)?.as_integer();
+                let index_val = self.expr(env, builder, index)?.as_integer();
                 out_if_err!();
+                let index = self.index_to_i64(env, builder, index, index_val);
 
                 let elem_ty = {
                     let ty = list_ty.gens(self.syms);
@@ -4163,7 +5267,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 let elem_llvm = self.to_llvm_ty(elem_ty);
 
                 let list = list_value.as_struct();
-                let index = self.check_list_index(builder, list, index);
+                let index = self.check_list_index(builder, list, index, self.ast.range(expr));
                 let element_ptr = self.collection_element_ptr(
                     builder,
                     list,
@@ -4370,6 +5474,20 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 let SymbolKind::Function(function) = self.syms.sym(func_sym).kind()
                 else { unreachable!() };
 
+                // `panic(msg)` gets the same location as the
+                // compiler's own panics
+                if let syms::func::FunctionKind::Extern(path) = function.kind()
+                && self.string_map.get(path) == "margarinePanic"
+                && let [arg] = args
+                && self.ty_info.expr(arg.expr).resolve(&[env.gens], self.syms).sym(self.syms) .ok() == Some(SymbolId::STR) {
+                    let message = self.expr(env, builder, arg.expr)?;
+                    self.emit_drop(env, builder, func, callable_ty);
+                    let source = self.ast.range(expr);
+                    self.emit_str_panic_at(builder, message, source);
+                    builder.unreachable();
+                    return Ok((*builder.const_unit(), result_ty));
+                }
+
 
                 let accessor = 
                 if let Expr::AccessField { val, .. } = self.ast.expr(lhs) {
@@ -4423,7 +5541,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
-            parser::nodes::expr::Expr::Closure { body, .. } => {
+            parser::nodes::expr::Expr::Closure { args: closure_args, body } => {
                 let ty = out_if_err!();
 
                 let closure = ty.sym(self.syms).unwrap();
@@ -4563,13 +5681,18 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     assert!(self.funcs.insert(hash, func).is_none());
 
                     let mut builder = func_ptr.builder(self.ctx, llvm_func_ty);
+                    let closure_name = self.string_map.get(closure_name);
+                    let closure_source = self.ast.range(expr);
+                    let previous_debug_scope = self.debug_function(
+                        &builder, func_ptr, closure_name, closure_name, closure_source);
+
                     let closure_ret = func_ty.ret().to_ty(combined_gens, self.syms).resolve(&[], self.syms);
                     let closure_ret = self.to_llvm_ty(closure_ret);
 
                     let mut env = Env {
                         vars: Vec::new(),
                         inouts: Vec::new(),
-                        loop_id: None,
+                        loops: vec![],
                         gens: combined_gens,
                         info: HashMap::new(),
                         ret_llvm_ty: Some(closure_ret),
@@ -4587,6 +5710,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                             let local = builder.local(capture_llvm_ty.repr);
                             builder.local_set(local, builder.load(capture_ptr, capture_llvm_ty.repr));
                             env.alloc_var(capture.0, local, capture_ty, true);
+                            self.debug_variable(&builder, capture.0, None, local, capture_ty, closure_source);
                         }
                     }
 
@@ -4595,6 +5719,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                         let arg_ty = arg.symbol().to_ty(env.gens, self.syms);
                         let arg_ty = arg_ty.resolve(&[], self.syms);
                         let param = builder.arg(i).unwrap();
+                        let source = closure_args.get(i).map_or(closure_source, |arg| arg.3);
                         if arg.is_inout() {
                             let llvm_ty = self.to_llvm_ty(arg_ty);
                             let local = builder.local(llvm_ty.repr);
@@ -4602,6 +5727,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                             builder.local_set(local, value);
                             env.alloc_var(arg.name(), local, arg_ty, true);
                             env.inouts.push((param, local));
+                            self.debug_variable(&builder, arg.name(), Some(i as u32 + 1), local, arg_ty, source);
                         } else {
                             // Own every by-value parameter: the caller keeps its own reference and
                             // releases it after the call, so the callee must hold its own copy to
@@ -4612,10 +5738,12 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                             let owned = self.emit_copy(&mut builder, value, arg_ty);
                             builder.local_set(param, owned);
                             env.alloc_var(arg.name(), param, arg_ty, false);
+                            self.debug_variable(&builder, arg.name(), Some(i as u32 + 1), param, arg_ty, source);
                         }
                     }
 
                     let result = self.expr(&mut env, &mut builder, body);
+                    self.debug_scope = previous_debug_scope;
 
                     match result {
                         Ok(v) => {
@@ -4642,14 +5770,20 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
-            parser::nodes::expr::Expr::Loop { body } => {
-                let lid = env.loop_id;
+            parser::nodes::expr::Expr::Loop { body, label, .. } => {
                 let mut value = Ok(());
 
+                let result_local =
+                    if result_err.is_some()
+                    || result_ty.is_never(self.syms)
+                    || result_ty.sym(self.syms).unwrap() == SymbolId::UNIT { None }
+                    else { Some(builder.local(self.to_llvm_ty(result_ty).repr)) };
+
                 builder.loop_indefinitely(
                 |builder, l| {
-                    env.loop_id = Some((l, env.vars.len()));
+                    env.loops.push((label, l, env.vars.len(), result_local));
                     let result = self.block(env, builder, &body);
+                    env.loops.pop();
 
                     if let Err(e) = result {
                         self.error(env, builder, e);
@@ -4657,10 +5791,12 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                     };
                 });
 
-                env.loop_id = lid;
                 out_if_err!();
 
-                *builder.const_unit()
+                match result_local {
+                    Some(local) => builder.local_get(local),
+                    None => *builder.const_unit(),
+                }
             },
 
 
@@ -4677,20 +5813,27 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
 
 
-            parser::nodes::expr::Expr::Continue => {
+            parser::nodes::expr::Expr::Continue { label } => {
                 out_if_err!();
 
-                let (loop_id, cleanup_start) = env.loop_id.unwrap();
+                let (_, loop_id, cleanup_start, _) = env.loop_target(label);
                 self.drop_locals(env, builder, cleanup_start);
                 builder.loop_continue(loop_id);
                 *builder.const_unit()
             },
 
 
-            parser::nodes::expr::Expr::Break => {
+            parser::nodes::expr::Expr::Break { label, value } => {
                 out_if_err!();
 
-                let (loop_id, cleanup_start) = env.loop_id.unwrap();
+                let (_, loop_id, cleanup_start, result_local) = env.loop_target(label);
+                if let Some(value) = value {
+                    let value = self.expr(env, builder, value)?;
+                    if let Some(local) = result_local {
+                        builder.local_set(local, value);
+                    }
+                }
+
                 self.drop_locals(env, builder, cleanup_start);
                 builder.loop_break(loop_id);
                 *builder.const_unit()
@@ -4724,23 +5867,33 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 let dest = self.to_llvm_ty(ty);
 
 
-                if lsym.is_int() && ty.is_float(self.syms) {
+                if ty.sym(self.syms) == Ok(SymbolId::DYN) {
+                    let lhs_ty = self.ty_info.expr(lhs).resolve(&[env.gens], self.syms);
+                    if lhs_ty.hash(self.syms) == result_ty.hash(self.syms) {
+                        lhs_val
+                    } else {
+                        self.dyn_value(builder, lhs_val, lhs_ty, result_ty)
+                    }
+                } else if lsym.is_int() && ty.is_float(self.syms) {
                     if lsym.is_sint() {
-                        let value = lhs_val.as_integer();
-                        builder.si_to_fp(value, dest.repr)
+                        builder.si_to_fp(lhs_val.as_integer(), dest.repr)
                     } else {
-                        let value = builder.int_cast(lhs_val.as_integer(), *self.i64, false).as_integer();
-                        builder.ui_to_fp(value, dest.repr)
+                        builder.ui_to_fp(lhs_val.as_integer(), dest.repr)
                     }
                 } else if lsym.is_float() && ty.is_int(self.syms) {
-                    if ty.sym(self.syms).unwrap() == SymbolId::BYTE {
-                        builder.fp_to_ui(lhs_val.as_fp(), dest.repr.as_integer())
-                    } else {
+                    if ty.sym(self.syms).unwrap().is_sint() {
                         builder.fp_to_si(lhs_val.as_fp(), dest.repr.as_integer())
+                    } else {
+                        builder.fp_to_ui(lhs_val.as_fp(), dest.repr.as_integer())
                     }
 
+                } else if lsym.is_float() && ty.is_float(self.syms) {
+                    builder.fp_cast(lhs_val.as_fp(), dest.repr)
                 } else if lsym.is_int() && ty.is_int(self.syms) {
                     builder.int_cast(lhs_val.as_integer(), dest.repr, lsym.is_sint())
+                } else if (lsym == SymbolId::CHAR && ty.is_int(self.syms))
+                       || (lsym == SymbolId::BYTE && ty.sym(self.syms) == Ok(SymbolId::CHAR)) {
+                    builder.int_cast(lhs_val.as_integer(), dest.repr, false)
                 } else if lsym == SymbolId::BOOL && ty.is_int(self.syms) {
                     let tag = builder.field_load(lhs_val.as_struct(), 0);
                     builder.int_cast(tag.as_integer(), dest.repr, false)
@@ -4784,6 +5937,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             parser::nodes::expr::Expr::Unwrap(expr_id) => {
                 let value = self.expr(env, builder, expr_id)?;
                 out_if_err!();
+                let source = self.ast.range(expr);
 
 
                 let some = builder.const_int(self.i32, 0, false);
@@ -4796,7 +5950,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
 
                 |builder| {
-                    self.emit_panic(builder, "attempted to unwrap a none value");
+                    self.emit_panic_at(builder, "attempted to unwrap a none value", Some(source));
                 }, 
                 );
 
@@ -4875,6 +6029,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
                 let local = builder.local(value.ty());
 
                 env.alloc_var(name, local, ty, false);
+                self.debug_variable(builder, name, None, local, ty, pattern.source());
 
                 builder.local_set(local, value);
             },
@@ -4892,19 +6047,403 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
                 for (i, &item) in items.iter().enumerate() {
                     let field = builder.field_load(value, i);
-                    let local = builder.local(field.ty());
 
                     let field_ty = cont.fields()[i].1.to_ty(gens, self.syms);
                     let field_ty = field_ty.resolve(&[], self.syms);
+                    let field_sym = self.to_llvm_ty(field_ty);
+
+                    self.resolve_pattern(env, builder, field_ty, field_sym, field, item);
+                }
+            },
+
+
+            PatternKind::Wildcard => (),
+
+
+            | PatternKind::Variant(..)
+            | PatternKind::Literal(_)
+            | PatternKind::Or(_) => unreachable!("refutable patterns are only parsed in 'match'"),
+        }
+    }
+
+
+    /// Walks `tree` storing the index of the selected arm
+    /// in `arms.arm` and copying its bindings into their locals
+    fn decision(
+        &mut self,
+        env: &mut Env<'_, 'ctx>, builder: &mut Builder<'ctx>,
+        tree: &Decision, arms: &mut MatchArms<'ctx>,
+    ) {
+        match tree {
+            Decision::Fail => builder.unreachable(),
+
+
+            Decision::Arm { arm, bindings, otherwise } => {
+                let mut locals = Vec::with_capacity(bindings.len());
+                for (name, place) in bindings {
+                    let (ptr, ty) = self.place_ptr(builder, arms.root, arms.root_ty, place);
+                    let value = self.place_load(builder, ptr, ty);
+                    let value = self.emit_copy(builder, value, ty);
+
+                    // or-patterns reach the same arm from many leaves
+                    let local = match arms.bindings[*arm].iter().find(|b| b.0 == *name) {
+                        Some(&(_, local, _)) => local,
+                        None => {
+                            let local = builder.local(value.ty());
+                            arms.bindings[*arm].push((*name, local, ty));
+                            local
+                        },
+                    };
 
-                    env.alloc_var(item, local, field_ty, false);
-                    builder.local_set(local, field);
+                    builder.local_set(local, value);
+                    locals.push((*name, local, ty));
                 }
+
+                let arm_index = builder.const_int(self.i32, *arm as i64, false);
+
+                let (Some(guard), Some(otherwise)) = (arms.guards[*arm], otherwise)
+                else {
+                    builder.local_set(arms.arm, *arm_index);
+                    return;
+                };
+
+                let vars_len = env.vars.len();
+                for &(name, local, ty) in &locals {
+                    env.vars.push((name, local, ty, false));
+                }
+
+                let cond = self.expr(env, builder, guard);
+                env.vars.truncate(vars_len);
+
+                let cond = match cond {
+                    Ok(v) => v,
+                    Err(err) => {
+                        self.error(env, builder, err);
+                        return;
+                    },
+                };
+
+                let tag = builder.field_load(cond.as_struct(), 0).as_integer();
+                let tag = builder.int_cast(tag, *self.ctx.bool(), false).as_bool();
+
+                builder.ite_ex(&mut (self, env, arms), tag,
+                |builder, (_, _, arms)| {
+                    builder.local_set(arms.arm, *arm_index);
+                },
+
+
+                |builder, (this, env, arms)| {
+                    for &(_, local, ty) in &locals {
+                        let binding = builder.local_get(local);
+                        this.emit_drop(env, builder, binding, ty);
+                    }
+
+                    this.decision(env, builder, otherwise, arms);
+                },
+                );
+            },
+
+
+            Decision::Switch { place, cases, default } => {
+                let (ptr, ty) = self.place_ptr(builder, arms.root, arms.root_ty, place);
+
+                if let Some((Ctor::Variant(_), _)) = cases.first() {
+                    let value = self.place_load(builder, ptr, ty);
+                    let tag = builder.field_load(value.as_struct(), 0).as_integer();
+
+                    builder.switch(tag, cases.iter(), |builder, (_, case)| {
+                        self.decision(env, builder, case, arms);
+                    });
+
+                    return;
+                }
+
+                self.literal_cases(env, builder, (ptr, ty), cases, default.as_deref(), arms);
             },
         }
     }
 
 
+    /// Tests the value behind `place` against each literal
+    /// in turn, falling back to `default`
+    fn literal_cases(
+        &mut self,
+        env: &mut Env<'_, 'ctx>, builder: &mut Builder<'ctx>,
+        place: (Ptr<'ctx>, Type), cases: &[(Ctor, Decision)],
+        default: Option<&Decision>, arms: &mut MatchArms<'ctx>,
+    ) {
+        let Some(((ctor, case), rest)) = cases.split_first()
+        else {
+            match default {
+                Some(default) => self.decision(env, builder, default, arms),
+                None => builder.unreachable(),
+            }
+
+            return;
+        };
+
+        let (ptr, ty) = place;
+        let value = self.place_load(builder, ptr, ty);
+
+        let cond = match *ctor {
+            Ctor::Int(v) => {
+                let value = value.as_integer();
+                let lit = builder.const_int(value.ty(), v, true);
+                builder.cmp_int(value, lit, IntCmp::Eq)
+            },
+
+
+            Ctor::Str(string) => {
+                let accum = builder.local(*self.ctx.bool());
+                let init = builder.const_bool(true);
+                builder.local_set(accum, *init);
+
+                let value = self.emit_copy(builder, value, ty);
+                let lit = self.str_literal(builder, string);
+                self.eq(env, builder, ty, accum, value, lit);
+                builder.local_get(accum).as_bool()
+            },
+
+
+            Ctor::Variant(_) => unreachable!(),
+        };
+
+        builder.ite_ex(&mut (self, env, arms), cond,
+        |builder, (this, env, arms)| this.decision(env, builder, case, arms),
+        |builder, (this, env, arms)| this.literal_cases(env, builder, place, rest, default, arms),
+        );
+    }
+
+
+    /// A pointer to the part of the value behind `root`
+    /// that `place` leads to, along with its type
+    fn place_ptr(
+        &mut self, builder: &mut Builder<'ctx>,
+        root: Ptr<'ctx>, root_ty: Type, place: &[Step],
+    ) -> (Ptr<'ctx>, Type) {
+        let mut ptr = root;
+        let mut ty = root_ty;
+
+        for step in place {
+            let llvm_ty = self.to_llvm_ty(ty);
+
+            let sym_id = ty.sym(self.syms).unwrap();
+            let SymbolKind::Container(cont) = self.syms.sym(sym_id).kind()
+            else { unreachable!() };
+
+            let gens = ty.gens(self.syms);
+            let gens = self.syms.get_gens(gens);
+
+            // enums are a `{ tag, payload }` pair
+            let (index, field) = match *step {
+                Step::Field(i) => (i, i),
+                Step::Payload(variant) => (1, variant),
+            };
+
+            let field_ty = cont.fields()[field].1.to_ty(gens, self.syms);
+            ty = field_ty.resolve(&[], self.syms);
+            ptr = builder.field_ptr(ptr, llvm_ty.strct.as_struct(), index);
+        }
+
+        (ptr, ty)
+    }
+
+
+    fn place_load(&mut self, builder: &mut Builder<'ctx>, ptr: Ptr<'ctx>, ty: Type) -> Value<'ctx> {
+        if ty.sym(self.syms).unwrap() == SymbolId::UNIT {
+            return *builder.const_unit();
+        }
+
+        let llvm_ty = self.to_llvm_ty(ty);
+        builder.load(ptr, llvm_ty.repr)
+    }
+
+
+    /// The value of the `const` or `static` `sym`
+    fn global(
+        &mut self, builder: &mut Builder<'ctx>,
+        sym: SymbolId, global: Global, ty: Type,
+    ) -> Result<Value<'ctx>, ErrorId> {
+        if let Some(e) = self.ty_info.decl(global.decl) {
+            return Err(e)
+        }
+
+        let llvm_ty = self.to_llvm_ty(ty);
+
+        if !global.is_const {
+            let ptr = self.global_ptr(builder, sym);
+            let value = builder.load(ptr.as_ptr(), llvm_ty.repr);
+            return Ok(self.emit_copy(builder, value, ty))
+        }
+
+        let value = self.ty_info.const_value(sym).unwrap();
+
+        // plain data lives in a constant global, the rest is built
+        // once by `margarineInitStatics` and shared like a static
+        let ptr = match self.globals.get(&sym) {
+            Some(ptr) => *ptr,
+
+            None => match self.const_initialiser(value, ty) {
+                Some(init) => {
+                    let name = self.string_map.get(self.syms.sym(sym).name()).to_string();
+                    let ptr = self.module.add_global(llvm_ty.repr, &name);
+                    ptr.set_initialiser(init);
+                    ptr.set_constant(true);
+                    self.globals.insert(sym, ptr);
+                    ptr
+                },
+
+                None => {
+                    self.const_inits.push((sym, ty));
+                    self.global_ptr(builder, sym)
+                },
+            },
+        };
+
+        let value = builder.load(ptr.as_ptr(), llvm_ty.repr);
+        Ok(self.emit_copy(builder, value, ty))
+    }
+
+
+    /// Builds the `const`s that aren't plain data into their
+    /// globals, building them may emit functions using more
+    fn init_consts(&mut self, builder: &mut Builder<'ctx>) {
+        while let Some((sym, ty)) = self.const_inits.pop() {
+            let value = self.ty_info.const_value(sym).unwrap();
+            let value = self.const_value(builder, value, ty);
+            builder.store(self.globals[&sym].as_ptr(), value);
+        }
+    }
+
+
+    /// The storage of the `static` `sym`
+    fn global_ptr(&mut self, builder: &Builder<'ctx>, sym: SymbolId) -> GlobalPtr<'ctx> {
+        if let Some(ptr) = self.globals.get(&sym) { return *ptr }
+
+        let SymbolKind::Global(global) = self.syms.sym(sym).kind()
+        else { unreachable!() };
+
+        let ty = global.ty.to_ty(&[], self.syms).resolve(&[], self.syms);
+        let llvm_ty = self.to_llvm_ty(ty);
+
+        let name = self.string_map.get(self.syms.sym(sym).name()).to_string();
+        let ptr = self.module.add_global(llvm_ty.repr, &name);
+        ptr.set_initialiser(builder.const_zero(llvm_ty.repr));
+        self.globals.insert(sym, ptr);
+        ptr
+    }
+
+
+    /// `value` as an LLVM constant if it's plain data
+    fn const_initialiser(&mut self, value: &ConstValue, ty: Type) -> Option<Value<'ctx>> {
+        Some(match value {
+            ConstValue::Int(v) => {
+                let llvm_ty = self.to_llvm_ty(ty).repr.as_integer();
+                *self.ctx.const_int(llvm_ty, *v as i64, true)
+            },
+
+            ConstValue::Float(v) if ty.sym(self.syms) == Ok(SymbolId::F32) => *self.ctx.const_f32(*v as f32),
+            ConstValue::Float(v) => *self.ctx.const_f64(*v),
+
+            ConstValue::Tuple(items) => {
+                let field_tys = self.field_types(ty);
+                let mut fields = Vec::with_capacity(items.len());
+                for (item, field_ty) in items.iter().zip(field_tys) {
+                    fields.push(self.const_initialiser(item, field_ty)?);
+                }
+
+                let strct = self.to_llvm_ty(ty).strct.as_struct();
+                *self.ctx.const_struct(strct, &fields)
+            },
+
+            ConstValue::Bool(_) | ConstValue::Str(_) | ConstValue::List(_) => return None,
+        })
+    }
+
+
+    /// Builds `value` at the current position of `builder`
+    fn const_value(&mut self, builder: &mut Builder<'ctx>, value: &ConstValue, ty: Type) -> Value<'ctx> {
+        match value {
+            ConstValue::Int(v) => {
+                let llvm_ty = self.to_llvm_ty(ty).repr.as_integer();
+                *builder.const_int(llvm_ty, *v as i64, true)
+            },
+
+            ConstValue::Float(v) => self.const_float(builder, ty, *v),
+
+            ConstValue::Bool(v) => {
+                let kind = builder.const_bool(*v);
+                let unit = *builder.const_unit();
+                self.create_enum(builder, Type::BOOL, *kind, unit, Type::UNIT)
+            },
+
+            ConstValue::Str(v) => self.str_literal(builder, *v),
+
+            ConstValue::Tuple(items) => {
+                let field_tys = self.field_types(ty);
+                let mut fields = Vec::with_capacity(items.len());
+                for (i, (item, field_ty)) in items.iter().zip(field_tys).enumerate() {
+                    let value = self.const_value(builder, item, field_ty);
+                    fields.push((self.string_map.num(i), value));
+                }
+
+                *self.create_struct(builder, ty, &fields)
+            },
+
+            ConstValue::List(items) => {
+                let gens = ty.gens(self.syms);
+                let elem_ty = self.syms.get_gens(gens)[0].1;
+                let elem_repr = self.to_llvm_ty(elem_ty).repr;
+
+                let len = builder.const_int(self.i64, items.len() as _, false);
+                let (list, buf) = self.collection_flat(builder, len, elem_repr);
+
+                for (i, item) in items.iter().enumerate() {
+                    let value = self.const_value(builder, item, elem_ty);
+                    let index = builder.const_int(self.i64, i as _, false);
+                    let ptr = builder.gep(buf, elem_repr, index);
+                    builder.store_tbaa(ptr, value, self.tbaa_element);
+                }
+
+                *list
+            },
+        }
+    }
+
+
+    /// The types of the fields of the tuple or struct `ty`
+    fn field_types(&mut self, ty: Type) -> Vec<Type> {
+        let sym_id = ty.sym(self.syms).unwrap();
+        let SymbolKind::Container(cont) = self.syms.sym(sym_id).kind()
+        else { unreachable!() };
+
+        let gens = ty.gens(self.syms);
+        let gens = self.syms.get_gens(gens);
+
+        cont.fields().iter()
+            .map(|field| field.1.to_ty(gens, self.syms).resolve(&[], self.syms))
+            .collect()
+    }
+
+
+    /// A freshly allocated `str` holding `string`
+    fn str_literal(&mut self, builder: &mut Builder<'ctx>, string: StringIndex) -> Value<'ctx> {
+        let string = self.string_map.get(string);
+        let len = builder.const_int(self.i64, string.len() as _, false);
+
+        let byte_ty = self.ctx.integer(8);
+        let byte_arr_ty = self.ctx.array(*self.ctx.integer(8), string.len());
+        let bytes = *self.ctx.const_str(string);
+        let global = self.module.add_global(*byte_arr_ty, "str_literal");
+        global.set_initialiser(bytes);
+        let bytes = builder.load(global.as_ptr(), *byte_arr_ty);
+
+        let (collection, data) = self.collection_flat(builder, len, *byte_ty);
+        builder.store_tbaa(data, bytes, self.tbaa_element);
+        *builder.struct_instance(self.str_ty, [*collection])
+    }
+
+
     fn create_struct(
         &mut self,
         builder: &mut Builder<'ctx>,
@@ -5035,6 +6574,12 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             return builder.call(self.rc_clone_fn.0, self.rc_clone_fn.1, &[value]);
         }
 
+        if sym_id == SymbolId::DYN {
+            let data = builder.field_load(value.as_struct(), 0);
+            builder.call(self.rc_clone_fn.0, self.rc_clone_fn.1, &[data]);
+            return value;
+        }
+
         if sym_id == SymbolId::LIST 
         || sym_id == SymbolId::LIST_ITER {
 
@@ -5218,6 +6763,17 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             return;
         }
 
+        // the vtable's first slot drops the value's allocation
+        if sym_id == SymbolId::DYN {
+            let data = builder.field_load(value.as_struct(), 0);
+            let vtable = builder.field_load(value.as_struct(), 1).as_ptr();
+            let drop_fn = builder.load(vtable, *self.ctx.ptr()).as_func();
+            let void = self.ctx.void();
+            let drop_fn_ty = void.fn_ty(self.ctx.arena, &[*self.ctx.ptr()], false);
+            builder.call(drop_fn, drop_fn_ty, &[data]);
+            return;
+        }
+
         if sym_id == SymbolId::LIST 
         || sym_id == SymbolId::LIST_ITER {
             let collection = 
@@ -5322,7 +6878,27 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
         trait_id: SymbolId,
         func_name: StringIndex,
     ) -> Option<&Function<'ctx>> {
-        let trait_ty = Type::Ty(trait_id, GenListId::EMPTY);
+        self.resolve_trait_method_ex(ty, Type::Ty(trait_id, GenListId::EMPTY), func_name)
+    }
+
+
+    fn resolve_trait_method_ex(
+        &mut self,
+        ty: Type,
+        trait_ty: Type,
+        func_name: StringIndex,
+    ) -> Option<&Function<'ctx>> {
+        self.trait_method(ty, trait_ty, func_name)?.ok()
+    }
+
+
+    /// `None` if `ty` has no implementation of `trait_ty`
+    fn trait_method(
+        &mut self,
+        ty: Type,
+        trait_ty: Type,
+        func_name: StringIndex,
+    ) -> Option<Result<&Function<'ctx>, ErrorId>> {
         let (ns, bindings) = self.syms.trait_implementation(ty, trait_ty)?;
 
         let func_sym = self.ns.get_ns(ns).get_sym(func_name).unwrap().ok()?;
@@ -5336,7 +6912,473 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
 
         let gens = self.syms.add_gens(gens.leak());
 
-        self.get_func(Type::Ty(func_sym, gens)).ok()
+        Some(self.get_func(Type::Ty(func_sym, gens)))
+    }
+
+
+    /// Fills `wrapper` with a lookup of its arguments in a table of
+    /// the results `func` returned so far, calling `func` on a miss
+    fn cached_function(
+        &mut self,
+        name: &str,
+        wrapper: FunctionPtr<'ctx>,
+        func: FunctionPtr<'ctx>,
+        func_ty: FunctionType<'ctx>,
+        args: &[Type],
+        ret: Type,
+    ) {
+        let ptr_ty = *self.ctx.ptr();
+        let mut fields = vec![ptr_ty, *self.i64];
+        fields.extend(args.iter().map(|arg| self.to_llvm_ty(*arg).repr));
+        fields.push(self.to_llvm_ty(ret).repr);
+
+        let node_ty = self.ctx.literal_struct(&fields, false);
+        let table_ty = self.ctx.literal_struct(&[ptr_ty, *self.i64, *self.i64], false);
+        let table = self.module.add_global(*table_ty, &format!("{name}.cache"));
+
+        let mut builder = wrapper.builder(self.ctx, func_ty);
+        table.set_initialiser(builder.const_zero(*table_ty));
+        let mut env = Env::default();
+
+        let params = (0..args.len())
+            .map(|i| builder.local_get(builder.arg(i).unwrap()))
+            .collect::<Vec<_>>();
+
+        // FNV-1a over the hash of every argument
+        let hash = builder.local(*self.i64);
+        let offset = builder.const_int(self.i64, 0xcbf29ce484222325u64 as i64, false);
+        builder.local_set(hash, *offset);
+        for (value, ty) in params.iter().zip(args) {
+            let value = self.hash_value(&mut env, &mut builder, *value, *ty);
+            let prime = builder.const_int(self.i64, 0x100000001b3, false);
+            let h = builder.local_get(hash).as_integer();
+            let h = builder.xor(h, value);
+            let h = builder.mul_int(h, prime);
+            builder.local_set(hash, *h);
+        }
+
+        let hash = builder.local_get(hash).as_integer();
+        let zero = builder.const_int(self.i64, 0, false);
+        let one = builder.const_int(self.i64, 1, false);
+
+        // hit
+        let capacity = builder.field_ptr_load(table.as_ptr(), table_ty, 1).as_integer();
+        let has_buckets = builder.cmp_int(capacity, zero, IntCmp::Ne);
+        builder.iff(has_buckets, |builder| {
+            let buckets = builder.field_ptr_load(table.as_ptr(), table_ty, 0).as_ptr();
+            let index = builder.and(hash, builder.sub_int(capacity, one));
+            let node = builder.local(ptr_ty);
+            builder.local_set(node, builder.load(builder.gep(buckets, ptr_ty, index), ptr_ty));
+
+            builder.loop_indefinitely(|builder, l| {
+                let node_ptr = builder.local_get(node).as_ptr();
+                builder.iff(builder.ptr_is_null(node_ptr), |builder| builder.loop_break(l));
+
+                let node_hash = builder.field_ptr_load(node_ptr, node_ty, 1).as_integer();
+                let is_match = builder.local(*self.ctx.bool());
+                builder.local_set(is_match, *builder.cmp_int(node_hash, hash, IntCmp::Eq));
+
+                builder.iff(builder.local_get(is_match).as_bool(), |builder| {
+                    for (i, (value, ty)) in params.iter().zip(args).enumerate() {
+                        let key = builder.field_ptr_load(node_ptr, node_ty, 2 + i);
+                        let key = self.emit_copy(builder, key, *ty);
+                        let value = self.emit_copy(builder, *value, *ty);
+                        self.eq(&mut env, builder, *ty, is_match, key, value);
+                    }
+                });
+
+                builder.iff(builder.local_get(is_match).as_bool(), |builder| {
+                    let result = builder.field_ptr_load(node_ptr, node_ty, 2 + args.len());
+                    let result = self.emit_copy(builder, result, ret);
+                    builder.ret(result);
+                });
+
+                builder.local_set(node, builder.field_ptr_load(node_ptr, node_ty, 0));
+            });
+        });
+
+        // miss
+        let mut call_args = params.clone();
+        call_args.push(*builder.ptr_null());
+        let result = builder.call(func, func_ty, &call_args);
+
+        let capacity = builder.field_ptr_load(table.as_ptr(), table_ty, 1).as_integer();
+        let length = builder.field_ptr_load(table.as_ptr(), table_ty, 2).as_integer();
+        let is_full = builder.cmp_int(length, capacity, IntCmp::UnsignedGe);
+        builder.iff(is_full, |builder| self.grow_cache(builder, table, table_ty, node_ty));
+
+        let node_size = self.const_usize(&builder, node_ty.size_of(self.module).unwrap());
+        let node_ptr = builder.call(self.alloc_fn.0, self.alloc_fn.1, &[*node_size]).as_ptr();
+        builder.field_store(node_ptr, node_ty, 1, hash);
+        for (i, (value, ty)) in params.iter().zip(args).enumerate() {
+            let value = self.emit_copy(&mut builder, *value, *ty);
+            builder.field_store(node_ptr, node_ty, 2 + i, &value);
+        }
+
+        let stored = self.emit_copy(&mut builder, result, ret);
+        builder.field_store(node_ptr, node_ty, 2 + args.len(), &stored);
+
+        let buckets = builder.field_ptr_load(table.as_ptr(), table_ty, 0).as_ptr();
+        let capacity = builder.field_ptr_load(table.as_ptr(), table_ty, 1).as_integer();
+        let index = builder.and(hash, builder.sub_int(capacity, one));
+        let bucket = builder.gep(buckets, ptr_ty, index);
+        builder.field_store(node_ptr, node_ty, 0, &builder.load(bucket, ptr_ty));
+        builder.store(bucket, *node_ptr);
+
+        let length = builder.field_ptr_load(table.as_ptr(), table_ty, 2).as_integer();
+        builder.field_store(table.as_ptr(), table_ty, 2, builder.add_int(length, one));
+
+        builder.ret(result);
+
+        self.caches.push(Cache { table, table_ty, node_ty, args: args.to_vec(), ret });
+    }
+
+
+    /// Doubles the bucket count of a cache table, relinking
+    /// its nodes into the new buckets
+    fn grow_cache(
+        &mut self,
+        builder: &mut Builder<'ctx>,
+        table: GlobalPtr<'ctx>,
+        table_ty: StructTy<'ctx>,
+        node_ty: StructTy<'ctx>,
+    ) {
+        let ptr_ty = *self.ctx.ptr();
+        let ptr_size = builder.const_int(self.i64, ptr_ty.size_of(self.module).unwrap() as i64, false);
+        let zero = builder.const_int(self.i64, 0, false);
+        let one = builder.const_int(self.i64, 1, false);
+
+        let buckets = builder.field_ptr_load(table.as_ptr(), table_ty, 0).as_ptr();
+        let capacity = builder.field_ptr_load(table.as_ptr(), table_ty, 1).as_integer();
+        let is_empty = builder.cmp_int(capacity, zero, IntCmp::Eq);
+        let doubled = builder.mul_int(capacity, builder.const_int(self.i64, 2, false));
+        let new_capacity = builder.select(is_empty, *builder.const_int(self.i64, 16, false), *doubled).as_integer();
+        let new_mask = builder.sub_int(new_capacity, one);
+
+        let size = builder.mul_int(new_capacity, ptr_size);
+        let size = builder.int_cast(size, *self.usize, false);
+        let new_buckets = builder.call(self.alloc_fn.0, self.alloc_fn.1, &[size]).as_ptr();
+
+        let i = builder.local(*self.i64);
+        builder.local_set(i, *zero);
+        builder.loop_indefinitely(|builder, l| {
+            let index = builder.local_get(i).as_integer();
+            builder.iff(builder.cmp_int(index, new_capacity, IntCmp::Eq), |builder| builder.loop_break(l));
+            builder.store(builder.gep(new_buckets, ptr_ty, index), *builder.ptr_null());
+            builder.local_set(i, *builder.add_int(index, one));
+        });
+
+        let node = builder.local(ptr_ty);
+        builder.local_set(i, *zero);
+        builder.loop_indefinitely(|builder, l| {
+            let index = builder.local_get(i).as_integer();
+            builder.iff(builder.cmp_int(index, capacity, IntCmp::Eq), |builder| builder.loop_break(l));
+            builder.local_set(node, builder.load(builder.gep(buckets, ptr_ty, index), ptr_ty));
+
+            builder.loop_indefinitely(|builder, l| {
+                let node_ptr = builder.local_get(node).as_ptr();
+                builder.iff(builder.ptr_is_null(node_ptr), |builder| builder.loop_break(l));
+
+                let next = builder.field_ptr_load(node_ptr, node_ty, 0);
+                let hash = builder.field_ptr_load(node_ptr, node_ty, 1).as_integer();
+                let bucket = builder.gep(new_buckets, ptr_ty, builder.and(hash, new_mask));
+                builder.field_store(node_ptr, node_ty, 0, &builder.load(bucket, ptr_ty));
+                builder.store(bucket, *node_ptr);
+                builder.local_set(node, next);
+            });
+
+            builder.local_set(i, *builder.add_int(index, one));
+        });
+
+        builder.iff(builder.bool_not(is_empty), |builder| {
+            let size = builder.mul_int(capacity, ptr_size);
+            let size = builder.int_cast(size, *self.usize, false);
+            builder.call(self.dealloc_fn.0, self.dealloc_fn.1, &[*buckets, size]);
+        });
+
+        builder.field_store(table.as_ptr(), table_ty, 0, new_buckets);
+        builder.field_store(table.as_ptr(), table_ty, 1, new_capacity);
+    }
+
+
+    /// FNV-1a over the bytes of the borrowed `str` in `value`
+    fn str_hash(&mut self, builder: &mut Builder<'ctx>, value: Value<'ctx>) -> Integer<'ctx> {
+        let byte_ty = self.ctx.integer(8);
+        let collection = builder.field_load(value.as_struct(), 0).as_struct();
+        let len = self.collection_length(builder, collection);
+
+        let size = builder.int_cast(len, *self.usize, false);
+        let data = builder.call(self.alloc_fn.0, self.alloc_fn.1, &[size]).as_ptr();
+        let output_index = builder.alloca(*self.i64);
+        let zero = builder.const_int(self.i64, 0, false);
+        builder.store(output_index, *zero);
+        self.collection_flatten(builder, collection, zero, len, data, output_index, *byte_ty, Some(Type::BYTE));
+
+        let one = builder.const_int(self.i64, 1, false);
+        let prime = builder.const_int(self.i64, 0x100000001b3, false);
+        let hash = builder.local(*self.i64);
+        builder.local_set(hash, *builder.const_int(self.i64, 0xcbf29ce484222325u64 as i64, false));
+        let i = builder.local(*self.i64);
+        builder.local_set(i, *zero);
+        builder.loop_indefinitely(|builder, l| {
+            let index = builder.local_get(i).as_integer();
+            builder.iff(builder.cmp_int(index, len, IntCmp::Eq), |builder| builder.loop_break(l));
+
+            let byte = builder.load(builder.gep(data, *byte_ty, index), *byte_ty).as_integer();
+            let byte = builder.int_cast(byte, *self.i64, false).as_integer();
+            let h = builder.xor(builder.local_get(hash).as_integer(), byte);
+            builder.local_set(hash, *builder.mul_int(h, prime));
+            builder.local_set(i, *builder.add_int(index, one));
+        });
+
+        builder.call(self.dealloc_fn.0, self.dealloc_fn.1, &[*data, size]);
+        builder.local_get(hash).as_integer()
+    }
+
+
+    /// The `i64` a cache table is keyed on for `value`
+    fn hash_value<'env>(
+        &mut self,
+        env: &mut Env<'env, 'ctx>,
+        builder: &mut Builder<'ctx>,
+        value: Value<'ctx>,
+        ty: Type,
+    ) -> Integer<'ctx> {
+        let sym = ty.sym(self.syms).unwrap();
+        if sym.is_int() || sym == SymbolId::CHAR {
+            return builder.int_cast(value.as_integer(), *self.i64, false).as_integer();
+        }
+
+        if sym == SymbolId::BOOL {
+            let tag = builder.field_load(value.as_struct(), 0).as_integer();
+            return builder.int_cast(tag, *self.i64, false).as_integer();
+        }
+
+        if sym == SymbolId::STR {
+            return self.str_hash(builder, value);
+        }
+
+        let func = self.resolve_trait_method(ty, SymbolId::HASH_TRAIT, StringMap::HASH)
+            .expect("semantic analysis must ensure cached arguments are hashable");
+
+        let (func_ptr, func_ty) = (func.func_ptr, func.func_ty);
+        let value = self.emit_copy(builder, value, ty);
+        self.call_function(env, builder, (func_ptr, builder.ptr_null()), func_ty, &[(value, ty, None)])
+            .as_integer()
+    }
+
+
+    /// Builds the function that drops every cached argument & result
+    /// and frees the tables, `main` or the embedder calls it on exit
+    fn free_caches(&mut self) -> (FunctionPtr<'ctx>, FunctionType<'ctx>) {
+        let func_ty = self.ctx.void().fn_ty(self.ctx.arena, &[], false);
+        let func = self.module.function("margarineFreeCaches", func_ty);
+
+        let mut builder = func.builder(self.ctx, func_ty);
+        let mut env = Env::default();
+        let ptr_ty = *self.ctx.ptr();
+
+        for cache in core::mem::take(&mut self.caches) {
+            let Cache { table, table_ty, node_ty, args, ret } = cache;
+            let zero = builder.const_int(self.i64, 0, false);
+            let one = builder.const_int(self.i64, 1, false);
+
+            let buckets = builder.field_ptr_load(table.as_ptr(), table_ty, 0).as_ptr();
+            let capacity = builder.field_ptr_load(table.as_ptr(), table_ty, 1).as_integer();
+            let node_size = self.const_usize(&builder, node_ty.size_of(self.module).unwrap());
+
+            let i = builder.local(*self.i64);
+            let node = builder.local(ptr_ty);
+            builder.local_set(i, *zero);
+            builder.loop_indefinitely(|builder, l| {
+                let index = builder.local_get(i).as_integer();
+                builder.iff(builder.cmp_int(index, capacity, IntCmp::Eq), |builder| builder.loop_break(l));
+                builder.local_set(node, builder.load(builder.gep(buckets, ptr_ty, index), ptr_ty));
+
+                builder.loop_indefinitely(|builder, l| {
+                    let node_ptr = builder.local_get(node).as_ptr();
+                    builder.iff(builder.ptr_is_null(node_ptr), |builder| builder.loop_break(l));
+
+                    for (i, ty) in args.iter().chain(core::iter::once(&ret)).enumerate() {
+                        let value = builder.field_ptr_load(node_ptr, node_ty, 2 + i);
+                        self.emit_drop(&mut env, builder, value, *ty);
+                    }
+
+                    builder.local_set(node, builder.field_ptr_load(node_ptr, node_ty, 0));
+                    builder.call(self.dealloc_fn.0, self.dealloc_fn.1, &[*node_ptr, *node_size]);
+                });
+
+                builder.local_set(i, *builder.add_int(index, one));
+            });
+
+            builder.iff(builder.cmp_int(capacity, zero, IntCmp::Ne), |builder| {
+                let ptr_size = builder.const_int(self.i64, ptr_ty.size_of(self.module).unwrap() as i64, false);
+                let size = builder.int_cast(builder.mul_int(capacity, ptr_size), *self.usize, false);
+                builder.call(self.dealloc_fn.0, self.dealloc_fn.1, &[*buckets, size]);
+            });
+
+            builder.store(table.as_ptr(), builder.const_zero(*table_ty));
+        }
+
+        builder.ret_void();
+        (func, func_ty)
+    }
+
+
+    /// Moves `value` into an allocation a `dyn_ty` points to
+    fn dyn_value(
+        &mut self,
+        builder: &mut Builder<'ctx>,
+        value: Value<'ctx>,
+        ty: Type,
+        dyn_ty: Type,
+    ) -> Value<'ctx> {
+        let llvm_ty = self.to_llvm_ty(ty);
+        let rc_ty = self.ctx.literal_struct(&[*self.usize, llvm_ty.repr], false);
+        let size = self.const_usize(builder, rc_ty.size_of(self.module).unwrap());
+        let data = builder.call(self.rc_alloc_fn.0, self.rc_alloc_fn.1, &[*size]).as_ptr();
+
+        let one = self.const_usize(builder, 1);
+        builder.store(data, *builder.struct_instance(rc_ty, [*one, value]));
+
+        let vtable = self.vtable(ty, dyn_ty);
+        *builder.struct_instance(self.dyn_ref, [*data, *vtable])
+    }
+
+
+    /// The vtable of `ty` as a `dyn_ty`. Its first slot drops the
+    /// allocation & the rest are shims, which take the allocation,
+    /// for the methods in the order of `SymbolMap::dyn_traits`
+    fn vtable(&mut self, ty: Type, dyn_ty: Type) -> GlobalPtr<'ctx> {
+        let key = (ty.hash(self.syms), dyn_ty.hash(self.syms));
+        if let Some(vtable) = self.vtables.get(&key) {
+            return *vtable;
+        }
+
+        let dyn_gens = dyn_ty.gens(self.syms);
+        let trait_ty = self.syms.get_gens(dyn_gens)[0].1;
+        let trait_id = trait_ty.sym(self.syms).unwrap();
+        let traits = self.syms.dyn_traits(trait_id).unwrap();
+
+        let trait_gens = trait_ty.gens(self.syms);
+        let mut bindings = self.syms.get_gens(trait_gens).to_vec();
+        bindings.push((BoundedGeneric::new(StringMap::SELF_TY, &[]), ty));
+
+        let name = format!(
+            "{} as {}",
+            ty.display(self.string_map, self.syms),
+            dyn_ty.display(self.string_map, self.syms),
+        );
+
+        let llvm_ty = self.to_llvm_ty(ty);
+        let rc_ty = self.ctx.literal_struct(&[*self.usize, llvm_ty.repr], false);
+        let ptr = *self.ctx.ptr();
+        let void = self.ctx.void();
+        let mut env = Env::default();
+
+        let mut slots = vec![];
+
+        let drop_fn_ty = void.fn_ty(self.ctx.arena, &[ptr], false);
+        let drop_fn = self.module.function(&format!("{name}.drop"), drop_fn_ty);
+        let mut builder = drop_fn.builder(self.ctx, drop_fn_ty);
+        let data = builder.local_get(builder.arg(0).unwrap());
+        let rc = self.syms.get_ty(SymbolId::RC, &[ty]);
+        self.emit_drop(&mut env, &mut builder, data, rc);
+        builder.ret_void();
+        slots.push(*drop_fn);
+
+        for tr in traits {
+            let Some(tr_id) = tr.sym()
+            else { continue };
+            let SymbolKind::Trait(trait_sym) = self.syms.sym(tr_id).kind()
+            else { continue };
+
+            let tr_ty = tr.to_ty(&bindings, self.syms).resolve(&[], self.syms);
+            for (func_name, _) in trait_sym.funcs {
+                let shim_name = format!("{name}.{}", self.string_map.get(*func_name));
+                let method = self.trait_method(ty, tr_ty, *func_name)
+                    .map(|func| func.map(|func| (func.func_ptr, func.func_ty)));
+
+                let shim =
+                match method {
+                    Some(Ok((func_ptr, func_ty))) => {
+                        let mut args = func_ty.args();
+                        args[0] = ptr;
+                        let shim_ty = func_ty.ret().fn_ty(self.ctx.arena, &args, false);
+                        let shim = self.module.function(&shim_name, shim_ty);
+
+                        let builder = shim.builder(self.ctx, shim_ty);
+                        let data = builder.local_get(builder.arg(0).unwrap()).as_ptr();
+
+                        let mut call_args = Vec::with_capacity(args.len());
+                        call_args.push(builder.field_ptr_load(data, rc_ty, 1));
+                        for i in 1..args.len() {
+                            call_args.push(builder.local_get(builder.arg(i).unwrap()));
+                        }
+
+                        let result = builder.call(func_ptr, func_ty, &call_args);
+                        builder.ret(result);
+                        shim
+                    },
+
+                    Some(Err(err)) => {
+                        let shim_ty = void.fn_ty(self.ctx.arena, &[ptr], false);
+                        let shim = self.module.function(&shim_name, shim_ty);
+
+                        let mut builder = shim.builder(self.ctx, shim_ty);
+                        self.error(&mut env, &mut builder, err);
+                        shim
+                    },
+
+                    // only traits every type implements lack an
+                    // implementation & those methods do nothing
+                    None => {
+                        let unit = self.ctx.unit();
+                        let shim_ty = unit.fn_ty(self.ctx.arena, &[ptr, ptr], false);
+                        let shim = self.module.function(&shim_name, shim_ty);
+
+                        let builder = shim.builder(self.ctx, shim_ty);
+                        builder.ret(*builder.const_unit());
+                        shim
+                    },
+                };
+
+                slots.push(*shim);
+            }
+        }
+
+        let vtable_ty = self.ctx.array(ptr, slots.len());
+        let vtable = self.module.add_global(*vtable_ty, &format!("{name}.vtable"));
+        vtable.set_initialiser(*self.ctx.const_array(ptr, &slots));
+        self.vtables.insert(key, vtable);
+        vtable
+    }
+
+
+    /// A trait's default body is written against `Self` and the
+    /// trait's generics rather than the impl's generics
+    fn default_method_gens(
+        &mut self,
+        receiver: Generic<'out>,
+        trait_ty: Generic<'out>,
+        gens: &[(BoundedGeneric<'out>, Type)],
+        func: syms::func::FunctionTy<'out>,
+    ) -> &'out [(BoundedGeneric<'out>, Type)] {
+        let trait_gens = trait_ty.sym().map(|sym| self.syms.sym(sym).generics()).unwrap_or(&[]);
+        let method_gens = &gens[gens.len() - func.declared_generics().len()..];
+
+        let mut env_gens = sti::vec::Vec::with_cap_in(
+            self.syms.arena(), 1 + trait_gens.len() + method_gens.len());
+
+        let receiver = receiver.to_ty(gens, self.syms).resolve(&[], self.syms);
+        env_gens.push((BoundedGeneric::new(StringMap::SELF_TY, &[]), receiver));
+        for (g, arg) in trait_gens.iter().zip(trait_ty.gens().unwrap_or(&[])) {
+            let arg = arg.to_ty(gens, self.syms).resolve(&[], self.syms);
+            env_gens.push((*g, arg));
+        }
+        env_gens.extend_from_slice(method_gens);
+
+        env_gens.leak()
     }
 
 
@@ -5421,6 +7463,83 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
     }
 
 
+    /// Decodes the UTF-8 sequence starting at the byte `lead`
+    /// points to, pulling its continuation bytes from the byte
+    /// iterator at `iter`. Returns the code point and the width
+    /// of the sequence as an `i64`
+    fn decode_utf8(&mut self, builder: &mut Builder<'ctx>, iter: Ptr<'ctx>, lead: Ptr<'ctx>) -> (Integer<'ctx>, Integer<'ctx>) {
+        let byte_ty = self.to_llvm_ty(Type::BYTE).repr;
+        let (next_fn, next_fn_ty) = self.create_collection_iter(byte_ty);
+
+        let lead = builder.load(lead, byte_ty).as_integer();
+        let lead = builder.int_cast(lead, *self.i32, false).as_integer();
+
+        // the width is told by the lead byte's high bits,
+        // its remaining bits are the top of the code point
+        let mut width = *builder.const_int(self.i64, 4, false);
+        let mut mask = *builder.const_int(self.i32, 0x07, false);
+        for (bound, w, m) in [(0xF0, 3, 0x0F), (0xE0, 2, 0x1F), (0x80, 1, 0x7F)] {
+            let bound = builder.const_int(self.i32, bound, false);
+            let is_below = builder.cmp_int(lead, bound, IntCmp::UnsignedLt);
+            width = builder.select(is_below, *builder.const_int(self.i64, w, false), width);
+            mask = builder.select(is_below, *builder.const_int(self.i32, m, false), mask);
+        }
+
+        let width = width.as_integer();
+        let code_point = builder.local(*self.i32);
+        builder.local_set(code_point, *builder.and(lead, mask.as_integer()));
+
+        for i in 1..4 {
+            let index = builder.const_int(self.i64, i, false);
+            let has_byte = builder.cmp_int(index, width, IntCmp::UnsignedLt);
+
+            builder.iff(has_byte, |builder| {
+                let byte = builder.call(next_fn, next_fn_ty, &[*iter]).as_ptr();
+                let is_byte = builder.bool_not(builder.ptr_is_null(byte));
+
+                builder.iff(is_byte, |builder| {
+                    let byte = builder.load(byte, byte_ty).as_integer();
+                    let byte = builder.int_cast(byte, *self.i32, false).as_integer();
+                    let bits = builder.and(byte, builder.const_int(self.i32, 0x3F, false));
+
+                    let value = builder.local_get(code_point).as_integer();
+                    let value = builder.shl(value, builder.const_int(self.i32, 6, false));
+                    builder.local_set(code_point, *builder.or(value, bits));
+                });
+            });
+        }
+
+        (builder.local_get(code_point).as_integer(), width)
+    }
+
+
+    /// A float constant of the float type `ty`
+    fn const_float(&mut self, builder: &Builder<'ctx>, ty: Type, value: f64) -> Value<'ctx> {
+        if ty.sym(self.syms) == Ok(SymbolId::F32) { *builder.const_f32(value as f32) }
+        else { *builder.const_f64(value) }
+    }
+
+
+    /// Widens a list index of any integer type to `i64`
+    fn index_to_i64(
+        &mut self, env: &Env<'_, 'ctx>, builder: &Builder<'ctx>,
+        index: ExprId, value: Integer<'ctx>,
+    ) -> Integer<'ctx> {
+        let ty = self.ty_info.expr(index).resolve(&[env.gens], self.syms);
+        let signed = ty.sym(self.syms).is_ok_and(|sym| sym.is_sint());
+        builder.int_cast(value, *self.i64, signed).as_integer()
+    }
+
+
+    /// Whether `ty` is an integer C extends when passing it,
+    /// `Some(true)` if it's sign extended
+    fn int_ext(&mut self, ty: Type) -> Option<bool> {
+        let sym = ty.sym(self.syms).ok()?;
+        if sym.int_bits()? >= 32 { return None }
+        Some(sym.is_sint())
+    }
+
+
     fn extern_abi(&self, ret: LLVMType<'ctx>) -> ExternAbi<'ctx> {
         let uses_large_struct_sret = matches!(
             self.target,
@@ -5438,6 +7557,187 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
     }
 
 
+    /// Emits `name`, a C ABI wrapper around the `@export`ed
+    /// function `sym` for the programs embedding the library
+    fn export_func(&mut self, sym: SymbolId, name: StringIndex) {
+        let SymbolKind::Function(sym_func) = self.syms.sym(sym).kind()
+        else { unreachable!() };
+
+        let args = sym_func.args().iter()
+            .map(|arg| arg.symbol().to_ty(&[], self.syms))
+            .collect::<Vec<_>>();
+        let ret = sym_func.ret().to_ty(&[], self.syms);
+        let returns_unit = ret.sym(self.syms).is_ok_and(|sym| sym == SymbolId::UNIT);
+
+        let llvm_ret = self.c_ty(ret);
+        let abi = self.extern_abi(llvm_ret);
+
+        let external_ret =
+        match abi {
+            _ if returns_unit => *self.ctx.void(),
+            ExternAbi::Direct => llvm_ret,
+            ExternAbi::SRet(_) => *self.ctx.void(),
+        };
+
+        let mut external_args = Vec::with_capacity(args.len() + 1);
+        if matches!(abi, ExternAbi::SRet(_)) {
+            external_args.push(*self.ctx.ptr());
+        }
+
+        for ty in &args {
+            external_args.push(self.c_ty(*ty));
+        }
+
+        // declare the wrapper before the function it wraps so
+        // it keeps its exact name
+        let external_fn_ty = external_ret.fn_ty(self.ctx.arena, &external_args, false);
+        let external_fn = self.module.function(self.string_map.get(name), external_fn_ty);
+        external_fn.set_linkage(Linkage::External);
+
+        // C passes integers narrower than 32 bits, `bool` included,
+        // extended to a full register
+        let c_ext = |conv: &mut Self, ty: Type| {
+            if ty.sym(conv.syms).is_ok_and(|sym| sym == SymbolId::BOOL) { return Some(false) }
+            conv.int_ext(ty)
+        };
+
+        let first_arg = matches!(abi, ExternAbi::SRet(_)) as u32;
+        for (i, ty) in args.iter().enumerate() {
+            if let Some(signed) = c_ext(self, *ty) {
+                external_fn.set_int_ext(self.ctx, Some(first_arg + i as u32), signed);
+            }
+        }
+
+        if !returns_unit
+        && matches!(abi, ExternAbi::Direct)
+        && let Some(signed) = c_ext(self, ret) {
+            external_fn.set_int_ext(self.ctx, None, signed);
+        }
+
+        if let ExternAbi::SRet(ret) = abi {
+            external_fn.set_sret(self.ctx, ret);
+        }
+
+        let func = self.get_func(Type::Ty(sym, GenListId::EMPTY))
+            .map(|func| (func.func_ptr, func.func_ty));
+
+        let mut builder = external_fn.builder(self.ctx, external_fn_ty);
+        let Ok((func_ptr, func_ty)) = func
+        else { builder.unreachable(); return };
+
+        // exported functions don't capture anything
+        let mut call_args = Vec::with_capacity(args.len() + 1);
+        for (i, ty) in args.iter().enumerate() {
+            let arg = builder.local_get(builder.arg(first_arg as usize + i).unwrap());
+            call_args.push(self.from_c(&mut builder, arg, *ty));
+        }
+        call_args.push(*builder.ptr_null());
+
+        let result = builder.call(func_ptr, func_ty, &call_args);
+        let result = if returns_unit { result } else { self.to_c(&mut builder, result, ret) };
+
+        match abi {
+            _ if returns_unit => builder.ret_void(),
+            ExternAbi::Direct => builder.ret(result),
+            ExternAbi::SRet(_) => {
+                let out = builder.local_get(builder.arg(0).unwrap());
+                builder.store(out.as_ptr(), result);
+                builder.ret_void();
+            }
+        }
+    }
+
+
+    /// The layout of `ty` in an `@export`ed signature. It's margarine's
+    /// own except that `bool`, an enum here, is a byte in C, so
+    /// structs holding one get a `.c` twin
+    fn c_ty(&mut self, ty: Type) -> LLVMType<'ctx> {
+        let repr = self.to_llvm_ty(ty).repr;
+        let sym = ty.sym(self.syms).unwrap();
+        if sym == SymbolId::BOOL { return *self.ctx.integer(8) }
+
+        let hash = ty.hash(self.syms);
+        if let Some(c_ty) = self.c_tys.get(&hash) { return *c_ty }
+
+        let symbol = self.syms.sym(sym);
+        let SymbolKind::Container(cont) = symbol.kind()
+        else { return repr };
+
+        let mut fields = Vec::with_capacity(cont.fields().len());
+        let mut same = true;
+        for (_, field) in cont.fields() {
+            let field = field.to_ty(&[], self.syms);
+            let c_ty = self.c_ty(field);
+            same &= c_ty == self.to_llvm_ty(field).repr;
+            fields.push(c_ty);
+        }
+
+        let c_ty = if same { repr } else {
+            let name = format!("{}.c", self.string_map.get(symbol.name()));
+            let strct = self.ctx.structure(&name);
+            strct.set_fields(&fields, false);
+            *strct
+        };
+
+        self.c_tys.insert(hash, c_ty);
+        c_ty
+    }
+
+
+    /// Converts `value` of type `ty` into its `c_ty` layout
+    fn to_c(&mut self, builder: &mut Builder<'ctx>, value: Value<'ctx>, ty: Type) -> Value<'ctx> {
+        let c_ty = self.c_ty(ty);
+        if c_ty == value.ty() { return value }
+
+        if ty.sym(self.syms) == Ok(SymbolId::BOOL) {
+            let tag = builder.field_load(value.as_struct(), 0).as_integer();
+            return builder.int_cast(tag, c_ty, false);
+        }
+
+        let fields = self.c_fields(ty);
+        let mut c_fields = Vec::with_capacity(fields.len());
+        for (i, field) in fields.into_iter().enumerate() {
+            let field_value = builder.field_load(value.as_struct(), i);
+            c_fields.push(self.to_c(builder, field_value, field));
+        }
+
+        *builder.struct_instance(c_ty.as_struct(), c_fields)
+    }
+
+
+    /// Converts `value`, in the `c_ty` layout of `ty`, back into a `ty`
+    fn from_c(&mut self, builder: &mut Builder<'ctx>, value: Value<'ctx>, ty: Type) -> Value<'ctx> {
+        let llvm_ty = self.to_llvm_ty(ty);
+        if llvm_ty.repr == value.ty() { return value }
+
+        if ty.sym(self.syms) == Ok(SymbolId::BOOL) {
+            let zero = builder.const_int(value.ty().as_integer(), 0, false);
+            let is_true = builder.cmp_int(value.as_integer(), zero, IntCmp::Ne);
+            let unit = *builder.const_unit();
+            return self.create_enum(builder, Type::BOOL, *is_true, unit, Type::UNIT);
+        }
+
+        let fields = self.c_fields(ty);
+        let mut values = Vec::with_capacity(fields.len());
+        for (i, field) in fields.into_iter().enumerate() {
+            let field_value = builder.field_load(value.as_struct(), i);
+            values.push(self.from_c(builder, field_value, field));
+        }
+
+        *builder.struct_instance(llvm_ty.strct.as_struct(), values)
+    }
+
+
+    /// The field types of the exported struct `ty`
+    fn c_fields(&mut self, ty: Type) -> Vec<Type> {
+        let sym = ty.sym(self.syms).unwrap();
+        let SymbolKind::Container(cont) = self.syms.sym(sym).kind()
+        else { unreachable!() };
+
+        cont.fields().iter().map(|(_, field)| field.to_ty(&[], self.syms)).collect()
+    }
+
+
     fn drop_locals(&mut self, env: &mut Env<'_, 'ctx>, builder: &mut Builder<'ctx>, start: usize) {
         for i in (start..env.vars.len()).rev() {
             let (_, local, ty, borrowed) = env.vars[i];
@@ -5496,7 +7796,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
         let sym = ty.sym(self.syms).unwrap();
 
         match sym {
-            SymbolId::I64 | SymbolId::BYTE => {
+            _ if sym.is_int() || sym == SymbolId::CHAR => {
 
                 let a = builder.local_get(accum).as_bool();
                 let b = builder.cmp_int(
@@ -5512,7 +7812,7 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
-            SymbolId::F64 => {
+            _ if sym.is_float() => {
 
                 let a = builder.local_get(accum).as_bool();
                 let b = builder.cmp_fp(
@@ -5535,6 +7835,22 @@ impl<'me, 'out, 'ast, 'str, 'ctx> Conversion<'me, 'out, 'ast, 'str, 'ctx> {
             },
 
 
+            SymbolId::BOOL => {
+
+                let a = builder.local_get(accum).as_bool();
+                let b = builder.cmp_int(
+                    builder.field_load(lhs.as_struct(), 0).as_integer(),
+                    builder.field_load(rhs.as_struct(), 0).as_integer(),
+                    IntCmp::Eq
+                );
+
+
+                let result = builder.bool_and(a, b);
+                builder.local_set(accum, *result);
+
+            },
+
+
             _ => {
                 let b = self.resolve_trait_method(
                     ty, 
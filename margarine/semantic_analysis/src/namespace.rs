@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::{HashMap, HashSet}};
 
 use common::{source::SourceRange, string_map::StringIndex};
 use errors::ErrorId;
@@ -30,6 +30,8 @@ pub struct Namespace {
 #[derive(Debug)]
 pub struct NamespaceMap {
     map: KVec<NamespaceId, Namespace>,
+    /// Every name that was looked up through `get_sym`
+    used: RefCell<HashSet<(NamespaceId, StringIndex)>>,
 }
 
 
@@ -43,7 +45,7 @@ pub enum SymbolGetResult {
 
 
 impl NamespaceMap {
-    pub fn new() -> Self { Self { map: KVec::new() } }
+    pub fn new() -> Self { Self { map: KVec::new(), used: RefCell::new(HashSet::new()) } }
     pub fn push(&mut self, mut ns: Namespace, parent: Option<NamespaceId>) -> NamespaceId {
         ns.parent = parent;
         self.map.push(ns)
@@ -63,6 +65,8 @@ impl NamespaceMap {
         let Some(entry) = self.get_ns(owner).entry(name)
         else { return SymbolGetResult::Undefined };
 
+        self.used.borrow_mut().insert((owner, name));
+
         if entry.visibility() != Visibility::Public 
         && !self.can_access(owner, requester) {
             return SymbolGetResult::Private
@@ -74,6 +78,10 @@ impl NamespaceMap {
         }
     }
 
+    pub fn is_used(&self, ns: NamespaceId, name: StringIndex) -> bool {
+        self.used.borrow().contains(&(ns, name))
+    }
+
     pub fn can_access(&self, owner: NamespaceId, mut requester: NamespaceId) -> bool {
         loop {
             if owner == requester { return true; }
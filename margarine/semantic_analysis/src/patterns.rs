@@ -0,0 +1,573 @@
+use std::collections::HashSet;
+
+use common::{source::SourceRange, string_map::StringIndex};
+use errors::ErrorId;
+use lexer::Literal;
+use parser::nodes::{expr::ExprId, Pattern, PatternKind};
+use sti::vec::KVec;
+
+use crate::{errors::Error, syms::{containers::ContainerKind, sym_map::SymbolId, ty::Type, SymbolKind}, TyChecker};
+
+
+/// One step from a value into one of its parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Step {
+    /// The nth field of a tuple
+    Field(usize),
+    /// The payload of the nth variant of an enum
+    Payload(usize),
+}
+
+
+/// A path from the matched value to one of its parts
+pub type Place = Vec<Step>;
+
+
+/// What a `Switch` compares the value at its place against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ctor {
+    /// The index of an enum variant
+    Variant(usize),
+    Int(i64),
+    Str(StringIndex),
+}
+
+
+/// A `match` lowered into a tree of tests, each arm's
+/// body is jumped to from every leaf that selects it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// No arm matches, only reachable if the match
+    /// isn't exhaustive which is a compile error
+    Fail,
+
+    /// Selects `arm`, binding each name to the value at
+    /// its place. If the arm has a guard `otherwise` is
+    /// what happens when it doesn't hold
+    Arm {
+        arm: usize,
+        bindings: Vec<(StringIndex, Place)>,
+        otherwise: Option<Box<Decision>>,
+    },
+
+    /// Branches on the value at `place`. Switching on an
+    /// enum has a case for every variant, in order, and
+    /// no default
+    Switch {
+        place: Place,
+        cases: Vec<(Ctor, Decision)>,
+        default: Option<Box<Decision>>,
+    },
+}
+
+
+/// A pattern resolved against the type it matches on
+#[derive(Debug, Clone)]
+pub(crate) enum Pat {
+    Any,
+    Binding(StringIndex),
+    Variant(usize, Box<Pat>),
+    Tuple(Vec<Pat>),
+    Int(i64),
+    Str(StringIndex),
+    Or(Vec<Pat>),
+}
+
+
+/// A binding introduced by a pattern
+pub(crate) type Binding = (StringIndex, Type, SourceRange);
+
+
+/// A row of the pattern matrix. Places without a column
+/// match anything
+#[derive(Debug, Clone)]
+struct Row {
+    columns: Vec<(Place, Type, Pat)>,
+    bindings: Vec<(StringIndex, Place)>,
+    arm: usize,
+    has_guard: bool,
+}
+
+
+impl<'me, 'out, 'temp, 'ast: 'out, 'str> TyChecker<'me, 'out, 'temp, 'ast, 'str> {
+    /// Resolves `pattern` against `ty`, pushing the names it
+    /// binds onto `bindings`. Bare names become variants if
+    /// `ty` is an enum with a variant of that name
+    pub(crate) fn lower_pattern(
+        &mut self, node: ExprId, pattern: Pattern, ty: Type, bindings: &mut Vec<Binding>,
+    ) -> Result<Pat, Error> {
+        let source = pattern.source();
+
+        if ty.is_err(&mut self.syms) {
+            // still bind the names so the arm's body can be checked
+            self.pattern_bindings(pattern, ty, bindings);
+            return Ok(Pat::Any);
+        }
+
+        Ok(match pattern.kind() {
+            PatternKind::Wildcard => Pat::Any,
+
+
+            PatternKind::Variable(name) => {
+                let variant = self.enum_variants(ty)
+                    .and_then(|variants| variants.iter().position(|v| v.0 == name));
+
+                match variant {
+                    Some(index) => Pat::Variant(index, Box::new(Pat::Any)),
+                    None => {
+                        bindings.push((name, ty, source));
+                        Pat::Binding(name)
+                    },
+                }
+            },
+
+
+            PatternKind::Variant(name, payload) => {
+                let Some(variants) = self.enum_variants(ty)
+                else { return Err(Error::MatchValueIsntEnum { source, typ: ty }) };
+
+                let Some(index) = variants.iter().position(|v| v.0 == name)
+                else { return Err(Error::InvalidMatch { name, range: source, value: ty }) };
+
+                let payload = match payload {
+                    Some(payload) => self.lower_pattern(node, *payload, variants[index].1, bindings)?,
+                    None => Pat::Any,
+                };
+
+                Pat::Variant(index, Box::new(payload))
+            },
+
+
+            PatternKind::Tuple([]) => {
+                if !ty.eq(&mut self.syms, Type::UNIT) {
+                    return Err(Error::InvalidType { source, found: Type::UNIT, expected: ty });
+                }
+
+                Pat::Any
+            },
+
+
+            PatternKind::Tuple(items) => {
+                let syms = sti::vec::Vec::from_value_in(self.output, items.len(), None);
+                let tuple = self.tuple_sym(source, &syms);
+                let gens = self.tuple_gens(items.len(), source, node.into());
+                let tuple = Type::Ty(tuple, gens);
+
+                if !tuple.eq(&mut self.syms, ty) {
+                    return Err(match self.tuple_fields(ty) {
+                        Some(fields) => Error::VariableTupleAndHintTupleSizeMismatch(source, fields.len(), items.len()),
+                        None => Error::VariableValueNotTuple(source),
+                    });
+                }
+
+                let fields = self.tuple_fields(ty).unwrap();
+                let mut pats = Vec::with_capacity(items.len());
+                for (item, field) in items.iter().zip(fields) {
+                    pats.push(self.lower_pattern(node, *item, field, bindings)?);
+                }
+
+                Pat::Tuple(pats)
+            },
+
+
+            PatternKind::Literal(Literal::Integer(int)) => {
+                let sym = ty.sym(&mut self.syms).ok().filter(|sym| sym.is_int());
+                let Some(sym) = sym
+                else {
+                    if !ty.eq(&mut self.syms, Type::I64) {
+                        return Err(Error::InvalidType { source, found: Type::I64, expected: ty });
+                    }
+
+                    return Ok(Pat::Int(int));
+                };
+
+                if !sym.int_fits(int.into()) {
+                    return Err(Error::LiteralOutOfRange { value: int, ty, source });
+                }
+
+                Pat::Int(int)
+            },
+
+
+            PatternKind::Literal(Literal::String(string)) => {
+                if !ty.eq(&mut self.syms, Type::STR) {
+                    return Err(Error::InvalidType { source, found: Type::STR, expected: ty });
+                }
+
+                if !self.syms.traits(SymbolId::STR).contains_key(&SymbolId::EQ_TRAIT) {
+                    return Err(Error::TypeDoesntImplTrait { source, ty, tr: SymbolId::EQ_TRAIT });
+                }
+
+                Pat::Str(string)
+            },
+
+
+            PatternKind::Literal(Literal::Char(char)) => {
+                if !ty.eq(&mut self.syms, Type::CHAR) {
+                    return Err(Error::InvalidType { source, found: Type::CHAR, expected: ty });
+                }
+
+                Pat::Int(char as i64)
+            },
+
+
+            PatternKind::Literal(_) => unreachable!("only integer, string and char literals are parsed as patterns"),
+
+
+            PatternKind::Or(alternatives) => {
+                let mut pats = Vec::with_capacity(alternatives.len());
+                let mut first = None;
+
+                for alternative in alternatives {
+                    let mut alt_bindings = vec![];
+                    pats.push(self.lower_pattern(node, *alternative, ty, &mut alt_bindings)?);
+
+                    let Some(first) = &first
+                    else { first = Some(alt_bindings); continue };
+
+                    self.check_or_bindings(first, &alt_bindings, alternative.source())?;
+                    self.check_or_bindings(&alt_bindings, first, alternatives[0].source())?;
+                }
+
+                bindings.extend(first.unwrap_or_default());
+                Pat::Or(pats)
+            },
+        })
+    }
+
+
+    /// Makes sure every name in `bindings` is also bound, with
+    /// the same type, in the alternative `others` came from
+    fn check_or_bindings(&mut self, bindings: &[Binding], others: &[Binding], source: SourceRange) -> Result<(), Error> {
+        for &(name, ty, _) in bindings {
+            let Some(&(_, other, other_source)) = others.iter().find(|b| b.0 == name)
+            else { return Err(Error::OrPatternBindingMismatch { name, source }) };
+
+            if !other.eq(&mut self.syms, ty) {
+                return Err(Error::InvalidType { source: other_source, found: other, expected: ty });
+            }
+        }
+
+        Ok(())
+    }
+
+
+    /// Every name `pattern` could bind, all typed as `ty`
+    fn pattern_bindings(&mut self, pattern: Pattern, ty: Type, bindings: &mut Vec<Binding>) {
+        match pattern.kind() {
+            PatternKind::Variable(name) => bindings.push((name, ty, pattern.source())),
+            PatternKind::Variant(_, Some(payload)) => self.pattern_bindings(*payload, ty, bindings),
+            PatternKind::Tuple(items) => items.iter().for_each(|item| self.pattern_bindings(*item, ty, bindings)),
+            PatternKind::Or([first, ..]) => self.pattern_bindings(*first, ty, bindings),
+            _ => (),
+        }
+    }
+
+
+    /// The variants of `ty` along with their payloads if
+    /// it's an enum
+    pub(crate) fn enum_variants(&mut self, ty: Type) -> Option<Vec<(StringIndex, Type)>> {
+        self.container_fields(ty, ContainerKind::Enum)
+            .map(|fields| fields.collect())
+    }
+
+
+    /// The field types of `ty` if it's a tuple
+    pub(crate) fn tuple_fields(&mut self, ty: Type) -> Option<Vec<Type>> {
+        self.container_fields(ty, ContainerKind::Tuple)
+            .map(|fields| fields.map(|f| f.1).collect())
+    }
+
+
+    fn container_fields(&mut self, ty: Type, kind: ContainerKind) -> Option<impl Iterator<Item=(StringIndex, Type)>> {
+        let sym = ty.sym(&mut self.syms).ok()?;
+        let SymbolKind::Container(cont) = self.syms.sym(sym).kind()
+        else { return None };
+
+        if cont.kind() != kind { return None }
+
+        let gens = ty.gens(&mut self.syms);
+        let gens = self.syms.get_gens(gens);
+
+        let fields = cont.fields().iter()
+            .map(|(name, field)| (*name, field.to_ty(gens, &mut self.syms)))
+            .collect::<Vec<_>>();
+
+        Some(fields.into_iter())
+    }
+
+
+    /// Builds the decision tree for the arms of a match on a
+    /// value of type `ty`. Each arm is its lowered pattern and
+    /// whether it has a guard
+    pub(crate) fn decision_tree(&mut self, ty: Type, arms: Vec<(Pat, bool)>) -> Decision {
+        let rows = arms.into_iter().enumerate()
+            .map(|(arm, (pat, has_guard))| Row {
+                columns: vec![(vec![], ty, pat)],
+                bindings: vec![],
+                arm,
+                has_guard,
+            })
+            .collect();
+
+        self.decide(rows)
+    }
+
+
+    fn decide(&mut self, rows: Vec<Row>) -> Decision {
+        let mut rows = rows.into_iter()
+            .flat_map(|row| self.normalise(row))
+            .collect::<Vec<_>>();
+
+        let Some(first) = rows.first()
+        else { return Decision::Fail };
+
+        // the first row matches without any further tests
+        let Some((place, ty, head)) = first.columns.first().cloned()
+        else {
+            let row = rows.remove(0);
+            let otherwise = row.has_guard.then(|| Box::new(self.decide(rows)));
+            return Decision::Arm { arm: row.arm, bindings: row.bindings, otherwise };
+        };
+
+
+        match head {
+            Pat::Variant(..) => {
+                let variants = self.enum_variants(ty).unwrap();
+                let mut cases = Vec::with_capacity(variants.len());
+
+                for (index, (_, payload_ty)) in variants.into_iter().enumerate() {
+                    let mut payload_place = place.clone();
+                    payload_place.push(Step::Payload(index));
+
+                    let rows = rows.iter()
+                        .filter_map(|row| {
+                            let mut row = row.clone();
+                            let Some(column) = row.columns.iter().position(|c| c.0 == place)
+                            else { return Some(row) };
+
+                            let (_, _, Pat::Variant(variant, payload)) = row.columns.remove(column)
+                            else { unreachable!() };
+
+                            if variant != index { return None }
+
+                            row.columns.push((payload_place.clone(), payload_ty, *payload));
+                            Some(row)
+                        })
+                        .collect();
+
+                    cases.push((Ctor::Variant(index), self.decide(rows)));
+                }
+
+                Decision::Switch { place, cases, default: None }
+            },
+
+
+            Pat::Int(_) | Pat::Str(_) => {
+                let mut ctors = vec![];
+                for row in &rows {
+                    if let Some(column) = row.columns.iter().find(|c| c.0 == place) {
+                        let ctor = ctor_of(&column.2);
+                        if !ctors.contains(&ctor) { ctors.push(ctor) }
+                    }
+                }
+
+                // rows which have `ctor` at `place`, or which don't test it
+                let specialise = |ctor: Option<Ctor>| rows.iter()
+                    .filter_map(|row| {
+                        let mut row = row.clone();
+                        let Some(column) = row.columns.iter().position(|c| c.0 == place)
+                        else { return Some(row) };
+
+                        let column = row.columns.remove(column);
+                        (Some(ctor_of(&column.2)) == ctor).then_some(row)
+                    })
+                    .collect::<Vec<_>>();
+
+                let specialised = ctors.iter().map(|c| (*c, specialise(Some(*c)))).collect::<Vec<_>>();
+                let default = specialise(None);
+
+                let cases = specialised.into_iter().map(|(c, rows)| (c, self.decide(rows))).collect();
+                let default = Some(Box::new(self.decide(default)));
+
+                Decision::Switch { place, cases, default }
+            },
+
+
+            _ => unreachable!("rows are normalised"),
+        }
+    }
+
+
+    /// Moves bindings out of `row`'s columns, drops the ones
+    /// which match anything, splits tuples into their fields
+    /// and or-patterns into a row per alternative
+    fn normalise(&mut self, mut row: Row) -> Vec<Row> {
+        let mut i = 0;
+        while i < row.columns.len() {
+            let (place, ty, pat) = &row.columns[i];
+            match pat {
+                Pat::Any => { row.columns.remove(i); },
+
+
+                Pat::Binding(name) => {
+                    row.bindings.push((*name, place.clone()));
+                    row.columns.remove(i);
+                },
+
+
+                Pat::Tuple(_) => {
+                    let (place, ty, Pat::Tuple(items)) = row.columns.remove(i)
+                    else { unreachable!() };
+
+                    let fields = self.tuple_fields(ty).unwrap();
+                    for (index, (item, field_ty)) in items.into_iter().zip(fields).enumerate() {
+                        let mut field = place.clone();
+                        field.push(Step::Field(index));
+                        row.columns.push((field, field_ty, item));
+                    }
+                },
+
+
+                Pat::Or(alternatives) => {
+                    let (place, ty) = (place.clone(), *ty);
+                    let alternatives = alternatives.clone();
+
+                    return alternatives.into_iter()
+                        .flat_map(|alternative| {
+                            let mut row = row.clone();
+                            row.columns[i] = (place.clone(), ty, alternative);
+                            self.normalise(row)
+                        })
+                        .collect();
+                },
+
+
+                Pat::Variant(..) | Pat::Int(_) | Pat::Str(_) => i += 1,
+            }
+        }
+
+        vec![row]
+    }
+
+
+    /// Reports the arms `tree` never selects, bar the `None` ones, and
+    /// the values it doesn't handle, returning the first error if any
+    pub(crate) fn check_decision_tree(
+        &mut self, node: ExprId, ty: Type, tree: &Decision, arms: &[Option<SourceRange>], range: SourceRange,
+    ) -> Option<ErrorId> {
+        let mut selected = HashSet::new();
+        let mut missing = vec![];
+        self.walk_decision(tree, ty, &mut vec![], &mut selected, &mut missing);
+
+        let mut first_error = None;
+        for (arm, source) in arms.iter().enumerate() {
+            if let Some(source) = source
+            && !selected.contains(&arm) {
+                let err = self.error(node, Error::UnreachableMatch { source: *source });
+                first_error.get_or_insert(err);
+            }
+        }
+
+        if !missing.is_empty() {
+            const SHOWN : usize = 3;
+
+            let mut names = missing.iter().take(SHOWN)
+                .map(|m| self.string_map.insert(m))
+                .collect::<Vec<_>>();
+
+            if missing.len() > SHOWN {
+                names.push(self.string_map.insert(&format!("and {} more", missing.len() - SHOWN)));
+            }
+
+            let err = self.error(node, Error::MissingMatch { missing: KVec::from_slice(&names), range });
+            first_error.get_or_insert(err);
+        }
+
+        first_error
+    }
+
+
+    fn walk_decision(
+        &mut self, tree: &Decision, ty: Type, path: &mut Vec<(Place, Ctor)>,
+        selected: &mut HashSet<usize>, missing: &mut Vec<String>,
+    ) {
+        match tree {
+            Decision::Fail => {
+                let witness = self.witness(&[], ty, path);
+                if !missing.contains(&witness) { missing.push(witness) }
+            },
+
+
+            Decision::Arm { arm, otherwise, .. } => {
+                selected.insert(*arm);
+                if let Some(otherwise) = otherwise {
+                    self.walk_decision(otherwise, ty, path, selected, missing);
+                }
+            },
+
+
+            Decision::Switch { place, cases, default } => {
+                for (ctor, case) in cases {
+                    path.push((place.clone(), *ctor));
+                    self.walk_decision(case, ty, path, selected, missing);
+                    path.pop();
+                }
+
+                if let Some(default) = default {
+                    self.walk_decision(default, ty, path, selected, missing);
+                }
+            },
+        }
+    }
+
+
+    /// Renders a value of type `ty` at `place` which satisfies
+    /// every test along `path`
+    fn witness(&mut self, place: &[Step], ty: Type, path: &[(Place, Ctor)]) -> String {
+        let ctor = path.iter().find(|(p, _)| p == place).map(|(_, c)| *c);
+
+        match ctor {
+            Some(Ctor::Variant(index)) => {
+                let (name, payload_ty) = self.enum_variants(ty).unwrap()[index];
+                let name = self.string_map.get(name).to_string();
+                if payload_ty.eq(&mut self.syms, Type::UNIT) { return name }
+
+                let mut payload = place.to_vec();
+                payload.push(Step::Payload(index));
+
+                format!("{name}({})", self.witness(&payload, payload_ty, path))
+            },
+
+            Some(Ctor::Int(int)) => int.to_string(),
+            Some(Ctor::Str(string)) => format!("{:?}", self.string_map.get(string)),
+
+            None => {
+                let is_tested = path.iter().any(|(p, _)| p.len() > place.len() && p.starts_with(place));
+                let fields = if is_tested { self.tuple_fields(ty) } else { None };
+
+                let Some(fields) = fields
+                else { return "_".to_string() };
+
+                let items = fields.into_iter().enumerate()
+                    .map(|(index, field_ty)| {
+                        let mut field = place.to_vec();
+                        field.push(Step::Field(index));
+                        self.witness(&field, field_ty, path)
+                    })
+                    .collect::<Vec<_>>();
+
+                format!("({})", items.join(", "))
+            },
+        }
+    }
+}
+
+
+fn ctor_of(pat: &Pat) -> Ctor {
+    match pat {
+        Pat::Int(int) => Ctor::Int(*int),
+        Pat::Str(string) => Ctor::Str(*string),
+        _ => unreachable!(),
+    }
+}
@@ -331,10 +331,11 @@ pub struct VariableScope {
     name   : StringIndex,
     ty     : Type,
     mutable: bool,
+    source : SourceRange,
 }
 
 impl VariableScope {
-    pub fn new(name: StringIndex, ty: Type, mutable: bool) -> Self { Self { name, ty, mutable } }
+    pub fn new(name: StringIndex, ty: Type, mutable: bool, source: SourceRange) -> Self { Self { name, ty, mutable, source } }
 
     #[inline(always)]
     pub fn ty(&self) -> Type { self.ty }
@@ -344,6 +345,10 @@ impl VariableScope {
 
     #[inline(always)]
     pub fn is_mutable(&self) -> bool { self.mutable }
+
+    /// Where the variable was bound
+    #[inline(always)]
+    pub fn source(&self) -> SourceRange { self.source }
 }
 
 
@@ -5,7 +5,8 @@ pub mod sym_map;
 
 use common::{string_map::StringIndex, ImmutableData};
 use errors::ErrorId;
-use crate::syms::sym_map::{BoundedGeneric, Generic};
+use parser::nodes::decl::DeclId;
+use crate::syms::sym_map::{BoundedGeneric, Generic, SymbolId};
 
 use self::{containers::Container, func::FunctionTy};
 
@@ -23,6 +24,7 @@ pub enum SymbolKind<'me> {
     Container(Container<'me>),
     Trait(Trait<'me>),
     Alias(Generic<'me>),
+    Global(Global<'me>),
     Opaque,
     Namespace,
     /// A symbol that failed to resolve; carries the originating error so
@@ -35,10 +37,29 @@ pub enum SymbolKind<'me> {
 #[derive(Debug, Clone, Copy)]
 pub struct Trait<'me> {
     pub funcs: &'me [(StringIndex, FunctionTy<'me>)],
+    /// Traits an implementor has to implement as well, in terms
+    /// of `Self` and the trait's generics
+    pub supertraits: &'me [Generic<'me>],
+    /// Functions with a default body, generic over `Self`
+    pub defaults: &'me [(StringIndex, SymbolId)],
+    /// Associated types every implementor has to name
+    pub types: &'me [StringIndex],
+    /// Associated constants every implementor has to define,
+    /// in terms of `Self` and the trait's generics
+    pub consts: &'me [(StringIndex, Generic<'me>)],
     pub synthesis: TraitSynthesis,
 }
 
 
+/// A module-level `const` or `static`
+#[derive(Debug, Clone, Copy)]
+pub struct Global<'me> {
+    pub ty: Generic<'me>,
+    pub decl: DeclId,
+    pub is_const: bool,
+}
+
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TraitSynthesis {
     None,
@@ -60,6 +60,27 @@ pub enum FunctionKind {
     ListIterNext,
     FloatSqrt,
 
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    CheckedAdd,
+    CheckedSub,
+    CheckedMul,
+    CheckedDiv,
+
+    StrChars,
+    StrCharIndices,
+    CharsNext,
+    CharIndicesNext,
+    StrConcat,
+    StrToStr,
+
+    /// Runs a `static`'s initialiser, registered as a startup
+    StaticInit(SymbolId),
+
+    /// Calls through slot `n` of a `dyn Trait`'s vtable
+    DynMethod(usize),
+
     Enum {
         sym: SymbolId,
         index: usize,
@@ -80,6 +101,26 @@ impl<'me> FunctionTy<'me> {
 }
 
 
+impl FunctionKind {
+    /// Whether this is one of the `wrapping_*` or `checked_*`
+    /// builtins, which only accept integers
+    pub fn is_int_builtin(self) -> bool {
+        matches!(self,
+            FunctionKind::WrappingAdd | FunctionKind::WrappingSub | FunctionKind::WrappingMul
+            | FunctionKind::CheckedAdd | FunctionKind::CheckedSub
+            | FunctionKind::CheckedMul | FunctionKind::CheckedDiv)
+    }
+
+
+    /// Whether this is one of the `checked_*` builtins
+    pub fn is_checked_int(self) -> bool {
+        matches!(self,
+            FunctionKind::CheckedAdd | FunctionKind::CheckedSub
+            | FunctionKind::CheckedMul | FunctionKind::CheckedDiv)
+    }
+}
+
+
 impl<'me> FunctionArgument<'me> {
     pub fn new(name: StringIndex, symbol: Generic<'me>) -> Self { Self { name, symbol, is_inout: false } }
     pub fn new_inout(name: StringIndex, symbol: Generic<'me>, is_inout: bool) -> Self { Self { name, symbol, is_inout } }
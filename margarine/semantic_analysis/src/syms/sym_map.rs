@@ -3,7 +3,7 @@ use std::collections::{HashMap, HashSet};
 use common::{copy_slice_in, source::SourceRange, string_map::{StringIndex, StringMap}, ImmutableData};
 use errors::ErrorId;
 use parser::nodes::{decl::{DeclId, Visibility}, NodeId};
-use sti::{arena::Arena, define_key, ext::FromIn, vec::KVec};
+use sti::{arena::Arena, define_key, ext::FromIn, key::Key, vec::KVec};
 
 use crate::{namespace::{Namespace, NamespaceId, NamespaceMap}, syms::{containers::{Container, ContainerKind}, func::{FunctionArgument, FunctionKind, FunctionTy}, SymbolKind, Trait, TraitSynthesis}};
 
@@ -22,6 +22,9 @@ pub struct TraitImplEntry<'me> {
     pub generics: &'me [BoundedGeneric<'me>],
     pub declaration: Option<DeclId>,
     pub bound_error: Option<ErrorId>,
+    /// The associated types the implementation names, in terms
+    /// of its generics
+    pub types: &'me [(StringIndex, Generic<'me>)],
 }
 
 pub type TraitMap<'me> = HashMap<SymbolId, Vec<TraitImplEntry<'me>>>;
@@ -31,6 +34,11 @@ pub struct SymbolMap<'me> {
     gens : KVec<GenListId, &'me [(BoundedGeneric<'me>, Type)]>,
     vars : KVec<VarId, Var>,
     closures: KVec<ClosureId, Closure>,
+    /// Placeholders for the associated types of generic placeholders
+    /// & what they're a projection of
+    assoc_placeholders: HashMap<(SymbolId, StringIndex), SymbolId>,
+    assoc_bases: HashMap<SymbolId, (SymbolId, StringIndex)>,
+    assoc_ns: NamespaceId,
     arena: &'me Arena,
 }
 
@@ -86,6 +94,8 @@ impl<'me> BoundedGeneric<'me> {
 pub enum GenericKind<'me> {
     Generic(BoundedGeneric<'me>),
     Sym(SymbolId, &'me [Generic<'me>]),
+    /// An associated type of a type, `T::Item`
+    Assoc(&'me Generic<'me>, StringIndex),
 }
 
 
@@ -111,6 +121,53 @@ impl<'me> SymbolMap<'me> {
         &mut self,
         ty: Type,
         trait_ty: Type,
+    ) -> Option<(NamespaceId, std::vec::Vec<(BoundedGeneric<'me>, Type)>)> {
+        let implementation = self.find_trait_implementation(ty, trait_ty)?;
+        if !self.implements_supertraits(ty, trait_ty) {
+            return None;
+        }
+
+        Some(implementation)
+    }
+
+
+    /// Whether `ty` implements every supertrait of `trait_ty`.
+    /// Supertrait impls are looked up directly as their own
+    /// supertraits were checked where they were declared
+    pub(crate) fn implements_supertraits(&mut self, ty: Type, trait_ty: Type) -> bool {
+        let Type::Ty(trait_id, _) = trait_ty.instantiate_shallow(self)
+        else { return false; };
+        let SymbolKind::Trait(tr) = self.sym(trait_id).kind()
+        else { return true; };
+        if tr.supertraits.is_empty() {
+            return true;
+        }
+
+        let trait_args_id = trait_ty.gens(self);
+        let trait_args = self.get_gens(trait_args_id);
+        let mut bindings = std::vec::Vec::with_capacity(trait_args.len() + 1);
+        bindings.extend_from_slice(trait_args);
+        bindings.push((BoundedGeneric::new(StringMap::SELF_TY, &[]), ty));
+
+        tr.supertraits.iter().all(|supertrait| {
+            let supertrait_ty = supertrait.to_ty(&bindings, self);
+            let Type::Ty(supertrait_id, _) = supertrait_ty.instantiate_shallow(self)
+            else { return false };
+
+            if self.find_trait_implementation(ty, supertrait_ty).is_some() {
+                return true;
+            }
+
+            matches!(self.sym(supertrait_id).kind(),
+                SymbolKind::Trait(Trait { synthesis: TraitSynthesis::UniversalNoop, .. }))
+        })
+    }
+
+
+    fn find_trait_implementation(
+        &mut self,
+        ty: Type,
+        trait_ty: Type,
     ) -> Option<(NamespaceId, std::vec::Vec<(BoundedGeneric<'me>, Type)>)> {
         let Type::Ty(trait_id, _) = trait_ty.instantiate_shallow(self)
         else { return None; };
@@ -154,6 +211,48 @@ impl<'me> SymbolMap<'me> {
     }
 
 
+    /// The traits a `dyn` of `trait_id` dispatches in vtable order,
+    /// the trait itself followed by its supertraits, in terms of
+    /// `Self` & the trait's generics.
+    /// `None` if one of them hasn't been computed yet
+    pub fn dyn_traits(&self, trait_id: SymbolId) -> Option<std::vec::Vec<Generic<'me>>> {
+        let sym = self.sym_ok(trait_id)?;
+        let mut args = sti::vec::Vec::with_cap_in(self.arena, sym.generics().len());
+        for g in sym.generics() {
+            args.push(Generic::new(SourceRange::ZERO, GenericKind::Generic(*g)));
+        }
+
+        let mut traits = vec![Generic::new(SourceRange::ZERO, GenericKind::Sym(trait_id, args.leak()))];
+        let mut i = 0;
+        while let Some(tr) = traits.get(i).copied() {
+            i += 1;
+
+            let Some(sym) = tr.sym()
+            else { continue };
+            let sym = self.sym_ok(sym)?;
+            let SymbolKind::Trait(trait_sym) = sym.kind()
+            else { continue };
+
+            let mut repls = std::vec::Vec::with_capacity(sym.generics().len());
+            for (g, arg) in sym.generics().iter().zip(tr.gens().unwrap_or(&[])) {
+                repls.push((g.name(), *arg));
+            }
+
+            for supertrait in trait_sym.supertraits {
+                let supertrait = supertrait.rec_replace_all(self.arena, &repls);
+                // supertraits may be cyclic or reached twice
+                if traits.iter().any(|t| t.sym() == supertrait.sym()) {
+                    continue;
+                }
+
+                traits.push(supertrait);
+            }
+        }
+
+        Some(traits)
+    }
+
+
     pub(crate) fn trait_arguments_satisfy_bounds(&mut self, trait_ty: Type) -> bool {
         let Type::Ty(trait_id, _) = trait_ty.instantiate_shallow(self)
         else { return false; };
@@ -267,10 +366,94 @@ impl<'me> SymbolMap<'me> {
                     self.match_impl_type(*pattern, actual, impl_gens, bindings)
                 })
             },
+
+            GenericKind::Assoc(..) => false,
         }
     }
 
 
+    /// The associated type `name` of `ty`.
+    /// Generic placeholders get a placeholder of their own which
+    /// `Type::resolve` swaps for the implementation's type
+    pub fn project(&mut self, ty: Type, name: StringIndex) -> Type {
+        let Ok(sym) = ty.sym(self)
+        else { return self.assoc_placeholder(SymbolId::MAX, name) };
+
+        if let SymbolKind::Container(cont) = self.sym(sym).kind()
+        && cont.kind() == ContainerKind::Generic
+        && !self.assoc_bases.contains_key(&sym) {
+            return self.assoc_placeholder(sym, name);
+        }
+
+        let mut traits: std::vec::Vec<_> = self.syms[sym].2.keys().copied().collect();
+        traits.sort();
+        for trait_id in traits {
+            let impls = self.syms[sym].2[&trait_id].clone();
+            for entry in impls {
+                let Some((_, assoc)) = entry.types.iter().find(|(n, _)| *n == name)
+                else { continue };
+
+                let mut bindings = std::vec::Vec::with_capacity(entry.generics.len());
+                if !self.match_impl_type(entry.receiver, ty, entry.generics, &mut bindings) {
+                    continue;
+                }
+
+                return assoc.to_ty(&bindings, self);
+            }
+        }
+
+        // only reachable after an error was reported, the placeholder
+        // won't unify with anything else
+        self.assoc_placeholder(SymbolId::MAX, name)
+    }
+
+
+    fn assoc_placeholder(&mut self, base: SymbolId, name: StringIndex) -> Type {
+        if let Some(sym) = self.assoc_placeholders.get(&(base, name)) {
+            return Type::Ty(*sym, GenListId::EMPTY);
+        }
+
+        let kind = SymbolKind::Container(Container::new(&[], ContainerKind::Generic));
+        let sym = self.syms.push((Ok(Symbol::new(name, &[], kind)), self.assoc_ns, HashMap::new()));
+        self.assoc_placeholders.insert((base, name), sym);
+        if base != SymbolId::MAX {
+            self.assoc_bases.insert(sym, (base, name));
+        }
+
+        Type::Ty(sym, GenListId::EMPTY)
+    }
+
+
+    /// The generic placeholder & associated type name `sym` is
+    /// the placeholder of
+    pub fn assoc_base(&self, sym: SymbolId) -> Option<(SymbolId, StringIndex)> {
+        self.assoc_bases.get(&sym).copied()
+    }
+
+
+    /// Whether one of `traits`, or their supertraits, declares
+    /// the associated type `name`.
+    /// `None` if one of them hasn't been computed yet
+    pub fn traits_declare_assoc(&self, traits: impl Iterator<Item=SymbolId>, name: StringIndex) -> Option<bool> {
+        let mut is_pending = false;
+        for trait_id in traits {
+            let Some(traits) = self.dyn_traits(trait_id)
+            else { is_pending = true; continue };
+
+            for tr in traits {
+                let Some(SymbolKind::Trait(tr)) = tr.sym().and_then(|sym| self.sym_ok(sym)).map(|sym| sym.kind())
+                else { continue };
+
+                if tr.types.contains(&name) {
+                    return Some(true);
+                }
+            }
+        }
+
+        if is_pending { None } else { Some(false) }
+    }
+
+
     pub fn add_enum(&mut self, ns_map: &mut NamespaceMap,
                     string_map: &mut StringMap,
                     id: SymbolId, range: SourceRange,
@@ -451,6 +634,7 @@ impl<'me> Generic<'me> {
         match self.kind {
             GenericKind::Generic(_) => None,
             GenericKind::Sym(v, _) => Some(v),
+            GenericKind::Assoc(..) => None,
         }
     }
 
@@ -458,6 +642,7 @@ impl<'me> Generic<'me> {
         match self.kind {
             GenericKind::Generic(_) => None,
             GenericKind::Sym(_, gens) => Some(gens),
+            GenericKind::Assoc(..) => None,
         }
     }
 
@@ -473,6 +658,24 @@ impl<'me> Generic<'me> {
                     arg.collect_generics(out);
                 }
             }
+            GenericKind::Assoc(base, _) => base.collect_generics(out),
+        }
+    }
+
+
+    /// Collects every `GenericKind::Assoc` in `self`
+    pub fn collect_assoc(self, out: &mut std::vec::Vec<Generic<'me>>) {
+        match self.kind {
+            GenericKind::Generic(_) => (),
+            GenericKind::Sym(_, args) => {
+                for arg in args {
+                    arg.collect_assoc(out);
+                }
+            }
+            GenericKind::Assoc(base, _) => {
+                out.push(self);
+                base.collect_assoc(out);
+            },
         }
     }
     
@@ -503,9 +706,48 @@ impl<'me> Generic<'me> {
                 
                 map.get_ty(symbol, &generics)
             },
+
+
+            GenericKind::Assoc(base, name) => {
+                let base = base.to_ty(gens, map);
+                map.project(base, name)
+            },
+        }
+    }
+
+    /// Like `rec_replace` but substitutes every name at once so
+    /// replacements never get replaced themselves
+    pub fn rec_replace_all(self, alloc: &'me Arena, repls: &[(StringIndex, Generic<'me>)]) -> Generic<'me> {
+        match self.kind {
+            GenericKind::Generic(v) => {
+                repls.iter()
+                    .find(|(name, _)| *name == v.name())
+                    .map(|(_, repl)| *repl)
+                    .unwrap_or(self)
+            },
+
+
+            GenericKind::Sym(symbol, generics) => {
+                let generics = {
+                    let mut vec = sti::vec::Vec::with_cap_in(alloc, generics.len());
+                    for g in generics {
+                        vec.push(g.rec_replace_all(alloc, repls));
+                    }
+                    vec
+                };
+
+                Generic::new(self.range, GenericKind::Sym(symbol, generics.leak()))
+            },
+
+
+            GenericKind::Assoc(base, name) => {
+                let base = alloc.alloc_new(base.rec_replace_all(alloc, repls));
+                Generic::new(self.range, GenericKind::Assoc(base, name))
+            },
         }
     }
 
+
     pub fn rec_replace(self, alloc: &'me Arena, gen_name: StringIndex, repl: Generic<'me>) -> Generic<'me> {
         match self.kind {
             GenericKind::Generic(v) => {
@@ -525,6 +767,47 @@ impl<'me> Generic<'me> {
                 
                 Generic::new(self.range, GenericKind::Sym(symbol, generics.leak()))
             },
+
+
+            GenericKind::Assoc(base, name) => {
+                let base = alloc.alloc_new(base.rec_replace(alloc, gen_name, repl));
+                Generic::new(self.range, GenericKind::Assoc(base, name))
+            },
+        }
+    }
+
+
+    /// Replaces the associated types of `base` with the ones
+    /// an implementation names
+    pub fn replace_assoc(
+        self, alloc: &'me Arena, base: Generic<'me>,
+        types: &[(StringIndex, Generic<'me>)],
+    ) -> Generic<'me> {
+        match self.kind {
+            GenericKind::Generic(_) => self,
+
+
+            GenericKind::Sym(symbol, generics) => {
+                let generics = {
+                    let mut vec = sti::vec::Vec::with_cap_in(alloc, generics.len());
+                    for g in generics {
+                        vec.push(g.replace_assoc(alloc, base, types));
+                    }
+                    vec
+                };
+
+                Generic::new(self.range, GenericKind::Sym(symbol, generics.leak()))
+            },
+
+
+            GenericKind::Assoc(assoc_base, name) => {
+                if *assoc_base == base
+                && let Some((_, ty)) = types.iter().find(|(n, _)| *n == name) {
+                    return *ty;
+                }
+
+                self
+            },
         }
     }
 }
@@ -536,7 +819,11 @@ impl<'me> SymbolMap<'me> {
         string_map: &mut StringMap
     ) -> Self {
 
-        let mut slf = Self { syms: KVec::new(), vars: KVec::new(), arena, gens: KVec::new(), closures: KVec::new(), };
+        let assoc_ns = ns_map.push(Namespace::new(StringMap::ITEM), None);
+        let mut slf = Self {
+            syms: KVec::new(), vars: KVec::new(), arena, gens: KVec::new(), closures: KVec::new(),
+            assoc_placeholders: HashMap::new(), assoc_bases: HashMap::new(), assoc_ns,
+        };
 
         assert_eq!(slf.gens.push(&[]), GenListId::EMPTY);
 
@@ -727,6 +1014,10 @@ impl<'me> SymbolMap<'me> {
                             &[],
                         )
                     )]),
+                    supertraits: &[],
+                    defaults: &[],
+                    types: &[],
+                    consts: &[],
                     synthesis: TraitSynthesis::None,
                 })
             );
@@ -1097,6 +1388,10 @@ impl<'me> SymbolMap<'me> {
                             &[],
                         )
                     )]),
+                    supertraits: &[],
+                    defaults: &[],
+                    types: &[],
+                    consts: &[],
                     synthesis: TraitSynthesis::UniversalNoop,
                 })
             );
@@ -1332,6 +1627,285 @@ impl<'me> SymbolMap<'me> {
             slf.add_sym(pending, sym);
         }
 
+        init!(I8);
+        init!(I16);
+        init!(I32);
+        init!(U16);
+        init!(U32);
+        init!(U64);
+        init!(USIZE);
+        init!(F32);
+
+        // wrapping_*<T>(lhs: T, rhs: T): T
+        // checked_*<T>(lhs: T, rhs: T): Option<T>
+        //
+        // `T` is checked to be an integer at the call site
+        let int_builtins = [
+            (StringMap::WRAPPING_ADD, SymbolId::WRAPPING_ADD, FunctionKind::WrappingAdd),
+            (StringMap::WRAPPING_SUB, SymbolId::WRAPPING_SUB, FunctionKind::WrappingSub),
+            (StringMap::WRAPPING_MUL, SymbolId::WRAPPING_MUL, FunctionKind::WrappingMul),
+            (StringMap::CHECKED_ADD, SymbolId::CHECKED_ADD, FunctionKind::CheckedAdd),
+            (StringMap::CHECKED_SUB, SymbolId::CHECKED_SUB, FunctionKind::CheckedSub),
+            (StringMap::CHECKED_MUL, SymbolId::CHECKED_MUL, FunctionKind::CheckedMul),
+            (StringMap::CHECKED_DIV, SymbolId::CHECKED_DIV, FunctionKind::CheckedDiv),
+        ];
+
+        for (name, id, kind) in int_builtins {
+            let t = BoundedGeneric::new(StringMap::T, &[]);
+            let pending = slf.pending(ns_map, None, name, 1);
+            assert_eq!(pending, id);
+
+            let t_gen = Generic::new(SourceRange::ZERO, GenericKind::Generic(t));
+            let ret =
+            if kind.is_checked_int() { Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::OPTION, arena.alloc_new([t_gen]))) }
+            else { t_gen };
+
+            let args = [
+                FunctionArgument::new(StringMap::VALUE, t_gen),
+                FunctionArgument::new(StringMap::VALUE, t_gen),
+            ];
+
+            let sym = Symbol::new(
+                name,
+                arena.alloc_new([t]),
+                SymbolKind::Function(FunctionTy::new(
+                    arena.alloc_new(args),
+                    ret,
+                    kind,
+                    None,
+                    arena.alloc_new([t]),
+                )),
+            );
+
+            slf.add_sym(pending, sym);
+        }
+
+        init!(CHAR);
+
+        // the 2-tuple, seeded here so builtins can return pairs.
+        // `TyChecker::tuple_sym` hands it out for every pair
+        {
+            let pending = slf.pending(ns_map, None, StringMap::INVALID_IDENT, 2);
+            assert_eq!(pending, SymbolId::TUPLE_PAIR);
+
+            let gens = arena.alloc_new([
+                BoundedGeneric::new(string_map.num(0), &[]),
+                BoundedGeneric::new(string_map.num(1), &[]),
+            ]);
+
+            let fields = arena.alloc_new([
+                (gens[0].name, Generic::new(SourceRange::ZERO, GenericKind::Generic(gens[0]))),
+                (gens[1].name, Generic::new(SourceRange::ZERO, GenericKind::Generic(gens[1]))),
+            ]);
+
+            let kind = SymbolKind::Container(Container::new(fields, ContainerKind::Tuple));
+            slf.add_sym(pending, Symbol::new(StringMap::TUPLE, gens, kind));
+        }
+
+        // Chars & CharIndices decode the bytes of a `str` as they
+        // walk them, `offset` is the byte offset of the next char
+        let byte = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::BYTE, &[]));
+        let int = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::I64, &[]));
+        let char = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::CHAR, &[]));
+        let bytes_iter = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::LIST_ITER, arena.alloc_new([byte])));
+        let str = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::STR, &[]));
+        let pair = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::TUPLE_PAIR, arena.alloc_new([int, char])));
+
+        for (name, id) in [(StringMap::CHARS, SymbolId::CHARS), (StringMap::CHAR_INDICES, SymbolId::CHAR_INDICES)] {
+            let pending = slf.pending(ns_map, None, name, 0);
+            assert_eq!(pending, id);
+
+            let fields = arena.alloc_new([(StringMap::VALUE, bytes_iter), (StringMap::OFFSET, int)]);
+            let kind = SymbolKind::Container(Container::new(fields, ContainerKind::Struct));
+            slf.add_sym(pending, Symbol::new(name, &[], kind));
+        }
+
+        // str::chars(self): Chars
+        // str::char_indices(self): CharIndices
+        // Chars::__next__(&self): Option<char>
+        // CharIndices::__next__(&self): Option<(int, char)>
+        // str::__to_str__(self): str
+        let chars = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::CHARS, &[]));
+        let char_indices = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::CHAR_INDICES, &[]));
+        let option = |g| Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::OPTION, arena.alloc_new([g])));
+
+        let methods = [
+            (SymbolId::STR, StringMap::STR_CHARS, SymbolId::STR_CHARS, str, chars, FunctionKind::StrChars),
+            (SymbolId::STR, StringMap::STR_CHAR_INDICES, SymbolId::STR_CHAR_INDICES, str, char_indices, FunctionKind::StrCharIndices),
+            (SymbolId::CHARS, StringMap::ITER_NEXT_FUNC, SymbolId::CHARS_NEXT, chars, option(char), FunctionKind::CharsNext),
+            (SymbolId::CHAR_INDICES, StringMap::ITER_NEXT_FUNC, SymbolId::CHAR_INDICES_NEXT, char_indices, option(pair), FunctionKind::CharIndicesNext),
+            (SymbolId::STR, StringMap::TO_STR_FUNC, SymbolId::STR_TO_STR, str, str, FunctionKind::StrToStr),
+        ];
+
+        for (owner, name, id, slf_ty, ret, kind) in methods {
+            let ns = slf.sym_ns(owner);
+            let path = string_map.concat(slf.sym(owner).name(), name);
+            let pending = slf.pending(ns_map, Some(ns), path, 0);
+            assert_eq!(pending, id);
+
+            // iterators advance in place
+            let is_inout = owner != SymbolId::STR;
+            let args = [FunctionArgument::new_inout(StringMap::SELF, slf_ty, is_inout)];
+
+            let sym = Symbol::new(
+                path,
+                &[],
+                SymbolKind::Function(FunctionTy::new(arena.alloc_new(args), ret, kind, None, &[])),
+            );
+
+            slf.add_sym(pending, sym);
+            ns_map.get_ns_mut(ns).add_sym_unchecked(name, id, Visibility::Public);
+        }
+
+        // $str_concat(left: str, right: str): str
+        // interpolated strings are lowered to it
+        {
+            let pending = slf.pending(ns_map, None, StringMap::STR_CONCAT, 0);
+            assert_eq!(pending, SymbolId::STR_CONCAT);
+
+            let args = [
+                FunctionArgument::new(StringMap::VALUE, str),
+                FunctionArgument::new(StringMap::VALUE, str),
+            ];
+
+            let sym = Symbol::new(
+                StringMap::STR_CONCAT,
+                &[],
+                SymbolKind::Function(FunctionTy::new(arena.alloc_new(args), str, FunctionKind::StrConcat, None, &[])),
+            );
+
+            slf.add_sym(pending, sym);
+        }
+
+        // The operator traits, sema falls back to them when an
+        // operand isn't a builtin number
+        // Add::add(self, value: Self): Self (and Sub, Mul, Div, Rem)
+        // Ord::cmp(self, value: Self): int
+        // Neg::neg(self): Self
+        // Index<T>::index(self, index: int): T
+        let self_ty = Generic::new(SourceRange::ZERO, GenericKind::Generic(BoundedGeneric::new(StringMap::SELF_TY, &[])));
+        let t = BoundedGeneric::new(StringMap::T, &[]);
+        let t_ty = Generic::new(SourceRange::ZERO, GenericKind::Generic(t));
+        let binary = [FunctionArgument::new(StringMap::SELF, self_ty), FunctionArgument::new(StringMap::VALUE, self_ty)];
+        let unary = [FunctionArgument::new(StringMap::SELF, self_ty)];
+        let index = [FunctionArgument::new(StringMap::SELF, self_ty), FunctionArgument::new(StringMap::VALUE, int)];
+
+        let traits = [
+            (StringMap::ADD_TRAIT, SymbolId::ADD_TRAIT, StringMap::ADD_FUNC, &binary[..], self_ty, None),
+            (StringMap::SUB_TRAIT, SymbolId::SUB_TRAIT, StringMap::SUB_FUNC, &binary[..], self_ty, None),
+            (StringMap::MUL_TRAIT, SymbolId::MUL_TRAIT, StringMap::MUL_FUNC, &binary[..], self_ty, None),
+            (StringMap::DIV_TRAIT, SymbolId::DIV_TRAIT, StringMap::DIV_FUNC, &binary[..], self_ty, None),
+            (StringMap::REM_TRAIT, SymbolId::REM_TRAIT, StringMap::REM_FUNC, &binary[..], self_ty, None),
+            (StringMap::ORD_TRAIT, SymbolId::ORD_TRAIT, StringMap::ORD_FUNC, &binary[..], int, None),
+            (StringMap::NEG_TRAIT, SymbolId::NEG_TRAIT, StringMap::NEG_FUNC, &unary[..], self_ty, None),
+            (StringMap::INDEX_TRAIT, SymbolId::INDEX_TRAIT, StringMap::INDEX_FUNC, &index[..], t_ty, Some(t)),
+        ];
+
+        for (name, id, func, args, ret, generic) in traits {
+            let gens: &[BoundedGeneric] = match generic {
+                Some(t) => arena.alloc_new([t]),
+                None => &[],
+            };
+
+            let pending = slf.pending(ns_map, None, name, gens.len());
+            assert_eq!(pending, id);
+
+            let sym = Symbol::new(
+                name,
+                gens,
+                SymbolKind::Trait(Trait {
+                    funcs: arena.alloc_new([
+                        (func, FunctionTy::new(copy_slice_in(arena, args), ret, FunctionKind::Trait, None, &[]))
+                    ]),
+                    supertraits: &[],
+                    defaults: &[],
+                    types: &[],
+                    consts: &[],
+                    synthesis: TraitSynthesis::None,
+                })
+            );
+
+            slf.add_sym(pending, sym);
+        }
+
+
+        // dyn Trait
+        {
+            let t = BoundedGeneric::new(StringMap::T, &[]);
+            let pending = slf.pending(ns_map, None, StringMap::DYN, 1);
+            assert_eq!(pending, SymbolId::DYN);
+            slf.add_sym(pending, Symbol::new(StringMap::DYN, arena.alloc_new([t]), SymbolKind::Opaque));
+        }
+
+
+        // Iterator { type Item; fn __next__(&self): Option<Self::Item> }
+        // `for` loops go through it, Chars & CharIndices implement it
+        {
+            let pending = slf.pending(ns_map, None, StringMap::ITERATOR_TRAIT, 0);
+            assert_eq!(pending, SymbolId::ITERATOR_TRAIT);
+
+            let item = Generic::new(SourceRange::ZERO, GenericKind::Assoc(arena.alloc_new(self_ty), StringMap::ITEM));
+            let args = [FunctionArgument::new_inout(StringMap::SELF, self_ty, true)];
+            let next = FunctionTy::new(arena.alloc_new(args), option(item), FunctionKind::Trait, None, &[]);
+
+            let sym = Symbol::new(
+                StringMap::ITERATOR_TRAIT,
+                &[],
+                SymbolKind::Trait(Trait {
+                    funcs: arena.alloc_new([(StringMap::ITER_NEXT_FUNC, next)]),
+                    supertraits: &[],
+                    defaults: &[],
+                    types: arena.alloc_new([StringMap::ITEM]),
+                    consts: &[],
+                    synthesis: TraitSynthesis::None,
+                })
+            );
+
+            slf.add_sym(pending, sym);
+
+            let iterator = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::ITERATOR_TRAIT, &[]));
+            for (receiver, item) in [(chars, char), (char_indices, pair)] {
+                let sym = receiver.sym().unwrap();
+                let namespace = slf.sym_ns(sym);
+                slf.traits(sym).entry(SymbolId::ITERATOR_TRAIT).or_default().push(TraitImplEntry {
+                    namespace,
+                    trait_ty: iterator,
+                    receiver,
+                    generics: &[],
+                    declaration: None,
+                    bound_error: None,
+                    types: arena.alloc_new([(StringMap::ITEM, item)]),
+                });
+            }
+        }
+
+
+        // Hash { fn hash(self): u64 }
+        // `@cached` functions key their table on it
+        {
+            let pending = slf.pending(ns_map, None, StringMap::HASH_TRAIT, 0);
+            assert_eq!(pending, SymbolId::HASH_TRAIT);
+
+            let args = [FunctionArgument::new(StringMap::SELF, self_ty)];
+            let u64 = Generic::new(SourceRange::ZERO, GenericKind::Sym(SymbolId::U64, &[]));
+            let hash = FunctionTy::new(arena.alloc_new(args), u64, FunctionKind::Trait, None, &[]);
+
+            let sym = Symbol::new(
+                StringMap::HASH_TRAIT,
+                &[],
+                SymbolKind::Trait(Trait {
+                    funcs: arena.alloc_new([(StringMap::HASH, hash)]),
+                    supertraits: &[],
+                    defaults: &[],
+                    types: &[],
+                    consts: &[],
+                    synthesis: TraitSynthesis::None,
+                })
+            );
+
+            slf.add_sym(pending, sym);
+        }
+
         slf
     }
 }
@@ -24,6 +24,16 @@ impl Type {
     pub fn resolve(self, env_gens: &[&[(BoundedGeneric<'_>, Type)]], map: &mut SymbolMap) -> Type {
         let ty = self.instantiate(map, 0);
 
+        // projections resolve once their base does
+        if let Ok(sym) = ty.sym(map)
+        && let Some((base, name)) = map.assoc_base(sym) {
+            let base = Type::Ty(base, GenListId::EMPTY).resolve(env_gens, map);
+            let projected = map.project(base, name);
+            if projected == ty { return ty }
+
+            return projected.resolve(env_gens, map);
+        }
+
         let gens = ty.gens(map);
         let func_gens = map.get_gens(gens);
 
@@ -35,7 +45,8 @@ impl Type {
 
             let ty = 
             if let SymbolKind::Container(cont) = sym_data.kind()
-            && cont.kind() == ContainerKind::Generic {
+            && cont.kind() == ContainerKind::Generic
+            && map.assoc_base(sym_id).is_none() {
                 let sym_name = sym_data.name;
                 let mut iter = env_gens.iter().map(|x| x.iter()).flatten();
 
@@ -109,6 +120,13 @@ impl Type {
         match self.instantiate_shallow(map) {
             Type::Ty(sym, gens) => {
                 let mut str = sti::string::String::new_in(string_map.arena());
+                if let Some((base, name)) = map.assoc_base(sym) {
+                    str.push(Type::Ty(base, GenListId::EMPTY).display(string_map, map));
+                    str.push("::");
+                    str.push(string_map.get(name));
+                    return str.leak();
+                }
+
                 let sym = map.sym(sym);
 
                 let gens = map.gens()[gens];
@@ -172,6 +190,10 @@ impl Type {
                     str.push(ty.display(string_map, map));
                     str.push_char(']');
                 }
+                else if matches!(sym.kind, SymbolKind::Opaque) && sym.name == StringMap::DYN {
+                    str.push("dyn ");
+                    str.push(gens[0].1.display(string_map, map));
+                }
                 else {
                     str.push(string_map.get(sym.name));
                     if !gens.is_empty() {
@@ -546,4 +568,13 @@ impl Type {
     pub const RANGE: Self = Self::Ty(SymbolId::RANGE, GenListId::EMPTY);
     pub const STR  : Self = Self::Ty(SymbolId::STR  , GenListId::EMPTY);
     pub const BYTE : Self = Self::Ty(SymbolId::BYTE, GenListId::EMPTY);
+    pub const I8   : Self = Self::Ty(SymbolId::I8   , GenListId::EMPTY);
+    pub const I16  : Self = Self::Ty(SymbolId::I16  , GenListId::EMPTY);
+    pub const I32  : Self = Self::Ty(SymbolId::I32  , GenListId::EMPTY);
+    pub const U16  : Self = Self::Ty(SymbolId::U16  , GenListId::EMPTY);
+    pub const U32  : Self = Self::Ty(SymbolId::U32  , GenListId::EMPTY);
+    pub const U64  : Self = Self::Ty(SymbolId::U64  , GenListId::EMPTY);
+    pub const USIZE: Self = Self::Ty(SymbolId::USIZE, GenListId::EMPTY);
+    pub const F32  : Self = Self::Ty(SymbolId::F32  , GenListId::EMPTY);
+    pub const CHAR : Self = Self::Ty(SymbolId::CHAR , GenListId::EMPTY);
 }
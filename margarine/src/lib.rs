@@ -13,6 +13,7 @@ use errors::ParserError;
 use errors::SemaError;
 use git2::Repository;
 pub use lexer::lex;
+use lexer::TokenList;
 use parser::errors::Error;
 use parser::nodes::decl::Decl;
 use parser::nodes::decl::DeclId;
@@ -28,11 +29,14 @@ pub use common::source::{FileData, Extension};
 pub use common::string_map::StringMap;
 pub use common::{DropTimer, source::SourceRange};
 use semantic_analysis::llvm_codegen;
+use semantic_analysis::lints::{Level, Warning};
 use common::symbol_id::SymbolId;
-pub use semantic_analysis::llvm_codegen::{CompilationSettings, Prelude};
+pub use semantic_analysis::llvm_codegen::{CompilationSettings, CrateType, Emit, Prelude};
+pub use llvm_api::module::OptLevel;
 pub use semantic_analysis::llvm_codegen::CompilationTarget;
 pub use semantic_analysis::{TyChecker};
-pub use errors::display;
+pub use errors::{display, diagnostic, Diagnostic, Label, Severity};
+use errors::fmt::render;
 use sha2::Digest;
 use sha2::Sha256;
 pub use sti::arena::Arena;
@@ -41,6 +45,9 @@ use sti::vec::KVec;
 
 
 pub use semantic_analysis;
+pub use message::MessageFormat;
+
+pub mod message;
 
 
 pub struct Compiler<'me> {
@@ -48,6 +55,17 @@ pub struct Compiler<'me> {
     pub arena: &'me Arena,
     pub string_map: StringMap<'me>,
     pub silent: bool,
+    pub message_format: MessageFormat,
+
+    /// Error free token lists of every file lexed so far along
+    /// with the hash of the source & the offset they were lexed at.
+    /// Saves a long running compiler (i.e. the lsp) from re-lexing
+    /// files that didn't change
+    lex_cache: HashMap<StringIndex, (u64, u32, TokenList)>,
+
+    /// The commit each repository was checked out at so
+    /// repeated runs don't touch git again
+    repo_cache: HashMap<PathBuf, String>,
 }
 
 
@@ -62,6 +80,7 @@ pub struct CompilationResult<'a> {
     pub errors: CompilationErrors,
     silent_ranges: Vec<SourceRange>,
     tests: Vec<(SymbolId, bool)>,
+    exports: Vec<(SymbolId, StringIndex)>,
     ast: AST<'a>,
     startups: KVec<u32, SymbolId>,
     ty_info: semantic_analysis::TyInfo<'a>,
@@ -72,12 +91,46 @@ pub struct CompilationResult<'a> {
 }
 
 
+/// Every error and warning as reported by `Compiler::check`
+#[derive(Debug)]
+pub struct Diagnostics {
+    /// Laid out like `CompilationErrors`: lexer and parser
+    /// errors per file, then all semantic errors
+    pub errors: [Vec<Vec<Diagnostic>>; 3],
+
+    /// Warnings along with the lints that were denied
+    pub lints: Vec<Diagnostic>,
+}
+
+
+impl Diagnostics {
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.errors.iter().flatten().flatten().chain(&self.lints)
+    }
+
+
+    /// The number of errors, denied lints included
+    pub fn error_count(&self) -> usize {
+        self.iter().filter(|d| d.severity == Severity::Error).count()
+    }
+
+
+    /// The number of denied lints. Errors still build, into runtime
+    /// panics, but a denied lint has nothing to panic with so these
+    /// are what stop a build
+    pub fn denied_count(&self) -> usize {
+        self.lints.iter().filter(|d| d.severity == Severity::Error).count()
+    }
+}
+
+
 #[derive(Debug)]
 pub struct CompilationErrors {
     pub lexer_errors : Vec<KVec<LexerError , lexer::errors::Error>>,
     pub parser_errors: Vec<KVec<ParserError, parser::errors::Error>>,
     pub sema_errors  : KVec<SemaError  , semantic_analysis::errors::Error>,
     pub sema_error_nodes: KVec<SemaError, NodeId>,
+    pub lints: Vec<(Warning, Level)>,
 }
 
 
@@ -90,22 +143,21 @@ impl<'me> Compiler<'me> {
             arena,
             string_map: StringMap::new(arena),
             silent: false,
+            message_format: MessageFormat::Human,
+            lex_cache: HashMap::new(),
+            repo_cache: HashMap::new(),
         }
     }
 
 
-    pub fn check(&self, result: &mut CompilationResult<'me>) -> [Vec<Vec<String>>; 3] {
+    pub fn check(&self, result: &mut CompilationResult<'me>) -> Diagnostics {
         let mut lex_error_files = Vec::with_capacity(result.errors.lexer_errors.len());
         for l in &result.errors.lexer_errors {
             let mut file = Vec::with_capacity(l.len());
             for e in l.iter() {
-                let report = display(e, &self.string_map, &self.files.files, &mut ());
-
-                if !cfg!(feature="fuzzer") {
-                    println!("{report}");
-                }
-
-                file.push(report);
+                let diagnostic = diagnostic(e, &self.string_map, &mut ());
+                self.report(&diagnostic);
+                file.push(diagnostic);
             }
             lex_error_files.push(file);
         }
@@ -114,31 +166,66 @@ impl<'me> Compiler<'me> {
         for l in &result.errors.parser_errors {
             let mut file = Vec::with_capacity(l.len());
             for e in l.iter() {
-                let report = display(e, &self.string_map, &self.files.files, &mut ());
-
-                if !cfg!(feature="fuzzer") {
-                    println!("{report}");
-                }
-
-                file.push(report);
+                let diagnostic = diagnostic(e, &self.string_map, &mut ());
+                self.report(&diagnostic);
+                file.push(diagnostic);
             }
             parse_error_files.push(file);
         }
 
         let mut sema_errors = Vec::with_capacity(result.errors.sema_errors.len());
         for (id, error) in &result.errors.sema_errors {
-            let report = display(error, &self.string_map, &self.files.files, &mut result.syms);
+            let diagnostic = diagnostic(error, &self.string_map, &mut result.syms);
 
-            if !cfg!(feature="fuzzer")
-            && !result.is_silent_error(result.errors.sema_error_nodes[id]) {
-                println!("{report}");
+            if !result.is_silent_error(result.errors.sema_error_nodes[id]) {
+                self.report(&diagnostic);
             }
 
-            sema_errors.push(report);
+            sema_errors.push(diagnostic);
         }
 
-        let errors = [lex_error_files, parse_error_files, vec![sema_errors]];
-        errors
+        let mut lints = Vec::with_capacity(result.errors.lints.len());
+        for (warning, level) in &result.errors.lints {
+            let mut diagnostic = diagnostic(warning, &self.string_map, &mut ());
+            let silent = result.is_silent(warning.source());
+
+            // silenced code still builds, so a denied lint in it stays a warning
+            if *level == Level::Deny && !silent {
+                diagnostic.severity = Severity::Error;
+            }
+
+            if !silent {
+                self.report(&diagnostic);
+            }
+
+            lints.push(diagnostic);
+        }
+
+        Diagnostics {
+            errors: [lex_error_files, parse_error_files, vec![sema_errors]],
+            lints,
+        }
+    }
+
+
+    /// Prints `diagnostic` unless the compiler was told to be quiet
+    fn report(&self, diagnostic: &Diagnostic) {
+        if cfg!(feature="fuzzer") || self.silent { return }
+
+        match self.message_format {
+            MessageFormat::Human => println!("{}", render(diagnostic, &self.string_map, &self.files.files)),
+            MessageFormat::Json => message::emit(message::diagnostic(diagnostic, &self.string_map, &self.files.files)),
+        }
+    }
+
+
+    fn report_download(&self, depth: usize, url: &str) {
+        if self.silent { return }
+
+        match self.message_format {
+            MessageFormat::Human => println!("{}{}{} {} {}", "|".dark_grey(), "-".repeat(depth+1).dark_grey(), ">".dark_grey(), "downloading...".green().bold(), url),
+            MessageFormat::Json => message::emit(serde_json::json!({ "reason": "downloading", "url": url })),
+        }
     }
 
 
@@ -146,7 +233,7 @@ impl<'me> Compiler<'me> {
         &mut self, 
         settings: &CompilationSettings, 
         result: &mut CompilationResult<'me>,
-        errors: [Vec<Vec<String>>; 3]
+        errors: Diagnostics,
     ) {
         result.codegen(self, &settings, settings.tests, errors);
     }
@@ -222,11 +309,18 @@ impl<'me> Compiler<'me> {
         cfg_env.insert(comp_target, target_triple);
 
         if !self.silent {
-            println!(
-                "{} {}",
-                "target:".green().bold(),
-                settings.compilation_target.margarine_target_triple(),
-            );
+            match self.message_format {
+                MessageFormat::Human => println!(
+                    "{} {}",
+                    "target:".green().bold(),
+                    settings.compilation_target.margarine_target_triple(),
+                ),
+
+                MessageFormat::Json => message::emit(serde_json::json!({
+                    "reason": "target",
+                    "target": settings.compilation_target.margarine_target_triple(),
+                })),
+            }
         }
 
         while let Some(entry) = stack.pop() {
@@ -234,7 +328,13 @@ impl<'me> Compiler<'me> {
             let file = self.files.get(entry.path).unwrap();
             let depth = entry.intercrate_depth as usize;
 
-            if !self.silent {
+            if !self.silent && self.message_format == MessageFormat::Json {
+                message::emit(serde_json::json!({
+                    "reason": "compiling",
+                    "path": display_compile_path(settings, file_path, &package_urls),
+                    "depth": depth,
+                }));
+            } else if !self.silent {
                 let display = display_compile_path(settings, file_path, &package_urls);
                 if depth != 0 {
                     println!(
@@ -257,8 +357,26 @@ impl<'me> Compiler<'me> {
 
 
             let (tokens, le) = DropTimer::with_timer("tokenisation", || {
-                let tokens = lex(&file, &mut self.string_map, source_offset);
-                tokens
+                let hash = {
+                    let mut hasher = std::hash::DefaultHasher::new();
+                    std::hash::Hash::hash(file.read(), &mut hasher);
+                    std::hash::Hasher::finish(&hasher)
+                };
+
+                if let Some((cached_hash, cached_offset, tokens)) = self.lex_cache.get(&entry.path) {
+                    if *cached_hash == hash {
+                        let mut tokens = tokens.clone();
+                        tokens.rebase(*cached_offset, source_offset);
+                        return (tokens, KVec::new());
+                    }
+                }
+
+                let (tokens, errors) = lex(&file, &mut self.string_map, source_offset);
+                if errors.is_empty() {
+                    self.lex_cache.insert(entry.path, (hash, source_offset, tokens.clone()));
+                }
+
+                (tokens, errors)
             });
 
             let (body, mut imports, link_files, mut pe) = 
@@ -330,9 +448,7 @@ impl<'me> Compiler<'me> {
                         }
                     };
 
-                    if !self.silent {
-                        println!("{}{}{} {} {}", "|".dark_grey(), "-".repeat(depth+1).dark_grey(), ">".dark_grey(), "downloading...".green().bold(), url);
-                    }
+                    self.report_download(depth, &url);
 
 
                     let hash = 
@@ -469,62 +585,67 @@ impl<'me> Compiler<'me> {
                             );
                         }
 
-                        let cached_repository =
-                        if std::fs::exists(&resource.path).unwrap_or(false) {
-                            Repository::open(&resource.path).ok()
-                        } else {
-                            None
-                        };
-
-                        let repository =
-                        if let Some(repository) = cached_repository {
-                            repository
-                        } else {
-                            // Missing or corrupted cache entry (e.g. leftovers
-                            // from an interrupted build): drop whatever is there
-                            // and clone afresh instead of poisoning the build.
-                            let _ = fs::remove_dir_all(&resource.path);
-
-                            if !self.silent {
-                                println!("{}{}{} {} {}", "|".dark_grey(), "-".repeat(depth+1).dark_grey(), ">".dark_grey(), "downloading...".green().bold(), url);
-                            }
+                        let commit =
+                        if let Some(commit) = self.repo_cache.get(&resource.path) { commit.clone() }
+                        else {
+                            let cached_repository =
+                            if std::fs::exists(&resource.path).unwrap_or(false) {
+                                Repository::open(&resource.path).ok()
+                            } else {
+                                None
+                            };
 
-                            match Repository::clone(&url, &resource.path) {
-                                Ok(repo) => repo,
-                                Err(_) => {
-                                    let err = pe.push(parser::errors::Error::RepoDoesntExist {
-                                        source,
-                                        path: repo,
-                                    });
-                                    global.set_decl(i, Decl::Error(errors::ErrorId::Parser((counter, err))));
-                                    continue;
+                            let repository =
+                            if let Some(repository) = cached_repository {
+                                repository
+                            } else {
+                                // Missing or corrupted cache entry (e.g. leftovers
+                                // from an interrupted build): drop whatever is there
+                                // and clone afresh instead of poisoning the build.
+                                let _ = fs::remove_dir_all(&resource.path);
+
+                                self.report_download(depth, &url);
+
+                                match Repository::clone(&url, &resource.path) {
+                                    Ok(repo) => repo,
+                                    Err(_) => {
+                                        let err = pe.push(parser::errors::Error::RepoDoesntExist {
+                                            source,
+                                            path: repo,
+                                        });
+                                        global.set_decl(i, Decl::Error(errors::ErrorId::Parser((counter, err))));
+                                        continue;
+                                    }
                                 }
-                            }
-                        };
+                            };
 
-                        let target_commit = "HEAD";
+                            let target_commit = "HEAD";
 
-                        let Ok(object) = repository.revparse_single(&target_commit) else {
-                            let err = pe.push(parser::errors::Error::RepoDoesntExist {
-                                source,
-                                path: repo,
-                            });
-                            global.set_decl(i, Decl::Error(errors::ErrorId::Parser((counter, err))));
-                            continue;
-                        };
+                            let Ok(object) = repository.revparse_single(&target_commit) else {
+                                let err = pe.push(parser::errors::Error::RepoDoesntExist {
+                                    source,
+                                    path: repo,
+                                });
+                                global.set_decl(i, Decl::Error(errors::ErrorId::Parser((counter, err))));
+                                continue;
+                            };
 
-                        if repository.checkout_tree(&object, None).is_err()
-                        || repository.set_head_detached(object.id()).is_err()
-                        {
-                            let err = pe.push(parser::errors::Error::RepoDoesntExist {
-                                source,
-                                path: repo,
-                            });
-                            global.set_decl(i, Decl::Error(errors::ErrorId::Parser((counter, err))));
-                            continue;
-                        }
+                            if repository.checkout_tree(&object, None).is_err()
+                            || repository.set_head_detached(object.id()).is_err()
+                            {
+                                let err = pe.push(parser::errors::Error::RepoDoesntExist {
+                                    source,
+                                    path: repo,
+                                });
+                                global.set_decl(i, Decl::Error(errors::ErrorId::Parser((counter, err))));
+                                continue;
+                            }
+
+                            let commit = object.id().to_string();
+                            self.repo_cache.insert(resource.path.clone(), commit.clone());
+                            commit
+                        };
 
-                        let commit = object.id().to_string();
                         build_lock.set(alias_str.to_string(), commit);
 
                         // if the module is already in the top level, skip it
@@ -600,6 +721,12 @@ impl<'me> Compiler<'me> {
 
         self.files.sort_by(&file_offsets);
 
+        // dependencies aren't linted, only the project's own files
+        let dependencies = file_offsets.iter()
+            .filter(|f| self.string_map.get(f.0).starts_with(&settings.cache))
+            .map(|f| f.1)
+            .collect::<HashSet<_>>();
+
         let temp = Arena::new();
         let sema = {
             let _1 = DropTimer::new("semantic analysis");
@@ -624,6 +751,15 @@ impl<'me> Compiler<'me> {
         }
 
 
+        let lints = sema.warnings.into_iter()
+            .filter(|(warning, _)| {
+                let start = warning.source().start();
+                file_offsets.iter().rev().find(|f| f.1 <= start)
+                    .is_some_and(|f| !dependencies.contains(&f.1))
+            })
+            .collect();
+
+
         let mut link_files = Vec::new();
         let mut seen_link_files = HashSet::new();
         for (_, link_file) in &sema.link_files {
@@ -641,11 +777,13 @@ impl<'me> Compiler<'me> {
                 parser_errors: parse_errors,
                 sema_errors: sema.errors.errors,
                 sema_error_nodes: sema.errors.nodes,
+                lints,
             },
 
             silent_ranges: merged_silent_ranges,
 
             tests,
+            exports: sema.exports.iter().copied().collect(),
             startups: sema.startups,
             ty_info: sema.type_info,
             scopes: sema.scopes,
@@ -666,19 +804,31 @@ impl<'me> CompilationResult<'me> {
         comp: &mut Compiler,
         settings: &CompilationSettings,
         tests: bool,
-        errors: [Vec<Vec<String>>; 3],
+        errors: Diagnostics,
     ) {
         let tests = 
         if tests { self.tests.iter().map(|s| s.0).collect() } 
         else { vec![] };
 
+        // codegen only needs the messages for runtime errors
+        let errors = errors.errors.map(|files| {
+            files.iter()
+                .map(|file| {
+                    file.iter()
+                        .map(|d| render(d, &comp.string_map, &comp.files.files))
+                        .collect()
+                })
+                .collect()
+        });
+
         llvm_codegen::run(
             &mut comp.string_map, &mut self.syms,
             &mut self.namespaces, &mut self.ast,
             &mut self.ty_info, errors,
-            self.file_offsets.len() as u32,
+            &comp.files.files,
             &self.startups,
             &tests,
+            &self.exports,
             settings,
         );
 
@@ -689,8 +839,27 @@ impl<'me> CompilationResult<'me> {
     /// Test symbol names paired with their `should_panic` flag.
     pub fn tests(&self) -> &[(SymbolId, bool)] { &self.tests }
 
+    /// `@export`ed functions paired with their C names.
+    pub fn exports(&self) -> &[(SymbolId, StringIndex)] { &self.exports }
+
+    pub fn ast(&self) -> &AST<'me> { &self.ast }
+
+    pub fn ty_info(&self) -> &semantic_analysis::TyInfo<'me> { &self.ty_info }
+
+    pub fn namespaces(&self) -> &semantic_analysis::namespace::NamespaceMap { &self.namespaces }
+
+    pub fn scopes(&self) -> &semantic_analysis::scope::ScopeMap<'me> { &self.scopes }
+
+    /// The global source offset each compiled file starts at.
+    pub fn file_offsets(&self) -> &[(StringIndex, u32)] { &self.file_offsets }
+
     fn is_silent_error(&self, node: NodeId) -> bool {
-        let (start, end) = self.ast.range(node).range();
+        self.is_silent(self.ast.range(node))
+    }
+
+
+    fn is_silent(&self, source: SourceRange) -> bool {
+        let (start, end) = source.range();
         let index = self.silent_ranges.partition_point(|range| range.range().0 <= start);
         index != 0 && end <= self.silent_ranges[index - 1].range().1
     }
@@ -734,27 +903,11 @@ mod tests {
 
     #[test]
     fn missing_link_file_is_a_parser_error() {
-        let arena = Arena::new();
-        let mut compiler = Compiler::new(&arena);
-        let name = compiler.string_map.insert("test.mar");
-        compiler.files.register(FileData::new(
+        let result = compile_source(
             "extern \"missing-linker-input.o\";\n\
              @cfg(env(\"PATH\", \"__margarine_cfg_disabled__\"))\n\
-             extern \"also-missing.o\";"
-                .to_string(),
-            name,
-            Extension::None,
-        ));
-
-        let result = compiler.run(&CompilationSettings {
-            compilation_target: CompilationTarget::Arm64AppleDarwin,
-            preludes: vec![],
-            entry: "test.mar".to_string(),
-            arena: &arena,
-            tests: false,
-            output: "program".to_string(),
-            cache: "artifacts".to_string(),
-        });
+             extern \"also-missing.o\";",
+        );
         let errors: Vec<_> = result.errors.parser_errors.iter().flatten().collect();
 
         assert_eq!(errors.len(), 1);
@@ -763,10 +916,7 @@ mod tests {
 
     #[test]
     fn conditional_trait_impl_requires_its_generic_bounds() {
-        let arena = Arena::new();
-        let mut compiler = Compiler::new(&arena);
-        let name = compiler.string_map.insert("test.mar");
-        compiler.files.register(FileData::new(
+        let result = compile_source(
             "trait Printable {}\n\
              impl Printable for int {}\n\
              impl<T0: Printable, T1: Printable> Printable for (T0, T1) {}\n\
@@ -776,21 +926,8 @@ mod tests {
              fn main() {\n\
                  requires_printable((1, 2));\n\
                  requires_printable(Wrapper { value: (1, false) });\n\
-             }"
-                .to_string(),
-            name,
-            Extension::None,
-        ));
-
-        let result = compiler.run(&CompilationSettings {
-            compilation_target: CompilationTarget::Arm64AppleDarwin,
-            preludes: vec![],
-            entry: "test.mar".to_string(),
-            arena: &arena,
-            tests: false,
-            output: "program".to_string(),
-            cache: "artifacts".to_string(),
-        });
+             }",
+        );
         let errors: Vec<_> = result.errors.sema_errors.iter().collect();
 
         assert_eq!(errors.iter().filter(|error| matches!(
@@ -799,20 +936,66 @@ mod tests {
         )).count(), 1);
     }
 
-    fn compile_source(source: &str) -> CompilationResult<'_> {
-        let arena = Box::leak(Box::new(Arena::new()));
-        let mut compiler = Compiler::new(arena);
-        let name = compiler.string_map.insert("test.mar");
-        compiler.files.register(FileData::new(source.to_owned(), name, Extension::None));
-        compiler.run(&CompilationSettings {
+    /// The settings every test compiles with, tests `configure`
+    /// what they need on top
+    fn test_settings(arena: &Arena) -> CompilationSettings<'_> {
+        CompilationSettings {
             compilation_target: CompilationTarget::Arm64AppleDarwin,
             preludes: vec![],
             entry: "test.mar".to_string(),
             arena,
             tests: false,
+            overflow_checks: true,
+            debug_info: false,
+            backtrace: false,
+            opt_level: OptLevel::default(),
+            emit: Vec::new(),
+            crate_type: CrateType::default(),
             output: "program".to_string(),
             cache: "artifacts".to_string(),
-        })
+        }
+    }
+
+    /// A silent compiler with `source` registered as `test.mar`
+    fn test_compiler(source: &str) -> Compiler<'static> {
+        let arena = Box::leak(Box::new(Arena::new()));
+        let mut compiler = Compiler::new(arena);
+        compiler.silent = true;
+        let name = compiler.string_map.insert("test.mar");
+        compiler.files.register(FileData::new(source.to_owned(), name, Extension::None));
+        compiler
+    }
+
+    /// Runs `source` up to codegen with the `configure`d settings
+    fn compile_with(
+        source: &str,
+        configure: impl FnOnce(&mut CompilationSettings),
+    ) -> (Compiler<'static>, CompilationResult<'static>) {
+        let mut compiler = test_compiler(source);
+        let mut settings = test_settings(compiler.arena);
+        configure(&mut settings);
+        let result = compiler.run(&settings);
+        (compiler, result)
+    }
+
+    fn compile_source(source: &str) -> CompilationResult<'static> {
+        compile_with(source, |_| {}).1
+    }
+
+    /// Asserts `source` reports exactly the `expected` errors, named by
+    /// their variant and in the order they're reported
+    #[track_caller]
+    fn assert_errors(source: &str, expected: &[&str]) {
+        fn name(error: &impl std::fmt::Debug) -> String {
+            format!("{error:?}").chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect()
+        }
+
+        let errors = compile_source(source).errors;
+        let lexer = errors.lexer_errors.iter().flat_map(|file| file.iter().map(name));
+        let parser = errors.parser_errors.iter().flat_map(|file| file.iter().map(name));
+        let sema = errors.sema_errors.iter().map(name);
+        let names = lexer.chain(parser).chain(sema).collect::<Vec<_>>();
+        assert_eq!(names, expected, "{source}");
     }
 
     #[test]
@@ -829,6 +1012,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn range_bounds_must_be_i64() {
+        assert_errors("fn main() { var n: u32 = 5; var range = 0..n; }", &["InvalidRange"]);
+        assert_errors("fn main() { var n = 5; var range = 0..n; }", &[]);
+    }
+
     #[test]
     fn semantic_errors_do_not_panic_codegen() {
         let result = compile_source(
@@ -977,6 +1166,642 @@ mod tests {
             semantic_analysis::errors::Error::CannotMutateCapturedValue { .. }
         )));
     }
+
+    #[test]
+    fn errors_are_reported_as_structured_diagnostics() {
+        let (compiler, mut result) = compile_with("fn main() { var value = missing + 1; }", |_| {});
+
+        let diagnostics = compiler.check(&mut result);
+        let diagnostic = diagnostics.errors[2][0].iter().find(|d| d.code == "E0218").unwrap();
+
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.primary().unwrap().span, SourceRange::new(24, 30));
+        assert!(render(diagnostic, &compiler.string_map, &compiler.files.files).contains("error[E0218]"));
+    }
+
+    #[test]
+    fn json_diagnostics_carry_lines_and_columns() {
+        let (compiler, mut result) = compile_with("fn main() {\n    missing\n}", |_| {});
+
+        let diagnostics = compiler.check(&mut result);
+        let diagnostic = diagnostics.errors[2][0].iter().find(|d| d.code == "E0218").unwrap();
+        let json = message::diagnostic(diagnostic, &compiler.string_map, &compiler.files.files);
+
+        assert_eq!(json["reason"], "diagnostic");
+        assert_eq!(json["labels"][0]["file"], "test.mar");
+        assert_eq!(json["labels"][0]["primary"], true);
+        assert_eq!(json["labels"][0]["line_start"], 2);
+        assert_eq!(json["labels"][0]["column_start"], 5);
+        assert_eq!(json["labels"][0]["column_end"], 12);
+    }
+
+    #[test]
+    fn lints_report_unused_and_unreachable_code() {
+        use semantic_analysis::lints::Warning;
+
+        let result = compile_source(
+            "mod a { pub fn exposed() {} }\n\
+             use a::exposed\n\
+             fn main() {\n\
+                 let unused = 1;\n\
+                 var never_mutated = 2;\n\
+                 let shadowed = never_mutated;\n\
+                 let shadowed = shadowed + 1;\n\
+                 let _ignored = shadowed;\n\
+             }\n\
+             fn early(): int {\n\
+                 return 0;\n\
+                 1\n\
+             }",
+        );
+
+        let lints = result.errors.lints.iter().map(|(w, _)| w).collect::<Vec<_>>();
+        assert!(lints.iter().any(|w| matches!(w, Warning::UnusedVariable { .. })));
+        assert!(lints.iter().any(|w| matches!(w, Warning::UnusedImport { .. })));
+        assert!(lints.iter().any(|w| matches!(w, Warning::UnusedMut { .. })));
+        assert!(lints.iter().any(|w| matches!(w, Warning::UnreachableCode { .. })));
+        assert!(lints.iter().any(|w| matches!(w, Warning::ShadowedBinding { .. })));
+        assert!(result.errors.lints.iter().all(|(_, level)| *level == semantic_analysis::lints::Level::Warn));
+    }
+
+    #[test]
+    fn lint_levels_are_set_by_attributes() {
+        use semantic_analysis::lints::{Level, Warning};
+
+        let result = compile_source(
+            "@allow(unused)\n\
+             fn allowed() { let unused = 1; }\n\
+             @deny(unused_variables)\n\
+             fn denied() { let unused = 1; }",
+        );
+
+        assert_eq!(result.errors.lints.len(), 1);
+        assert!(matches!(result.errors.lints[0], (Warning::UnusedVariable { .. }, Level::Deny)));
+    }
+
+    #[test]
+    fn only_denied_lints_stop_a_build() {
+        let source = "@silent\n\
+                      fn broken() { missing; }\n\
+                      @silent\n\
+                      @deny(unused)\n\
+                      fn quiet() { let unused = 1; }\n\
+                      fn main() {}";
+        let (compiler, mut result) = compile_with(source, |_| {});
+        let diagnostics = compiler.check(&mut result);
+        assert_eq!(diagnostics.error_count(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics.denied_count(), 0, "{diagnostics:?}");
+
+        let (compiler, mut result) = compile_with("@deny(unused)\nfn loud() { let unused = 1; }", |_| {});
+        let diagnostics = compiler.check(&mut result);
+        assert_eq!(diagnostics.denied_count(), 1, "{diagnostics:?}");
+    }
+
+    #[test]
+    fn non_exhaustive_matches_name_the_missing_cases() {
+        use semantic_analysis::errors::Error;
+
+        let (compiler, result) = compile_with(
+            "enum Inner { a, b }\n\
+             enum Outer { one(Inner), two(int), three }\n\
+             fn missing(v: Outer): int { match v { one(a) => 1, two(0) | three => 2 } }\n\
+             fn unreachable(v: Outer): int { match v { _ => 1, three => 2 } }\n\
+             fn mismatch(v: Outer): int { match v { two(x) | three => 1, _ => 2 } }\n\
+             fn complete(v: (Outer, int)): int { match v { (one(_), 1) => 1, (one(_) | two(_), _) => 2, (three, n) if n > 0 => n, (three, _) => 0 } }",
+            |_| {},
+        );
+
+        let errors = &result.errors.sema_errors;
+        assert_eq!(errors.len(), 3, "{errors:?}");
+
+        let missing = errors.iter().find_map(|e| match e {
+            Error::MissingMatch { missing, .. } => Some(missing.iter().map(|m| compiler.string_map.get(*m)).collect::<Vec<_>>()),
+            _ => None,
+        });
+
+        assert_eq!(missing.unwrap(), ["one(b)", "two(_)"]);
+        assert!(errors.iter().any(|e| matches!(e, Error::UnreachableMatch { .. })));
+        assert!(errors.iter().any(|e| matches!(e, Error::OrPatternBindingMismatch { .. })));
+    }
+
+    #[test]
+    fn if_let_bindings_are_scoped_to_their_body() {
+        let source = |else_body: &str| format!(
+            "enum Option<T> {{ some(T), none }}\n\
+             fn next(n: int): Option<int> {{ if n > 3 {{ return Option::none() }} Option::some(n) }}\n\
+             fn main() {{\n\
+                 let x = Option::some(5);\n\
+                 let y = if let some(a) = x {{ a }} else {{ {else_body} }};\n\
+                 var n = y;\n\
+                 while let some(b) = next(n) {{ n = n + b + 1; }}\n\
+             }}"
+        );
+
+        let valid = source("0");
+        assert_errors(&valid, &[]);
+
+        let out_of_scope = source("a");
+        assert_errors(&out_of_scope, &["VariableNotFound"]);
+    }
+
+    #[test]
+    fn if_let_fallback_arm() {
+        let irrefutable = "fn main() { let t = (1, 2); if let (a, b) = t { a + b; } else { }; while let (c, _) = t { break } }";
+        assert_errors(irrefutable, &[]);
+
+        let missing_else = "enum Option<T> { some(T), none }\n\
+                            fn main() { let x = Option::some(1); let y = if let some(a) = x { a }; }";
+        assert_errors(missing_else, &["IfMissingElse"]);
+
+        let unit = "enum Option<T> { some(T), none }\n\
+                    fn main() { let x = Option::some(1); if let some(a) = x { a; } }";
+        assert_errors(unit, &[]);
+    }
+
+    #[test]
+    fn labelled_loops_and_break_values() {
+        let valid = "fn main() {\n\
+                         var i = 0;\n\
+                         let found: int = 'outer: loop {\n\
+                             'inner: while i < 10 { i = i + 1; if i == 3 { break 'outer i } continue 'inner; }\n\
+                             break -1\n\
+                         };\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "fn main() {\n\
+                           loop { break 'missing; }\n\
+                           while true { break 5 }\n\
+                           let x = loop { if true { break 1 } break \"no\" };\n\
+                       }";
+        assert_errors(invalid, &["UndeclaredLabel", "BreakWithValueOutsideOfLoop", "BreakValuesDiffer"]);
+    }
+
+    #[test]
+    fn generic_type_namespaces() {
+        let pair = "struct Pair<T> { a: T, b: T }\n\
+                    impl<T> Pair<T> { fn new(a: T, b: T): Pair<T> { Pair { a: a, b: b } } }\n";
+
+        let valid = format!("{pair}fn main() {{ let p = Pair::<int>::new(1, 2); let a: int = p.a; }}");
+        assert_errors(&valid, &[]);
+
+        let invalid = format!("{pair}fn main() {{ let p = Pair::<int>::new(\"a\", 2); let s: str = Pair::<int>::new(1, 2).b; }}");
+        assert_errors(&invalid, &["InvalidType", "VariableValueAndHintDiffer"]);
+    }
+
+    #[test]
+    fn consts_are_evaluated_at_compile_time() {
+        use semantic_analysis::consts::ConstValue;
+
+        let valid = "const AREA: int = SIDE * SIDE + 1;\n\
+                     const SIDE: int = 1 << 3;\n\
+                     const TABLE: (int, float) = (-AREA, 0.5 * 3.0);\n\
+                     static START: int = AREA;\n\
+                     const ALL: u64 = 9223372036854775807u64 * 2 + 1;\n\
+                     const HALF: u64 = ALL / 2;\n\
+                     const ABOVE: bool = ALL >> 1 > HALF - 1;\n\
+                     fn main() { let x = TABLE.0 + START; let y = ALL + HALF; let z = ABOVE; }";
+        let result = compile_source(valid);
+        assert!(result.errors.sema_errors.is_empty(), "{:?}", result.errors.sema_errors);
+
+        let values = result.ast().exprs().kiter()
+            .filter_map(|(id, _)| result.ty_info().ident(id).flatten())
+            .filter_map(|sym| result.ty_info().const_value(sym))
+            .collect::<Vec<_>>();
+
+        assert!(values.contains(&&ConstValue::Int(8)));
+        assert!(values.contains(&&ConstValue::Int(65)));
+        assert!(values.contains(&&ConstValue::Tuple(vec![ConstValue::Int(-65), ConstValue::Float(1.5)])));
+        assert!(values.contains(&&ConstValue::Int(u64::MAX.into())));
+        assert!(values.contains(&&ConstValue::Int(i64::MAX.into())));
+        assert!(values.contains(&&ConstValue::Bool(true)));
+
+        let invalid = "fn f(): int { 1 }\n\
+                       const CALL: int = f();\n\
+                       const LOOP: int = LOOP + 1;\n\
+                       const ZERO: int = 1 / 0;\n\
+                       const WRONG: str = 5;\n\
+                       const NEGATIVE: u64 = 1u64 - 2;\n\
+                       const WIDE: u8 = 1u8 << 8;\n\
+                       static S: int = 0;\n\
+                       fn main() { S = 1; }";
+        assert_errors(invalid, &[
+            "GlobalValueAndTypeDiffer", "AssignmentToImmutableVariable", "NonConstantExpression",
+            "RecursiveConst", "ConstEvaluationFailed", "ConstEvaluationFailed", "ConstEvaluationFailed",
+        ]);
+    }
+
+
+    #[test]
+    fn sized_number_types() {
+        let valid = "fn takes(x: u8): u8 { x }\n\
+                     const MASK: u32 = 0xff;\n\
+                     fn main() {\n\
+                         let a: u8 = 200;\n\
+                         let b = a + 55;\n\
+                         let c = 5i32 * -3;\n\
+                         let d = takes(7) == b;\n\
+                         let e: f32 = 1.5;\n\
+                         let f = e * 2.0 + 1f32;\n\
+                         let g = c as i64 + 1;\n\
+                         let h = (g as u32 & MASK) as usize;\n\
+                         var i: i16 = 0;\n\
+                         i += 1;\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "fn main() {\n\
+                           let a: u8 = 256;\n\
+                           let b: u32 = -1;\n\
+                           let c = 1u8 + 1i32;\n\
+                           let x: u32 = 5;\n\
+                           let d = -x;\n\
+                           let e: i32 = 5i64;\n\
+                       }";
+        assert_errors(invalid, &["LiteralOutOfRange", "LiteralOutOfRange", "InvalidBinaryOp", "InvalidUnaryOp", "VariableValueAndHintDiffer"]);
+    }
+
+
+    #[test]
+    fn wrapping_and_checked_builtins() {
+        let valid = "fn main() {\n\
+                         let a: u8 = 250;\n\
+                         let b = wrapping_add(a, 10);\n\
+                         let c = checked_div(7i32, 0);\n\
+                         let d = match checked_sub(a, b) { some(v) => v, none => a };\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "fn main() {\n\
+                           let a = wrapping_add(1.5, 2.5);\n\
+                           let b = checked_mul(true, false);\n\
+                       }";
+        assert_errors(invalid, &["InvalidBinaryOp", "InvalidBinaryOp"]);
+    }
+
+    #[test]
+    fn char_literals_and_str_iteration() {
+        let valid = "fn main() {\n\
+                         let c: char = '\\u{1F600}';\n\
+                         let up = c > 'a' && c != '\\n';\n\
+                         let code = c as u32;\n\
+                         let b = 65u8 as char;\n\
+                         let kind = match c { 'a' | 'b' => 0, _ => 1 };\n\
+                         for ch in \"héllo\".chars() { let x: char = ch; }\n\
+                         for i, ch in \"héllo\".char_indices() { let y: int = i; }\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "fn main() {\n\
+                           let a = 'a' + 'b';\n\
+                           let b = 300 as char;\n\
+                       }";
+        assert_errors(invalid, &["InvalidBinaryOp", "InvalidCast"]);
+    }
+
+
+    #[test]
+    fn interpolated_strings() {
+        let valid = "struct Point { x: str, y: str }\n\
+                     impl Point { fn __to_str__(self): str { f\"({self.x}, {self.y})\" } }\n\
+                     trait ToStr { fn to_str(self): str }\n\
+                     impl ToStr for int { fn to_str(self): str { \"n\" } }\n\
+                     fn main() {\n\
+                         let name = \"world\";\n\
+                         let p = Point { x: \"1\", y: \"2\" };\n\
+                         let n = 1;\n\
+                         let s: str = f\"hello {name}, {{p}} is {p}!\";\n\
+                         let t: str = f\"{f\"{name}\"}\";\n\
+                         let u: str = f\"{n} & {n + 1}\";\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "struct Empty {}\n\
+                       fn main() {\n\
+                           let s = f\"{Empty {}}\";\n\
+                       }";
+        assert_errors(invalid, &["NoStringConversion"]);
+    }
+
+
+    #[test]
+    fn operators_dispatch_through_core_traits() {
+        use semantic_analysis::errors::Error;
+
+        let valid = "struct V { x: int }\n\
+                     impl Add for V { fn add(self, o: V): V { V { x: self.x + o.x } } }\n\
+                     impl Neg for V { fn neg(self): V { V { x: -self.x } } }\n\
+                     impl Ord for V { fn cmp(self, o: V): int { self.x - o.x } }\n\
+                     impl Index<int> for V { fn index(self, i: int): int { self.x + i } }\n\
+                     fn twice<T: Add>(a: T): T { a + a }\n\
+                     fn main() {\n\
+                         let a = V { x: 1 };\n\
+                         let b: V = -(a + twice(a));\n\
+                         let c: bool = a < b || a >= b;\n\
+                         let d: int = a[2];\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "struct V { x: int }\n\
+                       impl Index<int> for V { fn index(self, i: int): int { self.x } }\n\
+                       fn main() {\n\
+                           var a = V { x: 1 };\n\
+                           let b = a * a;\n\
+                           let c = true + true;\n\
+                           a[0] = 2;\n\
+                       }";
+        assert_errors(invalid, &["TypeDoesntImplTrait", "InvalidBinaryOp", "AssignIsNotLHSValue"]);
+        let errors = compile_source(invalid).errors.sema_errors;
+        assert!(matches!(errors.as_slice()[0], Error::TypeDoesntImplTrait { tr: SymbolId::MUL_TRAIT, .. }), "{errors:?}");
+    }
+
+
+    #[test]
+    fn trait_default_methods_and_supertraits() {
+        let valid = "trait Shape { fn area(self): int fn double_area(self): int { self.area() * 2 } }\n\
+                     trait Named: Shape { fn name(self): int fn describe(self): int { self.name() + self.double_area() } }\n\
+                     struct Sq { s: int }\n\
+                     impl Shape for Sq { fn area(self): int { self.s * self.s } }\n\
+                     impl Named for Sq { fn name(self): int { 1 } }\n\
+                     fn total<T: Named>(x: T): int { x.area() + x.describe() }\n\
+                     fn main() {\n\
+                         let q = Sq { s: 2 };\n\
+                         let a: int = q.double_area() + q.describe();\n\
+                         let b: int = total(q);\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "trait Shape { fn area(self): int fn double_area(self): int { self.area() * 2 } }\n\
+                       trait Named: Shape { fn name(self): int }\n\
+                       struct C {}\n\
+                       impl Named for C { fn name(self): int { 0 } }\n\
+                       trait Bad: C {}\n\
+                       struct D {}\n\
+                       impl Shape for D { fn double_area(self): int { 0 } }";
+        assert_errors(invalid, &["TypeDoesntImplTrait", "SupertraitIsNotATrait", "MissingFuncs"]);
+    }
+
+
+    #[test]
+    fn dyn_trait_objects() {
+        let valid = "trait Shape { fn area(self): int fn double_area(self): int { self.area() * 2 } }\n\
+                     trait Named: Shape { fn name(self): int }\n\
+                     struct Sq { s: int }\n\
+                     impl Shape for Sq { fn area(self): int { self.s * self.s } }\n\
+                     impl Named for Sq { fn name(self): int { 1 } }\n\
+                     fn total<T: Shape>(x: T): int { x.area() }\n\
+                     fn main() {\n\
+                         let shapes: [dyn Shape] = [Sq { s: 2 } as dyn Shape];\n\
+                         let named = Sq { s: 3 } as dyn Named;\n\
+                         let a: int = shapes[0].double_area() + total(shapes[0]);\n\
+                         let b: int = named.name() + named.area() + (named as dyn Shape).area();\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "trait Cmp { fn cmp(self, other: Self): int }\n\
+                       trait Map { fn map<T>(self, value: T): T }\n\
+                       trait Area { fn area(self): int }\n\
+                       struct C {}\n\
+                       fn a(x: dyn Cmp, y: dyn Map, z: dyn C) {}\n\
+                       fn b() { let c = C {} as dyn Area; }";
+        assert_errors(invalid, &["TraitNotObjectSafe", "TraitNotObjectSafe", "DynIsNotATrait", "TypeDoesntImplTrait"]);
+    }
+
+
+    #[test]
+    fn associated_items() {
+        use semantic_analysis::errors::Error;
+
+        let valid = "trait Container { type Elem const CAPACITY: int fn get(self, index: int): Self::Elem }\n\
+                     struct Bytes { b: int }\n\
+                     impl Container for Bytes {\n\
+                         type Elem = int\n\
+                         const CAPACITY: int = 8\n\
+                         fn get(self, index: int): int { self.b + index }\n\
+                     }\n\
+                     struct Countdown { n: int }\n\
+                     impl Iterator for Countdown {\n\
+                         type Item = int\n\
+                         fn __next__(&self): Option<Self::Item> {\n\
+                             if self.n == 0 { return none() }\n\
+                             self.n = self.n - 1;\n\
+                             some(self.n)\n\
+                         }\n\
+                     }\n\
+                     fn first<I: Iterator>(it: I): Option<I::Item> { var it = it; it.__next__() }\n\
+                     fn elem<C: Container>(c: C): C::Elem { c.get(0) }\n\
+                     fn main() {\n\
+                         let a: int = elem(Bytes { b: 1 }) + Bytes::CAPACITY;\n\
+                         let countdown = Countdown { n: 3 };\n\
+                         for i in countdown { let b: int = i; }\n\
+                         let c: Option<int> = first(Countdown { n: 2 });\n\
+                     }";
+        assert_errors(valid, &[]);
+
+        let invalid = "trait Container { type Elem const CAPACITY: int }\n\
+                       struct Bytes {}\n\
+                       impl Container for Bytes {}\n\
+                       fn f<C: Container>(c: C): C::Missing { c }";
+        assert_errors(invalid, &["AssocTypeNotFound", "MissingAssocItems"]);
+        let errors = compile_source(invalid).errors.sema_errors;
+        assert!(matches!(&errors.as_slice()[1], Error::MissingAssocItems { items, .. } if items.len() == 2), "{errors:?}");
+    }
+
+
+    #[test]
+    fn for_loops_prefer_the_iterator_trait() {
+        let source = "struct Words { n: int }\n\
+                      impl Words { fn __next__(&self): Option<str> { none() } }\n\
+                      impl Iterator for Words {\n\
+                          type Item = int\n\
+                          fn __next__(&self): Option<Self::Item> { none() }\n\
+                      }\n\
+                      fn main() { let words = Words { n: 0 }; for i in words { let b: int = i; } }";
+        assert_errors(source, &[]);
+    }
+
+
+    #[test]
+    fn cached_functions() {
+        let valid = "struct Key { a: int }\n\
+                     impl Eq for Key { fn eq(self, other: Key): bool { self.a == other.a } }\n\
+                     impl Hash for Key { fn hash(self): u64 { self.a as u64 } }\n\
+                     @cached fn fib(n: int): int { if n < 2 { return n } fib(n - 1) + fib(n - 2) }\n\
+                     impl Eq for str { fn eq(self, other: str): bool { true } }\n\
+                     @cached fn keyed<T: Eq + Hash>(key: T, c: char): int { 0 }\n\
+                     @cached fn named(name: str, flag: bool): int { 0 }\n\
+                     fn main() { let a: int = fib(10) + keyed(Key { a: 1 }, 'c') + named(\"a\", true); }";
+        assert_errors(valid, &[]);
+
+        let invalid = "struct Key { a: int }\n\
+                       @cached fn f(key: Key): int { key.a }\n\
+                       @cached fn g(&n: int): int { n }";
+        assert_errors(invalid, &["UnhashableCachedArg", "CachedInOutArg"]);
+
+        // without an `Eq` a `str` can't be compared to the cached ones
+        assert_errors("@cached fn f(name: str): int { 0 }", &["UnhashableCachedArg"]);
+    }
+
+    #[test]
+    fn exported_functions() {
+        let invalid = "@export(\"f\") fn f(s: str): int { 0 }\n\
+                       @export(\"g\") fn g(): int { 0 }\n\
+                       @export(\"g\") fn h(): int { 0 }\n\
+                       @export(\"not valid\") fn i(): int { 0 }\n\
+                       @export(\"j\") fn j<T>(): int { 0 }\n\
+                       @export(\"margarineInitStatics\") fn k() {}";
+        assert_errors(invalid, &["UnexportableType", "DuplicateExport", "InvalidValueForAttr", "InvalidValueForAttr", "DuplicateExport"]);
+
+        let source = "struct Point { x: f32, y: f32 }\n\
+                      struct Line { from: Point, to: Point }\n\
+                      @export(\"line_len\") fn len(line: Line, scale: u8): float { 0.0 }\n\
+                      @export(\"reset\") fn reset() {}\n\
+                      @export(\"count\") fn count(n: usize): usize { n }";
+        let (compiler, result) = compile_with(source, |settings| settings.crate_type = CrateType::StaticLib);
+        assert!(result.errors.sema_errors.is_empty(), "{:?}", result.errors.sema_errors);
+        assert_eq!(result.exports().len(), 3);
+
+        let header = semantic_analysis::c_header::generate(&compiler.string_map, &result.syms, result.exports(), "PROGRAM_H");
+        let point = header.find("typedef struct Point {\n    float x;\n    float y;\n} Point;").expect(&header);
+        let line = header.find("typedef struct Line {\n    Point from;\n    Point to;\n} Line;").expect(&header);
+        assert!(point < line, "{header}");
+        assert!(header.contains("double line_len(Line line, uint8_t scale);"), "{header}");
+        assert!(header.contains("void reset(void);"), "{header}");
+        assert!(header.contains("void margarineFreeCaches(void);"), "{header}");
+        assert!(header.contains("size_t count(size_t n);"), "{header}");
+        assert!(header.contains("#ifndef PROGRAM_H"), "{header}");
+        assert_eq!(semantic_analysis::c_header::include_guard("2d-shapes"), "MARGARINE_2D_SHAPES_H");
+
+        // the C names are the wrappers' own
+        let (ir, _) = emit_ir(source, |settings| settings.crate_type = CrateType::StaticLib);
+        for name in ["line_len", "reset", "count", "margarineInitStatics", "margarineFreeCaches"] {
+            assert!(ir.lines().any(|line| line.starts_with("define ")
+                && !line.contains(" internal ")
+                && line.contains(&format!(" @{name}("))), "no {name} in {ir}");
+        }
+        assert!(!ir.lines().any(|line| line.starts_with("define ") && line.contains(" @main(")), "{ir}");
+    }
+
+    #[test]
+    fn exported_bools_are_bytes() {
+        let source = "struct Flags { on: bool, level: u8 }\n\
+                      @export(\"toggle\") fn toggle(flags: Flags, force: bool): bool { force || !flags.on }\n\
+                      @export(\"flip\") fn flip(flags: Flags): Flags { Flags { on: !flags.on, level: flags.level } }";
+        let (compiler, result) = compile_with(source, |settings| settings.crate_type = CrateType::StaticLib);
+        assert!(result.errors.sema_errors.is_empty(), "{:?}", result.errors.sema_errors);
+
+        let header = semantic_analysis::c_header::generate(&compiler.string_map, &result.syms, result.exports(), "FLAGS_H");
+        assert!(header.contains("typedef struct Flags {\n    bool on;\n    uint8_t level;\n} Flags;"), "{header}");
+        assert!(header.contains("bool toggle(Flags flags, bool force);"), "{header}");
+
+        // C's `bool` is a byte, not margarine's tagged enum
+        let (ir, _) = emit_ir(source, |settings| settings.crate_type = CrateType::StaticLib);
+        let define = |name: &str| ir.lines()
+            .find(|line| line.starts_with("define ") && line.contains(&format!(" @{name}(")))
+            .unwrap_or_else(|| panic!("no {name} in {ir}"))
+            .to_string();
+
+        let toggle = define("toggle");
+        assert!(toggle.starts_with("define zeroext i8 @toggle(%Flags.c "), "{toggle}");
+        assert!(toggle.contains("i8 zeroext"), "{toggle}");
+        assert!(define("flip").starts_with("define %Flags.c @flip(%Flags.c "), "{ir}");
+    }
+
+    /// Compiles `source` through codegen with the `configure`d
+    /// settings and returns the path its artifacts start with
+    fn codegen_source(source: &str, configure: impl FnOnce(&mut CompilationSettings)) -> String {
+        static COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+        let mut compiler = test_compiler(source);
+
+        let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let output = std::env::temp_dir().join(format!("margarine-codegen-{}-{count}", std::process::id()));
+        let output = output.to_string_lossy().to_string();
+
+        let mut settings = test_settings(compiler.arena);
+        settings.emit = vec![Emit::LlvmIr];
+        settings.output = output.clone();
+        configure(&mut settings);
+
+        let mut result = compiler.run(&settings);
+        let errors = compiler.check(&mut result);
+        assert!(result.errors.sema_errors.is_empty(), "{:?}", result.errors.sema_errors);
+        compiler.codegen(&settings, &mut result, errors);
+        output
+    }
+
+    /// The unoptimised & optimised IR `--emit=llvm-ir` writes for `source`
+    fn emit_ir(source: &str, configure: impl FnOnce(&mut CompilationSettings)) -> (String, String) {
+        let output = codegen_source(source, configure);
+        let unoptimized = fs::read_to_string(format!("{output}.unoptimized.ll")).unwrap();
+        let optimized = fs::read_to_string(format!("{output}.ll")).unwrap();
+        (unoptimized, optimized)
+    }
+
+    #[test]
+    fn emitted_artifacts() {
+        let source = "fn six(): int { let a = 2; let b = 3; a * b }\n\
+                      fn main() { let c = six(); }";
+        let output = codegen_source(source, |settings| settings.emit = vec![Emit::LlvmIr, Emit::Asm]);
+        for extension in ["unoptimized.ll", "ll", "s", "o"] {
+            assert!(std::path::Path::new(&format!("{output}.{extension}")).exists(), "no {output}.{extension}");
+        }
+        assert!(!std::path::Path::new(&format!("{output}.bc")).exists());
+
+        // only the optimised module depends on the level
+        let (_, o0) = emit_ir(source, |settings| settings.opt_level = OptLevel::O0);
+        let (_, o3) = emit_ir(source, |settings| settings.opt_level = OptLevel::O3);
+        assert!(o3.matches("alloca").count() < o0.matches("alloca").count(), "{o3}");
+    }
+
+    #[test]
+    fn debug_info() {
+        let source = "fn add(a: int, b: int): int { let c = a + b; c }\n\
+                      fn main() { add(1, 2); }";
+        let (ir, _) = emit_ir(source, |settings| settings.debug_info = true);
+        assert!(ir.contains("!DICompileUnit("), "{ir}");
+        assert!(ir.contains("!DISubprogram("), "{ir}");
+        assert!(ir.contains("!DILocalVariable(name: \"a\", arg: 1"), "{ir}");
+        assert!(ir.contains("!DILocalVariable(name: \"c\""), "{ir}");
+
+        let (ir, _) = emit_ir(source, |_| {});
+        assert!(!ir.contains("!DICompileUnit("), "{ir}");
+    }
+
+    #[test]
+    fn consts_are_built_once() {
+        let source = "const AREA: int = 65;\n\
+                      const GREETING: str = \"hi\";\n\
+                      fn greet(): str { GREETING }\n\
+                      fn main() { let a = greet(); let b = GREETING; let c = AREA; }";
+        let (ir, _) = emit_ir(source, |_| {});
+        assert!(ir.contains("@AREA = constant i64 65"), "{ir}");
+        assert_eq!(ir.matches("c\"hi\"").count(), 1, "{ir}");
+    }
+
+    #[test]
+    fn panic_messages() {
+        let source = "extern { fn \"margarinePanic\" panic(msg: str): ! }\n\
+                      fn explode() { panic(\"boom\") }\n\
+                      fn index(): int { [1, 2][5] }\n\
+                      fn main() { explode(); index(); }";
+        let (ir, _) = emit_ir(source, |_| {});
+        assert!(ir.contains("explode' at test.mar:2:"), "{ir}");
+        assert!(ir.contains("list index out of bounds in '"), "{ir}");
+        assert!(ir.contains("index' at test.mar:3:"), "{ir}");
+        assert!(!ir.contains("backtrace_symbols_fd"), "{ir}");
+    }
+
+    #[test]
+    fn backtrace_panics() {
+        let source = "extern { fn \"margarinePanic\" panic(msg: str): ! }\n\
+                      fn main() { panic(\"boom\") }";
+        let (ir, _) = emit_ir(source, |settings| settings.backtrace = true);
+        assert!(ir.contains("define internal void @margarinePanic.backtrace"), "{ir}");
+        assert!(ir.contains("call void @backtrace_symbols_fd"), "{ir}");
+        assert!(ir.contains("call void @margarinePanic.backtrace"), "{ir}");
+        assert!(ir.contains("main' at test.mar:2:"), "{ir}");
+    }
 }
 
 
@@ -1044,6 +1869,31 @@ impl BuildLock {
 }
 
 
+/// The preludes from `MARGARINE_PRELUDE` (`alias=url;...`), or the
+/// default `core` and `std` of this compiler version.
+pub fn parse_env_preludes() -> Vec<Prelude> {
+    let preludes =
+    std::env::var("MARGARINE_PRELUDE")
+        .iter()
+        .flat_map(|s| s.split(';'))
+        .filter_map(|s| s.split_once('='))
+        .map(|(alias, url)| Prelude { alias: alias.into(), url: url.into() })
+        .collect::<Vec<_>>();
+
+
+    if preludes.is_empty() {
+        let url = format!("https://cdn.daymare.net/margarine/{}/share", env!("CARGO_PKG_VERSION"));
+
+        vec![
+            Prelude { alias: "core".into(), url: format!("{url}/core") },
+            Prelude { alias: "std".into(), url: format!("{url}/std") },
+        ]
+    } else {
+        preludes
+    }
+}
+
+
 fn resolve_url(package: &str) -> String {
     if !package.starts_with("pkg:") {
         package.to_string()
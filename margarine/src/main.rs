@@ -3,9 +3,10 @@ mod update;
 
 use std::{ffi::CString, fmt::Write, io::{self, Write as _}, path::PathBuf, process::Command, time::Instant};
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use colourful::ColourBrush;
-use margarine::{CompilationSettings, CompilationTarget, Prelude};
+use margarine::{message, parse_env_preludes, CompilationSettings, CompilationTarget, CrateType, Emit, MessageFormat, OptLevel};
+use serde_json::json;
 use sti::{arena::Arena};
 
 use crate::update::cmd_update;
@@ -41,6 +42,55 @@ struct Cli {
     command: Commands,
 }
 
+/// The code generation flags of `build`, `run` & `test`
+#[derive(Args)]
+struct CodegenArgs {
+    /// Don't trap on integer overflow and division by zero
+    #[arg(long)]
+    no_overflow_checks: bool,
+
+    /// Emit DWARF debug info for gdb & lldb
+    #[arg(long)]
+    debug: bool,
+
+    /// Print the call stack when the program panics
+    #[arg(long)]
+    backtrace: bool,
+
+    /// Optimization level, 0 to 3, defaults to `MARGARINE_OPT_LEVEL` or 3
+    #[arg(short = 'O', value_name = "LEVEL")]
+    opt_level: Option<OptLevel>,
+
+    /// Optimize fully and drop the overflow checks
+    #[arg(long, conflicts_with_all = ["opt_level", "no_overflow_checks"])]
+    release: bool,
+
+    /// Extra artifacts to write next to the output: llvm-ir, llvm-bc, asm or obj, the object file is always kept
+    #[arg(long, value_delimiter = ',', value_name = "KINDS")]
+    emit: Vec<Emit>,
+}
+
+
+impl CodegenArgs {
+    fn overflow_checks(&self) -> bool {
+        !self.no_overflow_checks && !self.release
+    }
+
+
+    /// `-O`, falling back to `MARGARINE_OPT_LEVEL` which is `O0` to `O3`
+    fn opt_level(&self) -> OptLevel {
+        if self.release { return OptLevel::O3 }
+        if let Some(level) = self.opt_level { return level }
+
+        let Ok(level) = std::env::var("MARGARINE_OPT_LEVEL")
+        else { return OptLevel::default() };
+
+        level.strip_prefix('O').unwrap_or(&level).parse()
+            .unwrap_or_else(|error| fail(2, format!("invalid MARGARINE_OPT_LEVEL: {error}")))
+    }
+}
+
+
 #[derive(Subcommand)]
 enum Commands {
     /// Library management
@@ -49,7 +99,7 @@ enum Commands {
         command: LibCommands,
     },
 
-    /// Compile a source file into an executable
+    /// Compile a source file into an executable or a C library
     Build {
         /// Source path
         #[arg(value_parser = existing_file_path)]
@@ -59,6 +109,10 @@ enum Commands {
         #[arg(long, default_value = "default")]
         target: CompilationTarget,
 
+        /// What to link: bin, staticlib or cdylib
+        #[arg(long, default_value = "bin")]
+        crate_type: CrateType,
+
         /// Output path
         #[arg(short, long)]
         output: Option<String>,
@@ -70,6 +124,13 @@ enum Commands {
         /// Reset the build cache before compiling
         #[arg(long)]
         update: bool,
+
+        /// How progress and diagnostics are printed, `human` or `json`
+        #[arg(long, default_value = "human")]
+        message_format: MessageFormat,
+
+        #[command(flatten)]
+        codegen: CodegenArgs,
     },
 
     /// Compile and run a source file
@@ -90,6 +151,13 @@ enum Commands {
         #[arg(long)]
         update: bool,
 
+        /// How progress and diagnostics are printed, `human` or `json`
+        #[arg(long, default_value = "human")]
+        message_format: MessageFormat,
+
+        #[command(flatten)]
+        codegen: CodegenArgs,
+
         /// Arguments passed to the program
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         program_args: Vec<String>,
@@ -112,6 +180,10 @@ enum Commands {
         /// Reset the build cache before checking
         #[arg(long)]
         update: bool,
+
+        /// How progress and diagnostics are printed, `human` or `json`
+        #[arg(long, default_value = "human")]
+        message_format: MessageFormat,
     },
 
 
@@ -135,6 +207,13 @@ enum Commands {
         /// Reset the build cache before testing
         #[arg(long)]
         update: bool,
+
+        /// How progress and diagnostics are printed, `human` or `json`
+        #[arg(long, default_value = "human")]
+        message_format: MessageFormat,
+
+        #[command(flatten)]
+        codegen: CodegenArgs,
     },
 
     /// Check GitHub for a newer release and show its notes
@@ -210,17 +289,20 @@ fn main() {
             }
         }
 
-        Commands::Build { path, target, output, cache, update } => {
-            let cache = reset_cache_if(update, cache);
-            compile_and_link(&path, target, output, Some(cache));
+        Commands::Build { path, target, crate_type, output, cache, update, message_format, codegen } => {
+            let cache = reset_cache_if(update, cache, message_format);
+            compile_and_link(&path, target, crate_type, output, Some(cache), message_format, &codegen);
         }
 
-        Commands::Run { path, target, cache, update, program_args } => {
-            let cache = reset_cache_if(update, cache);
+        Commands::Run { path, target, cache, update, message_format, codegen, program_args } => {
+            let cache = reset_cache_if(update, cache, message_format);
             let output =
-                compile_and_link(&path, target, Some(format!("{cache}/program")), Some(cache));
+                compile_and_link(&path, target, CrateType::Bin, Some(format!("{cache}/program")), Some(cache), message_format, &codegen);
 
-            println!("running '{output}'");
+            match message_format {
+                MessageFormat::Human => println!("running '{output}'"),
+                MessageFormat::Json => message::emit(json!({ "reason": "running", "executable": output })),
+            }
             let status = Command::new(&output)
                 .args(program_args)
                 .stdin(std::process::Stdio::inherit())
@@ -238,11 +320,12 @@ fn main() {
             }
         }
 
-        Commands::Test { path, filter, target, cache, update } => {
-            let cache = reset_cache_if(update, cache);
+        Commands::Test { path, filter, target, cache, update, message_format, codegen } => {
+            let cache = reset_cache_if(update, cache, message_format);
             let program = format!("{cache}/program");
             let arena = Arena::new();
             let mut compiler = margarine::Compiler::new(&arena);
+            compiler.message_format = message_format;
             let file = margarine::FileData::open(
                 &path.display().to_string(),
                 &mut compiler.string_map,
@@ -258,11 +341,23 @@ fn main() {
                 cache: cache.clone(),
                 arena: &arena,
                 tests: true,
+                overflow_checks: codegen.overflow_checks(),
+                debug_info: codegen.debug,
+                backtrace: codegen.backtrace,
+                opt_level: codegen.opt_level(),
+                emit: codegen.emit,
+                crate_type: CrateType::Bin,
             };
 
             let mut result = compiler.run(&settings);
             let errors = compiler.check(&mut result);
+            let denied = errors.denied_count();
+
             compiler.codegen(&settings, &mut result, errors);
+            if denied != 0 {
+                std::process::exit(COMPILE_ERROR);
+            }
+
             let link_files = result.link_files().to_vec();
             let tests = result.tests().iter()
                 .map(|(sym, should_panic)| (
@@ -284,7 +379,7 @@ fn main() {
                         .arg("-lc++abi")
                         .arg("-o")
                         .arg(&dylib);
-                    run_step("linking...", &mut clang)
+                    run_step("linking...", &mut clang, &dylib, message_format)
                 }
                 CompilationTarget::X86_64UnknownLinuxGnu
                 | CompilationTarget::Aarch64UnknownLinuxGnu => {
@@ -297,7 +392,10 @@ fn main() {
                         .arg("-lstdc++")
                         .arg("-o")
                         .arg(&dylib);
-                    run_step("linking...", &mut clang)
+                    if codegen.backtrace {
+                        clang.arg("-rdynamic");
+                    }
+                    run_step("linking...", &mut clang, &dylib, message_format)
                 }
                 CompilationTarget::Wasm32UnknownUnknown => {
                     fail(LINK_ERROR, "tests do not support the wasm32-unknown-unknown target");
@@ -307,14 +405,15 @@ fn main() {
                 fail(LINK_ERROR, "linking failed");
             }
 
-            let success = run_tests(&tests, filter, &dylib);
+            let success = run_tests(&tests, filter, &dylib, message_format);
             std::process::exit(if success { 0 } else { COMPILE_ERROR });
         }
 
-        Commands::Check { path, target, cache, update } => {
-            let cache = reset_cache_if(update, cache);
+        Commands::Check { path, target, cache, update, message_format } => {
+            let cache = reset_cache_if(update, cache, message_format);
             let arena = Arena::new();
             let mut compiler = margarine::Compiler::new(&arena);
+            compiler.message_format = message_format;
             let file = margarine::FileData::open(
                 &path.display().to_string(),
                 &mut compiler.string_map,
@@ -330,15 +429,29 @@ fn main() {
                 cache,
                 arena: &arena,
                 tests: false,
+                overflow_checks: true,
+                debug_info: false,
+                backtrace: false,
+                opt_level: OptLevel::default(),
+                emit: Vec::new(),
+                crate_type: CrateType::default(),
             };
 
             let mut result = compiler.run(&settings);
             let errors = compiler.check(&mut result);
-            let error_count = errors.iter().flatten().map(|file| file.len()).sum::<usize>();
-
-            if error_count == 0 {
+            let error_count = errors.error_count();
+
+            if message_format == MessageFormat::Json {
+                message::emit(json!({
+                    "reason": "check-finished",
+                    "success": error_count == 0,
+                    "errors": error_count,
+                }));
+            } else if error_count == 0 {
                 println!("{}", "no errors found".green());
-            } else {
+            }
+
+            if error_count != 0 {
                 std::process::exit(COMPILE_ERROR);
             }
         }
@@ -348,20 +461,28 @@ fn main() {
         }
 
         Commands::Clean { cache } => {
-            clean_artifacts(&cache.unwrap_or_else(|| "artifacts".to_string()));
+            clean_artifacts(&cache.unwrap_or_else(|| "artifacts".to_string()), MessageFormat::Human);
         }
     }
 }
 
 
 /// Compiles `path` with the Compiler + CompilationResult pipeline and links
-/// the result for `target`. Shared by `build` and `run`.
+/// the result for `target`. Shared by `build` and `run`. Returns the
+/// path of the linked artifact.
 fn compile_and_link(
     path: &PathBuf,
     target: CompilationTarget,
+    crate_type: CrateType,
     output: Option<String>,
     cache: Option<String>,
+    format: MessageFormat,
+    codegen: &CodegenArgs,
 ) -> String {
+    if crate_type.is_library() && target == CompilationTarget::Wasm32UnknownUnknown {
+        fail(LINK_ERROR, "libraries do not support the wasm32-unknown-unknown target");
+    }
+
     let cache = cache.unwrap_or("artifacts".to_string());
     let output = output
         .map(|s| PathBuf::from(s))
@@ -370,16 +491,20 @@ fn compile_and_link(
             let name = path.file_name()
                 .map(|s| s.to_string_lossy().to_string())
                 .unwrap_or("program".into());
+            let name = if crate_type.is_library() { format!("lib{name}") } else { name };
             let path = PathBuf::from(&cache);
             let path = path.join(name);
             let path = path.with_extension(target.output_suffix());
             path
         });
 
+    // a library's object & header sit next to it without its extension
+    let output = if crate_type.is_library() { output.with_extension("") } else { output };
     let output = output.to_string_lossy();
 
     let arena = Arena::new();
     let mut compiler = margarine::Compiler::new(&arena);
+    compiler.message_format = format;
     let file = margarine::FileData::open(
         &path.display().to_string(),
         &mut compiler.string_map,
@@ -395,16 +520,35 @@ fn compile_and_link(
         cache: cache.to_string(),
         arena: &arena,
         tests: false,
+        overflow_checks: codegen.overflow_checks(),
+        debug_info: codegen.debug,
+        backtrace: codegen.backtrace,
+        opt_level: codegen.opt_level(),
+        emit: codegen.emit.clone(),
+        crate_type,
     };
 
     let mut result = compiler.run(&settings);
     let errors = compiler.check(&mut result);
-    let error_count = errors.iter().flatten().map(|file| file.len()).sum::<usize>();
+    let denied = errors.denied_count();
 
     compiler.codegen(&settings, &mut result, errors);
+    if denied != 0 {
+        std::process::exit(COMPILE_ERROR);
+    }
+
     let link_files = result.link_files().to_vec();
 
+    let artifact = match crate_type {
+        CrateType::Bin => output.to_string(),
+        CrateType::StaticLib => format!("{output}.a"),
+        CrateType::CDylib => format!("{output}.{}", target.shared_library_suffix()),
+    };
+
     let link_ok = match target {
+        _ if crate_type == CrateType::StaticLib => {
+            archive(target, &output, &artifact, &link_files, format)
+        }
         CompilationTarget::Arm64AppleDarwin => {
             let c_target = target.c_target_triple();
             let mut clang = Command::new("clang");
@@ -417,8 +561,11 @@ fn compile_and_link(
                 .arg("-lc++")
                 .arg("-lc++abi")
                 .arg("-o")
-                .arg(&*output);
-            run_step("linking...", &mut clang)
+                .arg(&artifact);
+            if crate_type == CrateType::CDylib {
+                clang.arg("-shared");
+            }
+            run_step("linking...", &mut clang, &artifact, format)
         }
         CompilationTarget::X86_64UnknownLinuxGnu
         | CompilationTarget::Aarch64UnknownLinuxGnu => {
@@ -432,8 +579,15 @@ fn compile_and_link(
                 .arg("-lz")
                 .arg("-lstdc++")
                 .arg("-o")
-                .arg(&*output);
-            run_step("linking...", &mut clang)
+                .arg(&artifact);
+            if crate_type == CrateType::CDylib {
+                clang.arg("-shared");
+            }
+            // glibc only names the frames of exported symbols
+            if codegen.backtrace {
+                clang.arg("-rdynamic");
+            }
+            run_step("linking...", &mut clang, &artifact, format)
         }
         CompilationTarget::Wasm32UnknownUnknown => {
             let mut linker = Command::new("wasm-ld");
@@ -444,7 +598,7 @@ fn compile_and_link(
                 .args(&link_files)
                 .arg("-o")
                 .arg(&*output);
-            run_step("linking browser wasm...", &mut linker)
+            run_step("linking browser wasm...", &mut linker, &output, format)
         }
     };
 
@@ -452,7 +606,49 @@ fn compile_and_link(
         fail(LINK_ERROR, "linking failed");
     }
 
-    output.into()
+    if crate_type.is_library() {
+        let header = format!("{output}.h");
+        match format {
+            MessageFormat::Human => println!("{}", format!("wrote '{header}'").dim()),
+            MessageFormat::Json => message::emit(json!({ "reason": "header", "path": header })),
+        }
+    }
+
+    artifact
+}
+
+
+/// Bundles `output`'s object and the link files into the static library
+/// `artifact`, merging the link files that are archives themselves
+fn archive(target: CompilationTarget, output: &str, artifact: &str, link_files: &[String], format: MessageFormat) -> bool {
+    let _ = std::fs::remove_file(artifact);
+
+    if target == CompilationTarget::Arm64AppleDarwin {
+        let mut libtool = Command::new("libtool");
+        libtool.arg("-static")
+            .arg("-o")
+            .arg(artifact)
+            .arg(format!("{output}.o"))
+            .args(link_files);
+        return run_step("archiving...", &mut libtool, artifact, format);
+    }
+
+    // GNU ar only merges archives through an MRI script
+    let mut script = format!("CREATE {artifact}\nADDMOD {output}.o\n");
+    for file in link_files {
+        let command = if file.ends_with(".a") { "ADDLIB" } else { "ADDMOD" };
+        script.push_str(&format!("{command} {file}\n"));
+    }
+    script.push_str("SAVE\nEND\n");
+
+    let script_path = format!("{output}.mri");
+    let script_file = std::fs::write(&script_path, script)
+        .and_then(|_| std::fs::File::open(&script_path))
+        .unwrap_or_else(|error| panic!("failed to write '{script_path}': {error}"));
+
+    let mut ar = Command::new("ar");
+    ar.arg("-M").stdin(script_file);
+    run_step("archiving...", &mut ar, artifact, format)
 }
 
 
@@ -492,9 +688,14 @@ impl Drop for ArtifactsLock {
     }
 }
 
-fn clean_artifacts(cache: &str) {
+fn clean_artifacts(cache: &str, format: MessageFormat) {
+    let json = format == MessageFormat::Json;
     if !std::fs::exists(cache).unwrap_or(false) {
-        println!("{}", "nothing to clean".dim());
+        if json {
+            message::emit(json!({ "reason": "clean", "removed": 0, "failed": 0, "bytes": 0 }));
+        } else {
+            println!("{}", "nothing to clean".dim());
+        }
         return;
     }
 
@@ -530,7 +731,7 @@ fn clean_artifacts(cache: &str) {
         )
     };
 
-    let is_tty = unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1;
+    let is_tty = !json && unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1;
     let progress = |done: usize| {
         if !is_tty { return; }
         print!("\r     Removing {total} {}",
@@ -557,7 +758,14 @@ fn clean_artifacts(cache: &str) {
     if is_tty {
         print!("\r");
     }
-    if failures == 0 {
+    if json {
+        message::emit(json!({
+            "reason": "clean",
+            "removed": paths.len() - failures,
+            "failed": failures,
+            "bytes": total_bytes,
+        }));
+    } else if failures == 0 {
         println!("{} Removed {} files, {}",
             "✓".green(),
             paths.len(),
@@ -577,14 +785,14 @@ fn clean_artifacts(cache: &str) {
 /// when `update` is set. The lock file lives at the project root and is
 /// cache-independent; the commit map it holds is only consulted against the
 /// fresh clones a reset triggers anyway.
-fn reset_cache_if(update: bool, cache: Option<String>) -> String {
+fn reset_cache_if(update: bool, cache: Option<String>, format: MessageFormat) -> String {
     let cache = cache.unwrap_or_else(|| "artifacts".to_string());
     if update {
         if std::fs::exists("build.lock").unwrap() {
             std::fs::remove_file("build.lock").unwrap();
         }
 
-        clean_artifacts(&cache);
+        clean_artifacts(&cache, format);
     }
 
     cache
@@ -623,7 +831,31 @@ fn existing_file_path(path: &str) -> Result<PathBuf, String> {
 }
 
 
-fn run_step(label: &str, cmd: &mut Command) -> bool {
+/// Runs an external toolchain step that produces `artifact`
+fn run_step(label: &str, cmd: &mut Command, artifact: &str, format: MessageFormat) -> bool {
+    if format == MessageFormat::Json {
+        let output = cmd.output();
+        let (success, status, stdout, stderr) = match &output {
+            Ok(output) => (
+                output.status.success(),
+                output.status.code(),
+                String::from_utf8_lossy(&output.stdout).into_owned(),
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ),
+            Err(err) => (false, None, String::new(), format!("failed to start: {err}")),
+        };
+
+        message::emit(json!({
+            "reason": "link",
+            "success": success,
+            "artifact": artifact,
+            "status": status,
+            "stdout": stdout,
+            "stderr": stderr,
+        }));
+        return success;
+    }
+
     println!("{}", label.green().bold());
     match cmd.output() {
         Ok(output) if output.status.success() => true,
@@ -647,7 +879,42 @@ fn run_step(label: &str, cmd: &mut Command) -> bool {
 }
 
 
-fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> bool {
+fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str, format: MessageFormat) -> bool {
+    let json = format == MessageFormat::Json;
+
+    // reports how a single test went
+    let finish = |name: &str, event: &str, message: Option<&str>, output: &str| {
+        if json {
+            message::emit(json!({
+                "reason": "test",
+                "event": event,
+                "name": name,
+                "message": message,
+                "output": output,
+            }));
+        } else if event == "ok" {
+            println!("{}", "ok".green());
+        } else {
+            println!("{}", "FAILED".red());
+        }
+    };
+
+    let summary = |passed: u32, failed: u32, ignored: u32, elapsed: f64| {
+        message::emit(json!({
+            "reason": "test-result",
+            "success": failed == 0,
+            "passed": passed,
+            "failed": failed,
+            "ignored": ignored,
+            "duration": elapsed,
+        }));
+    };
+
+    if tests.is_empty() && json {
+        summary(0, 0, 0, 0.0);
+        return true;
+    }
+
     if tests.is_empty() {
         println!();
         println!("running 0 tests");
@@ -669,13 +936,19 @@ fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> b
         let lib_path = CString::new(dylib).unwrap();
         let lib = libc::dlopen(lib_path.as_ptr(), libc::RTLD_NOW);
         if lib.is_null() {
-            println!("failed to load {dylib}");
+            if json {
+                message::emit(json!({ "reason": "test-error", "message": format!("failed to load {dylib}") }));
+            } else {
+                println!("failed to load {dylib}");
+            }
             return false;
         }
 
-        println!();
-        println!("running {} tests", tests.len());
-        println!();
+        if !json {
+            println!();
+            println!("running {} tests", tests.len());
+            println!();
+        }
 
         let mut passed = 0u32;
         let mut failed = 0u32;
@@ -686,23 +959,29 @@ fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> b
             if let Some(ref filter) = filter {
                 if !name.contains(filter.as_str()) {
                     ignored += 1;
+                    if json { finish(name, "ignored", None, "") }
                     continue;
                 }
             }
 
-            let label = if *should_panic { " - should panic" } else { "" };
-            print!("test '{}'{} ... ", name, label);
-            io::stdout().flush().unwrap();
+            if json {
+                message::emit(json!({ "reason": "test", "event": "started", "name": name }));
+            } else {
+                let label = if *should_panic { " - should panic" } else { "" };
+                print!("test '{}'{} ... ", name, label);
+                io::stdout().flush().unwrap();
+            }
 
             let func = lookup_test(lib, name);
             if func.is_null() {
-                println!("{}", "FAILED".red());
+                finish(name, "failed", Some("function not found in dylib"), "");
                 failed += 1;
                 writeln!(&mut fails, "failed '{}': function not found in dylib", name).unwrap();
                 continue;
             }
 
             let func: unsafe extern "C" fn(*const u8) = std::mem::transmute(func);
+            let init_statics = lookup_test(lib, "margarineInitStatics");
 
             let mut pipe_fds: [i32; 2] = [0; 2];
             libc::pipe(pipe_fds.as_mut_ptr());
@@ -713,6 +992,10 @@ fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> b
                 libc::dup2(pipe_fds[1], 1);
                 libc::dup2(pipe_fds[1], 2);
                 libc::close(pipe_fds[1]);
+                if !init_statics.is_null() {
+                    let init_statics: unsafe extern "C" fn() = std::mem::transmute(init_statics);
+                    init_statics();
+                }
                 func(std::ptr::null());
                 libc::exit(0);
             }
@@ -743,7 +1026,7 @@ fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> b
             let exited_ok = wifexited(status) && wexitstatus(status) == 0;
 
             if timed_out {
-                println!("{}", "FAILED".red());
+                finish(name, "timed-out", Some(&format!("timed out after {timeout_ms}ms")), &output);
                 failed += 1;
                 writeln!(&mut fails, "failed '{}' (timed out after {}ms):\n{}",
                     name,
@@ -752,19 +1035,18 @@ fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> b
                 ).unwrap();
             } else if *should_panic {
                 if !exited_ok {
-                    println!("{}", "ok".green());
+                    finish(name, "ok", None, &output);
                     passed += 1;
                 } else {
-                    println!("{}", "FAILED".red());
+                    finish(name, "failed", Some("test did not panic as expected"), &output);
                     failed += 1;
                     writeln!(&mut fails, "failed '{}' (exit code 0): test did not panic as expected", name).unwrap();
                 }
             } else {
                 if exited_ok {
-                    println!("{}", "ok".green());
+                    finish(name, "ok", None, &output);
                     passed += 1;
                 } else {
-                    println!("{}", "FAILED".red());
                     failed += 1;
                     let reason = if wifsignaled(status) {
                         format!(" (signal {})", wtermsig(status))
@@ -773,6 +1055,7 @@ fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> b
                     } else {
                         String::new()
                     };
+                    finish(name, "failed", Some(reason.trim_start().trim_matches(['(', ')'])), &output);
                     writeln!(&mut fails, "failed '{}'{}:\n{}",
                         name,
                         reason,
@@ -784,6 +1067,11 @@ fn run_tests(tests: &[(String, bool)], filter: Option<String>, dylib: &str) -> b
 
         libc::dlclose(lib);
 
+        if json {
+            summary(passed, failed, ignored, start.elapsed().as_secs_f64());
+            return failed == 0;
+        }
+
         println!();
         if !fails.is_empty() {
             println!("failures:");
@@ -853,27 +1141,6 @@ fn wtermsig(status: i32) -> i32 {
 }
 
 
-fn parse_env_preludes() -> Vec<Prelude> {
-    let preludes = 
-    std::env::var("MARGARINE_PRELUDE")
-        .iter()
-        .flat_map(|s| s.split(';'))
-        .filter_map(|s| s.split_once('='))
-        .map(|(alias, url)| Prelude { alias: alias.into(), url: url.into() })
-        .collect::<Vec<_>>();
-
-
-    if preludes.is_empty() {
-        let url = format!("https://cdn.daymare.net/margarine/{VERSION}/share");
-
-        vec![
-            Prelude { alias: "core".into(), url: format!("{url}/core") },
-            Prelude { alias: "std".into(), url: format!("{url}/std") },
-        ]
-    } else {
-        preludes
-    }
-}
 
 
 
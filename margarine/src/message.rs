@@ -0,0 +1,79 @@
+use common::source::{FileData, SourceRange};
+use common::string_map::StringMap;
+use errors::Diagnostic;
+use errors::fmt::render;
+use serde_json::{json, Value};
+
+
+/// How the compiler reports progress and diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// Coloured, human readable output
+    #[default]
+    Human,
+
+    /// One JSON object per line, each with a `reason` field
+    /// telling what kind of message it is
+    Json,
+}
+
+
+impl std::str::FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!("unknown message format '{value}', expected 'human' or 'json'")),
+        }
+    }
+}
+
+
+/// Prints `message` as a single line
+pub fn emit(message: Value) {
+    println!("{message}");
+}
+
+
+/// The `diagnostic` message for `diagnostic`, spans are resolved
+/// into file names and 1-based lines & columns
+pub fn diagnostic(diagnostic: &Diagnostic, string_map: &StringMap, files: &[FileData]) -> Value {
+    let labels = diagnostic.labels.iter().enumerate()
+        .map(|(i, label)| {
+            let mut span = span(label.span, string_map, files);
+            span["primary"] = (i == 0).into();
+            span["message"] = label.message.clone().into();
+            span
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "reason": "diagnostic",
+        "severity": diagnostic.severity.as_str(),
+        "code": diagnostic.code,
+        "message": diagnostic.message,
+        "labels": labels,
+        "notes": diagnostic.notes,
+        "rendered": render(diagnostic, string_map, files),
+    })
+}
+
+
+fn span(source: SourceRange, string_map: &StringMap, files: &[FileData]) -> Value {
+    let (file, offset) = source.file(files);
+
+    let (line_start, column_start) = file.line_column((source.start() - offset) as usize);
+    let (line_end, column_end) = file.line_column((source.end() - offset) as usize);
+
+    json!({
+        "file": file.file_name(string_map),
+        "byte_start": source.start() - offset,
+        "byte_end": source.end() - offset + 1,
+        "line_start": line_start,
+        "column_start": column_start,
+        "line_end": line_end,
+        "column_end": column_end + 1,
+    })
+}